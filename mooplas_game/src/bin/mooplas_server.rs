@@ -0,0 +1,3 @@
+fn main() {
+  mooplas_game::run_headless_server();
+}
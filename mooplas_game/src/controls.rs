@@ -7,12 +7,14 @@ use avian2d::math::{AdjustPrecision, Scalar};
 use avian2d::prelude::{AngularVelocity, LinearVelocity};
 use bevy::app::{App, Plugin, Update};
 use bevy::input::ButtonInput;
+use bevy::input_focus::InputFocus;
 use bevy::log::*;
 use bevy::math::Vec3;
 use bevy::prelude::{
   IntoScheduleConfigs, KeyCode, MessageReader, MessageWriter, MonitorSelection, Query, Res, ResMut, Single, Time,
   Transform, Window, With, in_state,
 };
+use bevy::text::EditableText;
 use mooplas_networking::prelude::NetworkRole;
 
 /// A plugin that manages all player controls and input handling.
@@ -24,12 +26,15 @@ impl Plugin for ControlsPlugin {
       .add_systems(Update, settings_controls_system)
       .add_systems(
         Update,
-        player_input_action_system.run_if(in_state(AppState::Registering)),
+        player_input_action_system
+          .run_if(in_state(AppState::Registering))
+          .run_if(is_not_typing),
       )
       .add_systems(
         Update,
         send_continue_message_on_key_press_system
           .run_if(in_state(AppState::Registering))
+          .run_if(is_not_typing)
           .run_if(has_registered_players)
           .run_if(|network_role: Res<NetworkRole>| !network_role.is_client()),
       )
@@ -46,6 +51,14 @@ impl Plugin for ControlsPlugin {
   }
 }
 
+/// A run condition that is `false` while a text input field has focus, so that typing e.g. a chat message doesn't
+/// register players or start the game.
+fn is_not_typing(input_focus: Option<Res<InputFocus>>, text_input_query: Query<(), With<EditableText>>) -> bool {
+  input_focus
+    .and_then(|input_focus| input_focus.get())
+    .is_none_or(|entity| !text_input_query.contains(entity))
+}
+
 /// Handles player registration and unregistration based on keyboard input. Sends an event for the UI to update.
 fn player_input_action_system(
   keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -281,6 +294,32 @@ mod tests {
     assert!(has_input_action, "Expected an Action InputAction to be sent");
   }
 
+  #[test]
+  fn player_input_action_system_ignores_key_presses_while_typing() {
+    let mut app = setup();
+    app
+      .world_mut()
+      .resource_mut::<AvailableControlSchemes>()
+      .schemes
+      .push(ControlScheme::new(
+        ControlSchemeId(0),
+        KeyCode::KeyZ,
+        KeyCode::KeyC,
+        KeyCode::KeyX,
+      ));
+    let text_input = app.world_mut().spawn(EditableText::default()).id();
+    app.world_mut().insert_resource(InputFocus::from_entity(text_input));
+    change_app_state(&mut app, AppState::Registering);
+
+    handle_key_input(&mut app, TestKeyboardInput::Press(KeyCode::KeyX));
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<InputMessage>>()
+      .expect("Messages<InputAction> missing");
+    assert_eq!(messages.iter_current_update_messages().count(), 0);
+  }
+
   #[test]
   fn send_continue_message_on_key_press_system_sends_continue_message() {
     let mut app = setup();
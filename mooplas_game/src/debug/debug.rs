@@ -1,5 +1,7 @@
 use crate::debug::controls::DebugControlsPlugin;
 use crate::debug::gizmos::GizmosPlugin;
+#[cfg(feature = "online")]
+use crate::debug::link_conditioner_menu::LinkConditionerMenuPlugin;
 use bevy::app::{App, Plugin};
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::input::common_conditions::input_toggle_active;
@@ -8,7 +10,7 @@ use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
 /// A plugin that adds various debugging tools and utilities to the application. This includes tools from third party
-/// crates, such as world inspector, as well as custom debugging controls and gizmos.
+/// crates, such as world inspector, as well as custom debugging controls, gizmos and menus.
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
@@ -18,5 +20,7 @@ impl Plugin for DebugPlugin {
       .add_plugins(FrameTimeDiagnosticsPlugin::default())
       .add_plugins(WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::F1)))
       .add_plugins((DebugControlsPlugin, GizmosPlugin));
+    #[cfg(feature = "online")]
+    app.add_plugins(LinkConditionerMenuPlugin);
   }
 }
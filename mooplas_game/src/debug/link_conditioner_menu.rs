@@ -0,0 +1,72 @@
+#![cfg(feature = "online")]
+
+use bevy::app::{App, Plugin};
+use bevy::input::common_conditions::input_toggle_active;
+use bevy::log::info;
+use bevy::prelude::{IntoScheduleConfigs, KeyCode, ResMut, Result, resource_exists};
+use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use mooplas_networking::prelude::{ChannelConditions, ChannelType, LinkConditioner};
+
+/// The maximum latency and jitter, in milliseconds, that can be set in the link conditioner menu.
+const MAX_DELAY_MS: f32 = 1000.;
+
+/// A plugin that adds a dev menu, toggled with [F2], to change the network conditions that the [`LinkConditioner`]
+/// simulates while the game is running.
+pub struct LinkConditionerMenuPlugin;
+
+impl Plugin for LinkConditionerMenuPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      EguiPrimaryContextPass,
+      link_conditioner_menu_system
+        .run_if(resource_exists::<LinkConditioner>)
+        .run_if(input_toggle_active(false, KeyCode::F2)),
+    );
+  }
+}
+
+/// Shows the link conditioner menu, which enables or disables the link conditioner and sets the conditions of each
+/// channel. Only the conditions that apply to a channel are shown, e.g. reliable channels never drop messages.
+fn link_conditioner_menu_system(mut contexts: EguiContexts, mut link_conditioner: ResMut<LinkConditioner>) -> Result {
+  let mut edited_link_conditioner = *link_conditioner;
+  egui::Window::new("Link Conditioner").show(contexts.ctx_mut()?, |ui| {
+    ui.checkbox(&mut edited_link_conditioner.enabled, "Enabled");
+    for (channel, conditions) in [
+      (ChannelType::Unreliable, &mut edited_link_conditioner.unreliable),
+      (
+        ChannelType::ReliableOrdered,
+        &mut edited_link_conditioner.reliable_ordered,
+      ),
+      (
+        ChannelType::ReliableUnordered,
+        &mut edited_link_conditioner.reliable_unordered,
+      ),
+    ] {
+      channel_conditions_ui(ui, channel, conditions);
+    }
+    if ui.button("Reset").clicked() {
+      edited_link_conditioner = LinkConditioner::default();
+    }
+  });
+
+  // Only write to the resource when something has changed, so that change detection isn't triggered every frame
+  if edited_link_conditioner != *link_conditioner {
+    info!("[F2] Set link conditioner to [{:?}]", edited_link_conditioner);
+    *link_conditioner = edited_link_conditioner;
+  }
+  Ok(())
+}
+
+fn channel_conditions_ui(ui: &mut egui::Ui, channel: ChannelType, conditions: &mut ChannelConditions) {
+  ui.collapsing(format!("{channel:?}"), |ui| {
+    ui.add(egui::Slider::new(&mut conditions.latency_ms, 0.0..=MAX_DELAY_MS).text("Latency (ms)"));
+    ui.add(egui::Slider::new(&mut conditions.jitter_ms, 0.0..=MAX_DELAY_MS).text("Jitter (ms)"));
+    if channel == ChannelType::Unreliable {
+      ui.add(egui::Slider::new(&mut conditions.packet_loss, 0.0..=1.0).text("Packet loss"));
+      ui.add(egui::Slider::new(&mut conditions.duplication, 0.0..=1.0).text("Duplication"));
+    }
+    if channel != ChannelType::ReliableOrdered {
+      ui.add(egui::Slider::new(&mut conditions.reordering, 0.0..=1.0).text("Reordering"));
+    }
+  });
+}
@@ -3,5 +3,6 @@
 mod controls;
 mod debug;
 mod gizmos;
+mod link_conditioner_menu;
 
 pub use debug::DebugPlugin;
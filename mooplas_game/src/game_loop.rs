@@ -1,11 +1,11 @@
-#[cfg(feature = "online")]
-use crate::prelude::LocalPlayerRegistrationRequestMessage;
 use crate::prelude::constants::{RESOLUTION_HEIGHT, RESOLUTION_WIDTH};
 use crate::prelude::{
-  AppState, AvailableControlSchemes, ContinueMessage, ControlSchemeId, ExitLobbyMessage, PlayerId,
-  PlayerRegistrationMessage, RegisteredPlayer, RegisteredPlayers, Seed, SnakeHead, WinnerInfo, colour_for_player_id,
-  has_registered_players,
+  AppState, AvailableControlSchemes, ContinueMessage, ControlSchemeId, EliminationCause, ExitLobbyMessage,
+  PlayerEliminatedMessage, PlayerId, PlayerRegistrationMessage, RegisteredPlayer, RegisteredPlayers, Seed, SnakeHead,
+  WinnerInfo, colour_for_player_id, has_registered_players,
 };
+#[cfg(feature = "online")]
+use crate::prelude::{LocalPlayerReadyRequestMessage, LocalPlayerRegistrationRequestMessage};
 use crate::shared::{InputMessage, Player};
 use avian2d::prelude::Collisions;
 use bevy::app::{App, Plugin};
@@ -24,7 +24,8 @@ impl Plugin for GameLoopPlugin {
         handle_continue_message
           .run_if(in_state(AppState::Registering))
           .run_if(has_registered_players)
-          .run_if(|role: Res<NetworkRole>| role.is_server() || role.is_none()),
+          .run_if(|role: Res<NetworkRole>| role.is_server() || role.is_none())
+          .run_if(is_lobby_ready),
       )
       .add_systems(
         Update,
@@ -58,6 +59,12 @@ impl Plugin for GameLoopPlugin {
   }
 }
 
+/// A run condition that is `true` if the round can be started i.e. in a local game, or in online multiplayer once all
+/// registered players are ready.
+fn is_lobby_ready(network_role: Res<NetworkRole>, registered_players: Res<RegisteredPlayers>) -> bool {
+  network_role.is_none() || registered_players.are_all_ready()
+}
+
 /// Transitions the game from the registration/lobby state to the running state.
 fn handle_continue_message(
   mut messages: MessageReader<ContinueMessage>,
@@ -80,7 +87,9 @@ fn handle_exit_lobby_message(
   next_app_state.set(AppState::Preparing);
 }
 
-/// Handles player registration messages to add or remove players from the registered players list.
+/// Handles player registration messages to add or remove players from the registered players list. In online
+/// multiplayer, requests the registration of the control scheme instead, or toggles whether the player that is already
+/// registered with it is ready.
 fn player_registration_system(
   mut input_messages: MessageReader<InputMessage>,
   mut registered_players: ResMut<RegisteredPlayers>,
@@ -88,6 +97,7 @@ fn player_registration_system(
   #[cfg(feature = "online")] mut local_player_registration_request_message: MessageWriter<
     LocalPlayerRegistrationRequestMessage,
   >,
+  #[cfg(feature = "online")] mut local_player_ready_request_message: MessageWriter<LocalPlayerReadyRequestMessage>,
   mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
   _network_role: Res<NetworkRole>,
 ) {
@@ -104,14 +114,21 @@ fn player_registration_system(
 
       #[cfg(feature = "online")]
       if _network_role.is_client() || _network_role.is_server() {
-        let is_registered_locally = registered_players
+        if let Some(registered_player) = registered_players
           .players
           .iter()
-          .any(|registered_player| registered_player.is_local() && registered_player.input.id == control_scheme_id);
-        local_player_registration_request_message.write(LocalPlayerRegistrationRequestMessage {
-          control_scheme_id,
-          has_registered: !is_registered_locally,
-        });
+          .find(|registered_player| registered_player.is_local() && registered_player.input.id == control_scheme_id)
+        {
+          local_player_ready_request_message.write(LocalPlayerReadyRequestMessage {
+            player_id: registered_player.id,
+            is_ready: !registered_player.is_ready,
+          });
+        } else {
+          local_player_registration_request_message.write(LocalPlayerRegistrationRequestMessage {
+            control_scheme_id,
+            has_registered: true,
+          });
+        }
         continue;
       }
 
@@ -159,13 +176,15 @@ fn player_registration_system(
   }
 }
 
-/// Checks for collisions involving snake heads and marks players as dead if they collide.
+/// Checks for collisions involving snake heads and marks players as dead if they collide. Writes a
+/// [`PlayerEliminatedMessage`] with the cause of the elimination for every player that was alive until now.
 fn check_snake_collisions_system(
   mut registered_players: ResMut<RegisteredPlayers>,
   collisions: Collisions,
-  snake_head_query: Query<&PlayerId, With<SnakeHead>>,
+  snake_head_query: Query<(&PlayerId, &GlobalTransform), With<SnakeHead>>,
   player_id_query: Query<&PlayerId>,
   parent_query: Query<&ChildOf>,
+  mut player_eliminated_message: MessageWriter<PlayerEliminatedMessage>,
 ) {
   let resolve_player_id = |start: Entity| -> Option<PlayerId> {
     let mut current = start;
@@ -186,13 +205,25 @@ fn check_snake_collisions_system(
 
     let mut process_pair = |this_entity: Entity, other_entity: Entity| {
       if let Some(this_player_id) = resolve_player_id(this_entity) {
-        if let Ok(_) = snake_head_query.get(this_entity) {
+        if let Ok((_, global_transform)) = snake_head_query.get(this_entity) {
           if let Some(player) = registered_players.players.iter_mut().find(|p| p.id == this_player_id) {
             if let Some(other_player_id) = resolve_player_id(other_entity) {
-              if other_player_id.0 != this_player_id.0 {
+              let cause = if snake_head_query.get(other_entity).is_ok() {
+                debug!("[{:?}] collided head-on with [{:?}]", player.id, other_player_id);
+                EliminationCause::HeadOn(other_player_id)
+              } else if other_player_id.0 != this_player_id.0 {
                 debug!("[{:?}] collided with [{:?}]", player.id, other_player_id);
+                EliminationCause::OtherTail(other_player_id)
               } else {
                 debug!("[{:?}] collided with themselves", player.id);
+                EliminationCause::OwnTail
+              };
+              if player.alive {
+                player_eliminated_message.write(PlayerEliminatedMessage::new(
+                  player.id,
+                  cause,
+                  global_transform.translation().truncate(),
+                ));
               }
               player.alive = false;
             } else {
@@ -211,10 +242,12 @@ fn check_snake_collisions_system(
   }
 }
 
-/// Checks whether any snake head is "touching" the bounds. If so, mark the corresponding player as dead.
+/// Checks whether any snake head is "touching" the bounds. If so, mark the corresponding player as dead and write a
+/// [`PlayerEliminatedMessage`].
 fn check_screen_bounds_collisions_system(
   mut registered_players: ResMut<RegisteredPlayers>,
   snake_head_query: Query<(&GlobalTransform, &PlayerId), With<SnakeHead>>,
+  mut player_eliminated_message: MessageWriter<PlayerEliminatedMessage>,
 ) {
   let half_width = RESOLUTION_WIDTH as f32 / 2.;
   let half_height = RESOLUTION_HEIGHT as f32 / 2.;
@@ -222,12 +255,21 @@ fn check_screen_bounds_collisions_system(
   for (global_transform, player_id) in snake_head_query.iter() {
     let position = global_transform.translation();
     if position.x.abs() > half_width || position.y.abs() > half_height {
-      if let Some(player) = registered_players.players.iter_mut().find(|p| p.id == *player_id) {
+      if let Some(player) = registered_players
+        .players
+        .iter_mut()
+        .find(|p| p.id == *player_id && p.alive)
+      {
         debug!(
           "Player [{:?}] left bounds at position {:?} and is eliminated",
           player.id, position
         );
         player.alive = false;
+        player_eliminated_message.write(PlayerEliminatedMessage::new(
+          player.id,
+          EliminationCause::Wall,
+          position.truncate(),
+        ));
       }
     }
   }
@@ -449,6 +491,100 @@ mod tests {
     assert_eq!(local_registration_requests.iter_current_update_messages().count(), 1);
   }
 
+  #[cfg(feature = "online")]
+  #[test]
+  fn player_registration_in_client_mode_toggles_readiness_of_registered_player() {
+    let mut app = setup();
+    *app.world_mut().resource_mut::<NetworkRole>() = NetworkRole::Client;
+    let control_scheme = ControlScheme::new(ControlSchemeId(0), KeyCode::KeyA, KeyCode::KeyS, KeyCode::KeyD);
+    app
+      .world_mut()
+      .resource_mut::<AvailableControlSchemes>()
+      .schemes
+      .push(control_scheme.clone());
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(RegisteredPlayer::new_mutable(
+        PlayerId(3),
+        "Player 3".to_string(),
+        control_scheme,
+        Color::BLACK,
+      ))
+      .expect("Player should register");
+    app
+      .world_mut()
+      .write_message(InputMessage::Action(PlayerId(0)))
+      .expect("Failed to write InputAction message");
+    app.add_systems(Update, player_registration_system);
+
+    app.update();
+
+    let ready_requests: Vec<_> = app
+      .world()
+      .resource::<Messages<LocalPlayerReadyRequestMessage>>()
+      .iter_current_update_messages()
+      .copied()
+      .collect();
+    assert_eq!(
+      ready_requests,
+      vec![LocalPlayerReadyRequestMessage {
+        player_id: PlayerId(3),
+        is_ready: true,
+      }]
+    );
+    let registration_requests = app
+      .world()
+      .resource::<Messages<LocalPlayerRegistrationRequestMessage>>();
+    assert_eq!(registration_requests.iter_current_update_messages().count(), 0);
+  }
+
+  #[cfg(feature = "online")]
+  #[test]
+  fn handle_continue_message_waits_for_all_players_to_be_ready_in_online_mode() {
+    let mut app = setup();
+    *app.world_mut().resource_mut::<NetworkRole>() = NetworkRole::Server;
+    for id in 0..2 {
+      app
+        .world_mut()
+        .resource_mut::<RegisteredPlayers>()
+        .register(RegisteredPlayer::new_mutable(
+          PlayerId(id),
+          format!("Player {id}"),
+          ControlScheme::new(ControlSchemeId(id), KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL),
+          Color::BLACK,
+        ))
+        .expect("Player should register");
+    }
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .set_ready(PlayerId(0), true);
+    app.add_systems(Update, handle_continue_message.run_if(is_lobby_ready));
+    app
+      .world_mut()
+      .write_message(ContinueMessage)
+      .expect("Failed to write ContinueMessage");
+
+    app.update();
+    app.update();
+
+    assert_eq!(app.world().resource::<State<AppState>>(), &AppState::Loading);
+
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .set_ready(PlayerId(1), true);
+    app
+      .world_mut()
+      .write_message(ContinueMessage)
+      .expect("Failed to write ContinueMessage");
+    app.update();
+    app.update();
+
+    assert_eq!(app.world().resource::<State<AppState>>(), &AppState::Playing);
+  }
+
   #[test]
   fn reset_for_lobby_system_advances_seed_and_clears_round_state() {
     let mut app = setup();
@@ -503,6 +639,42 @@ mod tests {
     assert_eq!(state, &AppState::Playing);
   }
 
+  #[test]
+  fn check_screen_bounds_collisions_eliminates_player_once_with_wall_cause() {
+    let mut app = setup();
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(RegisteredPlayer::new_mutable(
+        PlayerId(1),
+        "Player 1".to_string(),
+        ControlScheme::new(ControlSchemeId(1), KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL),
+        Color::BLACK,
+      ))
+      .expect("Player should register");
+    let out_of_bounds = Vec3::new(RESOLUTION_WIDTH as f32, 0., 0.);
+    app
+      .world_mut()
+      .spawn((SnakeHead, PlayerId(1), GlobalTransform::from_translation(out_of_bounds)));
+    app.add_systems(Update, check_screen_bounds_collisions_system);
+
+    app.update();
+    app.update();
+
+    assert!(!app.world().resource::<RegisteredPlayers>().players[0].alive);
+    let messages = app.world().resource::<Messages<PlayerEliminatedMessage>>();
+    let mut cursor = messages.get_cursor();
+    let eliminated: Vec<_> = cursor.read(messages).copied().collect();
+    assert_eq!(
+      eliminated,
+      vec![PlayerEliminatedMessage::new(
+        PlayerId(1),
+        EliminationCause::Wall,
+        out_of_bounds.truncate()
+      )]
+    );
+  }
+
   #[test]
   fn transition_to_game_over_sets_winner_when_one_alive_remains() {
     let mut app = setup();
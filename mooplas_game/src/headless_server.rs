@@ -0,0 +1,461 @@
+use crate::app_state::AppStatePlugin;
+use crate::controls::ControlsPlugin;
+use crate::game_loop::GameLoopPlugin;
+use crate::initialisation::InitialisationPlugin;
+use crate::loading::LoadingPlugin;
+use crate::online::OnlinePlugin;
+use crate::player::PlayerPlugin;
+use crate::prelude::{
+  AppState, ConnectionInfoMessage, ContinueMessage, GameRules, LaunchConfig, MAX_PLAYERS, MenuName, NetworkingArgs,
+  RegisteredPlayers, SharedMessagesPlugin, SharedResourcesPlugin, ToggleMenuMessage, WinnerInfo,
+};
+use avian2d::PhysicsPlugins;
+use avian2d::prelude::Gravity;
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::asset::AssetMetaCheck;
+use bevy::prelude::*;
+use bevy::render::RenderPlugin;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DEFAULT_MIN_PLAYERS: u8 = 2;
+const TICK_RATE: f64 = 60.;
+/// How long to wait after enough players have registered and are ready before starting a round, giving others time to
+/// join.
+const AUTO_START_DELAY_SECONDS: f32 = 5.;
+/// How long to wait after a round has ended before opening the lobby for the next round.
+const NEXT_ROUND_DELAY_SECONDS: f32 = 5.;
+
+/// Runs a dedicated server without a window or rendering. Creates a room on the signalling server, runs the
+/// authoritative simulation, and starts rounds automatically once enough remote players have registered. Configured
+/// via command line arguments, see [`HeadlessServerArgs`].
+pub fn run_headless_server() {
+  let args = HeadlessServerArgs::parse();
+  let config = args.config().unwrap_or_else(|error| {
+    HeadlessServerArgs::command()
+      .error(ErrorKind::ArgumentConflict, error)
+      .exit()
+  });
+
+  let mut app = App::new();
+  app.insert_resource(LaunchConfig {
+    networking: args.networking,
+    ..default()
+  });
+  app
+    .add_plugins(
+      DefaultPlugins
+        .set(AssetPlugin {
+          meta_check: AssetMetaCheck::Never,
+          ..default()
+        })
+        .set(WindowPlugin {
+          primary_window: None,
+          exit_condition: ExitCondition::DontExit,
+          ..default()
+        })
+        .set(RenderPlugin {
+          // Keeps the asset types used by the simulation available without requiring a GPU
+          render_creation: RenderCreation::Automatic(WgpuSettings {
+            backends: None,
+            ..default()
+          }),
+          ..default()
+        })
+        .disable::<WinitPlugin>(),
+    )
+    .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1. / TICK_RATE)))
+    .add_plugins((PhysicsPlugins::default().with_length_unit(5.0),))
+    .insert_resource(Gravity::ZERO)
+    .add_plugins((
+      AppStatePlugin,
+      SharedResourcesPlugin,
+      SharedMessagesPlugin,
+      LoadingPlugin,
+      InitialisationPlugin,
+      PlayerPlugin,
+      GameLoopPlugin,
+      ControlsPlugin,
+      OnlinePlugin,
+      HeadlessServerPlugin { config },
+    ));
+
+  app.run();
+}
+
+/// The command line arguments of the headless server.
+#[derive(Parser, Debug)]
+#[command(
+  name = "mooplas_server",
+  about = "Runs a dedicated server without a window or rendering"
+)]
+struct HeadlessServerArgs {
+  /// The rules preset to use, one of standard, casual or competitive
+  #[arg(long, value_name = "PRESET", default_value = "standard", value_parser = parse_rules_preset)]
+  rules: GameRules,
+
+  /// The number of registered players required to start a round
+  #[arg(long, value_name = "N", default_value_t = DEFAULT_MIN_PLAYERS, value_parser = player_count_parser())]
+  min_players: u8,
+
+  /// The maximum number of players that can register for a round
+  #[arg(long, value_name = "N", default_value_t = MAX_PLAYERS, value_parser = player_count_parser())]
+  max_players: u8,
+
+  #[command(flatten)]
+  networking: NetworkingArgs,
+}
+
+impl HeadlessServerArgs {
+  fn config(&self) -> Result<HeadlessServerConfig, String> {
+    if self.min_players > self.max_players {
+      return Err(format!(
+        "[--min-players] ({}) must not exceed [--max-players] ({})",
+        self.min_players, self.max_players
+      ));
+    }
+    let mut rules = self.rules;
+    rules.max_players = self.max_players;
+
+    Ok(HeadlessServerConfig {
+      rules,
+      min_players: self.min_players,
+    })
+  }
+}
+
+fn parse_rules_preset(preset: &str) -> Result<GameRules, String> {
+  GameRules::from_preset(preset).ok_or_else(|| {
+    format!(
+      "Unknown rules preset [{preset}], expected one of [{}]",
+      GameRules::PRESETS.join(", ")
+    )
+  })
+}
+
+fn player_count_parser() -> clap::builder::RangedI64ValueParser<u8> {
+  clap::value_parser!(u8).range(1..=i64::from(MAX_PLAYERS))
+}
+
+/// The configuration of a headless server, parsed from the command line.
+#[derive(Debug, Clone, Copy)]
+struct HeadlessServerConfig {
+  rules: GameRules,
+  min_players: u8,
+}
+
+/// A plugin that drives the game loop of a headless server in place of the menus and the host's keyboard: it hosts a
+/// room, starts each round once enough players have registered and are ready, logs the result, and opens the lobby
+/// again.
+struct HeadlessServerPlugin {
+  config: HeadlessServerConfig,
+}
+
+impl Plugin for HeadlessServerPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(self.config.rules)
+      .insert_resource(AutoStart::new(self.config.min_players))
+      .init_resource::<MatchResults>()
+      .add_systems(OnEnter(AppState::Preparing), host_room_system)
+      .add_systems(Update, log_connection_info_system)
+      .add_systems(OnEnter(AppState::Registering), reset_auto_start_system)
+      .add_systems(Update, auto_start_round_system.run_if(in_state(AppState::Registering)))
+      .add_systems(
+        OnEnter(AppState::GameOver),
+        (log_match_result_system, reset_auto_start_system),
+      )
+      .add_systems(Update, auto_continue_system.run_if(in_state(AppState::GameOver)));
+  }
+}
+
+/// A resource that tracks the countdowns to the start of the next round and to the lobby of the next round.
+#[derive(Resource)]
+struct AutoStart {
+  min_players: u8,
+  countdown: Timer,
+  next_round_countdown: Timer,
+}
+
+impl AutoStart {
+  fn new(min_players: u8) -> Self {
+    Self {
+      min_players,
+      countdown: Timer::from_seconds(AUTO_START_DELAY_SECONDS, TimerMode::Once),
+      next_round_countdown: Timer::from_seconds(NEXT_ROUND_DELAY_SECONDS, TimerMode::Once),
+    }
+  }
+}
+
+/// A resource that holds the results of all rounds played on this server.
+#[derive(Resource, Default)]
+struct MatchResults {
+  rounds_played: u32,
+  wins: HashMap<String, u32>,
+}
+
+impl MatchResults {
+  /// Records the result of a round and returns its summary, e.g. "Round 3: [Alice] wins".
+  fn record(&mut self, winner: Option<&str>) -> String {
+    self.rounds_played += 1;
+    match winner {
+      Some(name) => {
+        *self.wins.entry(name.to_string()).or_default() += 1;
+        format!("Round {}: [{}] wins", self.rounds_played, name)
+      }
+      None => format!("Round {}: no winner", self.rounds_played),
+    }
+  }
+
+  /// Describes the number of wins of each player, most wins first, e.g. "Alice: 2, Bob: 1".
+  fn standings(&self) -> String {
+    let mut standings: Vec<(&String, &u32)> = self.wins.iter().collect();
+    standings.sort_by(|(a_name, a_wins), (b_name, b_wins)| b_wins.cmp(a_wins).then(a_name.cmp(b_name)));
+    standings
+      .iter()
+      .map(|(name, wins)| format!("{name}: {wins}"))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+}
+
+/// Opens a room on the signalling server, the same way the host game menu does.
+fn host_room_system(mut toggle_menu_message: MessageWriter<ToggleMenuMessage>) {
+  info!("Hosting a new room...");
+  toggle_menu_message.write(ToggleMenuMessage::set(MenuName::HostGameMenu));
+}
+
+fn log_connection_info_system(mut messages: MessageReader<ConnectionInfoMessage>) {
+  for message in messages.read() {
+    info!(
+      "Room is open, clients can join with room ID [{}]",
+      message.connection_string
+    );
+  }
+}
+
+fn reset_auto_start_system(mut auto_start: ResMut<AutoStart>) {
+  auto_start.countdown.reset();
+  auto_start.next_round_countdown.reset();
+}
+
+/// Starts the round once [`AutoStart::min_players`] have been registered and ready for [`AUTO_START_DELAY_SECONDS`].
+/// Restarts the countdown whenever the number of registered players drops below the minimum or a player isn't ready.
+fn auto_start_round_system(
+  time: Res<Time<Real>>,
+  registered_players: Res<RegisteredPlayers>,
+  mut auto_start: ResMut<AutoStart>,
+  mut continue_message: MessageWriter<ContinueMessage>,
+) {
+  if registered_players.count() < auto_start.min_players as usize || !registered_players.are_all_ready() {
+    auto_start.countdown.reset();
+    return;
+  }
+  if auto_start.countdown.elapsed().is_zero() {
+    info!(
+      "[{}] players are ready, starting the round in [{}] seconds",
+      registered_players.count(),
+      AUTO_START_DELAY_SECONDS
+    );
+  }
+  auto_start.countdown.tick(time.delta());
+  if auto_start.countdown.just_finished() {
+    info!("Starting the round with [{}] players", registered_players.count());
+    continue_message.write(ContinueMessage);
+  }
+}
+
+fn log_match_result_system(
+  winner: Res<WinnerInfo>,
+  registered_players: Res<RegisteredPlayers>,
+  mut match_results: ResMut<MatchResults>,
+) {
+  let winner_name = winner.get().and_then(|winner_id| {
+    registered_players
+      .players
+      .iter()
+      .find(|player| player.id == winner_id)
+      .map(|player| player.name.as_str())
+  });
+  let players = registered_players
+    .players
+    .iter()
+    .map(|player| player.name.as_str())
+    .collect::<Vec<_>>()
+    .join(", ");
+  info!("{} (players: {})", match_results.record(winner_name), players);
+  info!("Standings: {}", match_results.standings());
+}
+
+/// Opens the lobby for the next round [`NEXT_ROUND_DELAY_SECONDS`] after a round has ended. Uses real time because
+/// virtual time is paused during [`AppState::GameOver`].
+fn auto_continue_system(
+  time: Res<Time<Real>>,
+  mut auto_start: ResMut<AutoStart>,
+  mut continue_message: MessageWriter<ContinueMessage>,
+) {
+  auto_start.next_round_countdown.tick(time.delta());
+  if auto_start.next_round_countdown.just_finished() {
+    info!("Opening the lobby for the next round");
+    continue_message.write(ContinueMessage);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::{ControlScheme, PlayerId, RegisteredPlayer};
+  use bevy::state::app::StatesPlugin;
+
+  fn parse(args: &[&str]) -> Result<HeadlessServerArgs, String> {
+    HeadlessServerArgs::try_parse_from(std::iter::once("mooplas_server").chain(args.iter().copied()))
+      .map_err(|error| error.to_string())
+  }
+
+  fn config(args: &[&str]) -> Result<HeadlessServerConfig, String> {
+    parse(args).and_then(|args| args.config())
+  }
+
+  fn setup(min_players: u8) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      StatesPlugin,
+      SharedMessagesPlugin,
+      SharedResourcesPlugin,
+      AppStatePlugin,
+    ));
+    app.insert_resource(AutoStart::new(min_players));
+    app.add_systems(Update, auto_start_round_system);
+    app
+  }
+
+  fn register_players(app: &mut App, count: u8, is_ready: bool) {
+    let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
+    for id in 0..count {
+      let mut player = RegisteredPlayer::new_immutable_for_test(PlayerId(id), ControlScheme::test(0), Color::WHITE);
+      player.is_ready = is_ready;
+      registered_players.register(player).expect("Failed to register player");
+    }
+  }
+
+  fn has_continue_message(app: &mut App) -> bool {
+    app
+      .world_mut()
+      .resource_mut::<Messages<ContinueMessage>>()
+      .drain()
+      .count()
+      > 0
+  }
+
+  #[test]
+  fn config_defaults_to_standard_rules_and_two_players() {
+    let config = config(&[]).expect("Failed to parse empty arguments");
+
+    assert_eq!(config.min_players, DEFAULT_MIN_PLAYERS);
+    assert_eq!(config.rules.max_players, MAX_PLAYERS);
+    assert!(!config.rules.pause_while_reconnecting);
+  }
+
+  #[test]
+  fn config_applies_rules_preset_and_player_limits_in_any_order() {
+    let config = config(&["--max-players", "4", "--rules", "competitive", "--min-players", "3"])
+      .expect("Failed to parse arguments");
+
+    assert_eq!(config.min_players, 3);
+    assert_eq!(config.rules.max_players, 4);
+    assert!(config.rules.pause_while_reconnecting);
+  }
+
+  #[test]
+  fn config_accepts_networking_arguments() {
+    let args = parse(&[
+      "--ice-server-urls",
+      "turn:turn.example.com:3478",
+      "--signalling-server-url",
+      "wss://signal.example.com",
+      "--min-players",
+      "3",
+    ])
+    .expect("Failed to parse arguments");
+
+    assert_eq!(args.min_players, 3);
+    assert_eq!(
+      args.networking.signalling_server_url,
+      Some("wss://signal.example.com".to_string())
+    );
+    assert_eq!(
+      args.networking.ice_server_options().urls,
+      Some("turn:turn.example.com:3478".to_string())
+    );
+  }
+
+  #[test]
+  fn config_rejects_invalid_arguments() {
+    for invalid in [
+      vec!["--rules", "chaotic"],
+      vec!["--min-players"],
+      vec!["--min-players", "0"],
+      vec!["--max-players", "9"],
+      vec!["--min-players", "5", "--max-players", "4"],
+      vec!["--verbose"],
+      vec!["--ice-server-urls"],
+    ] {
+      assert!(config(&invalid).is_err(), "Expected {invalid:?} to be rejected");
+    }
+  }
+
+  #[test]
+  fn auto_start_round_system_waits_for_min_players() {
+    let mut app = setup(2);
+    register_players(&mut app, 1, true);
+    app.world_mut().resource_mut::<AutoStart>().countdown = Timer::from_seconds(0., TimerMode::Once);
+
+    app.update();
+    app.update();
+
+    assert!(!has_continue_message(&mut app));
+  }
+
+  #[test]
+  fn auto_start_round_system_starts_round_once_countdown_finishes() {
+    let mut app = setup(2);
+    register_players(&mut app, 2, true);
+    app.world_mut().resource_mut::<AutoStart>().countdown = Timer::from_seconds(0., TimerMode::Once);
+
+    app.update();
+
+    assert!(has_continue_message(&mut app));
+  }
+
+  #[test]
+  fn auto_start_round_system_waits_for_all_players_to_be_ready() {
+    let mut app = setup(2);
+    register_players(&mut app, 2, false);
+    app.world_mut().resource_mut::<AutoStart>().countdown = Timer::from_seconds(0., TimerMode::Once);
+
+    app.update();
+    app.update();
+
+    assert!(!has_continue_message(&mut app));
+  }
+
+  #[test]
+  fn match_results_records_rounds_and_sorts_standings_by_wins() {
+    let mut match_results = MatchResults::default();
+
+    assert_eq!(match_results.record(Some("Bob")), "Round 1: [Bob] wins");
+    assert_eq!(match_results.record(None), "Round 2: no winner");
+    match_results.record(Some("Alice"));
+    match_results.record(Some("Alice"));
+    match_results.record(Some("Carol"));
+
+    assert_eq!(match_results.rounds_played, 5);
+    assert_eq!(match_results.standings(), "Alice: 2, Bob: 1, Carol: 1");
+  }
+}
@@ -1,23 +1,28 @@
 use crate::app_state::AppState;
+use crate::online::host_migration::HostSuccession;
 use crate::online::structs::{LocalInputMapping, NetworkTransformInterpolation};
 use crate::online::utils;
 use crate::prelude::{
-  AvailableControlSchemes, ControlSchemeId, ExitLobbyMessage, InputMessage, LocalPlayerRegistrationRequestMessage,
-  MenuName, PlayerId, PlayerName, PlayerRegistrationMessage, RegisteredPlayers, Seed, SnakeHead, ToggleMenuMessage,
-  UiNotification, WinnerInfo,
+  AvailableControlSchemes, ChatMessage, ConnectionInfoMessage, ControlSchemeId, ExitLobbyMessage, InputMessage,
+  LocalPlayerReadyRequestMessage, LocalPlayerRegistrationRequestMessage, LocalSpectatingRequestMessage, MenuName,
+  Player, PlayerEliminatedMessage, PlayerId, PlayerName, PlayerRegistrationMessage, RegisteredPlayers, Seed,
+  SendChatMessage, SnakeHead, SnakeTail, Spectators, TailEventMessage, ToggleMenuMessage, UiNotification, WinnerInfo,
 };
 use bevy::app::Update;
+use bevy::ecs::system::SystemParam;
 use bevy::log::{debug, error_once, info, warn};
 use bevy::math::Quat;
 use bevy::prelude::{
-  App, Commands, Entity, IntoScheduleConfigs, MessageReader, MessageWriter, NextState, OnExit, Plugin, Query, Res,
-  ResMut, Resource, State, Time, Transform, With, Without, in_state, resource_exists,
+  App, Commands, Entity, IntoScheduleConfigs, MessageReader, MessageWriter, NextState, OnEnter, OnExit, Plugin, Query,
+  Res, ResMut, Resource, State, Time, Transform, With, Without, in_state, not, resource_exists,
 };
 use mooplas_networking::prelude::{
-  ChannelType, ClientId, ClientMessage, ClientNetworkingActive, InboundServerMessage, OutboundClientMessage,
-  PlayerStateUpdateMessage, SerialisableRegisteredPlayer, SerialisableRegistrationRequest,
-  SerialisableUnregistrationRequest, encode_to_bytes,
+  ClientId, ClientMessage, ClientNetworkingActive, InboundServerMessage, MAX_INPUT_FRAMES_PER_PACKET,
+  OutboundClientMessage, PlayerStateUpdateMessage, ReconnectToken, SerialisableChatMessage, SerialisableInput,
+  SerialisableInputFrame, SerialisablePlayerSnapshot, SerialisableReadyRequest, SerialisableRegisteredPlayer,
+  SerialisableRegistrationRequest, SerialisableTailEvent, SerialisableUnregistrationRequest,
 };
+use std::collections::VecDeque;
 
 /// A plugin that adds shared client-side online multiplayer capabilities to the game. Contains systems that are shared
 /// between different client implementations.
@@ -30,6 +35,94 @@ const CONNECTED_NOTIFICATION: &str = "Connected to game";
 const HOST_LEFT_NOTIFICATION: &str = "The host has left the game";
 const PLAYER_JOINED_NOTIFICATION: &str = "A player joined the game";
 const PLAYER_LEFT_NOTIFICATION: &str = "A player left the game";
+const SPECTATING_NOTIFICATION: &str = "Round in progress - you will join the next round";
+const RECONNECTED_NOTIFICATION: &str = "Reconnected - your players have been restored";
+
+/// The input frames that have been sent to the server, but haven't been acknowledged by it yet, oldest first. Each
+/// packet carries all of them, so that a lost packet doesn't lose any input.
+#[derive(Resource, Default)]
+struct UnacknowledgedInputs {
+  next_sequence: u32,
+  frames: VecDeque<SerialisableInputFrame>,
+}
+
+impl UnacknowledgedInputs {
+  /// Adds a frame containing the given inputs and returns all frames to send, oldest first. Drops the oldest frames if
+  /// there are more than [`MAX_INPUT_FRAMES_PER_PACKET`].
+  fn push(&mut self, inputs: Vec<SerialisableInput>) -> Vec<SerialisableInputFrame> {
+    self.frames.push_back(SerialisableInputFrame {
+      sequence: self.next_sequence,
+      inputs,
+    });
+    self.next_sequence += 1;
+    while self.frames.len() > MAX_INPUT_FRAMES_PER_PACKET {
+      self.frames.pop_front();
+    }
+    self.frames()
+  }
+
+  /// Returns all frames that haven't been acknowledged yet, oldest first.
+  fn frames(&self) -> Vec<SerialisableInputFrame> {
+    self.frames.iter().cloned().collect()
+  }
+
+  /// Forgets all frames up to and including the given sequence number.
+  fn acknowledge(&mut self, sequence: u32) {
+    self.frames.retain(|frame| frame.sequence > sequence);
+  }
+}
+
+/// Whether this client wants to spectate, as chosen when joining and changed between rounds. Requested again from every
+/// server that initialises the client, so that a spectator keeps spectating after a host migration.
+#[derive(Resource, Default)]
+struct JoinAsSpectator(bool);
+
+/// The [`ReconnectToken`] issued by the server of the room this client is connected to. Kept after the connection has
+/// dropped so that the client can reclaim its players when it rejoins the same room.
+#[derive(Resource, Default)]
+pub(crate) struct ReconnectCredentials {
+  /// The connection string (room ID or URL) of the most recent connection attempt.
+  current_room: Option<String>,
+  /// The connection string of the room the token was issued for and the token itself.
+  previous: Option<(String, ReconnectToken)>,
+}
+
+impl ReconnectCredentials {
+  /// Returns the connection string (room ID or URL) of the most recent connection attempt, if any.
+  pub(crate) fn current_room(&self) -> Option<&str> {
+    self.current_room.as_deref()
+  }
+
+  /// Returns the token to present to the server if the client has rejoined the room that issued it, and stores the new
+  /// token for the current room.
+  fn replace_token(&mut self, token: ReconnectToken) -> Option<ReconnectToken> {
+    let current_room = self.current_room.clone()?;
+    let previous_token = self
+      .previous
+      .take()
+      .filter(|(room, _)| *room == current_room)
+      .map(|(_, token)| token);
+    self.previous = Some((current_room, token));
+    previous_token
+  }
+}
+
+/// The state needed to reclaim the players of a previous connection to the same room.
+#[derive(SystemParam)]
+struct Reconnection<'w> {
+  credentials: ResMut<'w, ReconnectCredentials>,
+  host_succession: ResMut<'w, HostSuccession>,
+  pending_bootstrap: Option<ResMut<'w, PendingClientBootstrap>>,
+  outbound_client_message: MessageWriter<'w, OutboundClientMessage>,
+}
+
+/// The message writers used to forward replicated game state from the server to the rest of the application.
+#[derive(SystemParam)]
+struct ReplicationMessageWriters<'w> {
+  player_state_update: MessageWriter<'w, PlayerStateUpdateMessage>,
+  tail_event: MessageWriter<'w, TailEventMessage>,
+  player_eliminated: MessageWriter<'w, PlayerEliminatedMessage>,
+}
 
 #[derive(Resource)]
 struct PendingClientBootstrap {
@@ -38,19 +131,55 @@ struct PendingClientBootstrap {
   winner_info: Option<u8>,
 }
 
+/// Tracks the [`InboundServerMessage::WorldSnapshot`] of a client that joined while a round is in progress.
+#[derive(Resource, Default)]
+enum PendingWorldSnapshot {
+  #[default]
+  None,
+  /// The client joined mid-round and is waiting for the snapshot. Tail changes received now are already part of it.
+  Awaiting,
+  /// The snapshot has been received but not applied yet because the players haven't been spawned yet. Tail changes
+  /// received in the meantime must be applied after it.
+  Received {
+    players: Vec<SerialisablePlayerSnapshot>,
+    buffered_tail_events: Vec<SerialisableTailEvent>,
+  },
+}
+
 impl Plugin for ClientPlugin {
   fn build(&self, app: &mut App) {
     app
       .init_resource::<CurrentClientId>()
+      .init_resource::<PendingWorldSnapshot>()
+      .init_resource::<ReconnectCredentials>()
+      .init_resource::<UnacknowledgedInputs>()
+      .init_resource::<JoinAsSpectator>()
+      .add_systems(Update, (record_connection_info_system, record_spectating_choice_system))
+      .add_systems(OnEnter(AppState::Preparing), reset_spectators_system)
       .add_systems(
         Update,
-        handle_inbound_server_message.run_if(resource_exists::<ClientNetworkingActive>),
+        (
+          handle_inbound_server_message,
+          handle_player_readiness_system.after(handle_inbound_server_message),
+          handle_input_ack_system,
+          handle_spectators_message_system.after(handle_inbound_server_message),
+          handle_chat_message_system,
+          send_local_chat_message_system,
+        )
+          .run_if(resource_exists::<ClientNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        handle_local_spectating_request_message
+          .run_if(not(in_state(AppState::Playing)))
+          .run_if(resource_exists::<ClientNetworkingActive>),
       )
       .add_systems(OnExit(AppState::Initialising), apply_pending_client_bootstrap_system)
       .add_systems(
         Update,
         (
           handle_local_player_registration_request_message,
+          handle_local_player_ready_request_message,
           handle_local_exit_lobby_message,
         )
           .run_if(in_state(AppState::Registering))
@@ -62,6 +191,8 @@ impl Plugin for ClientPlugin {
           send_local_input_messages,
           add_interpolation_component_system,
           apply_state_interpolation_system,
+          apply_player_eliminated_message_system,
+          apply_pending_world_snapshot_system.run_if(has_received_world_snapshot),
         )
           .run_if(in_state(AppState::Playing))
           .run_if(resource_exists::<ClientNetworkingActive>),
@@ -153,7 +284,9 @@ fn handle_inbound_server_message(
   mut winner: ResMut<WinnerInfo>,
   mut seed: ResMut<Seed>,
   mut registration_message: MessageWriter<PlayerRegistrationMessage>,
-  mut player_state_update_message: MessageWriter<PlayerStateUpdateMessage>,
+  mut replication: ReplicationMessageWriters,
+  mut pending_world_snapshot: ResMut<PendingWorldSnapshot>,
+  mut reconnection: Reconnection,
   mut exit_lobby_message: MessageWriter<ExitLobbyMessage>,
   mut ui_notification: MessageWriter<UiNotification>,
 ) {
@@ -168,7 +301,7 @@ fn handle_inbound_server_message(
           PLAYER_JOINED_NOTIFICATION,
         );
       }
-      InboundServerMessage::ClientDisconnected { client_id } => {
+      InboundServerMessage::ClientDisconnected { client_id, .. } => {
         info!("[{:?}] disconnected", client_id);
         write_remote_client_lifecycle_notification(
           &mut ui_notification,
@@ -177,18 +310,32 @@ fn handle_inbound_server_message(
           PLAYER_LEFT_NOTIFICATION,
         );
       }
+      InboundServerMessage::HostSuccessionChanged { .. }
+      | InboundServerMessage::Ping { .. }
+      | InboundServerMessage::TimeSyncResponse { .. }
+      | InboundServerMessage::InputAck { .. }
+      | InboundServerMessage::Chat { .. }
+      | InboundServerMessage::PlayerReadyChanged { .. }
+      | InboundServerMessage::ReadyCountdownChanged { .. }
+      | InboundServerMessage::SpectatorsChanged { .. } => {}
       InboundServerMessage::ClientInitialised {
         seed: server_seed,
         client_id,
         current_state: server_state,
         registered_players: server_registered_players,
         winner_info,
+        reconnect_token,
       } => {
         seed.set(*server_seed);
         current_client_id.0 = Some(*client_id);
         ui_notification.write(UiNotification::info(CONNECTED_NOTIFICATION.to_string()));
         registered_players.clear();
         local_input_mapping.clear();
+        *pending_world_snapshot = if AppState::from(server_state) == AppState::Playing {
+          PendingWorldSnapshot::Awaiting
+        } else {
+          PendingWorldSnapshot::None
+        };
         commands.insert_resource(PendingClientBootstrap {
           target_state: AppState::from(server_state),
           registered_players: server_registered_players.clone(),
@@ -197,6 +344,52 @@ fn handle_inbound_server_message(
         if *current_state != AppState::Initialising {
           next_state.set(AppState::Initialising);
         }
+        if let Some(previous_token) = reconnection.credentials.replace_token(*reconnect_token) {
+          info!("Rejoined previous room, attempting to reclaim players...");
+          let reconnect_message = ClientMessage::Reconnect(previous_token);
+          reconnection
+            .outbound_client_message
+            .write(OutboundClientMessage::send(&reconnect_message));
+        }
+      }
+      InboundServerMessage::ReconnectAccepted {
+        registered_players: reclaimed_players,
+      } => {
+        info!(
+          "Server accepted reconnect, reclaiming [{}] players",
+          reclaimed_players.len()
+        );
+        if let Some(pending_bootstrap) = reconnection.pending_bootstrap.as_mut() {
+          reclaim_players_in_bootstrap(pending_bootstrap, reclaimed_players);
+        } else {
+          for player in reclaimed_players {
+            utils::unregister_remote_player_locally(
+              &mut registered_players,
+              &mut registration_message,
+              PlayerId(player.player_id),
+            );
+            register_player_locally(
+              &mut registered_players,
+              &available_control_schemes,
+              &mut local_input_mapping,
+              &mut current_client_id,
+              &mut registration_message,
+              &player.client_id,
+              &player.player_id,
+              &player.control_scheme_id,
+              &player.name,
+            );
+            if player.is_ready {
+              utils::set_player_ready_locally(
+                &mut registered_players,
+                &mut registration_message,
+                PlayerId(player.player_id),
+                true,
+              );
+            }
+          }
+        }
+        ui_notification.write(UiNotification::info(RECONNECTED_NOTIFICATION.to_string()));
       }
       InboundServerMessage::PlayerRegistered {
         client_id,
@@ -236,19 +429,93 @@ fn handle_inbound_server_message(
           winner.set((*player_id).into());
         }
       }
+      InboundServerMessage::ShutdownServer if reconnection.host_succession.has_successor() => {
+        info!("The host is shutting down, waiting for the connection to close to migrate to the new host...");
+      }
       InboundServerMessage::ShutdownServer => {
         exit_lobby_message.write(ExitLobbyMessage::forced_by_server());
         ui_notification.write(UiNotification::error(HOST_LEFT_NOTIFICATION.to_string()));
       }
+      InboundServerMessage::Kicked { reason } => {
+        warn!("Kicked by the server: {}", reason);
+        // The server ignores this client from now on, so it must neither become nor follow the next host
+        reconnection.host_succession.take_plan();
+        reconnection
+          .outbound_client_message
+          .write(OutboundClientMessage::Disconnect);
+        exit_lobby_message.write(ExitLobbyMessage::forced_by_server());
+        ui_notification.write(UiNotification::error(reason.to_string()));
+      }
       InboundServerMessage::UpdatePlayerStates { states } => {
         for (player_id, x, y, rotation_z) in states {
-          player_state_update_message.write(PlayerStateUpdateMessage::new(*player_id, (*x, *y), *rotation_z));
+          replication
+            .player_state_update
+            .write(PlayerStateUpdateMessage::new(*player_id, (*x, *y), *rotation_z));
         }
       }
+      InboundServerMessage::UpdateTails { events } => match &mut *pending_world_snapshot {
+        PendingWorldSnapshot::None => {
+          for event in events {
+            replication.tail_event.write(event.into());
+          }
+        }
+        PendingWorldSnapshot::Awaiting => {
+          debug!(
+            "Ignoring [{}] tail events that will be part of the world snapshot",
+            events.len()
+          );
+        }
+        PendingWorldSnapshot::Received {
+          buffered_tail_events, ..
+        } => buffered_tail_events.extend(events.iter().copied()),
+      },
+      InboundServerMessage::WorldSnapshot { players } => {
+        if matches!(*pending_world_snapshot, PendingWorldSnapshot::Awaiting) {
+          *pending_world_snapshot = PendingWorldSnapshot::Received {
+            players: players.clone(),
+            buffered_tail_events: Vec::new(),
+          };
+        } else {
+          debug!("Ignoring world snapshot because the client did not join mid-round");
+        }
+      }
+      InboundServerMessage::PlayerEliminated { player_id, cause, x, y } => {
+        replication.player_eliminated.write(PlayerEliminatedMessage::new(
+          PlayerId(*player_id),
+          (*cause).into(),
+          bevy::math::Vec2::new(*x, *y),
+        ));
+      }
     }
   }
 }
 
+/// Associates the players in a bootstrap that hasn't been applied yet with the client IDs they have been reclaimed by,
+/// so that they are registered as local players.
+fn reclaim_players_in_bootstrap(
+  pending_bootstrap: &mut PendingClientBootstrap,
+  reclaimed_players: &[SerialisableRegisteredPlayer],
+) {
+  for player in &mut pending_bootstrap.registered_players {
+    if let Some(reclaimed_player) = reclaimed_players
+      .iter()
+      .find(|reclaimed_player| reclaimed_player.player_id == player.player_id)
+    {
+      player.client_id = reclaimed_player.client_id;
+    }
+  }
+}
+
+/// Records the room that the client is connecting to, so that a [`ReconnectToken`] can be matched to it.
+pub(crate) fn record_connection_info_system(
+  mut messages: MessageReader<ConnectionInfoMessage>,
+  mut reconnect_credentials: ResMut<ReconnectCredentials>,
+) {
+  for message in messages.read() {
+    reconnect_credentials.current_room = Some(message.connection_string.clone());
+  }
+}
+
 // TODO: Check if there are better ways to run this system conditionally
 fn apply_pending_client_bootstrap_system(
   mut commands: Commands,
@@ -278,6 +545,14 @@ fn apply_pending_client_bootstrap_system(
       &player.control_scheme_id,
       &player.name,
     );
+    if player.is_ready {
+      utils::set_player_ready_locally(
+        &mut registered_players,
+        &mut registration_message,
+        PlayerId(player.player_id),
+        true,
+      );
+    }
   }
   if let Some(player_id) = pending_bootstrap.winner_info {
     winner.set(player_id.into());
@@ -295,8 +570,16 @@ fn handle_local_player_registration_request_message(
   local_input_mapping: Res<LocalInputMapping>,
   mut outbound_client_message: MessageWriter<OutboundClientMessage>,
   player_name: Res<PlayerName>,
+  spectators: Res<Spectators>,
 ) {
   for request in messages.read() {
+    if spectators.is_local_client_spectating {
+      debug!(
+        "Ignoring registration request for [{:?}] while spectating",
+        request.control_scheme_id
+      );
+      continue;
+    }
     let client_message = if request.has_registered {
       ClientMessage::RegistrationRequest(SerialisableRegistrationRequest {
         control_scheme_id: request.control_scheme_id.0,
@@ -315,11 +598,78 @@ fn handle_local_player_registration_request_message(
       })
     };
     debug!("Sending: [{:?}]", client_message);
-    let payload = encode_to_bytes(&client_message).expect("Failed to serialise player registration message");
-    outbound_client_message.write(OutboundClientMessage::Send {
-      channel: ChannelType::ReliableOrdered,
-      payload,
+    outbound_client_message.write(OutboundClientMessage::send(&client_message));
+  }
+}
+
+/// A system that handles local requests to mark a local player as ready or not ready by sending them to the server.
+/// The readiness is only applied once the server has confirmed it.
+fn handle_local_player_ready_request_message(
+  mut messages: MessageReader<LocalPlayerReadyRequestMessage>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  for request in messages.read() {
+    let client_message = ClientMessage::ReadyRequest(SerialisableReadyRequest {
+      player_id: request.player_id.into(),
+      is_ready: request.is_ready,
+    });
+    debug!("Sending: [{:?}]", client_message);
+    outbound_client_message.write(OutboundClientMessage::send(&client_message));
+  }
+}
+
+/// A system that applies the readiness of players and the countdown to the start of the round, as broadcast by the
+/// server.
+fn handle_player_readiness_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut registration_message: MessageWriter<PlayerRegistrationMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+) {
+  for message in messages.read() {
+    match message {
+      InboundServerMessage::PlayerReadyChanged { player_id, is_ready } => {
+        utils::set_player_ready_locally(
+          &mut registered_players,
+          &mut registration_message,
+          PlayerId(*player_id),
+          *is_ready,
+        );
+      }
+      InboundServerMessage::ReadyCountdownChanged { seconds } => {
+        ui_notification.write(utils::ready_countdown_notification(*seconds));
+      }
+      _ => {}
+    }
+  }
+}
+
+/// A system that forwards chat messages broadcast by the server to the rest of the application.
+fn handle_chat_message_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut chat_message: MessageWriter<ChatMessage>,
+) {
+  for message in messages.read() {
+    if let InboundServerMessage::Chat { sender_name, text } = message {
+      chat_message.write(ChatMessage {
+        sender_name: sender_name.clone(),
+        text: text.clone(),
+      });
+    }
+  }
+}
+
+/// A system that sends the chat messages of the local player to the server, which broadcasts them to everyone. Chat
+/// messages are only displayed once they come back from the server.
+fn send_local_chat_message_system(
+  mut messages: MessageReader<SendChatMessage>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  for message in messages.read() {
+    let client_message = ClientMessage::Chat(SerialisableChatMessage {
+      text: message.text.clone(),
     });
+    outbound_client_message.write(OutboundClientMessage::send(&client_message));
   }
 }
 
@@ -345,12 +695,16 @@ fn handle_local_exit_lobby_message(
 }
 
 /// A system that handles local input action messages for mutable players by sending them to the server in order to sync
-/// the movements of the local player(s) with the server.
+/// the movements of the local player(s) with the server. All inputs of a tick are sent as a single, sequenced frame,
+/// together with all frames the server hasn't acknowledged yet. Ticks without new input still resend the unacknowledged
+/// frames, so that the last input before the player stops pressing keys isn't lost with a dropped packet.
 fn send_local_input_messages(
   mut messages: MessageReader<InputMessage>,
   registered_players: Res<RegisteredPlayers>,
+  mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
   mut outbound_client_message: MessageWriter<OutboundClientMessage>,
 ) {
+  let mut inputs = Vec::new();
   for message in messages.read() {
     let player_id = match message {
       InputMessage::Action(player_id) => player_id,
@@ -361,14 +715,7 @@ fn send_local_input_messages(
       .iter()
       .find(|player| player.id == *player_id && player.is_local())
     {
-      if let Ok(payload) = encode_to_bytes(&ClientMessage::Input(message.into())) {
-        outbound_client_message.write(OutboundClientMessage::Send {
-          channel: ChannelType::Unreliable,
-          payload,
-        });
-      } else {
-        warn!("Failed to serialise input action message: {:?}", message);
-      }
+      inputs.push(message.into());
     } else {
       error_once!(
         "Received input action message for player ID [{}], but no matching local player was found: {:?}",
@@ -377,6 +724,184 @@ fn send_local_input_messages(
       );
     }
   }
+  let frames = if inputs.is_empty() {
+    unacknowledged_inputs.frames()
+  } else {
+    unacknowledged_inputs.push(inputs)
+  };
+  if frames.is_empty() {
+    return;
+  }
+
+  outbound_client_message.write(OutboundClientMessage::send(&ClientMessage::Input(frames)));
+}
+
+/// Records whether the user chose to join as a spectator.
+fn record_spectating_choice_system(
+  mut messages: MessageReader<ConnectionInfoMessage>,
+  mut join_as_spectator: ResMut<JoinAsSpectator>,
+) {
+  for message in messages.read() {
+    join_as_spectator.0 = message.is_spectating;
+  }
+}
+
+/// Keeps [`Spectators`] in sync with the server. Asks every server that initialises this client to let it spectate, if
+/// the user has chosen to.
+fn handle_spectators_message_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut spectators: ResMut<Spectators>,
+  join_as_spectator: Res<JoinAsSpectator>,
+  current_client_id: Res<CurrentClientId>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  for message in messages.read() {
+    match message {
+      InboundServerMessage::ClientInitialised { .. } => {
+        spectators.clear();
+        if join_as_spectator.0 {
+          outbound_client_message.write(OutboundClientMessage::send(&ClientMessage::SetSpectating(true)));
+        }
+      }
+      InboundServerMessage::SpectatorsChanged { spectators: clients } => {
+        spectators.clients = clients.clone();
+        spectators.is_local_client_spectating = current_client_id
+          .0
+          .is_some_and(|client_id| clients.contains(&client_id));
+      }
+      _ => {}
+    }
+  }
+}
+
+/// A system that handles local requests to spectate or play by sending them to the server. The change is only applied
+/// once the server has confirmed it.
+fn handle_local_spectating_request_message(
+  mut messages: MessageReader<LocalSpectatingRequestMessage>,
+  mut join_as_spectator: ResMut<JoinAsSpectator>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  for request in messages.read() {
+    join_as_spectator.0 = request.is_spectating;
+    let client_message = ClientMessage::SetSpectating(request.is_spectating);
+    debug!("Sending: [{:?}]", client_message);
+    outbound_client_message.write(OutboundClientMessage::send(&client_message));
+  }
+}
+
+/// Forgets all spectators when returning to the menu, since they belong to the game that was left.
+fn reset_spectators_system(mut spectators: ResMut<Spectators>) {
+  spectators.clear();
+}
+
+/// Forgets input frames once the server has acknowledged them. Starts over whenever the client is initialised by a
+/// server, since a new server hasn't seen any of the frames.
+fn handle_input_ack_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
+) {
+  for message in messages.read() {
+    match message {
+      InboundServerMessage::InputAck { sequence } => unacknowledged_inputs.acknowledge(*sequence),
+      InboundServerMessage::ClientInitialised { .. } => *unacknowledged_inputs = UnacknowledgedInputs::default(),
+      _ => {}
+    }
+  }
+}
+
+/// Marks players as eliminated as soon as the server says so, which causes their snake to stop immediately instead of
+/// only at the end of the round.
+fn apply_player_eliminated_message_system(
+  mut player_eliminated_messages: MessageReader<PlayerEliminatedMessage>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+) {
+  for message in player_eliminated_messages.read() {
+    if let Some(player) = registered_players
+      .players
+      .iter_mut()
+      .find(|p| p.id == message.player_id)
+    {
+      debug!("[{:?}] was eliminated by the server ({:?})", player.id, message.cause);
+      player.alive = false;
+    } else {
+      warn!("Received elimination for unknown player [{:?}]", message.player_id);
+    }
+  }
+}
+
+fn has_received_world_snapshot(pending_world_snapshot: Res<PendingWorldSnapshot>) -> bool {
+  matches!(*pending_world_snapshot, PendingWorldSnapshot::Received { .. })
+}
+
+/// Applies the [`InboundServerMessage::WorldSnapshot`] of a client that joined mid-round as soon as the players of the
+/// round have been spawned. Rebuilds all tails, moves all heads to where they are on the server and marks eliminated
+/// players. The client spectates the rest of the round since it has no registered players.
+fn apply_pending_world_snapshot_system(
+  mut pending_world_snapshot: ResMut<PendingWorldSnapshot>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut snake_head_query: Query<
+    (&PlayerId, &mut Transform, Option<&mut NetworkTransformInterpolation>),
+    (Without<Player>, Without<SnakeTail>),
+  >,
+  snake_tail_query: Query<(), With<SnakeTail>>,
+  mut tail_event_message: MessageWriter<TailEventMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+) {
+  if snake_tail_query.is_empty() {
+    return;
+  }
+  let PendingWorldSnapshot::Received {
+    players,
+    buffered_tail_events,
+  } = std::mem::take(&mut *pending_world_snapshot)
+  else {
+    return;
+  };
+
+  for player in &players {
+    let player_id = PlayerId(player.player_id);
+    tail_event_message.write_batch(tail_events_from_snapshot(player));
+    if let Some(registered_player) = registered_players.players.iter_mut().find(|p| p.id == player_id) {
+      registered_player.alive = player.alive;
+    }
+    let (x, y, rotation_z) = player.head;
+    for (_, mut transform, interpolation) in snake_head_query.iter_mut().filter(|(id, _, _)| **id == player_id) {
+      transform.translation.x = x;
+      transform.translation.y = y;
+      transform.rotation = Quat::from_rotation_z(rotation_z);
+      if let Some(mut interpolation) = interpolation {
+        interpolation.update_target(transform.translation.truncate(), transform.rotation);
+      }
+    }
+  }
+  for event in &buffered_tail_events {
+    tail_event_message.write(event.into());
+  }
+
+  info!("Applied world snapshot of [{}] players", players.len());
+  ui_notification.write(UiNotification::info(SPECTATING_NOTIFICATION.to_string()));
+}
+
+/// Converts the tail of a [`SerialisablePlayerSnapshot`] into the sequence of [`TailEventMessage`]s that would have
+/// built it, so that a late joiner builds its tails exactly like every other client.
+fn tail_events_from_snapshot(player: &SerialisablePlayerSnapshot) -> Vec<TailEventMessage> {
+  let player_id = PlayerId(player.player_id);
+  let mut events = Vec::new();
+  for (index, segment) in player.segments.iter().enumerate() {
+    if index > 0 {
+      events.push(TailEventMessage::GapStarted(player_id));
+      events.push(TailEventMessage::GapEnded(player_id));
+    }
+    events.extend(
+      segment
+        .iter()
+        .map(|(x, y)| TailEventMessage::Sampled(player_id, bevy::math::Vec2::new(*x, *y))),
+    );
+  }
+  if player.is_in_gap {
+    events.push(TailEventMessage::GapStarted(player_id));
+  }
+  events
 }
 
 // TODO: Consider if I should really interpolate local players too
@@ -434,7 +959,7 @@ mod tests {
   use bevy::math::Vec3;
   use bevy::prelude::*;
   use bevy::state::app::StatesPlugin;
-  use mooplas_networking::prelude::NetworkingMessagesPlugin;
+  use mooplas_networking::prelude::{NetworkingMessagesPlugin, decode_from_bytes};
   use std::time::Duration;
 
   fn setup() -> App {
@@ -450,7 +975,10 @@ mod tests {
     ));
     app
       .init_resource::<CurrentClientId>()
-      .init_resource::<LocalInputMapping>();
+      .init_resource::<LocalInputMapping>()
+      .init_resource::<PendingWorldSnapshot>()
+      .init_resource::<ReconnectCredentials>()
+      .init_resource::<HostSuccession>();
     app
   }
 
@@ -605,6 +1133,7 @@ mod tests {
         current_state: "Registering".to_string(),
         registered_players: Vec::new(),
         winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
       })
       .expect("Failed to write ClientInitialised message");
     app
@@ -640,8 +1169,10 @@ mod tests {
           player_id: 0,
           control_scheme_id: 0,
           name: "Host".to_string(),
+          is_ready: false,
         }],
         winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
       })
       .expect("Failed to write ClientInitialised message");
 
@@ -705,6 +1236,7 @@ mod tests {
         current_state: "Registering".to_string(),
         registered_players: Vec::new(),
         winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
       })
       .expect("Failed to write ClientInitialised message");
     app.update();
@@ -736,8 +1268,10 @@ mod tests {
           player_id: 0,
           control_scheme_id: 0,
           name: "Host".to_string(),
+          is_ready: false,
         }],
         winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
       })
       .expect("Failed to write ClientInitialised message");
     app.update();
@@ -763,6 +1297,7 @@ mod tests {
       .world_mut()
       .write_message(InboundServerMessage::ClientDisconnected {
         client_id: ClientId::from_u64(8),
+        is_forced: false,
       })
       .expect("Failed to write ClientDisconnected message");
     app.update();
@@ -793,6 +1328,7 @@ mod tests {
         current_state: "Registering".to_string(),
         registered_players: Vec::new(),
         winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
       })
       .expect("Failed to write ClientInitialised message");
     app.update();
@@ -823,4 +1359,365 @@ mod tests {
       Some(PlayerId(4))
     );
   }
+
+  #[test]
+  fn player_eliminated_message_from_server_marks_player_as_eliminated() {
+    let mut app = setup();
+    app.add_systems(
+      Update,
+      (handle_inbound_server_message, apply_player_eliminated_message_system).chain(),
+    );
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_immutable(
+        PlayerId(2),
+        "Player 2".to_string(),
+        crate::prelude::ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Player should register");
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::PlayerEliminated {
+        player_id: 2,
+        cause: mooplas_networking::prelude::SerialisableEliminationCause::OtherTail { killer_id: 1 },
+        x: 10.,
+        y: -5.,
+      })
+      .expect("Failed to write PlayerEliminated message");
+    app.update();
+
+    assert!(!app.world().resource::<RegisteredPlayers>().players[0].alive);
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<PlayerEliminatedMessage>>()
+      .expect("Messages<PlayerEliminatedMessage> missing");
+    let eliminated: Vec<_> = messages.iter_current_update_messages().copied().collect();
+    assert_eq!(
+      eliminated,
+      vec![PlayerEliminatedMessage::new(
+        PlayerId(2),
+        crate::prelude::EliminationCause::OtherTail(PlayerId(1)),
+        Vec2::new(10., -5.)
+      )]
+    );
+  }
+
+  #[test]
+  fn player_ready_changed_message_from_server_updates_readiness_and_lobby_ui() {
+    let mut app = setup();
+    app.add_systems(Update, handle_player_readiness_system);
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_immutable(
+        PlayerId(2),
+        "Player 2".to_string(),
+        crate::prelude::ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Player should register");
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::PlayerReadyChanged {
+        player_id: 2,
+        is_ready: true,
+      })
+      .expect("Failed to write PlayerReadyChanged message");
+    app.update();
+
+    assert!(app.world().resource::<RegisteredPlayers>().players[0].is_ready);
+    let registration_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<PlayerRegistrationMessage>>()
+      .iter_current_update_messages()
+      .copied()
+      .collect();
+    assert_eq!(
+      registration_messages,
+      vec![PlayerRegistrationMessage {
+        player_id: PlayerId(2),
+        control_scheme_id: None,
+        is_anyone_registered: true,
+      }]
+    );
+  }
+
+  #[test]
+  fn tail_events_from_snapshot_rebuilds_segments_and_gaps_in_order() {
+    let player = SerialisablePlayerSnapshot {
+      player_id: 3,
+      head: (0., 0., 0.),
+      alive: true,
+      segments: vec![vec![(1., 1.), (2., 2.)], vec![(5., 5.)]],
+      is_in_gap: true,
+    };
+
+    assert_eq!(
+      tail_events_from_snapshot(&player),
+      vec![
+        TailEventMessage::Sampled(PlayerId(3), Vec2::new(1., 1.)),
+        TailEventMessage::Sampled(PlayerId(3), Vec2::new(2., 2.)),
+        TailEventMessage::GapStarted(PlayerId(3)),
+        TailEventMessage::GapEnded(PlayerId(3)),
+        TailEventMessage::Sampled(PlayerId(3), Vec2::new(5., 5.)),
+        TailEventMessage::GapStarted(PlayerId(3)),
+      ]
+    );
+  }
+
+  #[test]
+  fn handle_inbound_server_message_buffers_tail_events_received_after_world_snapshot() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_server_message);
+    *app.world_mut().resource_mut::<PendingWorldSnapshot>() = PendingWorldSnapshot::Awaiting;
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::UpdateTails {
+        events: vec![SerialisableTailEvent::GapStarted { player_id: 1 }],
+      })
+      .expect("Failed to write UpdateTails message");
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::WorldSnapshot { players: Vec::new() })
+      .expect("Failed to write WorldSnapshot message");
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::UpdateTails {
+        events: vec![SerialisableTailEvent::GapEnded { player_id: 1 }],
+      })
+      .expect("Failed to write UpdateTails message");
+    app.update();
+
+    match &*app.world().resource::<PendingWorldSnapshot>() {
+      PendingWorldSnapshot::Received {
+        buffered_tail_events, ..
+      } => assert_eq!(
+        buffered_tail_events,
+        &vec![SerialisableTailEvent::GapEnded { player_id: 1 }]
+      ),
+      _ => panic!("Expected world snapshot to be received"),
+    }
+    let tail_events = app.world().resource::<Messages<TailEventMessage>>();
+    assert!(tail_events.is_empty());
+  }
+
+  #[test]
+  fn reconnect_credentials_only_return_token_issued_for_current_room() {
+    let mut credentials = ReconnectCredentials::default();
+    let first_token = ReconnectToken::new_random();
+    let second_token = ReconnectToken::new_random();
+    let third_token = ReconnectToken::new_random();
+
+    credentials.current_room = Some("room-a".to_string());
+    assert_eq!(credentials.replace_token(first_token), None);
+    assert_eq!(credentials.replace_token(second_token), Some(first_token));
+
+    credentials.current_room = Some("room-b".to_string());
+    assert_eq!(credentials.replace_token(third_token), None);
+  }
+
+  #[test]
+  fn handle_inbound_server_message_reclaims_players_after_rejoining_previous_room() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 1);
+    app.add_systems(Update, handle_inbound_server_message);
+    let previous_client_id = ClientId::from_u64(6);
+    let client_id = ClientId::from_u64(7);
+    let previous_token = ReconnectToken::new_random();
+    {
+      let mut credentials = app.world_mut().resource_mut::<ReconnectCredentials>();
+      credentials.current_room = Some("room".to_string());
+      credentials.previous = Some(("room".to_string(), previous_token));
+    }
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::ClientInitialised {
+        seed: 123,
+        client_id,
+        current_state: "Registering".to_string(),
+        registered_players: vec![SerialisableRegisteredPlayer {
+          client_id: previous_client_id,
+          player_id: 2,
+          control_scheme_id: 0,
+          name: "Test".to_string(),
+          is_ready: false,
+        }],
+        winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
+      })
+      .expect("Failed to write ClientInitialised message");
+    app.update();
+
+    let outbound_messages = app.world().resource::<Messages<OutboundClientMessage>>();
+    let sent_reconnect_token = outbound_messages
+      .iter_current_update_messages()
+      .find_map(|message| match message {
+        OutboundClientMessage::Send { payload, .. } => match decode_from_bytes::<ClientMessage>(payload) {
+          Ok(ClientMessage::Reconnect(token)) => Some(token),
+          _ => None,
+        },
+        _ => None,
+      });
+    assert_eq!(sent_reconnect_token, Some(previous_token));
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::ReconnectAccepted {
+        registered_players: vec![SerialisableRegisteredPlayer {
+          client_id,
+          player_id: 2,
+          control_scheme_id: 0,
+          name: "Test".to_string(),
+          is_ready: false,
+        }],
+      })
+      .expect("Failed to write ReconnectAccepted message");
+    app.update();
+
+    let pending_bootstrap = app.world().resource::<PendingClientBootstrap>();
+    assert_eq!(pending_bootstrap.registered_players[0].client_id, client_id);
+  }
+
+  #[test]
+  fn handle_spectators_message_system_requests_to_spectate_and_tracks_spectators() {
+    let mut app = setup();
+    app.init_resource::<JoinAsSpectator>();
+    app.add_systems(
+      Update,
+      (
+        record_spectating_choice_system,
+        handle_inbound_server_message,
+        handle_spectators_message_system.after(handle_inbound_server_message),
+      ),
+    );
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .write_message(ConnectionInfoMessage {
+        is_spectating: true,
+        ..ConnectionInfoMessage::new("room-456".to_string())
+      })
+      .expect("Failed to write ConnectionInfoMessage");
+    app.update();
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::ClientInitialised {
+        seed: 123,
+        client_id,
+        current_state: "Playing".to_string(),
+        registered_players: Vec::new(),
+        winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
+      })
+      .expect("Failed to write ClientInitialised message");
+    app.update();
+
+    let outbound_messages = app.world().resource::<Messages<OutboundClientMessage>>();
+    let has_requested_to_spectate = outbound_messages
+      .iter_current_update_messages()
+      .any(|message| match message {
+        OutboundClientMessage::Send { payload, .. } => matches!(
+          decode_from_bytes::<ClientMessage>(payload),
+          Ok(ClientMessage::SetSpectating(true))
+        ),
+        _ => false,
+      });
+    assert!(has_requested_to_spectate);
+    assert!(!app.world().resource::<Spectators>().is_local_client_spectating);
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::SpectatorsChanged {
+        spectators: vec![ClientId::from_u64(3), client_id],
+      })
+      .expect("Failed to write SpectatorsChanged message");
+    app.update();
+
+    let spectators = app.world().resource::<Spectators>();
+    assert!(spectators.is_local_client_spectating);
+    assert_eq!(spectators.count(), 2);
+  }
+
+  #[test]
+  fn unacknowledged_inputs_resends_frames_until_acknowledged() {
+    let mut unacknowledged_inputs = UnacknowledgedInputs::default();
+    let sequences = |frames: Vec<SerialisableInputFrame>| frames.iter().map(|f| f.sequence).collect::<Vec<_>>();
+
+    assert_eq!(
+      sequences(unacknowledged_inputs.push(vec![SerialisableInput::Action(0)])),
+      vec![0]
+    );
+    assert_eq!(
+      sequences(unacknowledged_inputs.push(vec![SerialisableInput::Action(0)])),
+      vec![0, 1]
+    );
+    unacknowledged_inputs.acknowledge(0);
+    assert_eq!(sequences(unacknowledged_inputs.push(Vec::new())), vec![1, 2]);
+  }
+
+  fn sent_input_sequences(app: &App) -> Vec<Vec<u32>> {
+    app
+      .world()
+      .resource::<Messages<OutboundClientMessage>>()
+      .iter_current_update_messages()
+      .filter_map(|message| match message {
+        OutboundClientMessage::Send { payload, .. } => match decode_from_bytes::<ClientMessage>(payload) {
+          Ok(ClientMessage::Input(frames)) => Some(frames.iter().map(|frame| frame.sequence).collect()),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn send_local_input_messages_resends_unacknowledged_frames_until_acknowledged() {
+    let mut app = setup();
+    app.init_resource::<UnacknowledgedInputs>();
+    app.add_systems(Update, (handle_input_ack_system, send_local_input_messages).chain());
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_mutable(
+        PlayerId(0),
+        "Alice".to_string(),
+        crate::prelude::ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Player should register");
+    app
+      .world_mut()
+      .write_message(InputMessage::Action(PlayerId(0)))
+      .expect("Failed to write InputMessage");
+    app.update();
+    assert_eq!(sent_input_sequences(&app), vec![vec![0]]);
+
+    app.update();
+    assert_eq!(sent_input_sequences(&app), vec![vec![0]]);
+
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::InputAck { sequence: 0 })
+      .expect("Failed to write InputAck message");
+    app.update();
+    assert!(sent_input_sequences(&app).is_empty());
+  }
+
+  #[test]
+  fn unacknowledged_inputs_limits_frames_per_packet() {
+    let mut unacknowledged_inputs = UnacknowledgedInputs::default();
+    let mut frames = Vec::new();
+    for _ in 0..MAX_INPUT_FRAMES_PER_PACKET + 2 {
+      frames = unacknowledged_inputs.push(Vec::new());
+    }
+
+    assert_eq!(frames.len(), MAX_INPUT_FRAMES_PER_PACKET);
+    assert_eq!(frames[0].sequence, 2);
+  }
 }
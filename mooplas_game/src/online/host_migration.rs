@@ -0,0 +1,271 @@
+use crate::prelude::{ControlSchemeId, PlayerId};
+use bevy::app::{App, Plugin, Update};
+use bevy::log::info;
+use bevy::prelude::{IntoScheduleConfigs, Message, MessageReader, Res, ResMut, Resource, resource_exists};
+use mooplas_networking::prelude::{
+  ClientId, ClientNetworkingActive, InboundServerMessage, NetworkRole, ReconnectToken,
+};
+use std::collections::HashMap;
+
+/// A plugin that keeps track of everything a client needs to know to take over as host, or to follow the client that
+/// does, if the host leaves. The transport is responsible for actually migrating to the new host.
+pub struct HostMigrationPlugin;
+
+impl Plugin for HostMigrationPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<HostSuccession>()
+      .add_message::<HostMigrationMessage>()
+      .add_systems(
+        Update,
+        track_host_succession_system
+          .run_if(resource_exists::<ClientNetworkingActive>)
+          .run_if(|network_role: Res<NetworkRole>| network_role.is_client()),
+      );
+  }
+}
+
+/// A message written by the transport once the local client has taken over as host, after which the lobby it has
+/// carried over needs to be adopted.
+#[derive(Message, Clone, Copy, Debug)]
+pub(crate) struct HostMigrationMessage;
+
+/// What the local client does if the host leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HostMigrationPlan {
+  /// Take over as host. Contains the client ID the local client had, which the signalling server uses to verify that
+  /// the local client was part of the room.
+  BecomeHost { previous_client_id: ClientId },
+  /// Reconnect to the room and wait for the successor to take over as host.
+  FollowSuccessor,
+}
+
+/// A client-side resource that mirrors the host's view of who takes over if the host leaves, and the lobby that will be
+/// carried over to the new host.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct HostSuccession {
+  own_client_id: Option<ClientId>,
+  successor: Option<ClientId>,
+  /// The [`ReconnectToken`]s of all clients, only known if the local client is the successor.
+  reconnect_tokens: Vec<(ClientId, ReconnectToken)>,
+  /// The client that registered each player and the control scheme it used.
+  player_owners: HashMap<PlayerId, (ClientId, ControlSchemeId)>,
+}
+
+impl HostSuccession {
+  /// Returns what to do now that the host has left and forgets the successor, so that a migration is only attempted
+  /// once. Returns `None` if no successor has been designated.
+  pub fn take_plan(&mut self) -> Option<HostMigrationPlan> {
+    let successor = self.successor.take()?;
+    let own_client_id = self.own_client_id?;
+    if successor == own_client_id {
+      Some(HostMigrationPlan::BecomeHost {
+        previous_client_id: own_client_id,
+      })
+    } else {
+      Some(HostMigrationPlan::FollowSuccessor)
+    }
+  }
+
+  #[cfg(test)]
+  pub(crate) fn test(own_client_id: ClientId, successor: ClientId) -> Self {
+    Self {
+      own_client_id: Some(own_client_id),
+      successor: Some(successor),
+      ..Self::default()
+    }
+  }
+
+  /// Returns `true` if a successor has been designated, `false` otherwise.
+  pub fn has_successor(&self) -> bool {
+    self.successor.is_some()
+  }
+
+  pub fn own_client_id(&self) -> Option<ClientId> {
+    self.own_client_id
+  }
+
+  pub fn reconnect_tokens(&self) -> &[(ClientId, ReconnectToken)] {
+    &self.reconnect_tokens
+  }
+
+  pub fn player_owners(&self) -> impl Iterator<Item = (PlayerId, ClientId, ControlSchemeId)> + '_ {
+    self
+      .player_owners
+      .iter()
+      .map(|(player_id, (client_id, control_scheme_id))| (*player_id, *client_id, *control_scheme_id))
+  }
+}
+
+/// Keeps the [`HostSuccession`] in sync with the messages the host sends.
+fn track_host_succession_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut host_succession: ResMut<HostSuccession>,
+) {
+  for message in messages.read() {
+    match message {
+      InboundServerMessage::ClientInitialised {
+        client_id,
+        registered_players,
+        ..
+      } => {
+        *host_succession = HostSuccession {
+          own_client_id: Some(*client_id),
+          ..HostSuccession::default()
+        };
+        for player in registered_players {
+          host_succession.player_owners.insert(
+            PlayerId(player.player_id),
+            (player.client_id, ControlSchemeId(player.control_scheme_id)),
+          );
+        }
+      }
+      InboundServerMessage::PlayerRegistered {
+        client_id,
+        player_id,
+        control_scheme_id,
+        ..
+      } => {
+        host_succession
+          .player_owners
+          .insert(PlayerId(*player_id), (*client_id, ControlSchemeId(*control_scheme_id)));
+      }
+      InboundServerMessage::PlayerUnregistered { player_id, .. } => {
+        host_succession.player_owners.remove(&PlayerId(*player_id));
+      }
+      InboundServerMessage::ReconnectAccepted { registered_players } => {
+        for player in registered_players {
+          if let Some((owner, _)) = host_succession.player_owners.get_mut(&PlayerId(player.player_id)) {
+            *owner = player.client_id;
+          }
+        }
+      }
+      InboundServerMessage::HostSuccessionChanged {
+        successor,
+        reconnect_tokens,
+      } => {
+        if host_succession.successor != *successor && *successor == host_succession.own_client_id {
+          info!("This client will take over as host if the host leaves");
+        }
+        host_succession.successor = *successor;
+        host_succession.reconnect_tokens = reconnect_tokens.clone();
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bevy::prelude::*;
+  use mooplas_networking::prelude::{NetworkingMessagesPlugin, SerialisableRegisteredPlayer};
+
+  fn setup() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, NetworkingMessagesPlugin));
+    app.init_resource::<HostSuccession>();
+    app.add_systems(Update, track_host_succession_system);
+    app
+  }
+
+  fn write_messages(app: &mut App, messages: Vec<InboundServerMessage>) {
+    for message in messages {
+      app
+        .world_mut()
+        .write_message(message)
+        .expect("Failed to write InboundServerMessage");
+    }
+    app.update();
+  }
+
+  #[test]
+  fn take_plan_returns_become_host_only_for_successor_and_only_once() {
+    let mut app = setup();
+    let own_client_id = ClientId::from_u64(7);
+    write_messages(
+      &mut app,
+      vec![
+        InboundServerMessage::ClientInitialised {
+          seed: 1,
+          client_id: own_client_id,
+          current_state: "Registering".to_string(),
+          registered_players: Vec::new(),
+          winner_info: None,
+          reconnect_token: ReconnectToken::new_random(),
+        },
+        InboundServerMessage::HostSuccessionChanged {
+          successor: Some(own_client_id),
+          reconnect_tokens: Vec::new(),
+        },
+      ],
+    );
+
+    let mut host_succession = app.world_mut().resource_mut::<HostSuccession>();
+    assert_eq!(
+      host_succession.take_plan(),
+      Some(HostMigrationPlan::BecomeHost {
+        previous_client_id: own_client_id
+      })
+    );
+    assert_eq!(host_succession.take_plan(), None);
+  }
+
+  #[test]
+  fn track_host_succession_system_tracks_player_owners() {
+    let mut app = setup();
+    let own_client_id = ClientId::from_u64(7);
+    let other_client_id = ClientId::from_u64(8);
+    let reclaimed_client_id = ClientId::from_u64(9);
+    write_messages(
+      &mut app,
+      vec![
+        InboundServerMessage::ClientInitialised {
+          seed: 1,
+          client_id: own_client_id,
+          current_state: "Registering".to_string(),
+          registered_players: vec![SerialisableRegisteredPlayer {
+            client_id: other_client_id,
+            player_id: 0,
+            control_scheme_id: 2,
+            name: "Other".to_string(),
+            is_ready: false,
+          }],
+          winner_info: None,
+          reconnect_token: ReconnectToken::new_random(),
+        },
+        InboundServerMessage::PlayerRegistered {
+          client_id: own_client_id,
+          player_id: 1,
+          control_scheme_id: 0,
+          name: "Own".to_string(),
+        },
+        InboundServerMessage::ReconnectAccepted {
+          registered_players: vec![SerialisableRegisteredPlayer {
+            client_id: reclaimed_client_id,
+            player_id: 0,
+            control_scheme_id: 2,
+            name: "Other".to_string(),
+            is_ready: false,
+          }],
+        },
+        InboundServerMessage::HostSuccessionChanged {
+          successor: Some(other_client_id),
+          reconnect_tokens: Vec::new(),
+        },
+      ],
+    );
+
+    let mut host_succession = app.world_mut().resource_mut::<HostSuccession>();
+    let mut player_owners = host_succession.player_owners().collect::<Vec<_>>();
+    player_owners.sort_by_key(|(player_id, _, _)| player_id.0);
+    assert_eq!(
+      player_owners,
+      vec![
+        (PlayerId(0), reclaimed_client_id, ControlSchemeId(2)),
+        (PlayerId(1), own_client_id, ControlSchemeId(0)),
+      ]
+    );
+    assert_eq!(host_succession.take_plan(), Some(HostMigrationPlan::FollowSuccessor));
+  }
+}
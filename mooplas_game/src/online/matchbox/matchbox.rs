@@ -1,19 +1,32 @@
 use crate::app_state::AppState;
-use crate::prelude::{ConnectionInfoMessage, MenuName, ToggleMenuMessage, UiNotification};
-use bevy::app::{App, Plugin, Update};
-use bevy::log::{debug, error, info};
+use crate::online::client::ReconnectCredentials;
+use crate::online::host_migration::{HostMigrationMessage, HostMigrationPlan, HostSuccession};
+use crate::prelude::{
+  ClientBannedMessage, ConnectionInfoMessage, GameRules, LaunchConfig, MAX_SPECTATORS, MenuName, PlayerName,
+  PublicRoomInfo, PublicRoomsMessage, RefreshPublicRoomsMessage, RegisteredPlayers, RoomPasswordMessage,
+  RoomPasswordRequiredMessage, RoomVisibilityMessage, TestSignallingServerMessage, ToggleMenuMessage, UiNotification,
+};
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::log::{debug, error, info, warn};
 use bevy::prelude::{
-  Commands, IntoScheduleConfigs, MessageReader, MessageWriter, NextState, On, Res, ResMut, State, in_state,
+  Commands, DetectChanges, IntoScheduleConfigs, Local, MessageReader, MessageWriter, NextState, On, Real, Res, ResMut,
+  Resource, State, Time, Timer, TimerMode, in_state, resource_exists,
 };
 use mooplas_networking::prelude::{
-  ClientNetworkingActive, NetworkErrorEvent, NetworkRole, ServerNetworkingActive, SignallingServerUrl,
+  ClientId, ClientNetworkingActive, InboundServerMessage, Lobby, NetworkErrorEvent, NetworkRole,
+  ServerNetworkingActive, SignallingServerUrl,
 };
 #[cfg(not(target_arch = "wasm32"))]
 use mooplas_networking_matchbox::prelude::start_signaling_server;
 use mooplas_networking_matchbox::prelude::{
-  MatchboxClientPlugin, ServerMatchboxPlugin, generate_room_id, remove_all_matchbox_resources, resolve_room_url,
-  start_socket,
+  IceServerConfig, IceServerOptions, MatchboxClientPlugin, MatchboxServerTransport, PublicRoom, RoomAccess,
+  RoomListing, ServerMatchboxPlugin, client_room_url, generate_room_id, generate_secret, peer_id_from_client_id,
+  remove_all_matchbox_resources, request_ban, request_capacity, request_health_check, request_ice_servers,
+  request_peer_removal, request_public_rooms, request_room_access, request_room_listing, request_successor,
+  resolve_room_url, start_client_socket, start_server_socket,
 };
+use std::sync::{Arc, Mutex};
+use url::Url;
 
 /// Plugin that adds online multiplayer capabilities for WASM targets using websocket/`bevy_matchbox` to the game.
 pub struct MatchboxPlugin;
@@ -21,44 +34,242 @@ pub struct MatchboxPlugin;
 impl Plugin for MatchboxPlugin {
   fn build(&self, app: &mut App) {
     info!("Online multiplayer using [bevy_matchbox] is enabled");
+    let ice_server_options = app
+      .world()
+      .get_resource::<LaunchConfig>()
+      .map(|launch_config| launch_config.networking.ice_server_options())
+      .unwrap_or_default();
     app
       .add_plugins((ServerMatchboxPlugin, MatchboxClientPlugin))
+      .init_resource::<SignallingCredentials>()
+      .insert_resource(IceServers::from_runtime_config(&ice_server_options))
+      .add_systems(Startup, request_ice_servers_system)
+      .add_systems(
+        Update,
+        (
+          handle_ice_servers_toggle_menu_message,
+          handle_ice_servers_response_system.run_if(resource_exists::<PendingIceServers>),
+        )
+          .chain(),
+      )
       .add_systems(Update, handle_toggle_menu_message.run_if(in_state(AppState::Preparing)))
       .add_systems(
         Update,
-        handle_connection_info_message
+        (
+          handle_connection_info_message,
+          handle_room_access_response_system.run_if(resource_exists::<PendingRoomAccess>),
+        )
+          .chain()
           .run_if(in_state(AppState::Preparing))
           .run_if(|network_role: Res<NetworkRole>| network_role.is_client()),
       )
+      .add_systems(
+        Update,
+        (continue_host_migration_system, complete_host_migration_system)
+          .chain()
+          .run_if(resource_exists::<PendingHostMigration>),
+      )
+      .add_systems(
+        Update,
+        (
+          handle_refresh_public_rooms_message,
+          handle_public_rooms_response_system.run_if(resource_exists::<PendingPublicRooms>),
+          handle_test_signalling_server_message,
+          handle_health_check_response_system.run_if(resource_exists::<PendingHealthCheck>),
+        )
+          .chain()
+          .run_if(in_state(AppState::Preparing)),
+      )
+      .add_systems(
+        Update,
+        (
+          request_ban_system,
+          request_peer_removal_system,
+          request_successor_system,
+          handle_room_password_message,
+          handle_room_visibility_message,
+          update_room_listing_system
+            .run_if(resource_exists::<ListedRoom>)
+            .run_if(has_joined_hosted_room),
+          update_room_capacity_system
+            .run_if(resource_exists::<DeclaredRoomCapacity>)
+            .run_if(has_joined_hosted_room),
+        )
+          .chain()
+          .run_if(resource_exists::<HostedRoomUrl>),
+      )
       .add_observer(receive_network_error_event);
   }
 }
 
+const HOST_LEFT_NOTIFICATION: &str = "The host has left the game";
+const HOST_MIGRATION_NOTIFICATION: &str = "The host has left the game - migrating to a new host...";
+/// How long to wait before reconnecting, giving the signalling server time to notice that the host has left.
+const HOST_MIGRATION_RECONNECT_DELAY_SECONDS: f32 = 1.;
+const HOST_MIGRATION_TIMEOUT_SECONDS: f32 = 15.;
+const PASSWORD_REQUIRED_NOTIFICATION: &str = "This room is protected by a password - please enter it to join";
+const PASSWORD_INVALID_NOTIFICATION: &str = "The password is not valid";
+const ROOM_FULL_NOTIFICATION: &str = "Room is full";
+const ROOM_ACCESS_LOCK_ERROR: &str = "Room access response mutex is poisoned";
+const PUBLIC_ROOMS_LOCK_ERROR: &str = "Public rooms response mutex is poisoned";
+const ICE_SERVERS_LOCK_ERROR: &str = "ICE servers response mutex is poisoned";
+const HEALTH_CHECK_LOCK_ERROR: &str = "Health check response mutex is poisoned";
+
+/// A resource with the secrets this app presents to the signalling server: the identity with which it connects as a
+/// client, which lets the signalling server refuse it if it has been banned, and the key with which it authorises its
+/// requests as a host. Both are kept for the lifetime of the app, so that a client keeps its identity when it
+/// reconnects.
+#[derive(Resource)]
+struct SignallingCredentials {
+  identity: String,
+  host_key: String,
+  /// The password of the room this app has most recently joined as a client, if any. Needed to reconnect to the room
+  /// during a host migration.
+  room_password: Option<String>,
+}
+
+impl Default for SignallingCredentials {
+  fn default() -> Self {
+    Self {
+      identity: generate_secret(),
+      host_key: generate_secret(),
+      room_password: None,
+    }
+  }
+}
+
+/// A resource with the ICE servers that new sockets use. Unless ICE servers have been configured locally, they are
+/// retrieved from the signalling server, which may include short-lived TURN credentials, and fall back to the defaults
+/// if it doesn't provide any.
+#[derive(Resource, Default)]
+struct IceServers {
+  config: IceServerConfig,
+  is_configured_locally: bool,
+}
+
+impl IceServers {
+  fn from_runtime_config(options: &IceServerOptions) -> Self {
+    match IceServerConfig::from_runtime_config(options) {
+      Ok(Some(config)) => {
+        info!("Using locally configured ICE servers {:?}", config.urls);
+        Self {
+          config,
+          is_configured_locally: true,
+        }
+      }
+      Ok(None) => Self::default(),
+      Err(error) => {
+        error!("Ignoring invalid ICE server configuration: {}", error);
+        Self::default()
+      }
+    }
+  }
+}
+
+/// A resource that exists while the ICE servers are being retrieved from the signalling server. The response is written
+/// from the HTTP request's callback.
+#[derive(Resource)]
+struct PendingIceServers(Arc<Mutex<Option<Result<IceServerConfig, String>>>>);
+
+/// A resource that exists while the client is waiting for the signalling server to tell it whether it may join a room
+/// with the password it has entered, if any. The response is written from the HTTP request's callback.
+#[derive(Resource)]
+struct PendingRoomAccess {
+  room_url: String,
+  password: Option<String>,
+  response: Arc<Mutex<Option<RoomAccess>>>,
+}
+
+/// A resource that holds the URL of the room while this app is its host. Used to ban clients from the room at the
+/// signalling server.
+#[derive(Resource)]
+struct HostedRoomUrl(String);
+
+/// A resource that holds the capacity that was declared to the signalling server for the hosted room last, so that it
+/// is only declared again when it changes.
+#[derive(Resource)]
+struct DeclaredRoomCapacity(u16);
+
+/// A resource that exists while the hosted room is listed in the signalling server's public room list. Holds the
+/// listing that was sent last, so that the listing is only sent again when it changes.
+#[derive(Resource, Default)]
+struct ListedRoom(Option<RoomListing>);
+
+/// A resource that exists while the public room list is being retrieved from the signalling server. The response is
+/// written from the HTTP request's callback.
+#[derive(Resource)]
+struct PendingPublicRooms(Arc<Mutex<Option<Result<Vec<PublicRoom>, String>>>>);
+
+/// A resource that exists while the signalling server with the given URL is being tested for connectivity. The response
+/// is written from the HTTP request's callback.
+#[derive(Resource)]
+struct PendingHealthCheck {
+  url: String,
+  response: Arc<Mutex<Option<Result<(), String>>>>,
+}
+
+/// A resource that exists while the client is migrating to a new host after the previous host has left.
+#[derive(Resource)]
+struct PendingHostMigration {
+  plan: HostMigrationPlan,
+  room_url: String,
+  reconnect_delay: Timer,
+  timeout: Timer,
+  has_reconnected: bool,
+}
+
+impl PendingHostMigration {
+  fn new(plan: HostMigrationPlan, room_url: String) -> Self {
+    Self {
+      plan,
+      room_url,
+      reconnect_delay: Timer::from_seconds(HOST_MIGRATION_RECONNECT_DELAY_SECONDS, TimerMode::Once),
+      timeout: Timer::from_seconds(HOST_MIGRATION_TIMEOUT_SECONDS, TimerMode::Once),
+      has_reconnected: false,
+    }
+  }
+}
+
 fn handle_toggle_menu_message(
   mut commands: Commands,
   mut messages: MessageReader<ToggleMenuMessage>,
   mut network_role: ResMut<NetworkRole>,
   signalling_server_url: Res<SignallingServerUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  game_rules: Res<GameRules>,
+  registered_players: Res<RegisteredPlayers>,
+  ice_servers: Res<IceServers>,
   mut connection_info_message: MessageWriter<ConnectionInfoMessage>,
   mut ui_message: MessageWriter<UiNotification>,
 ) {
   for message in messages.read() {
     match message.active {
-      MenuName::MainMenu | MenuName::PlayOnlineMenu | MenuName::EnterNameMenu => *network_role = NetworkRole::None,
+      MenuName::MainMenu | MenuName::PlayOnlineMenu | MenuName::EnterNameMenu | MenuName::BrowseGamesMenu => {
+        *network_role = NetworkRole::None
+      }
       MenuName::HostGameMenu => *network_role = NetworkRole::Server,
       MenuName::JoinGameMenu => *network_role = NetworkRole::Client,
     }
     match *network_role {
-      NetworkRole::None => remove_all_matchbox_resources(&mut commands),
+      NetworkRole::None => {
+        remove_all_matchbox_resources(&mut commands);
+        commands.remove_resource::<HostedRoomUrl>();
+        commands.remove_resource::<DeclaredRoomCapacity>();
+        commands.remove_resource::<PendingRoomAccess>();
+        commands.remove_resource::<ListedRoom>();
+      }
       NetworkRole::Server => {
         #[cfg(not(target_arch = "wasm32"))]
         start_signaling_server(&mut commands);
-        let room_id = generate_room_id();
-        let room_url = host_room_url(signalling_server_url.as_str(), &room_id);
-        let connection_info = ConnectionInfoMessage::new(room_id);
-        match start_socket(&mut commands, &room_url) {
-          Ok(()) => {
-            debug!("Server started with room URL [{}]", room_url);
+        match host_new_room(
+          &mut commands,
+          &signalling_server_url,
+          &signalling_credentials,
+          &ice_servers,
+          room_capacity(game_rules.max_players, &registered_players),
+          None,
+        ) {
+          Ok(connection_info) => {
             connection_info_message.write(connection_info);
             commands.insert_resource(ServerNetworkingActive);
           }
@@ -75,6 +286,26 @@ fn handle_toggle_menu_message(
   }
 }
 
+/// Connects to a new room as its host, with the given capacity and protected by the given password if any. Returns the
+/// connection info with which others can join the room.
+fn host_new_room(
+  commands: &mut Commands,
+  signalling_server_url: &SignallingServerUrl,
+  signalling_credentials: &SignallingCredentials,
+  ice_servers: &IceServers,
+  capacity: u16,
+  password: Option<&str>,
+) -> Result<ConnectionInfoMessage, String> {
+  let room_id = generate_room_id();
+  let room_url = format!("{}/{}", signalling_server_url.as_str().trim_end_matches('/'), room_id);
+  let host_room_url = host_room_url(&room_url, &signalling_credentials.host_key, capacity, password)?;
+  start_server_socket(commands, &host_room_url, &ice_servers.config)?;
+  debug!("Server started with room URL [{}]", room_url);
+  commands.insert_resource(HostedRoomUrl(room_url));
+  commands.insert_resource(DeclaredRoomCapacity(capacity));
+  Ok(ConnectionInfoMessage::new(room_id))
+}
+
 fn handle_connection_info_message(
   mut messages: MessageReader<ConnectionInfoMessage>,
   mut commands: Commands,
@@ -91,31 +322,534 @@ fn handle_connection_info_message(
       }
     };
     debug!(
-      "Received [ConnectionInfoMessage] with connection info [{}], resolved room URL [{}], attempting to start socket now...",
+      "Received [ConnectionInfoMessage] with connection info [{}], resolved room URL [{}], checking access now...",
       message.connection_string, room_url,
     );
 
-    match start_socket(&mut commands, &room_url) {
-      Ok(()) => {
-        info!("Created client with connection to [{}]", room_url);
-        commands.insert_resource(ClientNetworkingActive);
-      }
-      Err(e) => {
-        error!("An error occurred: {}", e);
-        ui_message.write(UiNotification::error(e));
-      }
+    let response = Arc::new(Mutex::new(None));
+    let callback_response = response.clone();
+    request_room_access(&room_url, message.password.as_deref(), move |room_access| {
+      *callback_response.lock().expect(ROOM_ACCESS_LOCK_ERROR) = Some(room_access);
+    });
+    commands.insert_resource(PendingRoomAccess {
+      room_url,
+      password: message.password.clone(),
+      response,
+    });
+  }
+}
+
+/// Connects to the room once the signalling server has granted access to it, or asks the user for the room's password
+/// if it is missing or wrong.
+fn handle_room_access_response_system(
+  mut commands: Commands,
+  pending_room_access: Res<PendingRoomAccess>,
+  mut signalling_credentials: ResMut<SignallingCredentials>,
+  ice_servers: Res<IceServers>,
+  mut password_required_message: MessageWriter<RoomPasswordRequiredMessage>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  let Some(room_access) = pending_room_access
+    .response
+    .lock()
+    .expect(ROOM_ACCESS_LOCK_ERROR)
+    .take()
+  else {
+    return;
+  };
+  commands.remove_resource::<PendingRoomAccess>();
+  let room_url = match room_access {
+    RoomAccess::PasswordRequired => {
+      info!("Room [{}] is protected by a password", pending_room_access.room_url);
+      password_required_message.write(RoomPasswordRequiredMessage);
+      ui_message.write(UiNotification::error(PASSWORD_REQUIRED_NOTIFICATION.to_string()));
+      return;
     }
+    RoomAccess::PasswordInvalid => {
+      info!("Password for room [{}] is not valid", pending_room_access.room_url);
+      ui_message.write(UiNotification::error(PASSWORD_INVALID_NOTIFICATION.to_string()));
+      return;
+    }
+    RoomAccess::RoomFull => {
+      info!("Room [{}] is full", pending_room_access.room_url);
+      ui_message.write(UiNotification::error(ROOM_FULL_NOTIFICATION.to_string()));
+      return;
+    }
+    RoomAccess::Granted => client_room_url(
+      &pending_room_access.room_url,
+      &signalling_credentials.identity,
+      pending_room_access.password.as_deref(),
+    ),
+  };
+
+  match room_url.and_then(|room_url| start_client_socket(&mut commands, &room_url, &ice_servers.config)) {
+    Ok(()) => {
+      info!("Created client with connection to [{}]", pending_room_access.room_url);
+      commands.insert_resource(ClientNetworkingActive);
+      signalling_credentials.room_password = pending_room_access.password.clone();
+    }
+    Err(e) => {
+      error!("An error occurred: {}", e);
+      ui_message.write(UiNotification::error(e));
+    }
+  }
+}
+
+/// Returns the number of clients a room holds. Every player client needs at least one player, so a room can't hold
+/// more player clients than there are player slots left once the host's local players have registered, plus up to
+/// [`MAX_SPECTATORS`] spectators who don't take a player slot.
+fn room_capacity(max_players: u8, registered_players: &RegisteredPlayers) -> u16 {
+  let local_player_count = registered_players
+    .players
+    .iter()
+    .filter(|player| player.is_local())
+    .count();
+  let remote_player_slots = usize::from(max_players).saturating_sub(local_player_count);
+  u16::try_from(remote_player_slots).unwrap_or(u16::MAX) + u16::from(MAX_SPECTATORS)
+}
+
+/// Returns the URL with which the host connects to its room, including the key with which it authorises bans, the
+/// capacity of the room and the password that protects it, if any.
+fn host_room_url(room_url: &str, host_key: &str, capacity: u16, password: Option<&str>) -> Result<String, String> {
+  let mut url = Url::parse(room_url).map_err(|error| format!("Invalid room URL [{room_url}]: {error}"))?;
+  url
+    .query_pairs_mut()
+    .append_pair("role", "host")
+    .append_pair("key", host_key)
+    .append_pair("capacity", &capacity.to_string());
+  if let Some(password) = password {
+    url.query_pairs_mut().append_pair("password", password);
   }
+  Ok(url.to_string())
 }
 
-fn host_room_url(signalling_server_base_url: &str, room_id: &str) -> String {
+/// Returns the URL with which a former client takes over as host of a room whose host has left.
+fn successor_room_url(room_url: &str, previous_client_id: ClientId, host_key: &str, capacity: u16) -> String {
   format!(
-    "{}/{}?role=host",
-    signalling_server_base_url.trim_end_matches('/'),
-    room_id
+    "{}?role=host&successor={}&key={}&capacity={}",
+    room_url,
+    peer_id_from_client_id(previous_client_id),
+    host_key,
+    capacity
   )
 }
 
+/// Asks the signalling server to refuse every client that the host has banned, so that it can't rejoin the room.
+fn request_ban_system(
+  mut messages: MessageReader<ClientBannedMessage>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+) {
+  for message in messages.read() {
+    request_ban(&hosted_room_url.0, &signalling_credentials.host_key, message.client_id);
+  }
+}
+
+/// Asks the signalling server to remove every client that the host has disconnected, e.g. for sending malformed packets
+/// or because it was kicked, since Matchbox can't close the WebRTC connection to a single peer by itself.
+fn request_peer_removal_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+) {
+  for message in messages.read() {
+    if let InboundServerMessage::ClientDisconnected {
+      client_id,
+      is_forced: true,
+    } = message
+    {
+      request_peer_removal(&hosted_room_url.0, &signalling_credentials.host_key, *client_id);
+    }
+  }
+}
+
+/// Tells the signalling server which client takes over as host if the host leaves, whenever a client connects or
+/// disconnects and the successor announced via [`InboundServerMessage::HostSuccessionChanged`] changes as a result.
+fn request_successor_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  lobby: Res<Lobby>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  mut designated_successor: Local<Option<ClientId>>,
+) {
+  let has_connections_changed = messages.read().any(|message| {
+    matches!(
+      message,
+      InboundServerMessage::ClientConnected { .. } | InboundServerMessage::ClientDisconnected { .. }
+    )
+  });
+  if !has_connections_changed {
+    return;
+  }
+  let Some(successor) = lobby.successor() else {
+    return;
+  };
+  if *designated_successor == Some(successor) {
+    return;
+  }
+  *designated_successor = Some(successor);
+  request_successor(&hosted_room_url.0, &signalling_credentials.host_key, successor);
+}
+
+/// Protects the hosted room with the password that the host has entered, or removes the protection if there is none.
+/// The signalling server only accepts a password with the host's connection request, so the host moves to a new room,
+/// which is possible because no client can have joined while the host game menu is open.
+fn handle_room_password_message(
+  mut commands: Commands,
+  mut messages: MessageReader<RoomPasswordMessage>,
+  signalling_server_url: Res<SignallingServerUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  ice_servers: Res<IceServers>,
+  game_rules: Res<GameRules>,
+  registered_players: Res<RegisteredPlayers>,
+  listed_room: Option<ResMut<ListedRoom>>,
+  mut connection_info_message: MessageWriter<ConnectionInfoMessage>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  let Some(message) = messages.read().last() else {
+    return;
+  };
+  match host_new_room(
+    &mut commands,
+    &signalling_server_url,
+    &signalling_credentials,
+    &ice_servers,
+    room_capacity(game_rules.max_players, &registered_players),
+    message.password.as_deref(),
+  ) {
+    Ok(connection_info) => {
+      connection_info_message.write(connection_info);
+      if let Some(mut listed_room) = listed_room {
+        listed_room.0 = None;
+      }
+      let text = match message.password {
+        Some(_) => "Password set - share the new room ID, players now need the password to join",
+        None => "Password removed - share the new room ID, anyone with it can join",
+      };
+      ui_message.write(UiNotification::info(text.to_string()));
+    }
+    Err(e) => {
+      error!("Failed to move to a new room: {}", e);
+      ui_message.write(UiNotification::error(e));
+    }
+  }
+}
+
+/// Lists the hosted room in the signalling server's public room list, or removes it from the list again.
+fn handle_room_visibility_message(
+  mut commands: Commands,
+  mut messages: MessageReader<RoomVisibilityMessage>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  for message in messages.read() {
+    if message.is_public {
+      commands.init_resource::<ListedRoom>();
+      ui_message.write(UiNotification::info(
+        "Room is public - anyone can find it via [Browse Games]".to_string(),
+      ));
+    } else {
+      commands.remove_resource::<ListedRoom>();
+      request_room_listing(&hosted_room_url.0, &signalling_credentials.host_key, None);
+      ui_message.write(UiNotification::info(
+        "Room is private - only players with the room ID can join".to_string(),
+      ));
+    }
+  }
+}
+
+/// Returns whether the signalling server has accepted the host into the hosted room, before which the room can't be
+/// listed.
+fn has_joined_hosted_room(server_transport: Option<Res<MatchboxServerTransport>>) -> bool {
+  server_transport.is_some_and(|server_transport| server_transport.has_joined_room())
+}
+
+/// Keeps the hosted room's entry in the public room list up to date, e.g. when players register or leave.
+fn update_room_listing_system(
+  mut listed_room: ResMut<ListedRoom>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  registered_players: Res<RegisteredPlayers>,
+  player_name: Res<PlayerName>,
+  game_rules: Res<GameRules>,
+) {
+  let listing = RoomListing {
+    name: format!("{}'s game", player_name.get()),
+    player_count: u8::try_from(registered_players.count()).unwrap_or(u8::MAX),
+    max_players: game_rules.max_players,
+  };
+  if listed_room.0.as_ref() == Some(&listing) {
+    return;
+  }
+  request_room_listing(&hosted_room_url.0, &signalling_credentials.host_key, Some(&listing));
+  listed_room.0 = Some(listing);
+}
+
+/// Keeps the capacity of the hosted room at the signalling server up to date as the host's local players register or
+/// leave, so that remote players are turned away once no player slot is left for them.
+fn update_room_capacity_system(
+  mut declared_room_capacity: ResMut<DeclaredRoomCapacity>,
+  hosted_room_url: Res<HostedRoomUrl>,
+  signalling_credentials: Res<SignallingCredentials>,
+  registered_players: Res<RegisteredPlayers>,
+  game_rules: Res<GameRules>,
+) {
+  let capacity = room_capacity(game_rules.max_players, &registered_players);
+  if declared_room_capacity.0 == capacity {
+    return;
+  }
+  request_capacity(&hosted_room_url.0, &signalling_credentials.host_key, capacity);
+  declared_room_capacity.0 = capacity;
+}
+
+/// Asks the signalling server for its public room list.
+fn handle_refresh_public_rooms_message(
+  mut commands: Commands,
+  mut messages: MessageReader<RefreshPublicRoomsMessage>,
+  signalling_server_url: Res<SignallingServerUrl>,
+) {
+  if messages.read().count() == 0 {
+    return;
+  }
+  let response = Arc::new(Mutex::new(None));
+  let callback_response = response.clone();
+  request_public_rooms(signalling_server_url.as_str(), move |public_rooms| {
+    *callback_response.lock().expect(PUBLIC_ROOMS_LOCK_ERROR) = Some(public_rooms);
+  });
+  commands.insert_resource(PendingPublicRooms(response));
+}
+
+/// Forwards the public room list to the UI once the signalling server has responded.
+fn handle_public_rooms_response_system(
+  mut commands: Commands,
+  pending_public_rooms: Res<PendingPublicRooms>,
+  mut public_rooms_message: MessageWriter<PublicRoomsMessage>,
+) {
+  let Some(public_rooms) = pending_public_rooms.0.lock().expect(PUBLIC_ROOMS_LOCK_ERROR).take() else {
+    return;
+  };
+  commands.remove_resource::<PendingPublicRooms>();
+  if let Err(error) = &public_rooms {
+    warn!("Unable to retrieve the public room list: {}", error);
+  }
+  let rooms = public_rooms.map(|rooms| {
+    rooms
+      .into_iter()
+      .map(|room| PublicRoomInfo {
+        room_id: room.room_id,
+        name: room.name,
+        player_count: room.player_count,
+        max_players: room.max_players,
+        password_required: room.password_required,
+      })
+      .collect()
+  });
+  public_rooms_message.write(PublicRoomsMessage { rooms });
+}
+
+/// Checks whether the signalling server the user has entered can be reached.
+fn handle_test_signalling_server_message(
+  mut commands: Commands,
+  mut messages: MessageReader<TestSignallingServerMessage>,
+) {
+  let Some(message) = messages.read().last() else {
+    return;
+  };
+  let response = Arc::new(Mutex::new(None));
+  let callback_response = response.clone();
+  request_health_check(&message.url, move |health_check| {
+    *callback_response.lock().expect(HEALTH_CHECK_LOCK_ERROR) = Some(health_check);
+  });
+  commands.insert_resource(PendingHealthCheck {
+    url: message.url.clone(),
+    response,
+  });
+}
+
+/// Tells the user whether the signalling server could be reached once it has responded.
+fn handle_health_check_response_system(
+  mut commands: Commands,
+  pending_health_check: Res<PendingHealthCheck>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  let Some(health_check) = pending_health_check
+    .response
+    .lock()
+    .expect(HEALTH_CHECK_LOCK_ERROR)
+    .take()
+  else {
+    return;
+  };
+  commands.remove_resource::<PendingHealthCheck>();
+  match health_check {
+    Ok(()) => {
+      info!("Signalling server [{}] is reachable", pending_health_check.url);
+      ui_message.write(UiNotification::info(format!(
+        "Signalling server [{}] is reachable",
+        pending_health_check.url
+      )));
+    }
+    Err(error) => {
+      warn!(
+        "Signalling server [{}] is not reachable: {}",
+        pending_health_check.url, error
+      );
+      ui_message.write(UiNotification::error(format!(
+        "Unable to reach signalling server [{}]: {}",
+        pending_health_check.url, error
+      )));
+    }
+  }
+}
+
+/// Asks the signalling server for the ICE servers to use, unless they have been configured locally.
+fn request_ice_servers_system(
+  mut commands: Commands,
+  ice_servers: Res<IceServers>,
+  signalling_server_url: Res<SignallingServerUrl>,
+) {
+  if !ice_servers.is_configured_locally {
+    start_ice_servers_request(&mut commands, signalling_server_url.as_str());
+  }
+}
+
+/// Asks the signalling server for the ICE servers to use again whenever the online menu is opened, so that any TURN
+/// credentials are still valid by the time a socket is opened, or whenever another signalling server has been chosen.
+fn handle_ice_servers_toggle_menu_message(
+  mut commands: Commands,
+  mut messages: MessageReader<ToggleMenuMessage>,
+  ice_servers: Res<IceServers>,
+  signalling_server_url: Res<SignallingServerUrl>,
+) {
+  let has_opened_online_menu = messages
+    .read()
+    .any(|message| message.active == MenuName::PlayOnlineMenu);
+  let has_changed_signalling_server = signalling_server_url.is_changed() && !signalling_server_url.is_added();
+  if (has_opened_online_menu || has_changed_signalling_server) && !ice_servers.is_configured_locally {
+    start_ice_servers_request(&mut commands, signalling_server_url.as_str());
+  }
+}
+
+fn start_ice_servers_request(commands: &mut Commands, signalling_server_url: &str) {
+  let response = Arc::new(Mutex::new(None));
+  let callback_response = response.clone();
+  request_ice_servers(signalling_server_url, move |ice_servers| {
+    *callback_response.lock().expect(ICE_SERVERS_LOCK_ERROR) = Some(ice_servers);
+  });
+  commands.insert_resource(PendingIceServers(response));
+}
+
+/// Uses the ICE servers provided by the signalling server once it has responded, or keeps the current ones if it
+/// couldn't provide any e.g. because it is the embedded native development signalling server.
+fn handle_ice_servers_response_system(
+  mut commands: Commands,
+  pending_ice_servers: Res<PendingIceServers>,
+  mut ice_servers: ResMut<IceServers>,
+) {
+  let Some(response) = pending_ice_servers.0.lock().expect(ICE_SERVERS_LOCK_ERROR).take() else {
+    return;
+  };
+  commands.remove_resource::<PendingIceServers>();
+  match response {
+    Ok(config) => {
+      debug!("Using ICE servers {:?} provided by the signalling server", config.urls);
+      ice_servers.config = config;
+    }
+    Err(error) => info!(
+      "Signalling server didn't provide ICE servers, using {:?}: {}",
+      ice_servers.config.urls, error
+    ),
+  }
+}
+
+/// Reconnects to the room once the signalling server has had time to notice that the host has left, either as the new
+/// host or as a client of the new host, depending on the [`HostMigrationPlan`]. Gives up if a client hasn't been
+/// initialised by the new host in time.
+fn continue_host_migration_system(
+  mut commands: Commands,
+  time: Res<Time<Real>>,
+  mut pending_host_migration: ResMut<PendingHostMigration>,
+  mut network_role: ResMut<NetworkRole>,
+  signalling_credentials: Res<SignallingCredentials>,
+  game_rules: Res<GameRules>,
+  registered_players: Res<RegisteredPlayers>,
+  ice_servers: Res<IceServers>,
+  mut host_migration_message: MessageWriter<HostMigrationMessage>,
+) {
+  pending_host_migration.timeout.tick(time.delta());
+  if pending_host_migration.timeout.is_finished() {
+    warn!("No new host has taken over in time, giving up on host migration");
+    commands.remove_resource::<PendingHostMigration>();
+    commands.trigger(NetworkErrorEvent::Disconnect(HOST_LEFT_NOTIFICATION.to_string()));
+    return;
+  }
+  if pending_host_migration.has_reconnected {
+    return;
+  }
+  pending_host_migration.reconnect_delay.tick(time.delta());
+  if !pending_host_migration.reconnect_delay.is_finished() {
+    return;
+  }
+
+  pending_host_migration.has_reconnected = true;
+  let capacity = room_capacity(game_rules.max_players, &registered_players);
+  let room_url = match pending_host_migration.plan {
+    HostMigrationPlan::BecomeHost { previous_client_id } => Ok(successor_room_url(
+      &pending_host_migration.room_url,
+      previous_client_id,
+      &signalling_credentials.host_key,
+      capacity,
+    )),
+    HostMigrationPlan::FollowSuccessor => client_room_url(
+      &pending_host_migration.room_url,
+      &signalling_credentials.identity,
+      signalling_credentials.room_password.as_deref(),
+    ),
+  };
+  let start_socket = match pending_host_migration.plan {
+    HostMigrationPlan::BecomeHost { .. } => start_server_socket,
+    HostMigrationPlan::FollowSuccessor => start_client_socket,
+  };
+  let room_url = match room_url
+    .and_then(|room_url| start_socket(&mut commands, &room_url, &ice_servers.config).map(|()| room_url))
+  {
+    Ok(room_url) => room_url,
+    Err(error) => {
+      error!("Failed to reconnect for host migration: {}", error);
+      commands.remove_resource::<PendingHostMigration>();
+      commands.trigger(NetworkErrorEvent::Disconnect(HOST_LEFT_NOTIFICATION.to_string()));
+      return;
+    }
+  };
+  match pending_host_migration.plan {
+    HostMigrationPlan::BecomeHost { .. } => {
+      info!("Taking over as host with room URL [{}]", room_url);
+      commands.insert_resource(ServerNetworkingActive);
+      commands.insert_resource(HostedRoomUrl(pending_host_migration.room_url.clone()));
+      commands.insert_resource(DeclaredRoomCapacity(capacity));
+      commands.remove_resource::<PendingHostMigration>();
+      *network_role = NetworkRole::Server;
+      host_migration_message.write(HostMigrationMessage);
+    }
+    HostMigrationPlan::FollowSuccessor => {
+      info!("Reconnecting to the new host with room URL [{}]", room_url);
+      commands.insert_resource(ClientNetworkingActive);
+    }
+  }
+}
+
+/// Completes the host migration of a client once it has been initialised by the new host.
+fn complete_host_migration_system(mut commands: Commands, mut messages: MessageReader<InboundServerMessage>) {
+  if messages
+    .read()
+    .any(|message| matches!(message, InboundServerMessage::ClientInitialised { .. }))
+  {
+    info!("Host migration complete");
+    commands.remove_resource::<PendingHostMigration>();
+  }
+}
+
 #[allow(clippy::never_loop)]
 fn receive_network_error_event(
   error_event: On<NetworkErrorEvent>,
@@ -123,10 +857,43 @@ fn receive_network_error_event(
   current_app_state: Res<State<AppState>>,
   mut next_app_state: ResMut<NextState<AppState>>,
   mut network_role: ResMut<NetworkRole>,
+  mut host_succession: ResMut<HostSuccession>,
+  reconnect_credentials: Res<ReconnectCredentials>,
+  signalling_server_url: Res<SignallingServerUrl>,
   mut ui_message: MessageWriter<UiNotification>,
 ) {
   let error = error_event.event();
+  if matches!(error, &NetworkErrorEvent::MalformedPacket(_)) {
+    // Already logged by the networking code, which also drops the peer once it has sent too many malformed packets
+    return;
+  }
   if matches!(error, &NetworkErrorEvent::Disconnect(_)) {
+    remove_all_matchbox_resources(&mut commands);
+    commands.remove_resource::<PendingHostMigration>();
+
+    // If a successor has been designated, we migrate to it instead of ending the game
+    if network_role.is_client()
+      && !matches!(**current_app_state, AppState::Preparing)
+      && let Some(plan) = host_succession.take_plan()
+    {
+      match reconnect_credentials
+        .current_room()
+        .map(|room| resolve_room_url(signalling_server_url.as_str(), room))
+      {
+        Some(Ok(room_url)) => {
+          info!(
+            "Connection lost: [{}] - migrating to new host as [{:?}]...",
+            error, plan
+          );
+          commands.insert_resource(PendingHostMigration::new(plan, room_url));
+          ui_message.write(UiNotification::info(HOST_MIGRATION_NOTIFICATION.to_string()));
+          return;
+        }
+        Some(Err(error)) => warn!("Unable to migrate to new host: {}", error),
+        None => warn!("Unable to migrate to new host because the room is unknown"),
+      }
+    }
+
     let next_state = match **current_app_state {
       AppState::Preparing => AppState::Preparing,
       _ => AppState::GameOver,
@@ -135,7 +902,6 @@ fn receive_network_error_event(
       "Connection lost: [{}] - removing networking resources and setting state to [{:?}]...",
       error, next_state
     );
-    remove_all_matchbox_resources(&mut commands);
 
     // If the connection is lost during the preparation phase, we want to stay in the preparation phase to allow the
     // user to try connecting again
@@ -165,6 +931,7 @@ fn receive_network_error_event(
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::prelude::{ControlScheme, PlayerId, RegisteredPlayer};
   use bevy::prelude::*;
   use bevy::state::app::StatesPlugin;
 
@@ -174,6 +941,10 @@ mod tests {
     app.init_state::<AppState>();
     app.add_message::<UiNotification>();
     app.insert_resource(NetworkRole::Client);
+    app.init_resource::<HostSuccession>();
+    app.init_resource::<ReconnectCredentials>();
+    app.insert_resource(SignallingServerUrl::new("ws://localhost:3536"));
+    app.init_resource::<IceServers>();
     app.add_observer(receive_network_error_event);
     app
   }
@@ -194,13 +965,329 @@ mod tests {
   }
 
   #[test]
-  fn host_room_url_adds_host_role_without_changing_room_id() {
+  fn host_room_url_adds_host_role_key_and_capacity_without_changing_room_id() {
+    assert_eq!(
+      host_room_url("wss://signal.example.com/room-456", "secret", 13, None).expect("Expected valid room URL"),
+      "wss://signal.example.com/room-456?role=host&key=secret&capacity=13"
+    );
+  }
+
+  #[test]
+  fn host_room_url_adds_encoded_password() {
     assert_eq!(
-      host_room_url("wss://signal.example.com/", "room-456"),
-      "wss://signal.example.com/room-456?role=host"
+      host_room_url("wss://signal.example.com/room-456", "secret", 13, Some("open sesame&"))
+        .expect("Expected valid room URL"),
+      "wss://signal.example.com/room-456?role=host&key=secret&capacity=13&password=open+sesame%26"
     );
   }
 
+  #[test]
+  fn successor_room_url_adds_host_role_previous_peer_id_key_and_capacity() {
+    let previous_client_id = ClientId::from_u64(7);
+    assert_eq!(
+      successor_room_url("wss://signal.example.com/room-456", previous_client_id, "secret", 13),
+      format!(
+        "wss://signal.example.com/room-456?role=host&successor={}&key=secret&capacity=13",
+        peer_id_from_client_id(previous_client_id)
+      )
+    );
+  }
+
+  fn registered_players(local_player_count: u8, remote_player_count: u8) -> RegisteredPlayers {
+    let mut registered_players = RegisteredPlayers::default();
+    for id in 0..local_player_count + remote_player_count {
+      let player = if id < local_player_count {
+        RegisteredPlayer::new_mutable_dead(PlayerId(id), ControlScheme::test(id), Color::WHITE)
+      } else {
+        RegisteredPlayer::new_immutable_for_test(PlayerId(id), ControlScheme::test(id), Color::WHITE)
+      };
+      registered_players.register(player).expect("Failed to register player");
+    }
+    registered_players
+  }
+
+  #[test]
+  fn room_capacity_leaves_room_for_remaining_remote_players_and_spectators() {
+    let spectators = u16::from(MAX_SPECTATORS);
+    assert_eq!(room_capacity(5, &registered_players(0, 0)), 5 + spectators);
+    assert_eq!(room_capacity(5, &registered_players(2, 2)), 3 + spectators);
+    assert_eq!(room_capacity(5, &registered_players(5, 0)), spectators);
+  }
+
+  #[test]
+  fn update_room_capacity_system_declares_capacity_once_local_players_register() {
+    let mut app = setup();
+    app.init_resource::<SignallingCredentials>();
+    app.insert_resource(GameRules::default());
+    app.insert_resource(registered_players(0, 0));
+    app.insert_resource(HostedRoomUrl("ws://localhost:3536/room-456".to_string()));
+    let max_players = app.world().resource::<GameRules>().max_players;
+    let initial_capacity = room_capacity(max_players, &RegisteredPlayers::default());
+    app.insert_resource(DeclaredRoomCapacity(initial_capacity));
+    app.add_systems(Update, update_room_capacity_system);
+
+    app.update();
+    assert_eq!(app.world().resource::<DeclaredRoomCapacity>().0, initial_capacity);
+
+    app.insert_resource(registered_players(2, 0));
+    app.update();
+    assert_eq!(app.world().resource::<DeclaredRoomCapacity>().0, initial_capacity - 2);
+  }
+
+  fn pending_room_access(room_access: RoomAccess) -> PendingRoomAccess {
+    PendingRoomAccess {
+      room_url: "ws://localhost:3536/room-456".to_string(),
+      password: Some("hunter3".to_string()),
+      response: Arc::new(Mutex::new(Some(room_access))),
+    }
+  }
+
+  #[test]
+  fn handle_room_access_response_system_prompts_for_password_when_room_requires_one() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordRequiredMessage>();
+    app.init_resource::<SignallingCredentials>();
+    app.insert_resource(pending_room_access(RoomAccess::PasswordRequired));
+    app.add_systems(
+      Update,
+      handle_room_access_response_system.run_if(resource_exists::<PendingRoomAccess>),
+    );
+
+    app.update();
+
+    let password_required_messages = app.world().resource::<Messages<RoomPasswordRequiredMessage>>();
+    assert_eq!(password_required_messages.iter_current_update_messages().count(), 1);
+    assert_eq!(
+      notification_texts(&mut app),
+      vec![PASSWORD_REQUIRED_NOTIFICATION.to_string()]
+    );
+    assert!(!app.world().contains_resource::<PendingRoomAccess>());
+    assert!(!app.world().contains_resource::<ClientNetworkingActive>());
+    assert_eq!(app.world().resource::<SignallingCredentials>().room_password, None);
+  }
+
+  #[test]
+  fn handle_room_access_response_system_reports_invalid_password_without_connecting() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordRequiredMessage>();
+    app.init_resource::<SignallingCredentials>();
+    app.insert_resource(pending_room_access(RoomAccess::PasswordInvalid));
+    app.add_systems(
+      Update,
+      handle_room_access_response_system.run_if(resource_exists::<PendingRoomAccess>),
+    );
+
+    app.update();
+
+    let password_required_messages = app.world().resource::<Messages<RoomPasswordRequiredMessage>>();
+    assert_eq!(password_required_messages.iter_current_update_messages().count(), 0);
+    assert_eq!(
+      notification_texts(&mut app),
+      vec![PASSWORD_INVALID_NOTIFICATION.to_string()]
+    );
+    assert!(!app.world().contains_resource::<ClientNetworkingActive>());
+  }
+
+  #[test]
+  fn handle_room_access_response_system_reports_full_room_without_connecting() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordRequiredMessage>();
+    app.init_resource::<SignallingCredentials>();
+    app.insert_resource(pending_room_access(RoomAccess::RoomFull));
+    app.add_systems(
+      Update,
+      handle_room_access_response_system.run_if(resource_exists::<PendingRoomAccess>),
+    );
+
+    app.update();
+
+    assert_eq!(notification_texts(&mut app), vec![ROOM_FULL_NOTIFICATION.to_string()]);
+    assert!(!app.world().contains_resource::<PendingRoomAccess>());
+    assert!(!app.world().contains_resource::<ClientNetworkingActive>());
+  }
+
+  #[test]
+  fn handle_room_access_response_system_waits_for_response() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordRequiredMessage>();
+    app.init_resource::<SignallingCredentials>();
+    let mut pending_room_access = pending_room_access(RoomAccess::Granted);
+    pending_room_access.response = Arc::new(Mutex::new(None));
+    app.insert_resource(pending_room_access);
+    app.add_systems(
+      Update,
+      handle_room_access_response_system.run_if(resource_exists::<PendingRoomAccess>),
+    );
+
+    app.update();
+
+    assert!(app.world().contains_resource::<PendingRoomAccess>());
+    assert!(notification_texts(&mut app).is_empty());
+  }
+
+  #[test]
+  fn handle_public_rooms_response_system_forwards_public_rooms_to_ui() {
+    let mut app = setup();
+    app.add_message::<PublicRoomsMessage>();
+    let public_rooms = vec![PublicRoom {
+      room_id: "ABC123".to_string(),
+      name: "Alice's game".to_string(),
+      player_count: 2,
+      max_players: 5,
+      password_required: true,
+    }];
+    app.insert_resource(PendingPublicRooms(Arc::new(Mutex::new(Some(Ok(public_rooms))))));
+    app.add_systems(
+      Update,
+      handle_public_rooms_response_system.run_if(resource_exists::<PendingPublicRooms>),
+    );
+
+    app.update();
+
+    let public_rooms_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<PublicRoomsMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(
+      public_rooms_messages,
+      vec![PublicRoomsMessage {
+        rooms: Ok(vec![PublicRoomInfo {
+          room_id: "ABC123".to_string(),
+          name: "Alice's game".to_string(),
+          player_count: 2,
+          max_players: 5,
+          password_required: true,
+        }])
+      }]
+    );
+    assert!(!app.world().contains_resource::<PendingPublicRooms>());
+  }
+
+  #[test]
+  fn handle_ice_servers_response_system_uses_ice_servers_provided_by_signalling_server() {
+    let mut app = setup();
+    let config = IceServerConfig {
+      urls: vec!["turn:turn.example.com:3478".to_string()],
+      username: Some("1700000000:mooplas".to_string()),
+      credential: Some("secret".to_string()),
+    };
+    app.insert_resource(PendingIceServers(Arc::new(Mutex::new(Some(Ok(config.clone()))))));
+    app.add_systems(
+      Update,
+      handle_ice_servers_response_system.run_if(resource_exists::<PendingIceServers>),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<IceServers>().config, config);
+    assert!(!app.world().contains_resource::<PendingIceServers>());
+  }
+
+  #[test]
+  fn handle_ice_servers_response_system_keeps_current_ice_servers_when_signalling_server_fails() {
+    let mut app = setup();
+    app.insert_resource(PendingIceServers(Arc::new(Mutex::new(Some(Err(
+      "[404] Not Found".to_string(),
+    ))))));
+    app.add_systems(
+      Update,
+      handle_ice_servers_response_system.run_if(resource_exists::<PendingIceServers>),
+    );
+
+    app.update();
+
+    assert_eq!(app.world().resource::<IceServers>().config, IceServerConfig::default());
+    assert!(!app.world().contains_resource::<PendingIceServers>());
+  }
+
+  #[test]
+  fn handle_health_check_response_system_reports_unreachable_signalling_server() {
+    let mut app = setup();
+    app.insert_resource(PendingHealthCheck {
+      url: "wss://signal.example.com".to_string(),
+      response: Arc::new(Mutex::new(Some(Err("[404] Not Found".to_string())))),
+    });
+    app.add_systems(
+      Update,
+      handle_health_check_response_system.run_if(resource_exists::<PendingHealthCheck>),
+    );
+
+    app.update();
+
+    assert_eq!(
+      notification_texts(&mut app),
+      vec!["Unable to reach signalling server [wss://signal.example.com]: [404] Not Found".to_string()]
+    );
+    assert!(!app.world().contains_resource::<PendingHealthCheck>());
+  }
+
+  #[test]
+  fn request_ice_servers_system_does_nothing_when_ice_servers_are_configured_locally() {
+    let mut app = setup();
+    app.insert_resource(IceServers {
+      config: IceServerConfig::default(),
+      is_configured_locally: true,
+    });
+    app.add_systems(Update, request_ice_servers_system);
+
+    app.update();
+
+    assert!(!app.world().contains_resource::<PendingIceServers>());
+  }
+
+  #[test]
+  fn handle_room_visibility_message_lists_room_when_made_public() {
+    let mut app = setup();
+    app.add_message::<RoomVisibilityMessage>();
+    app.init_resource::<SignallingCredentials>();
+    app.insert_resource(HostedRoomUrl("ws://localhost:3536/room-456".to_string()));
+    app.add_systems(Update, handle_room_visibility_message);
+    app
+      .world_mut()
+      .write_message(RoomVisibilityMessage { is_public: true })
+      .expect("Failed to write RoomVisibilityMessage");
+
+    app.update();
+
+    assert_eq!(
+      app
+        .world()
+        .get_resource::<ListedRoom>()
+        .map(|listed_room| &listed_room.0),
+      Some(&None)
+    );
+    assert_eq!(
+      notification_texts(&mut app),
+      vec!["Room is public - anyone can find it via [Browse Games]".to_string()]
+    );
+  }
+
+  #[test]
+  fn receive_network_error_event_starts_host_migration_when_successor_is_designated() {
+    let mut app = setup();
+    set_app_state(&mut app, AppState::Playing);
+    app.insert_resource(HostSuccession::test(ClientId::from_u64(7), ClientId::from_u64(8)));
+    app
+      .world_mut()
+      .write_message(ConnectionInfoMessage::new("room-456".to_string()))
+      .expect("Failed to write ConnectionInfoMessage");
+    app.add_systems(Update, crate::online::client::record_connection_info_system);
+    app.update();
+
+    app
+      .world_mut()
+      .trigger(NetworkErrorEvent::Disconnect("Host disconnected".to_string()));
+    app.update();
+
+    let pending_host_migration = app.world().resource::<PendingHostMigration>();
+    assert_eq!(pending_host_migration.plan, HostMigrationPlan::FollowSuccessor);
+    assert_eq!(pending_host_migration.room_url, "ws://localhost:3536/room-456");
+    assert_eq!(*app.world().resource::<NetworkRole>(), NetworkRole::Client);
+    assert_eq!(*app.world().resource::<State<AppState>>().get(), AppState::Playing);
+  }
+
   #[test]
   fn receive_network_error_event_writes_host_left_notification_for_client_disconnect_after_preparing() {
     let mut app = setup();
@@ -214,6 +1301,27 @@ mod tests {
     assert_eq!(*app.world().resource::<NetworkRole>(), NetworkRole::None);
   }
 
+  #[test]
+  fn receive_network_error_event_ignores_malformed_packet() {
+    let mut app = setup();
+    set_app_state(&mut app, AppState::Playing);
+
+    app.world_mut().trigger(NetworkErrorEvent::MalformedPacket(
+      mooplas_networking::prelude::MalformedPacket {
+        client_id: None,
+        channel: mooplas_networking::prelude::ChannelType::Unreliable,
+        payload_size: 1,
+        failures: 1,
+        error: "Hit the end of buffer".to_string(),
+      },
+    ));
+    app.update();
+
+    assert!(notification_texts(&mut app).is_empty());
+    assert_eq!(*app.world().resource::<NetworkRole>(), NetworkRole::Client);
+    assert_eq!(*app.world().resource::<State<AppState>>().get(), AppState::Playing);
+  }
+
   #[test]
   fn receive_network_error_event_keeps_preparing_connection_failure_notification() {
     let mut app = setup();
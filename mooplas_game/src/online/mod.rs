@@ -9,9 +9,15 @@ mod structs;
 #[cfg(feature = "online")]
 mod server;
 
+#[cfg(feature = "online")]
+mod validation;
+
 #[cfg(feature = "online")]
 mod client;
 
+#[cfg(feature = "online")]
+mod host_migration;
+
 #[cfg(feature = "online")]
 mod matchbox;
 
@@ -8,12 +8,23 @@ pub struct OnlinePlugin;
 impl Plugin for OnlinePlugin {
   #[allow(unused_variables)]
   fn build(&self, app: &mut App) {
+    #[cfg(feature = "online")]
+    if let Some(launch_config) = app.world().get_resource::<crate::prelude::LaunchConfig>() {
+      let launch_url = launch_config.networking.signalling_server_url.clone();
+      app.insert_resource(mooplas_networking::prelude::SignallingServerUrl::from_runtime_config(
+        launch_url,
+      ));
+    }
+
     #[cfg(feature = "online")]
     app.add_plugins((
       mooplas_networking::prelude::NetworkingResourcesPlugin,
       mooplas_networking::prelude::NetworkingMessagesPlugin,
+      mooplas_networking::prelude::NetworkStatsPlugin,
+      mooplas_networking::prelude::ClockSyncPlugin,
       crate::online::server::ServerPlugin,
       crate::online::client::ClientPlugin,
+      crate::online::host_migration::HostMigrationPlugin,
     ));
 
     #[cfg(feature = "online")]
@@ -23,3 +34,213 @@ impl Plugin for OnlinePlugin {
     app.add_plugins(crate::online::matchbox::MatchboxPlugin);
   }
 }
+
+#[cfg(all(test, feature = "online"))]
+mod tests {
+  use crate::app_state::AppStatePlugin;
+  use crate::initialisation::InitialisationPlugin;
+  use crate::online::client::ClientPlugin;
+  use crate::online::host_migration::HostMigrationPlugin;
+  use crate::online::server::ServerPlugin;
+  use crate::online::structs::LocalInputMapping;
+  use crate::prelude::{
+    AppState, ControlSchemeId, EliminationCause, LocalPlayerReadyRequestMessage, LocalPlayerRegistrationRequestMessage,
+    PlayerEliminatedMessage, PlayerId, PlayerName, RegisteredPlayers, SharedMessagesPlugin, SharedResourcesPlugin,
+    WinnerInfo,
+  };
+  use bevy::prelude::*;
+  use bevy::state::app::StatesPlugin;
+  use mooplas_networking::prelude::{
+    ClientNetworkingActive, ClientTransportPlugin, LoopbackClientTransport, LoopbackNetwork, LoopbackServerTransport,
+    NetworkRole, NetworkingMessagesPlugin, NetworkingResourcesPlugin, ServerNetworkingActive, ServerTransportPlugin,
+  };
+
+  fn setup(network_role: NetworkRole) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      StatesPlugin,
+      AppStatePlugin,
+      SharedMessagesPlugin,
+      SharedResourcesPlugin,
+      InitialisationPlugin,
+      NetworkingMessagesPlugin,
+      NetworkingResourcesPlugin,
+      ServerPlugin,
+      ClientPlugin,
+      HostMigrationPlugin,
+    ));
+    app.init_resource::<LocalInputMapping>();
+    app.insert_resource(network_role);
+    app
+  }
+
+  fn setup_host(network: &LoopbackNetwork) -> App {
+    let mut app = setup(NetworkRole::Server);
+    app.add_plugins(ServerTransportPlugin::<LoopbackServerTransport>::default());
+    app.insert_resource(network.server());
+    app.insert_resource(ServerNetworkingActive);
+    app
+  }
+
+  /// Records the players that the client has been told were eliminated.
+  #[derive(Resource, Default)]
+  struct EliminatedPlayers(Vec<PlayerId>);
+
+  fn record_eliminated_players_system(
+    mut messages: MessageReader<PlayerEliminatedMessage>,
+    mut eliminated_players: ResMut<EliminatedPlayers>,
+  ) {
+    eliminated_players
+      .0
+      .extend(messages.read().map(|message| message.player_id));
+  }
+
+  fn setup_client(network: &LoopbackNetwork, name: &str) -> App {
+    let mut app = setup(NetworkRole::Client);
+    app.init_resource::<EliminatedPlayers>();
+    app.add_systems(Update, record_eliminated_players_system);
+    app.add_plugins(ClientTransportPlugin::<LoopbackClientTransport>::default());
+    app.world_mut().resource_mut::<PlayerName>().set(name.to_string());
+    app.insert_resource(network.connect_client());
+    app.insert_resource(ClientNetworkingActive);
+    app
+  }
+
+  /// Updates the host and every client a number of times, so that messages and state changes can make their way through
+  /// the network in both directions.
+  fn run(host: &mut App, clients: &mut [App]) {
+    for _ in 0..10 {
+      host.update();
+      for client in clients.iter_mut() {
+        client.update();
+      }
+    }
+  }
+
+  fn app_state(app: &App) -> AppState {
+    *app.world().resource::<State<AppState>>().get()
+  }
+
+  fn set_app_state(app: &mut App, state: AppState) {
+    app.world_mut().resource_mut::<NextState<AppState>>().set(state);
+  }
+
+  fn local_player_id(app: &App) -> PlayerId {
+    app
+      .world()
+      .resource::<RegisteredPlayers>()
+      .players
+      .iter()
+      .find(|player| player.is_local())
+      .map(|player| player.id)
+      .expect("Expected the client to have registered a local player")
+  }
+
+  fn player_names(app: &App) -> Vec<String> {
+    let mut names: Vec<String> = app
+      .world()
+      .resource::<RegisteredPlayers>()
+      .players
+      .iter()
+      .map(|player| player.name.clone())
+      .collect();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn host_and_clients_play_a_round_through_lobby_round_and_game_over() {
+    let network = LoopbackNetwork::new();
+    let mut host = setup_host(&network);
+    set_app_state(&mut host, AppState::Initialising);
+    run(&mut host, &mut []);
+    assert_eq!(app_state(&host), AppState::Registering);
+
+    // Lobby: clients join, register a player each and ready up
+    let mut clients = vec![
+      setup_client(&network, "Alice"),
+      setup_client(&network, "Bob"),
+      setup_client(&network, "Carol"),
+    ];
+    run(&mut host, &mut clients);
+    for client in &clients {
+      assert_eq!(app_state(client), AppState::Registering);
+    }
+
+    for client in &mut clients {
+      client
+        .world_mut()
+        .write_message(LocalPlayerRegistrationRequestMessage {
+          control_scheme_id: ControlSchemeId(0),
+          has_registered: true,
+        })
+        .expect("Failed to write LocalPlayerRegistrationRequestMessage");
+    }
+    run(&mut host, &mut clients);
+    let expected_names = vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+    assert_eq!(player_names(&host), expected_names);
+    for client in &clients {
+      assert_eq!(player_names(client), expected_names);
+    }
+
+    for client in &mut clients {
+      let player_id = local_player_id(client);
+      client
+        .world_mut()
+        .write_message(LocalPlayerReadyRequestMessage {
+          player_id,
+          is_ready: true,
+        })
+        .expect("Failed to write LocalPlayerReadyRequestMessage");
+    }
+    run(&mut host, &mut clients);
+    assert!(host.world().resource::<RegisteredPlayers>().are_all_ready());
+    for client in &clients {
+      assert!(client.world().resource::<RegisteredPlayers>().are_all_ready());
+    }
+
+    // Round: the host starts the round and eliminates all players but one
+    set_app_state(&mut host, AppState::Playing);
+    run(&mut host, &mut clients);
+    for client in &clients {
+      assert_eq!(app_state(client), AppState::Playing);
+    }
+
+    let winner_id = local_player_id(&clients[0]);
+    for client in &clients[1..] {
+      let player_id = local_player_id(client);
+      host
+        .world_mut()
+        .write_message(PlayerEliminatedMessage::new(
+          player_id,
+          EliminationCause::Wall,
+          Vec2::ZERO,
+        ))
+        .expect("Failed to write PlayerEliminatedMessage");
+    }
+    let mut eliminated_player_ids: Vec<u8> = clients[1..].iter().map(|client| local_player_id(client).0).collect();
+    eliminated_player_ids.sort();
+    run(&mut host, &mut clients);
+    for client in &clients {
+      let mut received_player_ids: Vec<u8> = client
+        .world()
+        .resource::<EliminatedPlayers>()
+        .0
+        .iter()
+        .map(|player_id| player_id.0)
+        .collect();
+      received_player_ids.sort();
+      assert_eq!(received_player_ids, eliminated_player_ids);
+    }
+
+    // Game over: every client learns the winner
+    host.world_mut().resource_mut::<WinnerInfo>().set(winner_id);
+    set_app_state(&mut host, AppState::GameOver);
+    run(&mut host, &mut clients);
+    for client in &clients {
+      assert_eq!(app_state(client), AppState::GameOver);
+      assert_eq!(client.world().resource::<WinnerInfo>().get_as_u8(), Some(winner_id.0));
+    }
+  }
+}
@@ -1,19 +1,27 @@
 use crate::app_state::AppState;
+use crate::online::host_migration::{HostMigrationMessage, HostSuccession};
 use crate::online::utils;
+use crate::online::validation::{self, ClientViolations, Validation, Violation};
 use crate::prelude::{
-  AvailableControlSchemes, ControlSchemeId, ExitLobbyMessage, InputMessage, LocalPlayerRegistrationRequestMessage,
-  MAX_PLAYERS, MenuName, PlayerId, PlayerName, PlayerRegistrationMessage, RegisteredPlayers, Seed, SnakeHead,
-  ToggleMenuMessage, UiNotification, WinnerInfo,
+  AvailableControlSchemes, ChatMessage, ClientBannedMessage, ContinueMessage, ControlSchemeId, ExitLobbyMessage,
+  GameRules, InputMessage, LocalPlayerReadyRequestMessage, LocalPlayerRegistrationRequestMessage, MAX_PLAYERS,
+  MAX_SPECTATORS, MenuName, ModeratePlayerMessage, ModerationAction, Player, PlayerEliminatedMessage, PlayerId,
+  PlayerName, PlayerRegistrationMessage, RegisteredPlayers, Seed, SendChatMessage, SnakeHead, SnakeTail,
+  TailEventMessage, ToggleMenuMessage, UiNotification, WinnerInfo,
 };
+use bevy::ecs::system::SystemParam;
 use bevy::log::{debug, info, warn};
 use bevy::prelude::{
-  App, Commands, IntoScheduleConfigs, MessageReader, MessageWriter, NextState, OnExit, Plugin, Query, Res, ResMut,
-  Resource, State, StateTransitionEvent, Time, Timer, TimerMode, Transform, Update, With, in_state, resource_exists,
+  App, Commands, IntoScheduleConfigs, MessageReader, MessageWriter, NextState, OnExit, Plugin, Query, Real, Res,
+  ResMut, Resource, State, StateTransitionEvent, Time, Timer, TimerMode, Transform, Update, Virtual, With, Without,
+  in_state, resource_exists,
 };
 use mooplas_networking::prelude::{
-  ChannelType, ClientId, InboundClientMessage, InboundServerMessage, Lobby, OutboundServerMessage, PlayerInLobby,
-  SerialisableRegisteredPlayer, SerialisableUnregistrationRequest, ServerNetworkingActive, encode_to_bytes,
+  ClientId, DisconnectReason, InboundClientMessage, InboundServerMessage, Lobby, OutboundServerMessage, PlayerInLobby,
+  ReconnectToken, SerialisableInputFrame, SerialisablePlayerSnapshot, SerialisableReadyRequest,
+  SerialisableRegisteredPlayer, SerialisableTailEvent, SerialisableUnregistrationRequest, ServerNetworkingActive,
 };
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 /// A plugin that contains systems related to processing and broadcasting messages on the server, which are shared
@@ -23,6 +31,11 @@ pub struct ServerPlugin;
 impl Plugin for ServerPlugin {
   fn build(&self, app: &mut App) {
     app
+      .init_resource::<ReservedSlots>()
+      .init_resource::<AppliedInputSequences>()
+      .init_resource::<ClientViolations>()
+      .init_resource::<BannedClients>()
+      .init_resource::<ReadyCountdown>()
       .add_systems(
         Update,
         (
@@ -36,17 +49,44 @@ impl Plugin for ServerPlugin {
         Update,
         (
           handle_local_player_registration_request_message,
+          handle_local_player_ready_request_message,
+          handle_moderate_player_message,
           process_and_broadcast_local_exit_lobby_message,
+          ready_countdown_system.after(handle_inbound_client_message),
         )
           .run_if(in_state(AppState::Registering))
           .run_if(resource_exists::<ServerNetworkingActive>),
       )
+      .add_systems(OnExit(AppState::Registering), reset_ready_countdown_system)
       .add_systems(
         Update,
-        broadcast_player_states_system
+        (
+          broadcast_player_states_system,
+          broadcast_tail_events_system.after(handle_inbound_server_message),
+          broadcast_player_eliminated_system,
+        )
           .run_if(in_state(AppState::Playing))
           .run_if(resource_exists::<ServerNetworkingActive>),
       )
+      .add_systems(
+        Update,
+        broadcast_host_succession_system
+          .after(handle_inbound_server_message)
+          .run_if(resource_exists::<ServerNetworkingActive>),
+      )
+      .add_systems(Update, adopt_migrated_lobby_system)
+      .add_systems(
+        Update,
+        expire_reserved_slots_system.run_if(resource_exists::<ServerNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        pause_while_reconnecting_system.run_if(resource_exists::<ServerNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        broadcast_local_chat_message_system.run_if(resource_exists::<ServerNetworkingActive>),
+      )
       .add_systems(
         Update,
         disconnect_all_clients_system
@@ -57,9 +97,70 @@ impl Plugin for ServerPlugin {
   }
 }
 
-const CLIENT_MESSAGE_SERIALISATION: &str = "Failed to serialise client message";
 const PLAYER_JOINED_NOTIFICATION: &str = "A player joined the game";
 const PLAYER_LEFT_NOTIFICATION: &str = "A player left the game";
+const PLAYER_CONNECTION_LOST_NOTIFICATION: &str = "A player lost connection - waiting for them to reconnect";
+const PLAYER_RECONNECTED_NOTIFICATION: &str = "A player reconnected";
+const PLAYER_KICKED_NOTIFICATION: &str = "A player has been kicked";
+const PLAYER_BANNED_NOTIFICATION: &str = "A player has been banned";
+const HOST_MIGRATED_NOTIFICATION: &str = "The host has left the game - you are now the host";
+/// The name with which the chat messages of clients without any registered players are shown.
+const SPECTATOR_CHAT_NAME: &str = "Spectator";
+
+/// A resource that tracks the grace period of each reserved slot i.e. the players of a disconnected client that are
+/// held until the client reconnects with its [`ReconnectToken`]. The reservations themselves are stored in [`Lobby`].
+#[derive(Resource, Default)]
+struct ReservedSlots {
+  grace_periods: HashMap<ReconnectToken, Timer>,
+  /// Whether the game is currently paused because of a reserved slot, as per [`GameRules`].
+  has_paused_game: bool,
+}
+
+/// A resource that holds the countdown to the automatic start of the round once all players are ready, if the
+/// [`GameRules`] ask for one and it has started.
+#[derive(Resource, Default)]
+struct ReadyCountdown(Option<Timer>);
+
+/// A resource that holds the sequence number of the latest input frame applied for each client. Used to apply each
+/// frame exactly once and in order, even though clients send every frame several times and packets may be reordered.
+#[derive(Resource, Default)]
+struct AppliedInputSequences(HashMap<ClientId, u32>);
+
+impl AppliedInputSequences {
+  /// Returns the frames that haven't been applied yet, in order, and marks them as applied.
+  fn take_new_frames<'a>(
+    &mut self,
+    client_id: ClientId,
+    frames: impl IntoIterator<Item = &'a SerialisableInputFrame>,
+  ) -> Vec<&'a SerialisableInputFrame> {
+    let last_applied = self.0.get(&client_id).copied();
+    let mut new_frames: Vec<&SerialisableInputFrame> = frames
+      .into_iter()
+      .filter(|frame| last_applied.is_none_or(|sequence| frame.sequence > sequence))
+      .collect();
+    new_frames.sort_by_key(|frame| frame.sequence);
+    new_frames.dedup_by_key(|frame| frame.sequence);
+    if let Some(latest) = new_frames.last() {
+      self.0.insert(client_id, latest.sequence);
+    }
+    new_frames
+  }
+}
+
+/// The state with which the server keeps track of clients, their reserved slots and their misbehaviour.
+#[derive(SystemParam)]
+struct ClientModeration<'w> {
+  lobby: ResMut<'w, Lobby>,
+  reserved_slots: ResMut<'w, ReservedSlots>,
+  client_violations: ResMut<'w, ClientViolations>,
+  banned_clients: Res<'w, BannedClients>,
+}
+
+/// A resource that holds the [`ReconnectToken`]s of the clients that the host has banned, so that they can't reclaim
+/// their players for the rest of the session. Refusing new connections of banned clients is up to the networking
+/// implementation, which is informed via [`ClientBannedMessage`].
+#[derive(Resource, Default)]
+struct BannedClients(HashSet<ReconnectToken>);
 
 // A resource to schedule the actual disconnect after broadcasting the shutdown message.
 #[derive(Resource)]
@@ -79,6 +180,7 @@ fn handle_registration_request(
   lobby: &mut ResMut<Lobby>,
   registers_local_player: bool,
   name: String,
+  max_players: u8,
 ) {
   if lobby.is_control_scheme_registered(&client_id, control_scheme_id.0) {
     warn!(
@@ -87,7 +189,7 @@ fn handle_registration_request(
     );
     return;
   }
-  let Some(player_id) = next_available_player_id(registered_players) else {
+  let Some(player_id) = next_available_player_id(registered_players, max_players) else {
     warn!("No player IDs are available for client [{}]", client_id);
     return;
   };
@@ -123,22 +225,18 @@ fn broadcast_player_registered(
   control_scheme_id: ControlSchemeId,
   name: String,
 ) {
-  let payload = encode_to_bytes(&InboundServerMessage::PlayerRegistered {
+  let message = InboundServerMessage::PlayerRegistered {
     client_id,
     player_id: player_id.0,
     control_scheme_id: control_scheme_id.0,
     name,
-  })
-  .expect(CLIENT_MESSAGE_SERIALISATION);
-  outbound_server_message.write(OutboundServerMessage::Broadcast {
-    channel: ChannelType::ReliableOrdered,
-    payload,
-  });
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
 }
 
-/// Returns the first [`PlayerId`] that is not registered, or `None` if all possible player IDs are taken.
-fn next_available_player_id(registered_players: &RegisteredPlayers) -> Option<PlayerId> {
-  (0..MAX_PLAYERS as usize)
+/// Returns the first [`PlayerId`] that is not registered, or `None` if the first `max_players` player IDs are taken.
+fn next_available_player_id(registered_players: &RegisteredPlayers, max_players: u8) -> Option<PlayerId> {
+  (0..max_players.min(MAX_PLAYERS) as usize)
     .map(|index| PlayerId(index as u8))
     .find(|candidate| !registered_players.players.iter().any(|player| player.id == *candidate))
 }
@@ -183,61 +281,89 @@ fn broadcast_player_unregistered(
   client_id: ClientId,
   player_id: PlayerId,
 ) {
-  let payload = encode_to_bytes(&InboundServerMessage::PlayerUnregistered {
+  let message = InboundServerMessage::PlayerUnregistered {
     client_id,
     player_id: player_id.0,
-  })
-  .expect(CLIENT_MESSAGE_SERIALISATION);
-  outbound_server_message.write(OutboundServerMessage::Broadcast {
-    channel: ChannelType::ReliableOrdered,
-    payload,
-  });
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
 }
 
 fn broadcast_client_connected(
   outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
   connected_client_id: ClientId,
 ) {
-  let payload = encode_to_bytes(&InboundServerMessage::ClientConnected {
+  let message = InboundServerMessage::ClientConnected {
     client_id: connected_client_id,
-  })
-  .expect(CLIENT_MESSAGE_SERIALISATION);
-  outbound_server_message.write(OutboundServerMessage::BroadcastExcept {
-    except_client_id: connected_client_id,
-    channel: ChannelType::ReliableOrdered,
-    payload,
-  });
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast_except(connected_client_id, &message));
 }
 
 fn broadcast_client_disconnected(
   outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
   disconnected_client_id: ClientId,
+  is_forced: bool,
 ) {
-  let payload = encode_to_bytes(&InboundServerMessage::ClientDisconnected {
+  let message = InboundServerMessage::ClientDisconnected {
     client_id: disconnected_client_id,
-  })
-  .expect(CLIENT_MESSAGE_SERIALISATION);
-  outbound_server_message.write(OutboundServerMessage::BroadcastExcept {
-    except_client_id: disconnected_client_id,
-    channel: ChannelType::ReliableOrdered,
-    payload,
-  });
+    is_forced,
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast_except(
+    disconnected_client_id,
+    &message,
+  ));
 }
 
 /// Processes any incoming messages from clients by applying them locally and broadcasting them to all other clients,
 /// if necessary.
 fn handle_inbound_client_message(
   mut messages: MessageReader<InboundClientMessage>,
-  mut lobby: ResMut<Lobby>,
+  mut moderation: ClientModeration,
   mut registered_players: ResMut<RegisteredPlayers>,
   available_control_schemes: Res<AvailableControlSchemes>,
   mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
   mut input_message: MessageWriter<InputMessage>,
   mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+  rules: Res<GameRules>,
+  mut applied_input_sequences: ResMut<AppliedInputSequences>,
+  time: Res<Time<Real>>,
+  mut chat_message: MessageWriter<ChatMessage>,
+  current_state: Res<State<AppState>>,
 ) {
   for message in messages.read() {
+    if let Err(violation) = moderation.client_violations.check_rate(message, time.elapsed()) {
+      if let Some(violation) = violation {
+        record_violation(
+          &mut moderation,
+          &mut outbound_server_message,
+          &mut registered_players,
+          &mut player_registration_message,
+          message.client_id(),
+          violation,
+        );
+      }
+      continue;
+    }
     match message {
       InboundClientMessage::RegistrationRequest(message, client_id) => {
+        if moderation.lobby.is_spectating(client_id) {
+          warn!("Ignoring registration of spectating client [{}]", client_id);
+          continue;
+        }
+        let validation = validation::validate_name(&message.name);
+        if let Some(violation) = validation.violation() {
+          record_violation(
+            &mut moderation,
+            &mut outbound_server_message,
+            &mut registered_players,
+            &mut player_registration_message,
+            *client_id,
+            violation.clone(),
+          );
+        }
+        let Some(name) = validation.value() else {
+          continue;
+        };
         handle_registration_request(
           &mut outbound_server_message,
           &mut registered_players,
@@ -245,9 +371,10 @@ fn handle_inbound_client_message(
           &mut player_registration_message,
           *client_id,
           ControlSchemeId(message.control_scheme_id),
-          &mut lobby,
+          &mut moderation.lobby,
           false,
-          message.name.clone(),
+          name,
+          rules.max_players,
         );
       }
       InboundClientMessage::UnregistrationRequest(message, client_id) => {
@@ -257,23 +384,405 @@ fn handle_inbound_client_message(
           &mut player_registration_message,
           *client_id,
           *message,
-          &mut lobby,
+          &mut moderation.lobby,
           false,
         );
       }
-      InboundClientMessage::Input(message, client_id) => {
-        let message: InputMessage = message.into();
-        let player_id = match message {
-          InputMessage::Action(player_id) => player_id,
-          InputMessage::Move(player_id, _) => player_id,
+      InboundClientMessage::Input(frames, client_id) => {
+        let validation = validation::validate_input_frames(frames);
+        if let Some(violation) = validation.violation() {
+          record_violation(
+            &mut moderation,
+            &mut outbound_server_message,
+            &mut registered_players,
+            &mut player_registration_message,
+            *client_id,
+            violation.clone(),
+          );
+        }
+        let Some(valid_frames) = validation.value() else {
+          continue;
+        };
+        // Frames that have all been applied before are acknowledged again, since the client keeps resending them until
+        // an acknowledgement reaches it
+        let Some(latest_sequence) = valid_frames.iter().map(|frame| frame.sequence).max() else {
+          continue;
+        };
+        let new_frames = applied_input_sequences.take_new_frames(*client_id, valid_frames);
+        for input in new_frames.iter().flat_map(|frame| &frame.inputs) {
+          let input = match validation::validate_input(*input) {
+            Validation::Valid(input) => input,
+            Validation::Clamped(input, violation) => {
+              record_violation(
+                &mut moderation,
+                &mut outbound_server_message,
+                &mut registered_players,
+                &mut player_registration_message,
+                *client_id,
+                violation,
+              );
+              input
+            }
+            Validation::Rejected(violation) => {
+              record_violation(
+                &mut moderation,
+                &mut outbound_server_message,
+                &mut registered_players,
+                &mut player_registration_message,
+                *client_id,
+                violation,
+              );
+              continue;
+            }
+          };
+          let message: InputMessage = (&input).into();
+          let player_id = match message {
+            InputMessage::Action(player_id) => player_id,
+            InputMessage::Move(player_id, _) => player_id,
+          };
+          if moderation.lobby.validate_registration(client_id, &player_id.into()) {
+            input_message.write(message);
+            continue;
+          }
+          warn!("Received invalid input action on [Unreliable] channel: {:?}", message);
+        }
+        let input_ack = InboundServerMessage::InputAck {
+          sequence: latest_sequence,
+        };
+        outbound_server_message.write(OutboundServerMessage::send(*client_id, &input_ack));
+      }
+      InboundClientMessage::Reconnect(token, client_id) => {
+        if moderation.banned_clients.0.contains(token) {
+          warn!("Refusing reconnect of banned client [{}]", client_id);
+          kick_client(
+            &mut outbound_server_message,
+            &mut registered_players,
+            &mut player_registration_message,
+            &mut moderation.reserved_slots,
+            &mut moderation.lobby,
+            *client_id,
+            DisconnectReason::BannedByHost,
+          );
+          continue;
+        }
+        let Some(reclaimed_players) = moderation.lobby.reclaim_slot(token, *client_id) else {
+          warn!(
+            "Ignoring reconnect of client [{}] with unknown or expired token",
+            client_id
+          );
+          continue;
+        };
+        moderation.reserved_slots.grace_periods.remove(token);
+        info!(
+          "Client [{}] reconnected and reclaimed [{}] players",
+          client_id,
+          reclaimed_players.len()
+        );
+        let registered_players = lobby_snapshot(&moderation.lobby, &registered_players)
+          .into_iter()
+          .filter(|player| player.client_id == *client_id)
+          .collect();
+        let reconnect_accepted = InboundServerMessage::ReconnectAccepted { registered_players };
+        outbound_server_message.write(OutboundServerMessage::send(*client_id, &reconnect_accepted));
+        ui_notification.write(UiNotification::info(PLAYER_RECONNECTED_NOTIFICATION.to_string()));
+      }
+      InboundClientMessage::Chat(message, client_id) => {
+        if let Err(violation) = moderation.client_violations.check_chat_rate(*client_id, time.elapsed()) {
+          record_violation(
+            &mut moderation,
+            &mut outbound_server_message,
+            &mut registered_players,
+            &mut player_registration_message,
+            *client_id,
+            violation,
+          );
+          continue;
+        }
+        let text_validation = validation::validate_chat_text(&message.text);
+        if let Some(violation) = text_validation.violation() {
+          record_violation(
+            &mut moderation,
+            &mut outbound_server_message,
+            &mut registered_players,
+            &mut player_registration_message,
+            *client_id,
+            violation.clone(),
+          );
+        }
+        let Some(text) = text_validation.value() else {
+          continue;
         };
-        if lobby.validate_registration(client_id, &player_id.into()) {
-          input_message.write(message);
+        let sender_name = chat_sender_name(&moderation.lobby, &registered_players, *client_id);
+        broadcast_chat_message(&mut outbound_server_message, &mut chat_message, sender_name, text);
+      }
+      InboundClientMessage::ReadyRequest(request, client_id) => {
+        if *current_state.get() != AppState::Registering {
+          debug!("Ignoring ready request of client [{}] outside of the lobby", client_id);
           continue;
         }
-        warn!("Received invalid input action on [Unreliable] channel: {:?}", message);
+        handle_ready_request(
+          &mut outbound_server_message,
+          &mut registered_players,
+          &mut player_registration_message,
+          *client_id,
+          *request,
+          &moderation.lobby,
+        );
+      }
+      InboundClientMessage::SetSpectating(is_spectating, client_id) => {
+        handle_spectating_request(
+          &mut outbound_server_message,
+          &mut registered_players,
+          &mut player_registration_message,
+          *client_id,
+          *is_spectating,
+          &mut moderation.lobby,
+          *current_state.get(),
+        );
       }
+      InboundClientMessage::Pong(..) | InboundClientMessage::TimeSyncRequest(..) => {}
+    }
+  }
+}
+
+/// Makes a client a spectator or a player and informs all clients. A client that becomes a spectator loses its players,
+/// which is why players can only become spectators between rounds. A client without players, such as one that joined
+/// mid-round, can switch at any time.
+fn handle_spectating_request(
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  player_registration_message: &mut MessageWriter<PlayerRegistrationMessage>,
+  client_id: ClientId,
+  is_spectating: bool,
+  lobby: &mut ResMut<Lobby>,
+  current_state: AppState,
+) {
+  if is_spectating {
+    if current_state == AppState::Playing && !lobby.get_registered_players_cloned(&client_id).is_empty() {
+      warn!(
+        "Ignoring request of client [{}] to spectate while its players are playing",
+        client_id
+      );
+      return;
+    }
+    if !lobby.is_spectating(&client_id) && lobby.spectators().len() >= MAX_SPECTATORS as usize {
+      warn!(
+        "Ignoring request of client [{}] to spectate because all spectator slots are taken",
+        client_id
+      );
+      return;
+    }
+    unregister_all_players_of_client(
+      outbound_server_message,
+      registered_players,
+      player_registration_message,
+      client_id,
+      lobby,
+    );
+  }
+  if lobby.set_spectating(client_id, is_spectating) {
+    info!(
+      "Client [{}] is {} spectating",
+      client_id,
+      if is_spectating { "now" } else { "no longer" }
+    );
+    broadcast_spectators_changed(outbound_server_message, lobby);
+  }
+}
+
+fn broadcast_spectators_changed(outbound_server_message: &mut MessageWriter<OutboundServerMessage>, lobby: &Lobby) {
+  let message = InboundServerMessage::SpectatorsChanged {
+    spectators: lobby.spectators().to_vec(),
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
+}
+
+/// Marks a player as ready or not ready, if the client owns it, and informs all clients.
+fn handle_ready_request(
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  player_registration_message: &mut MessageWriter<PlayerRegistrationMessage>,
+  client_id: ClientId,
+  request: SerialisableReadyRequest,
+  lobby: &Lobby,
+) {
+  if !lobby.validate_registration(&client_id, &request.player_id) {
+    warn!(
+      "Ignoring invalid ready request for client [{}] and player [{}]",
+      client_id, request.player_id
+    );
+    return;
+  }
+  if !utils::set_player_ready_locally(
+    registered_players,
+    player_registration_message,
+    request.player_id.into(),
+    request.is_ready,
+  ) {
+    return;
+  }
+  let message = InboundServerMessage::PlayerReadyChanged {
+    player_id: request.player_id.0,
+    is_ready: request.is_ready,
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
+}
+
+/// Returns the name with which the chat messages of the given client are shown: the name of the first player it has
+/// registered, or [`SPECTATOR_CHAT_NAME`] if it hasn't registered any. Names are validated when players register, so
+/// they can be used as they are.
+fn chat_sender_name(lobby: &Lobby, registered_players: &RegisteredPlayers, client_id: ClientId) -> String {
+  lobby
+    .get_registered_players_cloned(&client_id)
+    .into_iter()
+    .find_map(|player_id| {
+      registered_players
+        .players
+        .iter()
+        .find(|player| player.id.0 == u8::from(player_id))
+        .map(|player| player.name.clone())
+    })
+    .unwrap_or_else(|| SPECTATOR_CHAT_NAME.to_string())
+}
+
+/// Broadcasts a chat message to all clients and writes it locally, so that it is also displayed on the server.
+fn broadcast_chat_message(
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  chat_message: &mut MessageWriter<ChatMessage>,
+  sender_name: String,
+  text: String,
+) {
+  let message = InboundServerMessage::Chat {
+    sender_name: sender_name.clone(),
+    text: text.clone(),
+  };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
+  chat_message.write(ChatMessage { sender_name, text });
+}
+
+/// A system that broadcasts the chat messages of the local player on the server to all clients.
+fn broadcast_local_chat_message_system(
+  mut messages: MessageReader<SendChatMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut chat_message: MessageWriter<ChatMessage>,
+  player_name: Res<PlayerName>,
+) {
+  for message in messages.read() {
+    let Some(text) = validation::validate_chat_text(&message.text).value() else {
+      continue;
+    };
+    broadcast_chat_message(
+      &mut outbound_server_message,
+      &mut chat_message,
+      player_name.get().to_string(),
+      text,
+    );
+  }
+}
+
+/// Records a violation of the given client. Informs the client why it is being kicked and disconnects it once it has
+/// committed too many violations.
+fn record_violation(
+  moderation: &mut ClientModeration,
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  player_registration_message: &mut MessageWriter<PlayerRegistrationMessage>,
+  client_id: ClientId,
+  violation: Violation,
+) {
+  warn!("Client [{}] {}", client_id, violation);
+  if !moderation.client_violations.record(client_id) {
+    return;
+  }
+  warn!("Disconnecting client [{}] after too many violations", client_id);
+  kick_client(
+    outbound_server_message,
+    registered_players,
+    player_registration_message,
+    &mut moderation.reserved_slots,
+    &mut moderation.lobby,
+    client_id,
+    DisconnectReason::TooManyViolations,
+  );
+}
+
+/// Informs the given client why it is being disconnected, disconnects it and removes all of its players. Since its
+/// [`ReconnectToken`] is revoked, the client can't reclaim its players by reconnecting. Returns the revoked token, if
+/// any.
+fn kick_client(
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  player_registration_message: &mut MessageWriter<PlayerRegistrationMessage>,
+  reserved_slots: &mut ReservedSlots,
+  lobby: &mut ResMut<Lobby>,
+  client_id: ClientId,
+  reason: DisconnectReason,
+) -> Option<ReconnectToken> {
+  let token = lobby.revoke_reconnect_token(client_id);
+  if let Some(token) = token {
+    reserved_slots.grace_periods.remove(&token);
+  }
+  outbound_server_message.write(OutboundServerMessage::send(
+    client_id,
+    &InboundServerMessage::Kicked { reason },
+  ));
+  outbound_server_message.write(OutboundServerMessage::Disconnect { client_id });
+  unregister_all_players_of_client(
+    outbound_server_message,
+    registered_players,
+    player_registration_message,
+    client_id,
+    lobby,
+  );
+  token
+}
+
+/// Processes the host's requests to kick or ban the client that registered a player. The client loses all of its
+/// players and can't reclaim them. A banned client's token is remembered so that any attempt to reconnect with it is
+/// refused.
+fn handle_moderate_player_message(
+  mut messages: MessageReader<ModeratePlayerMessage>,
+  mut lobby: ResMut<Lobby>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut reserved_slots: ResMut<ReservedSlots>,
+  mut banned_clients: ResMut<BannedClients>,
+  mut client_banned_message: MessageWriter<ClientBannedMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+) {
+  for message in messages.read() {
+    let Some(client_id) = lobby.get_client_id_by_player_id(&message.player_id.into()) else {
+      warn!("Unable to {:?} unknown player [{}]", message.action, message.player_id);
+      continue;
+    };
+    if client_id == host_client_id() {
+      warn!(
+        "Ignoring request to {:?} player [{}] of the host",
+        message.action, message.player_id
+      );
+      continue;
+    }
+
+    let (reason, notification) = match message.action {
+      ModerationAction::Kick => (DisconnectReason::KickedByHost, PLAYER_KICKED_NOTIFICATION),
+      ModerationAction::Ban => (DisconnectReason::BannedByHost, PLAYER_BANNED_NOTIFICATION),
+    };
+    info!("Removing client [{}] from the lobby: {:?}", client_id, reason);
+    let token = kick_client(
+      &mut outbound_server_message,
+      &mut registered_players,
+      &mut player_registration_message,
+      &mut reserved_slots,
+      &mut lobby,
+      client_id,
+      reason,
+    );
+    if message.action == ModerationAction::Ban {
+      banned_clients.0.extend(token);
+      client_banned_message.write(ClientBannedMessage { client_id });
     }
+    ui_notification.write(UiNotification::info(notification.to_string()));
   }
 }
 
@@ -289,6 +798,10 @@ fn handle_inbound_server_message(
   mut registered_players: ResMut<RegisteredPlayers>,
   mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
   mut ui_notification: MessageWriter<UiNotification>,
+  mut reserved_slots: ResMut<ReservedSlots>,
+  rules: Res<GameRules>,
+  mut applied_input_sequences: ResMut<AppliedInputSequences>,
+  mut client_violations: ResMut<ClientViolations>,
 ) {
   for message in messages.read() {
     match message {
@@ -300,19 +813,21 @@ fn handle_inbound_server_message(
         } else {
           *current_state.get()
         };
-        let seed_message = encode_to_bytes(&InboundServerMessage::ClientInitialised {
+        let client_initialised = InboundServerMessage::ClientInitialised {
           seed: seed.get(),
           client_id: *client_id,
           current_state: target_state.to_string(),
           registered_players: lobby_snapshot(&lobby, &registered_players),
           winner_info: winner.get_as_u8(),
-        })
-        .expect(CLIENT_MESSAGE_SERIALISATION);
-        outbound_server_message.write(OutboundServerMessage::Send {
-          client_id: *client_id,
-          channel: ChannelType::ReliableOrdered,
-          payload: seed_message,
-        });
+          reconnect_token: lobby.issue_reconnect_token(*client_id),
+        };
+        outbound_server_message.write(OutboundServerMessage::send(*client_id, &client_initialised));
+        if !lobby.spectators().is_empty() {
+          let spectators_changed = InboundServerMessage::SpectatorsChanged {
+            spectators: lobby.spectators().to_vec(),
+          };
+          outbound_server_message.write(OutboundServerMessage::send(*client_id, &spectators_changed));
+        }
         broadcast_client_connected(&mut outbound_server_message, *client_id);
         ui_notification.write(UiNotification::info(PLAYER_JOINED_NOTIFICATION.to_string()));
 
@@ -321,21 +836,39 @@ fn handle_inbound_server_message(
           next_state.set(AppState::Initialising);
         }
       }
-      InboundServerMessage::ClientDisconnected { client_id } => {
+      InboundServerMessage::ClientDisconnected { client_id, is_forced } => {
         info!("Client with ID [{}] disconnected", client_id);
+        applied_input_sequences.0.remove(client_id);
+        client_violations.remove(client_id);
 
-        for player_id in lobby.get_registered_players_cloned(client_id) {
-          handle_unregistration_request(
-            &mut outbound_server_message,
-            &mut registered_players,
-            &mut player_registration_message,
-            *client_id,
-            SerialisableUnregistrationRequest { player_id },
-            &mut lobby,
-            false,
+        broadcast_client_disconnected(&mut outbound_server_message, *client_id, *is_forced);
+        if lobby.set_spectating(*client_id, false) {
+          broadcast_spectators_changed(&mut outbound_server_message, &lobby);
+        }
+        if *is_forced {
+          // A client that the server has disconnected must not be able to reclaim its players
+          if let Some(token) = lobby.revoke_reconnect_token(*client_id) {
+            reserved_slots.grace_periods.remove(&token);
+          }
+        } else if let Some(token) = lobby.reserve_slot(*client_id) {
+          info!(
+            "Holding the players of client [{}] for [{}] seconds",
+            client_id, rules.reconnect_grace_period_seconds
+          );
+          reserved_slots.grace_periods.insert(
+            token,
+            Timer::from_seconds(rules.reconnect_grace_period_seconds, TimerMode::Once),
           );
+          ui_notification.write(UiNotification::info(PLAYER_CONNECTION_LOST_NOTIFICATION.to_string()));
+          continue;
         }
-        broadcast_client_disconnected(&mut outbound_server_message, *client_id);
+        unregister_all_players_of_client(
+          &mut outbound_server_message,
+          &mut registered_players,
+          &mut player_registration_message,
+          *client_id,
+          &mut lobby,
+        );
         ui_notification.write(UiNotification::info(PLAYER_LEFT_NOTIFICATION.to_string()));
       }
       _ => {}
@@ -343,19 +876,181 @@ fn handle_inbound_server_message(
   }
 }
 
-fn lobby_snapshot(lobby: &Lobby, registered_players: &RegisteredPlayers) -> Vec<SerialisableRegisteredPlayer> {
-  let mut registrations: Vec<(ClientId, PlayerInLobby)> = lobby
-    .registered
-    .iter()
-    .flat_map(|(registered_client_id, players)| {
-      players
-        .iter()
-        .map(|registration| (*registered_client_id, *registration))
-    })
-    .collect();
-  registrations.sort_by_key(|(_, registration)| (registration.player_id.0, registration.control_scheme_id));
+fn unregister_all_players_of_client(
+  outbound_server_message: &mut MessageWriter<OutboundServerMessage>,
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  player_registration_message: &mut MessageWriter<PlayerRegistrationMessage>,
+  client_id: ClientId,
+  lobby: &mut ResMut<Lobby>,
+) {
+  for player_id in lobby.get_registered_players_cloned(&client_id) {
+    handle_unregistration_request(
+      outbound_server_message,
+      registered_players,
+      player_registration_message,
+      client_id,
+      SerialisableUnregistrationRequest { player_id },
+      lobby,
+      false,
+    );
+  }
+}
 
-  registrations
+/// Informs every client which client takes over as host if the host leaves, whenever a client connects or
+/// disconnects. Only the successor receives the [`ReconnectToken`]s it needs to let the others reclaim their players.
+fn broadcast_host_succession_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  lobby: Res<Lobby>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+) {
+  let has_connections_changed = messages.read().any(|message| {
+    matches!(
+      message,
+      InboundServerMessage::ClientConnected { .. } | InboundServerMessage::ClientDisconnected { .. }
+    )
+  });
+  if !has_connections_changed {
+    return;
+  }
+
+  let successor = lobby.successor();
+  for client_id in &lobby.connected {
+    let reconnect_tokens = if successor == Some(*client_id) {
+      lobby.reconnect_tokens()
+    } else {
+      Vec::new()
+    };
+    let message = InboundServerMessage::HostSuccessionChanged {
+      successor,
+      reconnect_tokens,
+    };
+    outbound_server_message.write(OutboundServerMessage::send(*client_id, &message));
+  }
+}
+
+/// Rebuilds the [`Lobby`] from the [`HostSuccession`] once the local client has taken over as host. The players of
+/// the other clients are held until they reconnect with the tokens issued by the previous host, while the players of
+/// the previous host leave with it. A round in progress can't be continued because the previous host's authoritative
+/// state is lost, so it is ended.
+fn adopt_migrated_lobby_system(
+  mut messages: MessageReader<HostMigrationMessage>,
+  host_succession: Res<HostSuccession>,
+  rules: Res<GameRules>,
+  mut lobby: ResMut<Lobby>,
+  mut reserved_slots: ResMut<ReservedSlots>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
+  current_state: Res<State<AppState>>,
+  mut next_state: ResMut<NextState<AppState>>,
+  mut ui_notification: MessageWriter<UiNotification>,
+) {
+  if messages.read().count() == 0 {
+    return;
+  }
+
+  info!("Taking over as host, adopting the lobby of the previous host");
+  lobby.clear();
+  reserved_slots.grace_periods.clear();
+  let reconnect_tokens: HashMap<ClientId, ReconnectToken> =
+    host_succession.reconnect_tokens().iter().copied().collect();
+  for (player_id, owner, control_scheme_id) in host_succession.player_owners() {
+    if Some(owner) == host_succession.own_client_id() {
+      lobby.register_player(host_client_id(), player_id.into(), control_scheme_id.0);
+    } else if owner != host_client_id() && reconnect_tokens.contains_key(&owner) {
+      lobby.register_player(owner, player_id.into(), control_scheme_id.0);
+    } else {
+      utils::unregister_remote_player_locally(&mut registered_players, &mut player_registration_message, player_id);
+    }
+  }
+  for (client_id, token) in reconnect_tokens {
+    if lobby.reserve_migrated_slot(client_id, token) {
+      reserved_slots.grace_periods.insert(
+        token,
+        Timer::from_seconds(rules.reconnect_grace_period_seconds, TimerMode::Once),
+      );
+    }
+  }
+
+  if *current_state.get() == AppState::Playing {
+    next_state.set(AppState::GameOver);
+  }
+  ui_notification.write(UiNotification::info(HOST_MIGRATED_NOTIFICATION.to_string()));
+}
+
+/// Advances the grace period of every reserved slot and removes the players of any client that hasn't reconnected in
+/// time.
+fn expire_reserved_slots_system(
+  time: Res<Time<Real>>,
+  mut reserved_slots: ResMut<ReservedSlots>,
+  mut lobby: ResMut<Lobby>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+) {
+  let mut expired_tokens = Vec::new();
+  for (token, grace_period) in reserved_slots.grace_periods.iter_mut() {
+    grace_period.tick(time.delta());
+    if grace_period.is_finished() {
+      expired_tokens.push(*token);
+    }
+  }
+
+  for token in expired_tokens {
+    reserved_slots.grace_periods.remove(&token);
+    let Some(client_id) = lobby.release_reserved_slot(&token) else {
+      continue;
+    };
+    info!(
+      "Client [{}] did not reconnect in time, removing their players",
+      client_id
+    );
+    unregister_all_players_of_client(
+      &mut outbound_server_message,
+      &mut registered_players,
+      &mut player_registration_message,
+      client_id,
+      &mut lobby,
+    );
+    ui_notification.write(UiNotification::info(PLAYER_LEFT_NOTIFICATION.to_string()));
+  }
+}
+
+/// Pauses a round in progress while the players of a disconnected client are held for them, if the [`GameRules`] say
+/// so, and resumes it once the client has reconnected, its grace period has expired or the round has ended.
+fn pause_while_reconnecting_system(
+  lobby: Res<Lobby>,
+  rules: Res<GameRules>,
+  current_state: Res<State<AppState>>,
+  mut reserved_slots: ResMut<ReservedSlots>,
+  mut time: ResMut<Time<Virtual>>,
+) {
+  let should_pause =
+    rules.pause_while_reconnecting && lobby.has_reserved_slots() && *current_state.get() == AppState::Playing;
+  if should_pause && !reserved_slots.has_paused_game {
+    info!("Pausing the game until all disconnected clients have reconnected");
+    time.pause();
+    reserved_slots.has_paused_game = true;
+  } else if !should_pause && reserved_slots.has_paused_game {
+    info!("Resuming the game");
+    time.unpause();
+    reserved_slots.has_paused_game = false;
+  }
+}
+
+fn lobby_snapshot(lobby: &Lobby, registered_players: &RegisteredPlayers) -> Vec<SerialisableRegisteredPlayer> {
+  let mut registrations: Vec<(ClientId, PlayerInLobby)> = lobby
+    .registered
+    .iter()
+    .flat_map(|(registered_client_id, players)| {
+      players
+        .iter()
+        .map(|registration| (*registered_client_id, *registration))
+    })
+    .collect();
+  registrations.sort_by_key(|(_, registration)| (registration.player_id.0, registration.control_scheme_id));
+
+  registrations
     .into_iter()
     .filter_map(|(registered_client_id, registration)| {
       let player_id = PlayerId(registration.player_id.0);
@@ -376,6 +1071,7 @@ fn lobby_snapshot(lobby: &Lobby, registered_players: &RegisteredPlayers) -> Vec<
         player_id: registration.player_id.0,
         control_scheme_id: registration.control_scheme_id,
         name: registered_player.name.clone(),
+        is_ready: registered_player.is_ready,
       })
     })
     .collect()
@@ -398,13 +1094,99 @@ fn broadcast_player_states_system(
     return;
   }
 
-  if let Ok(payload) = encode_to_bytes(&InboundServerMessage::UpdatePlayerStates { states }) {
-    outbound_server_message.write(OutboundServerMessage::Broadcast {
-      channel: ChannelType::Unreliable,
-      payload,
-    });
-  } else {
-    warn!("Failed to serialise player states message");
+  outbound_server_message.write(OutboundServerMessage::broadcast(
+    &InboundServerMessage::UpdatePlayerStates { states },
+  ));
+}
+
+/// Broadcasts all authoritative tail changes of this frame to all clients in a single message. Uses a reliable, ordered
+/// channel because clients build their tails from these events incrementally, so none may be lost or reordered.
+///
+/// Clients that connected mid-round additionally receive a [`InboundServerMessage::WorldSnapshot`]. It is sent from
+/// this system, after the tail changes, so that every tail change is either contained in the snapshot or sent after
+/// it.
+fn broadcast_tail_events_system(
+  mut tail_event_messages: MessageReader<TailEventMessage>,
+  mut inbound_server_messages: MessageReader<InboundServerMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  registered_players: Res<RegisteredPlayers>,
+  snake_head_query: Query<(&Transform, &PlayerId), (Without<Player>, Without<SnakeTail>)>,
+  snake_tail_query: Query<(&SnakeTail, &PlayerId)>,
+) {
+  let events: Vec<SerialisableTailEvent> = tail_event_messages.read().map(SerialisableTailEvent::from).collect();
+  if !events.is_empty() {
+    outbound_server_message.write(OutboundServerMessage::broadcast(&InboundServerMessage::UpdateTails {
+      events,
+    }));
+  }
+
+  for message in inbound_server_messages.read() {
+    let InboundServerMessage::ClientConnected { client_id } = message else {
+      continue;
+    };
+    let players = world_snapshot(&registered_players, &snake_head_query, &snake_tail_query);
+    debug!(
+      "Sending world snapshot of [{}] players to [{}]",
+      players.len(),
+      client_id
+    );
+    let message = InboundServerMessage::WorldSnapshot { players };
+    outbound_server_message.write(OutboundServerMessage::send(*client_id, &message));
+  }
+}
+
+/// Captures the head transform, alive status and tail of every player in the current round. Heads of eliminated players
+/// no longer have the [`SnakeHead`] component, which is why heads are identified as the entities with a [`PlayerId`]
+/// that are neither the [`Player`] nor its [`SnakeTail`].
+fn world_snapshot(
+  registered_players: &RegisteredPlayers,
+  snake_head_query: &Query<(&Transform, &PlayerId), (Without<Player>, Without<SnakeTail>)>,
+  snake_tail_query: &Query<(&SnakeTail, &PlayerId)>,
+) -> Vec<SerialisablePlayerSnapshot> {
+  snake_head_query
+    .iter()
+    .map(|(transform, player_id)| {
+      let (_, _, rotation_z) = transform.rotation.to_euler(bevy::math::EulerRot::XYZ);
+      let alive = registered_players
+        .players
+        .iter()
+        .any(|player| player.id == *player_id && player.alive);
+      let (segments, is_in_gap) = snake_tail_query
+        .iter()
+        .find(|(_, tail_player_id)| *tail_player_id == player_id)
+        .map(|(snake_tail, _)| {
+          let segments = snake_tail
+            .segments
+            .iter()
+            .map(|segment| {
+              segment
+                .positions()
+                .iter()
+                .map(|position| (position.x, position.y))
+                .collect()
+            })
+            .collect();
+          (segments, snake_tail.gap_samples_remaining > 0)
+        })
+        .unwrap_or_default();
+      SerialisablePlayerSnapshot {
+        player_id: player_id.0,
+        head: (transform.translation.x, transform.translation.y, rotation_z),
+        alive,
+        segments,
+        is_in_gap,
+      }
+    })
+    .collect()
+}
+
+/// Broadcasts every elimination to all clients so that they can stop the snake immediately and show the kill feed.
+fn broadcast_player_eliminated_system(
+  mut player_eliminated_messages: MessageReader<PlayerEliminatedMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+) {
+  for message in player_eliminated_messages.read() {
+    outbound_server_message.write(OutboundServerMessage::broadcast(&InboundServerMessage::from(message)));
   }
 }
 
@@ -416,6 +1198,7 @@ fn handle_local_player_registration_request_message(
   mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
   mut outbound_server_message: MessageWriter<OutboundServerMessage>,
   player_name: Res<PlayerName>,
+  rules: Res<GameRules>,
 ) {
   for request in messages.read() {
     if request.has_registered {
@@ -429,6 +1212,7 @@ fn handle_local_player_registration_request_message(
         &mut lobby,
         true,
         player_name.get().to_string(),
+        rules.max_players,
       );
       continue;
     }
@@ -460,6 +1244,66 @@ fn handle_local_player_registration_request_message(
   }
 }
 
+/// A system that handles local requests to mark the host's players as ready or not ready.
+fn handle_local_player_ready_request_message(
+  mut messages: MessageReader<LocalPlayerReadyRequestMessage>,
+  lobby: Res<Lobby>,
+  mut registered_players: ResMut<RegisteredPlayers>,
+  mut player_registration_message: MessageWriter<PlayerRegistrationMessage>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+) {
+  for request in messages.read() {
+    handle_ready_request(
+      &mut outbound_server_message,
+      &mut registered_players,
+      &mut player_registration_message,
+      host_client_id(),
+      SerialisableReadyRequest {
+        player_id: request.player_id.into(),
+        is_ready: request.is_ready,
+      },
+      &lobby,
+    );
+  }
+}
+
+/// Starts the round automatically once all players have been ready for [`GameRules::ready_countdown_seconds`], if set.
+/// Informs all clients when the countdown starts and when it is cancelled because a player is no longer ready.
+fn ready_countdown_system(
+  time: Res<Time<Real>>,
+  rules: Res<GameRules>,
+  registered_players: Res<RegisteredPlayers>,
+  mut ready_countdown: ResMut<ReadyCountdown>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut ui_notification: MessageWriter<UiNotification>,
+  mut continue_message: MessageWriter<ContinueMessage>,
+) {
+  let countdown_seconds = rules
+    .ready_countdown_seconds
+    .filter(|_| registered_players.are_all_ready());
+  let seconds = match (countdown_seconds, &mut ready_countdown.0) {
+    (Some(_), Some(timer)) => {
+      if timer.tick(time.delta()).just_finished() {
+        info!("All players are ready, starting the round");
+        continue_message.write(ContinueMessage);
+      }
+      return;
+    }
+    (None, None) => return,
+    (seconds, countdown) => {
+      *countdown = seconds.map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+      seconds
+    }
+  };
+  let message = InboundServerMessage::ReadyCountdownChanged { seconds };
+  outbound_server_message.write(OutboundServerMessage::broadcast(&message));
+  ui_notification.write(utils::ready_countdown_notification(seconds));
+}
+
+fn reset_ready_countdown_system(mut ready_countdown: ResMut<ReadyCountdown>) {
+  ready_countdown.0 = None;
+}
+
 /// A system that handles local state change events and broadcasts them to all connected clients.
 fn handle_local_state_transition_event(
   mut messages: MessageReader<StateTransitionEvent<AppState>>,
@@ -473,15 +1317,7 @@ fn handle_local_state_transition_event(
         winner_info: winner.get_as_u8(),
       };
       debug!("Broadcasting: {:?}", server_event);
-      if let Ok(payload) = encode_to_bytes(&server_event) {
-        outbound_server_message.write(OutboundServerMessage::Broadcast {
-          channel: ChannelType::ReliableOrdered,
-          payload,
-        });
-      } else {
-        warn!("{}: {:?}", CLIENT_MESSAGE_SERIALISATION, server_event);
-        return;
-      }
+      outbound_server_message.write(OutboundServerMessage::broadcast(&server_event));
     }
   }
 }
@@ -495,11 +1331,7 @@ fn process_and_broadcast_local_exit_lobby_message(
 ) {
   for _ in messages.read() {
     info!("Informing all clients about intention to shut down server and scheduling shutdown...");
-    let payload = encode_to_bytes(&InboundServerMessage::ShutdownServer).expect(CLIENT_MESSAGE_SERIALISATION);
-    outbound_server_message.write(OutboundServerMessage::Broadcast {
-      channel: ChannelType::ReliableOrdered,
-      payload,
-    });
+    outbound_server_message.write(OutboundServerMessage::broadcast(&InboundServerMessage::ShutdownServer));
     commands.insert_resource(ShutdownCountdown(Timer::new(
       Duration::from_millis(500),
       TimerMode::Once,
@@ -518,11 +1350,13 @@ fn disconnect_all_clients_system(
   mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
   mut next_app_state: ResMut<NextState<AppState>>,
   mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+  mut banned_clients: ResMut<BannedClients>,
 ) {
   countdown.0.tick(time.delta());
   if countdown.0.just_finished() {
     info!("Disconnecting all clients now...");
     lobby.clear();
+    banned_clients.0.clear();
     registered_players.clear();
     outbound_server_message.write(OutboundServerMessage::DisconnectAll);
     commands.remove_resource::<ShutdownCountdown>();
@@ -543,7 +1377,8 @@ mod tests {
   use bevy::prelude::*;
   use bevy::state::app::StatesPlugin;
   use mooplas_networking::prelude::{
-    NetworkingMessagesPlugin, NetworkingResourcesPlugin, SerialisableRegistrationRequest, decode_from_bytes,
+    ChannelType, ClientNetworkingActive, NetworkRole, NetworkingMessagesPlugin, NetworkingResourcesPlugin,
+    SerialisableChatMessage, SerialisableInput, SerialisableRegistrationRequest, decode_from_bytes,
   };
 
   fn setup() -> App {
@@ -557,6 +1392,11 @@ mod tests {
       NetworkingResourcesPlugin,
       AppStatePlugin,
     ));
+    app.init_resource::<ReservedSlots>();
+    app.init_resource::<AppliedInputSequences>();
+    app.init_resource::<ClientViolations>();
+    app.init_resource::<BannedClients>();
+    app.init_resource::<ReadyCountdown>();
     app
   }
 
@@ -771,6 +1611,7 @@ mod tests {
       .world_mut()
       .write_message(InboundServerMessage::ClientDisconnected {
         client_id: disconnected_client_id,
+        is_forced: false,
       })
       .expect("Failed to queue ClientDisconnected message");
     app.update();
@@ -796,7 +1637,7 @@ mod tests {
         ..
       } if *except_client_id == disconnected_client_id => matches!(
         decode_from_bytes::<InboundServerMessage>(payload),
-        Ok(InboundServerMessage::ClientDisconnected { client_id }) if client_id == disconnected_client_id
+        Ok(InboundServerMessage::ClientDisconnected { client_id, .. }) if client_id == disconnected_client_id
       ),
       _ => false,
     });
@@ -808,118 +1649,1140 @@ mod tests {
   }
 
   #[test]
-  fn reinitialise_keeps_active_seed_unchanged() {
-    let mut app = setup();
-    app.world_mut().resource_mut::<Seed>().set(41);
-    app.add_systems(Update, reinitialise);
-
-    app.update();
-
-    assert_eq!(app.world().resource::<Seed>().get(), 41);
-  }
-
-  #[test]
-  fn broadcast_player_states_system_sends_state_updates_for_all_snake_heads() {
+  fn disconnected_client_can_reclaim_its_players_with_reconnect_token() {
     let mut app = setup();
-    app.add_systems(Update, broadcast_player_states_system);
+    app.add_systems(Update, (handle_inbound_server_message, handle_inbound_client_message));
+    let previous_client_id = ClientId::from_u64(7);
+    let new_client_id = ClientId::from_u64(8);
+    {
+      let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
+      registered_players
+        .register(crate::prelude::RegisteredPlayer::new_immutable(
+          PlayerId(0),
+          "Remote".to_string(),
+          ControlScheme::test(0),
+          Color::WHITE,
+        ))
+        .expect("Remote player should register");
+    }
+    let token = {
+      let mut lobby = app.world_mut().resource_mut::<Lobby>();
+      lobby.register_player(previous_client_id, PlayerId(0).into(), 0);
+      lobby.issue_reconnect_token(previous_client_id)
+    };
 
     app
       .world_mut()
-      .spawn((Transform::from_xyz(100.0, 200.0, 0.0), PlayerId(1), SnakeHead));
-    app
-      .world_mut()
-      .spawn((Transform::from_xyz(150.0, 250.0, 0.0), PlayerId(2), SnakeHead));
+      .write_message(InboundServerMessage::ClientDisconnected {
+        client_id: previous_client_id,
+        is_forced: false,
+      })
+      .expect("Failed to queue ClientDisconnected message");
     app.update();
 
-    let messages = app
-      .world_mut()
-      .get_resource_mut::<Messages<OutboundServerMessage>>()
-      .expect("Messages<OutgoingServerMessage> missing");
-    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
-    assert_eq!(message_vec.len(), 1);
-    match &message_vec[0] {
-      OutboundServerMessage::Broadcast { channel, payload } => {
-        assert!(matches!(channel, ChannelType::Unreliable));
-        assert!(!payload.is_empty());
-      }
-      _ => panic!("Expected broadcast message"),
-    }
-  }
+    assert_eq!(app.world().resource::<RegisteredPlayers>().count(), 1);
+    assert!(app.world().resource::<Lobby>().has_reserved_slots());
+    assert!(
+      app
+        .world()
+        .resource::<ReservedSlots>()
+        .grace_periods
+        .contains_key(&token)
+    );
 
-  #[test]
-  fn broadcast_player_states_system_does_not_send_when_no_snake_heads() {
-    let mut app = setup();
-    app.add_systems(Update, broadcast_player_states_system);
+    app
+      .world_mut()
+      .write_message(InboundClientMessage::Reconnect(token, new_client_id))
+      .expect("Failed to queue Reconnect message");
     app.update();
 
-    let messages = app
-      .world_mut()
-      .get_resource_mut::<Messages<OutboundServerMessage>>()
-      .expect("Messages<OutgoingServerMessage> missing");
-    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
-    assert_eq!(message_vec.len(), 0);
+    let lobby = app.world().resource::<Lobby>();
+    assert!(!lobby.has_reserved_slots());
+    assert!(lobby.validate_registration(&new_client_id, &PlayerId(0).into()));
+    assert!(app.world().resource::<ReservedSlots>().grace_periods.is_empty());
+    let messages = app.world().resource::<Messages<OutboundServerMessage>>();
+    let reclaimed_player_ids: Vec<_> = messages
+      .iter_current_update_messages()
+      .find_map(|message| match message {
+        OutboundServerMessage::Send { client_id, payload, .. } if *client_id == new_client_id => {
+          match decode_from_bytes::<InboundServerMessage>(payload) {
+            Ok(InboundServerMessage::ReconnectAccepted { registered_players }) => Some(registered_players),
+            _ => None,
+          }
+        }
+        _ => None,
+      })
+      .expect("Expected ReconnectAccepted to be sent to reconnected client")
+      .iter()
+      .map(|player| player.player_id)
+      .collect();
+    assert_eq!(reclaimed_player_ids, vec![0]);
   }
 
   #[test]
-  fn handle_inbound_client_message_assigns_next_available_player_id() {
+  fn forcibly_disconnected_client_can_not_reclaim_its_players() {
     let mut app = setup();
-    add_control_schemes(&mut app, 3);
-    app.add_systems(Update, handle_inbound_client_message);
-
+    app.add_systems(Update, (handle_inbound_server_message, handle_inbound_client_message));
+    let previous_client_id = ClientId::from_u64(7);
     {
       let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
       registered_players
-        .register(crate::prelude::RegisteredPlayer::new_mutable(
+        .register(crate::prelude::RegisteredPlayer::new_immutable(
           PlayerId(0),
-          "Player 0".to_string(),
+          "Remote".to_string(),
           ControlScheme::test(0),
           Color::WHITE,
         ))
-        .expect("Host player should register");
+        .expect("Remote player should register");
     }
+    let token = {
+      let mut lobby = app.world_mut().resource_mut::<Lobby>();
+      lobby.register_player(previous_client_id, PlayerId(0).into(), 0);
+      lobby.issue_reconnect_token(previous_client_id)
+    };
 
     app
       .world_mut()
-      .write_message(InboundClientMessage::RegistrationRequest(
-        SerialisableRegistrationRequest {
-          control_scheme_id: 0,
-          name: "Test".to_string(),
-        },
-        ClientId::from_u64(42),
-      ))
-      .expect("Failed to queue InboundClientMessage");
+      .write_message(InboundServerMessage::ClientDisconnected {
+        client_id: previous_client_id,
+        is_forced: true,
+      })
+      .expect("Failed to queue ClientDisconnected message");
     app.update();
 
-    let registered_players = app.world().resource::<RegisteredPlayers>();
-    let remote_player = registered_players
-      .players
-      .iter()
-      .find(|player| player.id == PlayerId(1))
-      .expect("Expected a server-assigned player ID");
-    assert!(remote_player.is_remote());
-    assert_eq!(remote_player.input.id, ControlSchemeId(0));
+    assert_eq!(app.world().resource::<RegisteredPlayers>().count(), 0);
+    assert!(!app.world().resource::<Lobby>().has_reserved_slots());
+    assert!(app.world().resource::<ReservedSlots>().grace_periods.is_empty());
 
-    let messages = app
+    let new_client_id = ClientId::from_u64(8);
+    app
       .world_mut()
-      .get_resource_mut::<Messages<OutboundServerMessage>>()
-      .expect("Messages<OutgoingServerMessage> missing");
-    let broadcasts: Vec<_> = messages.iter_current_update_messages().collect();
-    assert_eq!(broadcasts.len(), 1);
-    match broadcasts[0] {
-      OutboundServerMessage::Broadcast { payload, .. } => {
-        let decoded: InboundServerMessage = decode_from_bytes(payload).expect("Expected decodable payload");
-        assert!(matches!(
-          decoded,
-          InboundServerMessage::PlayerRegistered {
-            client_id,
-            player_id: 1,
-            control_scheme_id: 0,
-            ..
-          } if client_id == ClientId::from_u64(42)
-        ));
-      }
-      _ => panic!("Expected a broadcast message"),
-    }
+      .write_message(InboundClientMessage::Reconnect(token, new_client_id))
+      .expect("Failed to queue Reconnect message");
+    app.update();
+
+    assert!(
+      !app
+        .world()
+        .resource::<Lobby>()
+        .validate_registration(&new_client_id, &PlayerId(0).into())
+    );
+  }
+
+  #[test]
+  fn handle_moderate_player_message_bans_client_and_refuses_its_reconnect() {
+    let mut app = setup();
+    app.add_systems(
+      Update,
+      (handle_moderate_player_message, handle_inbound_client_message).chain(),
+    );
+    let banned_client_id = ClientId::from_u64(7);
+    {
+      let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
+      for player_id in [PlayerId(1), PlayerId(2)] {
+        registered_players
+          .register(crate::prelude::RegisteredPlayer::new_immutable(
+            player_id,
+            "Remote".to_string(),
+            ControlScheme::test(player_id.0),
+            Color::WHITE,
+          ))
+          .expect("Remote player should register");
+      }
+    }
+    let token = {
+      let mut lobby = app.world_mut().resource_mut::<Lobby>();
+      lobby.register_player(banned_client_id, PlayerId(1).into(), 0);
+      lobby.register_player(banned_client_id, PlayerId(2).into(), 1);
+      lobby.issue_reconnect_token(banned_client_id)
+    };
+
+    app
+      .world_mut()
+      .write_message(ModeratePlayerMessage {
+        player_id: PlayerId(2),
+        action: ModerationAction::Ban,
+      })
+      .expect("Failed to queue ModeratePlayerMessage");
+    app.update();
+
+    assert_eq!(app.world().resource::<RegisteredPlayers>().count(), 0);
+    assert!(
+      app
+        .world()
+        .resource::<Lobby>()
+        .get_registered_players_cloned(&banned_client_id)
+        .is_empty()
+    );
+    let banned_messages: Vec<ClientBannedMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<ClientBannedMessage>>()
+      .drain()
+      .collect();
+    assert_eq!(
+      banned_messages,
+      vec![ClientBannedMessage {
+        client_id: banned_client_id
+      }]
+    );
+    let outbound_messages: Vec<OutboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .collect();
+    assert!(outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Send { client_id, payload, .. } if *client_id == banned_client_id && matches!(
+        decode_from_bytes(payload),
+        Ok(InboundServerMessage::Kicked { reason: DisconnectReason::BannedByHost })
+      )
+    )));
+    assert!(outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Disconnect { client_id } if *client_id == banned_client_id
+    )));
+
+    let new_client_id = ClientId::from_u64(8);
+    app
+      .world_mut()
+      .write_message(InboundClientMessage::Reconnect(token, new_client_id))
+      .expect("Failed to queue Reconnect message");
+    app.update();
+
+    let outbound_messages: Vec<OutboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .collect();
+    assert!(outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Disconnect { client_id } if *client_id == new_client_id
+    )));
+    assert!(!outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Send { payload, .. }
+        if matches!(decode_from_bytes(payload), Ok(InboundServerMessage::ReconnectAccepted { .. }))
+    )));
+  }
+
+  #[test]
+  fn handle_moderate_player_message_ignores_players_of_the_host() {
+    let mut app = setup();
+    app.add_systems(Update, handle_moderate_player_message);
+    app
+      .world_mut()
+      .resource_mut::<Lobby>()
+      .register_player(host_client_id(), PlayerId(0).into(), 0);
+
+    app
+      .world_mut()
+      .write_message(ModeratePlayerMessage {
+        player_id: PlayerId(0),
+        action: ModerationAction::Kick,
+      })
+      .expect("Failed to queue ModeratePlayerMessage");
+    app.update();
+
+    assert!(
+      app
+        .world()
+        .resource::<Lobby>()
+        .validate_registration(&host_client_id(), &PlayerId(0).into())
+    );
+    assert!(
+      app
+        .world()
+        .resource::<Messages<OutboundServerMessage>>()
+        .iter_current_update_messages()
+        .next()
+        .is_none()
+    );
+  }
+
+  #[test]
+  fn handle_inbound_client_message_only_lets_clients_ready_their_own_players() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 2);
+    app.add_systems(Update, handle_inbound_client_message);
+    set_app_state(&mut app, AppState::Registering);
+    let client_id = ClientId::from_u64(1);
+    let other_client_id = ClientId::from_u64(2);
+    for (owner, player_id) in [(client_id, 0), (other_client_id, 1)] {
+      app
+        .world_mut()
+        .resource_mut::<Lobby>()
+        .register_player(owner, PlayerId(player_id).into(), player_id);
+      app
+        .world_mut()
+        .resource_mut::<RegisteredPlayers>()
+        .register(crate::prelude::RegisteredPlayer::new_immutable(
+          PlayerId(player_id),
+          format!("Player {player_id}"),
+          ControlScheme::test(player_id),
+          Color::WHITE,
+        ))
+        .expect("Player should register");
+    }
+
+    for player_id in [0, 1] {
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::ReadyRequest(
+          SerialisableReadyRequest {
+            player_id: PlayerId(player_id).into(),
+            is_ready: true,
+          },
+          client_id,
+        ))
+        .expect("Failed to queue ready request");
+    }
+    app.update();
+
+    let registered_players = app.world().resource::<RegisteredPlayers>();
+    let readiness: Vec<bool> = registered_players
+      .players
+      .iter()
+      .map(|player| player.is_ready)
+      .collect();
+    assert_eq!(readiness, vec![true, false]);
+    let broadcasts: Vec<InboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .filter_map(|message| match message {
+        OutboundServerMessage::Broadcast { payload, .. } => decode_from_bytes(&payload).ok(),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(broadcasts.len(), 1);
+    assert!(matches!(
+      broadcasts[0],
+      InboundServerMessage::PlayerReadyChanged {
+        player_id: 0,
+        is_ready: true
+      }
+    ));
+  }
+
+  #[test]
+  fn ready_countdown_system_starts_countdown_once_all_players_are_ready_and_cancels_it() {
+    let mut app = setup();
+    app.add_systems(Update, ready_countdown_system);
+    app.world_mut().resource_mut::<GameRules>().ready_countdown_seconds = Some(5.);
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_immutable(
+        PlayerId(0),
+        "Player 0".to_string(),
+        ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Player should register");
+    let countdown_broadcasts = |app: &mut App| -> Vec<Option<f32>> {
+      app
+        .world_mut()
+        .resource_mut::<Messages<OutboundServerMessage>>()
+        .drain()
+        .filter_map(|message| match message {
+          OutboundServerMessage::Broadcast { payload, .. } => match decode_from_bytes(&payload) {
+            Ok(InboundServerMessage::ReadyCountdownChanged { seconds }) => Some(seconds),
+            _ => None,
+          },
+          _ => None,
+        })
+        .collect()
+    };
+
+    app.update();
+    assert!(countdown_broadcasts(&mut app).is_empty());
+    assert!(app.world().resource::<ReadyCountdown>().0.is_none());
+
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .set_ready(PlayerId(0), true);
+    app.update();
+    assert_eq!(countdown_broadcasts(&mut app), vec![Some(5.)]);
+    assert!(app.world().resource::<ReadyCountdown>().0.is_some());
+
+    app.update();
+    assert!(countdown_broadcasts(&mut app).is_empty());
+
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .set_ready(PlayerId(0), false);
+    app.update();
+    assert_eq!(countdown_broadcasts(&mut app), vec![None]);
+    assert!(app.world().resource::<ReadyCountdown>().0.is_none());
+  }
+
+  #[test]
+  fn adopt_migrated_lobby_system_reserves_players_of_other_clients_and_drops_previous_host_players() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 3);
+    app.add_plugins(crate::online::host_migration::HostMigrationPlugin);
+    app.add_systems(Update, adopt_migrated_lobby_system);
+    app.insert_resource(ClientNetworkingActive);
+    *app.world_mut().resource_mut::<NetworkRole>() = NetworkRole::Client;
+    let own_client_id = ClientId::from_u64(7);
+    let other_client_id = ClientId::from_u64(8);
+    let other_token = ReconnectToken::new_random();
+    {
+      let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
+      for (player_id, name) in [(0, "Previous host"), (1, "Own"), (2, "Other")] {
+        registered_players
+          .register(crate::prelude::RegisteredPlayer::new_immutable(
+            PlayerId(player_id),
+            name.to_string(),
+            ControlScheme::test(player_id),
+            Color::WHITE,
+          ))
+          .expect("Player should register");
+      }
+    }
+    let registered_player = |client_id, player_id: u8| SerialisableRegisteredPlayer {
+      client_id,
+      player_id,
+      control_scheme_id: player_id,
+      name: String::new(),
+      is_ready: false,
+    };
+    for message in [
+      InboundServerMessage::ClientInitialised {
+        seed: 1,
+        client_id: own_client_id,
+        current_state: "Registering".to_string(),
+        registered_players: vec![
+          registered_player(host_client_id(), 0),
+          registered_player(own_client_id, 1),
+          registered_player(other_client_id, 2),
+        ],
+        winner_info: None,
+        reconnect_token: ReconnectToken::new_random(),
+      },
+      InboundServerMessage::HostSuccessionChanged {
+        successor: Some(own_client_id),
+        reconnect_tokens: vec![(other_client_id, other_token)],
+      },
+    ] {
+      app
+        .world_mut()
+        .write_message(message)
+        .expect("Failed to write InboundServerMessage");
+    }
+    app.update();
+
+    app
+      .world_mut()
+      .write_message(HostMigrationMessage)
+      .expect("Failed to write HostMigrationMessage");
+    app.update();
+
+    let lobby = app.world().resource::<Lobby>();
+    assert!(lobby.validate_registration(&host_client_id(), &PlayerId(1).into()));
+    assert!(lobby.validate_registration(&other_client_id, &PlayerId(2).into()));
+    assert_eq!(lobby.get_client_id_by_player_id(&PlayerId(0).into()), None);
+    assert_eq!(lobby.reconnect_tokens(), vec![(other_client_id, other_token)]);
+    assert!(
+      app
+        .world()
+        .resource::<ReservedSlots>()
+        .grace_periods
+        .contains_key(&other_token)
+    );
+    let registered_player_ids: Vec<_> = app
+      .world()
+      .resource::<RegisteredPlayers>()
+      .players
+      .iter()
+      .map(|player| player.id)
+      .collect();
+    assert!(!registered_player_ids.contains(&PlayerId(0)));
+  }
+
+  #[test]
+  fn reinitialise_keeps_active_seed_unchanged() {
+    let mut app = setup();
+    app.world_mut().resource_mut::<Seed>().set(41);
+    app.add_systems(Update, reinitialise);
+
+    app.update();
+
+    assert_eq!(app.world().resource::<Seed>().get(), 41);
+  }
+
+  #[test]
+  fn broadcast_player_states_system_sends_state_updates_for_all_snake_heads() {
+    let mut app = setup();
+    app.add_systems(Update, broadcast_player_states_system);
+
+    app
+      .world_mut()
+      .spawn((Transform::from_xyz(100.0, 200.0, 0.0), PlayerId(1), SnakeHead));
+    app
+      .world_mut()
+      .spawn((Transform::from_xyz(150.0, 250.0, 0.0), PlayerId(2), SnakeHead));
+    app.update();
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutgoingServerMessage> missing");
+    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
+    assert_eq!(message_vec.len(), 1);
+    match &message_vec[0] {
+      OutboundServerMessage::Broadcast { channel, payload } => {
+        assert!(matches!(channel, ChannelType::Unreliable));
+        assert!(!payload.is_empty());
+      }
+      _ => panic!("Expected broadcast message"),
+    }
+  }
+
+  #[test]
+  fn broadcast_player_states_system_does_not_send_when_no_snake_heads() {
+    let mut app = setup();
+    app.add_systems(Update, broadcast_player_states_system);
+    app.update();
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutgoingServerMessage> missing");
+    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
+    assert_eq!(message_vec.len(), 0);
+  }
+
+  #[test]
+  fn broadcast_tail_events_system_sends_all_events_in_order_on_reliable_channel() {
+    let mut app = setup();
+    app.add_systems(Update, broadcast_tail_events_system);
+
+    app
+      .world_mut()
+      .write_message(TailEventMessage::Sampled(PlayerId(1), Vec2::new(10.0, 20.0)))
+      .expect("Failed to write TailEventMessage");
+    app
+      .world_mut()
+      .write_message(TailEventMessage::GapStarted(PlayerId(1)))
+      .expect("Failed to write TailEventMessage");
+    app.update();
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutgoingServerMessage> missing");
+    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
+    assert_eq!(message_vec.len(), 1);
+    match &message_vec[0] {
+      OutboundServerMessage::Broadcast { channel, payload } => {
+        assert!(matches!(channel, ChannelType::ReliableOrdered));
+        let decoded: InboundServerMessage = decode_from_bytes(payload).expect("Failed to decode payload");
+        match decoded {
+          InboundServerMessage::UpdateTails { events } => assert_eq!(
+            events,
+            vec![
+              SerialisableTailEvent::Sampled {
+                player_id: 1,
+                x: 10.0,
+                y: 20.0
+              },
+              SerialisableTailEvent::GapStarted { player_id: 1 },
+            ]
+          ),
+          other => panic!("Expected UpdateTails, got {:?}", other),
+        }
+      }
+      _ => panic!("Expected broadcast message"),
+    }
+  }
+
+  #[test]
+  fn broadcast_tail_events_system_does_not_send_when_no_tail_events() {
+    let mut app = setup();
+    app.add_systems(Update, broadcast_tail_events_system);
+    app.update();
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutgoingServerMessage> missing");
+    assert_eq!(messages.iter_current_update_messages().count(), 0);
+  }
+
+  #[test]
+  fn broadcast_tail_events_system_sends_world_snapshot_to_connected_client() {
+    let mut app = setup();
+    app.add_systems(Update, broadcast_tail_events_system);
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_mutable(
+        PlayerId(1),
+        "Host".to_string(),
+        ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Host player should register");
+    app
+      .world_mut()
+      .spawn((Transform::from_xyz(100.0, 200.0, 0.0), PlayerId(1), SnakeHead));
+    let mut snake_tail = SnakeTail::new(Color::WHITE);
+    snake_tail.segments[0].push_position(Vec2::new(90.0, 200.0));
+    snake_tail.gap_samples_remaining = 3;
+    app.world_mut().spawn((snake_tail, PlayerId(1)));
+
+    let late_client_id = ClientId::from_u64(8);
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::ClientConnected {
+        client_id: late_client_id,
+      })
+      .expect("Failed to queue ClientConnected message");
+    app.update();
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutboundServerMessage> missing");
+    let message_vec: Vec<_> = messages.iter_current_update_messages().collect();
+    assert_eq!(message_vec.len(), 1);
+    match &message_vec[0] {
+      OutboundServerMessage::Send { client_id, payload, .. } => {
+        assert_eq!(*client_id, late_client_id);
+        let decoded: InboundServerMessage = decode_from_bytes(payload).expect("Failed to decode payload");
+        match decoded {
+          InboundServerMessage::WorldSnapshot { players } => assert_eq!(
+            players,
+            vec![SerialisablePlayerSnapshot {
+              player_id: 1,
+              head: (100.0, 200.0, 0.0),
+              alive: true,
+              segments: vec![vec![(90.0, 200.0)]],
+              is_in_gap: true,
+            }]
+          ),
+          other => panic!("Expected WorldSnapshot, got {:?}", other),
+        }
+      }
+      _ => panic!("Expected send message"),
+    }
+  }
+
+  #[test]
+  fn handle_inbound_client_message_assigns_next_available_player_id() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 3);
+    app.add_systems(Update, handle_inbound_client_message);
+
+    {
+      let mut registered_players = app.world_mut().resource_mut::<RegisteredPlayers>();
+      registered_players
+        .register(crate::prelude::RegisteredPlayer::new_mutable(
+          PlayerId(0),
+          "Player 0".to_string(),
+          ControlScheme::test(0),
+          Color::WHITE,
+        ))
+        .expect("Host player should register");
+    }
+
+    app
+      .world_mut()
+      .write_message(InboundClientMessage::RegistrationRequest(
+        SerialisableRegistrationRequest {
+          control_scheme_id: 0,
+          name: "Test".to_string(),
+        },
+        ClientId::from_u64(42),
+      ))
+      .expect("Failed to queue InboundClientMessage");
+    app.update();
+
+    let registered_players = app.world().resource::<RegisteredPlayers>();
+    let remote_player = registered_players
+      .players
+      .iter()
+      .find(|player| player.id == PlayerId(1))
+      .expect("Expected a server-assigned player ID");
+    assert!(remote_player.is_remote());
+    assert_eq!(remote_player.input.id, ControlSchemeId(0));
+
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutgoingServerMessage> missing");
+    let broadcasts: Vec<_> = messages.iter_current_update_messages().collect();
+    assert_eq!(broadcasts.len(), 1);
+    match broadcasts[0] {
+      OutboundServerMessage::Broadcast { payload, .. } => {
+        let decoded: InboundServerMessage = decode_from_bytes(payload).expect("Expected decodable payload");
+        assert!(matches!(
+          decoded,
+          InboundServerMessage::PlayerRegistered {
+            client_id,
+            player_id: 1,
+            control_scheme_id: 0,
+            ..
+          } if client_id == ClientId::from_u64(42)
+        ));
+      }
+      _ => panic!("Expected a broadcast message"),
+    }
+  }
+
+  #[test]
+  fn handle_inbound_client_message_rejects_registration_above_max_players() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 3);
+    app.world_mut().resource_mut::<GameRules>().max_players = 1;
+    app.add_systems(Update, handle_inbound_client_message);
+
+    for client_id in [1, 2] {
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::RegistrationRequest(
+          SerialisableRegistrationRequest {
+            control_scheme_id: 0,
+            name: format!("Client {client_id}"),
+          },
+          ClientId::from_u64(client_id),
+        ))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let registered_players = app.world().resource::<RegisteredPlayers>();
+    assert_eq!(registered_players.count(), 1);
+    assert_eq!(registered_players.players[0].name, "Client 1");
+  }
+
+  #[test]
+  fn handle_inbound_client_message_lets_spectators_watch_without_taking_a_player_slot() {
+    let mut app = setup();
+    add_control_schemes(&mut app, 3);
+    app.world_mut().resource_mut::<GameRules>().max_players = 1;
+    app.add_systems(Update, handle_inbound_client_message);
+    let spectator_client_id = ClientId::from_u64(1);
+    let player_client_id = ClientId::from_u64(2);
+    let registration_request = |client_id| {
+      InboundClientMessage::RegistrationRequest(
+        SerialisableRegistrationRequest {
+          control_scheme_id: 0,
+          name: "Test".to_string(),
+        },
+        client_id,
+      )
+    };
+
+    app
+      .world_mut()
+      .write_message(registration_request(spectator_client_id))
+      .expect("Failed to queue InboundClientMessage");
+    app.update();
+    for message in [
+      InboundClientMessage::SetSpectating(true, spectator_client_id),
+      registration_request(spectator_client_id),
+      registration_request(player_client_id),
+    ] {
+      app
+        .world_mut()
+        .write_message(message)
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let lobby = app.world().resource::<Lobby>();
+    assert_eq!(lobby.spectators(), &[spectator_client_id]);
+    assert!(lobby.get_registered_players_cloned(&spectator_client_id).is_empty());
+    assert!(lobby.validate_registration(&player_client_id, &PlayerId(0).into()));
+    let messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<OutboundServerMessage>>()
+      .expect("Messages<OutboundServerMessage> missing");
+    let spectators: Vec<_> = messages
+      .iter_current_update_messages()
+      .filter_map(|message| match message {
+        OutboundServerMessage::Broadcast { payload, .. } => match decode_from_bytes(payload) {
+          Ok(InboundServerMessage::SpectatorsChanged { spectators }) => Some(spectators),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect();
+    assert_eq!(spectators, vec![vec![spectator_client_id]]);
+
+    // Players can't become spectators during a round, but spectators can become players at any time
+    set_app_state(&mut app, AppState::Playing);
+    for message in [
+      InboundClientMessage::SetSpectating(true, player_client_id),
+      InboundClientMessage::SetSpectating(false, spectator_client_id),
+    ] {
+      app
+        .world_mut()
+        .write_message(message)
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let lobby = app.world().resource::<Lobby>();
+    assert!(lobby.spectators().is_empty());
+    assert!(lobby.validate_registration(&player_client_id, &PlayerId(0).into()));
+  }
+
+  #[test]
+  fn applied_input_sequences_returns_each_frame_once_and_in_order() {
+    let mut applied_input_sequences = AppliedInputSequences::default();
+    let client_id = ClientId::from_u64(1);
+    let frame = |sequence| SerialisableInputFrame {
+      sequence,
+      inputs: Vec::new(),
+    };
+    let sequences = |frames: Vec<&SerialisableInputFrame>| frames.iter().map(|f| f.sequence).collect::<Vec<_>>();
+
+    let first_packet = [frame(1), frame(0)];
+    assert_eq!(
+      sequences(applied_input_sequences.take_new_frames(client_id, &first_packet)),
+      vec![0, 1]
+    );
+    let redundant_packet = [frame(0), frame(1), frame(2)];
+    assert_eq!(
+      sequences(applied_input_sequences.take_new_frames(client_id, &redundant_packet)),
+      vec![2]
+    );
+    let stale_packet = [frame(1)];
+    assert!(
+      applied_input_sequences
+        .take_new_frames(client_id, &stale_packet)
+        .is_empty()
+    );
+    assert_eq!(
+      sequences(applied_input_sequences.take_new_frames(ClientId::from_u64(2), &stale_packet)),
+      vec![1]
+    );
+  }
+
+  #[test]
+  fn handle_inbound_client_message_applies_redundant_inputs_once_and_acknowledges_every_packet() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_client_message);
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .resource_mut::<Lobby>()
+      .register_player(client_id, PlayerId(3).into(), 0);
+    let frame = |sequence, direction| SerialisableInputFrame {
+      sequence,
+      inputs: vec![SerialisableInput::Move(3, direction)],
+    };
+
+    for frames in [
+      vec![frame(0, 1.)],
+      vec![frame(0, 1.), frame(1, -1.)],
+      vec![frame(0, 1.), frame(1, -1.)],
+    ] {
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::Input(frames, client_id))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let directions: Vec<f32> = app
+      .world_mut()
+      .resource_mut::<Messages<InputMessage>>()
+      .drain()
+      .filter_map(|message| match message {
+        InputMessage::Move(PlayerId(3), direction) => Some(direction),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(directions, vec![1., -1.]);
+    let acknowledged_sequences: Vec<u32> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .filter_map(|message| match message {
+        OutboundServerMessage::Send { payload, .. } => match decode_from_bytes(&payload) {
+          Ok(InboundServerMessage::InputAck { sequence }) => Some(sequence),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect();
+    assert_eq!(acknowledged_sequences, vec![0, 1, 1]);
+  }
+
+  #[test]
+  fn handle_inbound_client_message_clamps_invalid_inputs_and_kicks_repeat_offenders() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_client_message);
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .resource_mut::<Lobby>()
+      .register_player(client_id, PlayerId(3).into(), 0);
+
+    for sequence in 0..validation::MAX_VIOLATIONS {
+      let frames = vec![SerialisableInputFrame {
+        sequence,
+        inputs: vec![SerialisableInput::Move(3, 100.)],
+      }];
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::Input(frames, client_id))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let directions: Vec<f32> = app
+      .world_mut()
+      .resource_mut::<Messages<InputMessage>>()
+      .drain()
+      .filter_map(|message| match message {
+        InputMessage::Move(PlayerId(3), direction) => Some(direction),
+        _ => None,
+      })
+      .collect();
+    // The input that gets the client kicked is dropped along with the client's players
+    assert_eq!(directions, vec![1.; validation::MAX_VIOLATIONS as usize - 1]);
+    assert!(
+      app
+        .world()
+        .resource::<Lobby>()
+        .get_registered_players_cloned(&client_id)
+        .is_empty()
+    );
+    let outbound_messages: Vec<OutboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .collect();
+    let was_kicked = outbound_messages.iter().any(|message| match message {
+      OutboundServerMessage::Send { payload, .. } => {
+        matches!(
+          decode_from_bytes(payload),
+          Ok(InboundServerMessage::Kicked {
+            reason: DisconnectReason::TooManyViolations
+          })
+        )
+      }
+      _ => false,
+    });
+    assert!(was_kicked);
+    assert!(outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Disconnect { client_id: id } if *id == client_id
+    )));
+  }
+
+  #[test]
+  fn handle_inbound_client_message_does_not_kick_client_sending_300_inputs_per_second() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_client_message);
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .resource_mut::<Lobby>()
+      .register_player(client_id, PlayerId(3).into(), 0);
+
+    for sequence in 0..300 {
+      let frames = vec![SerialisableInputFrame {
+        sequence,
+        inputs: vec![SerialisableInput::Move(3, 1.)],
+      }];
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::Input(frames, client_id))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    assert_eq!(
+      app
+        .world()
+        .resource::<Lobby>()
+        .get_registered_players_cloned(&client_id)
+        .len(),
+      1
+    );
+    let outbound_messages: Vec<OutboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .collect();
+    assert!(!outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Disconnect { client_id: id } if *id == client_id
+    )));
+    let acknowledgement_count = outbound_messages
+      .iter()
+      .filter(|message| match message {
+        OutboundServerMessage::Send { payload, .. } => {
+          matches!(decode_from_bytes(payload), Ok(InboundServerMessage::InputAck { .. }))
+        }
+        _ => false,
+      })
+      .count();
+    assert_eq!(acknowledgement_count, 300);
+  }
+
+  #[test]
+  fn client_kicked_for_too_many_violations_can_not_reclaim_its_players() {
+    let mut app = setup();
+    app.add_systems(
+      Update,
+      (handle_inbound_client_message, handle_inbound_server_message).chain(),
+    );
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_immutable(
+        PlayerId(3),
+        "Remote".to_string(),
+        ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Remote player should register");
+    let token = {
+      let mut lobby = app.world_mut().resource_mut::<Lobby>();
+      lobby.register_player(client_id, PlayerId(3).into(), 0);
+      lobby.issue_reconnect_token(client_id)
+    };
+
+    for sequence in 0..validation::MAX_VIOLATIONS {
+      let frames = vec![SerialisableInputFrame {
+        sequence,
+        inputs: vec![SerialisableInput::Move(3, 100.)],
+      }];
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::Input(frames, client_id))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+    app
+      .world_mut()
+      .write_message(InboundServerMessage::ClientDisconnected {
+        client_id,
+        is_forced: true,
+      })
+      .expect("Failed to queue ClientDisconnected message");
+    app.update();
+
+    assert_eq!(app.world().resource::<RegisteredPlayers>().count(), 0);
+    assert!(!app.world().resource::<Lobby>().has_reserved_slots());
+    assert!(app.world().resource::<ReservedSlots>().grace_periods.is_empty());
+
+    let new_client_id = ClientId::from_u64(8);
+    app
+      .world_mut()
+      .write_message(InboundClientMessage::Reconnect(token, new_client_id))
+      .expect("Failed to queue Reconnect message");
+    app.update();
+
+    let outbound_messages: Vec<OutboundServerMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .collect();
+    assert!(!outbound_messages.iter().any(|message| matches!(
+      message,
+      OutboundServerMessage::Send { payload, .. }
+        if matches!(decode_from_bytes(payload), Ok(InboundServerMessage::ReconnectAccepted { .. }))
+    )));
+    assert!(
+      app
+        .world()
+        .resource::<Lobby>()
+        .get_registered_players_cloned(&new_client_id)
+        .is_empty()
+    );
+  }
+
+  #[test]
+  fn handle_inbound_client_message_broadcasts_sanitised_chat_messages_and_drops_spam() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_client_message);
+    let client_id = ClientId::from_u64(7);
+    app
+      .world_mut()
+      .resource_mut::<RegisteredPlayers>()
+      .register(crate::prelude::RegisteredPlayer::new_immutable(
+        PlayerId(0),
+        "Alice".to_string(),
+        ControlScheme::test(0),
+        Color::WHITE,
+      ))
+      .expect("Remote player should register");
+    app
+      .world_mut()
+      .resource_mut::<Lobby>()
+      .register_player(client_id, PlayerId(0).into(), 0);
+
+    for _ in 0..10 {
+      app
+        .world_mut()
+        .write_message(InboundClientMessage::Chat(
+          SerialisableChatMessage {
+            text: "gg\n".to_string(),
+          },
+          client_id,
+        ))
+        .expect("Failed to queue InboundClientMessage");
+    }
+    app.update();
+
+    let chat_messages: Vec<ChatMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<ChatMessage>>()
+      .drain()
+      .collect();
+    assert_eq!(chat_messages.len(), 5);
+    assert!(
+      chat_messages
+        .iter()
+        .all(|message| message.sender_name == "Alice" && message.text == "gg")
+    );
+    let broadcast_count = app
+      .world_mut()
+      .resource_mut::<Messages<OutboundServerMessage>>()
+      .drain()
+      .filter(|message| match message {
+        OutboundServerMessage::Broadcast { channel, payload } => {
+          *channel == ChannelType::ReliableOrdered
+            && matches!(decode_from_bytes(payload), Ok(InboundServerMessage::Chat { .. }))
+        }
+        _ => false,
+      })
+      .count();
+    assert_eq!(broadcast_count, 5);
+  }
+
+  #[test]
+  fn handle_inbound_client_message_names_chat_messages_of_clients_without_players_as_spectator() {
+    let mut app = setup();
+    app.add_systems(Update, handle_inbound_client_message);
+
+    app
+      .world_mut()
+      .write_message(InboundClientMessage::Chat(
+        SerialisableChatMessage { text: "hi".to_string() },
+        ClientId::from_u64(7),
+      ))
+      .expect("Failed to queue InboundClientMessage");
+    app.update();
+
+    let chat_messages: Vec<ChatMessage> = app
+      .world_mut()
+      .resource_mut::<Messages<ChatMessage>>()
+      .drain()
+      .collect();
+    assert_eq!(chat_messages.len(), 1);
+    assert_eq!(chat_messages[0].sender_name, SPECTATOR_CHAT_NAME);
   }
 
   #[test]
@@ -1,7 +1,11 @@
-use crate::prelude::{ControlSchemeId, InputMessage, PlayerId};
+use crate::prelude::{
+  ControlSchemeId, EliminationCause, InputMessage, PlayerEliminatedMessage, PlayerId, TailEventMessage,
+};
 use bevy::math::{Quat, Vec2};
 use bevy::prelude::{Component, Resource};
-use mooplas_networking::prelude::SerialisableInput;
+use mooplas_networking::prelude::{
+  InboundServerMessage, SerialisableEliminationCause, SerialisableInput, SerialisableTailEvent,
+};
 use std::collections::HashMap;
 
 /// A component for interpolating network-synchronised transforms, controlled by the server. Used in an attempt to
@@ -76,6 +80,67 @@ impl Into<InputMessage> for &SerialisableInput {
   }
 }
 
+impl From<&TailEventMessage> for SerialisableTailEvent {
+  fn from(value: &TailEventMessage) -> Self {
+    match value {
+      TailEventMessage::Sampled(player_id, position) => SerialisableTailEvent::Sampled {
+        player_id: player_id.0,
+        x: position.x,
+        y: position.y,
+      },
+      TailEventMessage::GapStarted(player_id) => SerialisableTailEvent::GapStarted { player_id: player_id.0 },
+      TailEventMessage::GapEnded(player_id) => SerialisableTailEvent::GapEnded { player_id: player_id.0 },
+    }
+  }
+}
+
+impl Into<TailEventMessage> for &SerialisableTailEvent {
+  fn into(self) -> TailEventMessage {
+    match self {
+      &SerialisableTailEvent::Sampled { player_id, x, y } => {
+        TailEventMessage::Sampled(PlayerId(player_id), Vec2::new(x, y))
+      }
+      &SerialisableTailEvent::GapStarted { player_id } => TailEventMessage::GapStarted(PlayerId(player_id)),
+      &SerialisableTailEvent::GapEnded { player_id } => TailEventMessage::GapEnded(PlayerId(player_id)),
+    }
+  }
+}
+
+impl From<EliminationCause> for SerialisableEliminationCause {
+  fn from(value: EliminationCause) -> Self {
+    match value {
+      EliminationCause::Wall => SerialisableEliminationCause::Wall,
+      EliminationCause::OwnTail => SerialisableEliminationCause::OwnTail,
+      EliminationCause::OtherTail(killer_id) => SerialisableEliminationCause::OtherTail { killer_id: killer_id.0 },
+      EliminationCause::HeadOn(other_player_id) => SerialisableEliminationCause::HeadOn {
+        other_player_id: other_player_id.0,
+      },
+    }
+  }
+}
+
+impl Into<EliminationCause> for SerialisableEliminationCause {
+  fn into(self) -> EliminationCause {
+    match self {
+      SerialisableEliminationCause::Wall => EliminationCause::Wall,
+      SerialisableEliminationCause::OwnTail => EliminationCause::OwnTail,
+      SerialisableEliminationCause::OtherTail { killer_id } => EliminationCause::OtherTail(PlayerId(killer_id)),
+      SerialisableEliminationCause::HeadOn { other_player_id } => EliminationCause::HeadOn(PlayerId(other_player_id)),
+    }
+  }
+}
+
+impl From<&PlayerEliminatedMessage> for InboundServerMessage {
+  fn from(value: &PlayerEliminatedMessage) -> Self {
+    InboundServerMessage::PlayerEliminated {
+      player_id: value.player_id.0,
+      cause: value.cause.into(),
+      x: value.position.x,
+      y: value.position.y,
+    }
+  }
+}
+
 /// A client-side resource that maps local control schemes to server-assigned player identities.
 /// Only relevant in online multiplayer mode.
 #[cfg(feature = "online")]
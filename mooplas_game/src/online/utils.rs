@@ -1,6 +1,6 @@
 use crate::online::structs::LocalInputMapping;
 use crate::prelude::{
-  AvailableControlSchemes, ControlSchemeId, PlayerId, PlayerRegistrationMessage, RegisteredPlayers,
+  AvailableControlSchemes, ControlSchemeId, PlayerId, PlayerRegistrationMessage, RegisteredPlayers, UiNotification,
   colour_for_player_id,
 };
 use crate::shared::RegisteredPlayer;
@@ -119,3 +119,46 @@ pub(crate) fn unregister_local_player_locally(
     Err(error) => warn!("[{}] was not registered: {}", player_id, error),
   }
 }
+
+pub(crate) fn set_player_ready_locally(
+  registered_players: &mut ResMut<RegisteredPlayers>,
+  messages: &mut MessageWriter<PlayerRegistrationMessage>,
+  player_id: PlayerId,
+  is_ready: bool,
+) -> bool {
+  if !registered_players.set_ready(player_id, is_ready) {
+    debug!(
+      "Readiness of [{}] is already [{}] or it is not registered",
+      player_id, is_ready
+    );
+    return false;
+  }
+  info!(
+    "[{}] is {}",
+    player_id,
+    if is_ready { "ready" } else { "no longer ready" }
+  );
+  let control_scheme_id = registered_players
+    .players
+    .iter()
+    .find(|player| player.id == player_id && player.is_local())
+    .map(|player| player.input.id);
+  messages.write(PlayerRegistrationMessage {
+    player_id,
+    control_scheme_id,
+    is_anyone_registered: true,
+  });
+  true
+}
+
+/// Returns the notification shown when the countdown to the automatic start of the round starts (`Some`) or is
+/// cancelled (`None`).
+pub(crate) fn ready_countdown_notification(seconds: Option<f32>) -> UiNotification {
+  match seconds {
+    Some(seconds) => UiNotification::info(format!(
+      "All players are ready - starting in {} seconds",
+      seconds.ceil()
+    )),
+    None => UiNotification::info("Not all players are ready anymore - countdown cancelled".to_string()),
+  }
+}
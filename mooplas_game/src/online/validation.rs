@@ -0,0 +1,417 @@
+use crate::prelude::MAX_PLAYERS;
+use crate::prelude::constants::{MAX_CHAT_MESSAGE_LENGTH, MAX_PLAYER_NAME_LENGTH};
+use bevy::prelude::Resource;
+use mooplas_networking::prelude::{
+  ClientId, InboundClientMessage, MAX_INPUT_FRAMES_PER_PACKET, SerialisableInput, SerialisableInputFrame,
+};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// The number of input, pong and time sync messages a client may send per second. Clients send an input packet every
+/// frame while any of their inputs is unacknowledged, so this leaves headroom above the highest realistic frame rates.
+const MAX_PERIODIC_MESSAGES_PER_SECOND: u32 = 1000;
+/// The number of other messages, such as registration or ready requests, a client may send per second.
+const MAX_MESSAGES_PER_SECOND: u32 = 50;
+/// The number of chat messages a client may send per [`CHAT_RATE_LIMIT_WINDOW`].
+const MAX_CHAT_MESSAGES_PER_WINDOW: u32 = 5;
+/// The number of inputs a single input frame may contain, i.e. a movement and an action for every player.
+const MAX_INPUTS_PER_FRAME: usize = 2 * MAX_PLAYERS as usize;
+/// The number of violations after which a client is disconnected.
+pub(crate) const MAX_VIOLATIONS: u32 = 20;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+const CHAT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(5);
+
+/// A way in which a message from a client broke the rules that the server enforces.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Violation {
+  MessageRateExceeded(u32),
+  ChatRateExceeded,
+  TooManyInputFrames(usize),
+  TooManyInputs(usize),
+  InvalidMoveDirection(f32),
+  InvalidName(String),
+  InvalidChatMessage(String),
+}
+
+impl Display for Violation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Violation::MessageRateExceeded(limit) => write!(f, "sent more than {} messages per second", limit),
+      Violation::ChatRateExceeded => write!(
+        f,
+        "sent more than {} chat messages in {} seconds",
+        MAX_CHAT_MESSAGES_PER_WINDOW,
+        CHAT_RATE_LIMIT_WINDOW.as_secs()
+      ),
+      Violation::TooManyInputFrames(count) => write!(f, "sent {} input frames in a single packet", count),
+      Violation::TooManyInputs(count) => write!(f, "sent {} inputs in a single frame", count),
+      Violation::InvalidMoveDirection(direction) => write!(f, "sent invalid move direction {}", direction),
+      Violation::InvalidName(name) => write!(f, "sent invalid name {:?}", name),
+      Violation::InvalidChatMessage(text) => write!(f, "sent invalid chat message {:?}", text),
+    }
+  }
+}
+
+/// The result of validating a value received from a client.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Validation<T> {
+  /// The value is valid.
+  Valid(T),
+  /// The value was out of range and has been clamped into it. The violation still counts against the client.
+  Clamped(T, Violation),
+  /// The value is unusable and must be ignored.
+  Rejected(Violation),
+}
+
+impl<T> Validation<T> {
+  /// Returns the violation, if any.
+  pub(crate) fn violation(&self) -> Option<&Violation> {
+    match self {
+      Validation::Valid(_) => None,
+      Validation::Clamped(_, violation) | Validation::Rejected(violation) => Some(violation),
+    }
+  }
+
+  /// Returns the usable value, if any.
+  pub(crate) fn value(self) -> Option<T> {
+    match self {
+      Validation::Valid(value) | Validation::Clamped(value, _) => Some(value),
+      Validation::Rejected(_) => None,
+    }
+  }
+}
+
+/// Validates the number of frames in an input packet and the number of inputs in each of its frames. Frames with too
+/// many inputs are dropped.
+pub(crate) fn validate_input_frames(frames: &[SerialisableInputFrame]) -> Validation<Vec<&SerialisableInputFrame>> {
+  if frames.len() > MAX_INPUT_FRAMES_PER_PACKET {
+    return Validation::Rejected(Violation::TooManyInputFrames(frames.len()));
+  }
+  let (valid_frames, invalid_frames): (Vec<_>, Vec<_>) = frames
+    .iter()
+    .partition(|frame| frame.inputs.len() <= MAX_INPUTS_PER_FRAME);
+  match invalid_frames.first() {
+    Some(frame) => Validation::Clamped(valid_frames, Violation::TooManyInputs(frame.inputs.len())),
+    None => Validation::Valid(valid_frames),
+  }
+}
+
+/// Validates a single input. Move directions must be between -1 and 1: larger values are clamped, non-finite values are
+/// rejected.
+pub(crate) fn validate_input(input: SerialisableInput) -> Validation<SerialisableInput> {
+  match input {
+    SerialisableInput::Move(_, direction) if !direction.is_finite() => {
+      Validation::Rejected(Violation::InvalidMoveDirection(direction))
+    }
+    SerialisableInput::Move(player_id, direction) if !(-1. ..=1.).contains(&direction) => Validation::Clamped(
+      SerialisableInput::Move(player_id, direction.clamp(-1., 1.)),
+      Violation::InvalidMoveDirection(direction),
+    ),
+    input => Validation::Valid(input),
+  }
+}
+
+/// Validates a player name. Surrounding whitespace is ignored, control characters are removed, and overly long names
+/// are truncated. Names that are empty after that are rejected.
+pub(crate) fn validate_name(name: &str) -> Validation<String> {
+  validate_text(name, MAX_PLAYER_NAME_LENGTH, Violation::InvalidName)
+}
+
+/// Validates the text of a chat message in the same way as [`validate_name`], just with a higher length limit.
+pub(crate) fn validate_chat_text(text: &str) -> Validation<String> {
+  validate_text(text, MAX_CHAT_MESSAGE_LENGTH, Violation::InvalidChatMessage)
+}
+
+fn validate_text(text: &str, max_length: usize, violation: fn(String) -> Violation) -> Validation<String> {
+  let trimmed_text = text.trim();
+  let sanitised_text: String = trimmed_text
+    .chars()
+    .filter(|character| !character.is_control())
+    .take(max_length)
+    .collect::<String>()
+    .trim()
+    .to_string();
+  if sanitised_text.is_empty() {
+    Validation::Rejected(violation(text.to_string()))
+  } else if sanitised_text != trimmed_text {
+    Validation::Clamped(sanitised_text, violation(text.to_string()))
+  } else {
+    Validation::Valid(sanitised_text)
+  }
+}
+
+/// Counts events within a fixed time window.
+#[derive(Default)]
+struct RateWindow {
+  start: Duration,
+  count: u32,
+}
+
+impl RateWindow {
+  /// Counts an event at the given time. Returns `true` if more than `limit` events have occurred in the current window.
+  fn count(&mut self, now: Duration, window: Duration, limit: u32) -> bool {
+    if now.saturating_sub(self.start) >= window {
+      self.start = now;
+      self.count = 0;
+    }
+    self.count += 1;
+    self.count > limit
+  }
+
+  /// Returns `true` if the last counted event was the first one in the current window to exceed `limit`.
+  fn is_first_excess(&self, limit: u32) -> bool {
+    self.count == limit + 1
+  }
+}
+
+/// The message rate and violations of a single client.
+#[derive(Default)]
+struct ClientRecord {
+  periodic_messages: RateWindow,
+  messages: RateWindow,
+  chat_messages: RateWindow,
+  violations: u32,
+}
+
+/// A resource that rate-limits the messages of each client and counts their violations, so that abusive clients can be
+/// disconnected.
+#[derive(Resource, Default)]
+pub(crate) struct ClientViolations {
+  clients: HashMap<ClientId, ClientRecord>,
+}
+
+impl ClientViolations {
+  /// Counts a message from the given client, received at the given (real) time. Inputs, pongs and time sync requests,
+  /// which clients send by themselves as often as every frame, count against a separate, higher limit than other
+  /// messages. Returns an error if the client has sent too many messages recently, in which case the message must be
+  /// ignored. Only the first message over the limit of a window comes with a [`Violation::MessageRateExceeded`], so
+  /// that a single burst can't get a client disconnected.
+  pub(crate) fn check_rate(&mut self, message: &InboundClientMessage, now: Duration) -> Result<(), Option<Violation>> {
+    let record = self.clients.entry(message.client_id()).or_default();
+    let (window, limit) = match message {
+      InboundClientMessage::Input(..) | InboundClientMessage::Pong(..) | InboundClientMessage::TimeSyncRequest(..) => {
+        (&mut record.periodic_messages, MAX_PERIODIC_MESSAGES_PER_SECOND)
+      }
+      _ => (&mut record.messages, MAX_MESSAGES_PER_SECOND),
+    };
+    if !window.count(now, RATE_LIMIT_WINDOW, limit) {
+      return Ok(());
+    }
+    Err(
+      window
+        .is_first_excess(limit)
+        .then_some(Violation::MessageRateExceeded(limit)),
+    )
+  }
+
+  /// Counts a chat message from the given client, in addition to [`ClientViolations::check_rate`]. Returns
+  /// [`Violation::ChatRateExceeded`] if the client is spamming the chat, in which case the message must be ignored.
+  pub(crate) fn check_chat_rate(&mut self, client_id: ClientId, now: Duration) -> Result<(), Violation> {
+    let record = self.clients.entry(client_id).or_default();
+    if record
+      .chat_messages
+      .count(now, CHAT_RATE_LIMIT_WINDOW, MAX_CHAT_MESSAGES_PER_WINDOW)
+    {
+      return Err(Violation::ChatRateExceeded);
+    }
+    Ok(())
+  }
+
+  /// Records a violation of the given client. Returns `true` if the client has committed too many violations and must
+  /// be disconnected.
+  pub(crate) fn record(&mut self, client_id: ClientId) -> bool {
+    let record = self.clients.entry(client_id).or_default();
+    record.violations += 1;
+    record.violations == MAX_VIOLATIONS
+  }
+
+  /// Forgets everything about the given client e.g. after it has disconnected.
+  pub(crate) fn remove(&mut self, client_id: &ClientId) {
+    self.clients.remove(client_id);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn frame(sequence: u32, input_count: usize) -> SerialisableInputFrame {
+    SerialisableInputFrame {
+      sequence,
+      inputs: vec![SerialisableInput::Action(0); input_count],
+    }
+  }
+
+  #[test]
+  fn validate_input_clamps_out_of_range_and_rejects_non_finite_directions() {
+    assert!(matches!(
+      validate_input(SerialisableInput::Move(1, -0.5)),
+      Validation::Valid(SerialisableInput::Move(1, -0.5))
+    ));
+    assert!(matches!(
+      validate_input(SerialisableInput::Move(1, 50.)),
+      Validation::Clamped(SerialisableInput::Move(1, 1.), Violation::InvalidMoveDirection(50.))
+    ));
+    assert!(matches!(
+      validate_input(SerialisableInput::Move(1, f32::NAN)),
+      Validation::Rejected(Violation::InvalidMoveDirection(_))
+    ));
+    assert!(matches!(
+      validate_input(SerialisableInput::Action(1)),
+      Validation::Valid(SerialisableInput::Action(1))
+    ));
+  }
+
+  #[test]
+  fn validate_input_frames_rejects_oversized_packets_and_drops_oversized_frames() {
+    let too_many_frames: Vec<_> = (0..=MAX_INPUT_FRAMES_PER_PACKET as u32)
+      .map(|sequence| frame(sequence, 1))
+      .collect();
+    assert_eq!(
+      validate_input_frames(&too_many_frames).violation(),
+      Some(&Violation::TooManyInputFrames(MAX_INPUT_FRAMES_PER_PACKET + 1))
+    );
+
+    let frames = [frame(0, 1), frame(1, MAX_INPUTS_PER_FRAME + 1)];
+    let validation = validate_input_frames(&frames);
+    assert_eq!(
+      validation.violation(),
+      Some(&Violation::TooManyInputs(MAX_INPUTS_PER_FRAME + 1))
+    );
+    let valid_frames = validation.value().expect("Expected the valid frame to be kept");
+    assert_eq!(valid_frames, vec![&frames[0]]);
+  }
+
+  #[test]
+  fn validate_name_sanitises_and_rejects_names() {
+    assert_eq!(validate_name(" Alice "), Validation::Valid("Alice".to_string()));
+    assert_eq!(
+      validate_name("Bob\u{7}by"),
+      Validation::Clamped("Bobby".to_string(), Violation::InvalidName("Bob\u{7}by".to_string()))
+    );
+    assert_eq!(
+      validate_name("A very long name indeed").value(),
+      Some("A very lon".to_string())
+    );
+    assert!(matches!(validate_name(" \n "), Validation::Rejected(_)));
+  }
+
+  #[test]
+  fn validate_chat_text_sanitises_and_truncates_text() {
+    assert_eq!(validate_chat_text("gg"), Validation::Valid("gg".to_string()));
+    let long_text = "a".repeat(MAX_CHAT_MESSAGE_LENGTH + 5);
+    assert_eq!(
+      validate_chat_text(&long_text),
+      Validation::Clamped(
+        "a".repeat(MAX_CHAT_MESSAGE_LENGTH),
+        Violation::InvalidChatMessage(long_text.clone())
+      )
+    );
+    assert!(matches!(validate_chat_text("\t"), Validation::Rejected(_)));
+  }
+
+  #[test]
+  fn check_chat_rate_limits_chat_messages_per_window() {
+    let mut client_violations = ClientViolations::default();
+    let client_id = ClientId::from_u64(1);
+
+    for _ in 0..MAX_CHAT_MESSAGES_PER_WINDOW {
+      assert!(
+        client_violations
+          .check_chat_rate(client_id, Duration::from_secs(1))
+          .is_ok()
+      );
+    }
+    assert_eq!(
+      client_violations.check_chat_rate(client_id, Duration::from_secs(2)),
+      Err(Violation::ChatRateExceeded)
+    );
+    assert!(
+      client_violations
+        .check_chat_rate(client_id, Duration::from_secs(6))
+        .is_ok()
+    );
+  }
+
+  fn input(client_id: ClientId) -> InboundClientMessage {
+    InboundClientMessage::Input(vec![frame(0, 1)], client_id)
+  }
+
+  fn spectating_request(client_id: ClientId) -> InboundClientMessage {
+    InboundClientMessage::SetSpectating(true, client_id)
+  }
+
+  #[test]
+  fn check_rate_limits_messages_per_window() {
+    let mut client_violations = ClientViolations::default();
+    let client_id = ClientId::from_u64(1);
+
+    for _ in 0..MAX_MESSAGES_PER_SECOND {
+      assert!(
+        client_violations
+          .check_rate(&spectating_request(client_id), Duration::from_millis(100))
+          .is_ok()
+      );
+    }
+    assert_eq!(
+      client_violations.check_rate(&spectating_request(client_id), Duration::from_millis(900)),
+      Err(Some(Violation::MessageRateExceeded(MAX_MESSAGES_PER_SECOND)))
+    );
+    assert_eq!(
+      client_violations.check_rate(&spectating_request(client_id), Duration::from_millis(950)),
+      Err(None)
+    );
+    assert!(
+      client_violations
+        .check_rate(&input(client_id), Duration::from_millis(950))
+        .is_ok()
+    );
+    assert!(
+      client_violations
+        .check_rate(&spectating_request(ClientId::from_u64(2)), Duration::from_millis(900))
+        .is_ok()
+    );
+    assert!(
+      client_violations
+        .check_rate(&spectating_request(client_id), Duration::from_millis(1100))
+        .is_ok()
+    );
+  }
+
+  #[test]
+  fn check_rate_limits_periodic_messages_separately_with_a_higher_limit() {
+    let mut client_violations = ClientViolations::default();
+    let client_id = ClientId::from_u64(1);
+
+    for _ in 0..MAX_PERIODIC_MESSAGES_PER_SECOND {
+      assert!(
+        client_violations
+          .check_rate(&input(client_id), Duration::from_millis(100))
+          .is_ok()
+      );
+    }
+    assert_eq!(
+      client_violations.check_rate(&input(client_id), Duration::from_millis(900)),
+      Err(Some(Violation::MessageRateExceeded(MAX_PERIODIC_MESSAGES_PER_SECOND)))
+    );
+    assert!(
+      client_violations
+        .check_rate(&spectating_request(client_id), Duration::from_millis(900))
+        .is_ok()
+    );
+  }
+
+  #[test]
+  fn record_requests_disconnect_once_after_max_violations() {
+    let mut client_violations = ClientViolations::default();
+    let client_id = ClientId::from_u64(1);
+
+    let disconnects: Vec<bool> = (0..MAX_VIOLATIONS + 1)
+      .map(|_| client_violations.record(client_id))
+      .collect();
+
+    assert_eq!(disconnects.iter().filter(|&&disconnect| disconnect).count(), 1);
+    assert!(disconnects[MAX_VIOLATIONS as usize - 1]);
+  }
+}
@@ -1,11 +1,15 @@
 use crate::prelude::constants::*;
-use crate::prelude::{AppState, Player, PlayerId, RegisteredPlayers, SnakeHead, SnakeSegment, SnakeTail, SpawnPoints};
+use crate::prelude::{
+  AppState, Player, PlayerEliminatedMessage, PlayerId, RegisteredPlayers, SnakeHead, SnakeSegment, SnakeTail,
+  SpawnPoints, TailEventMessage,
+};
 use avian2d::math::Vector;
 use avian2d::prelude::*;
 use bevy::asset::RenderAssetUsages;
 use bevy::ecs::relationship::Relationship;
 use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::prelude::*;
+use mooplas_networking::prelude::NetworkRole;
 
 /// A plugin that manages player spawning and snake tail updates.
 pub struct PlayerPlugin;
@@ -17,17 +21,30 @@ impl Plugin for PlayerPlugin {
       .add_systems(
         Update,
         ((
-          update_snake_tail_segments_system,
+          (
+            update_snake_tail_segments_system.run_if(|role: Res<NetworkRole>| role.is_server() || role.is_none()),
+            apply_tail_event_messages_system.run_if(|role: Res<NetworkRole>| role.is_client()),
+          ),
           update_active_segment_collider_system,
           update_active_segment_mesh_system,
           disable_eliminated_players_system,
         )
           .run_if(in_state(AppState::Playing))
           .chain(),),
-      );
+      )
+      .add_systems(Update, (spawn_death_effect_system, animate_death_effect_system))
+      .add_systems(OnExit(AppState::GameOver), despawn_death_effects_system);
   }
 }
 
+const DEATH_EFFECT_DURATION_SECONDS: f32 = 0.6;
+const DEATH_EFFECT_MAX_SCALE: f32 = 4.;
+
+/// A short-lived, expanding and fading ring shown where a player was eliminated. Uses real time so that the effect for
+/// the final elimination of a round completes while the game is paused.
+#[derive(Component)]
+struct DeathEffect(Timer);
+
 /// A bundle that contains the components needed for a basic kinematic character controller.
 #[derive(Bundle)]
 struct PhysicsController {
@@ -113,21 +130,23 @@ fn spawn_players_system(
 }
 
 /// Samples each player's position and updates their [`SnakeTail`] segments accordingly. Creates mesh and collider
-/// entities as needed.
+/// entities as needed. Only runs where the tails are simulated authoritatively i.e. on the server or in a local game.
+/// Every change is also written as a [`TailEventMessage`] so that it can be replicated to clients.
 fn update_snake_tail_segments_system(
   mut commands: Commands,
-  mut snake_tail_query: Query<(Entity, &mut SnakeTail), Without<SnakeHead>>,
+  mut snake_tail_query: Query<(Entity, &mut SnakeTail, &PlayerId), Without<SnakeHead>>,
   snake_head_query: Query<(&Transform, &ChildOf), With<SnakeHead>>,
   mut meshes: ResMut<Assets<Mesh>>,
   mut materials: ResMut<Assets<ColorMaterial>>,
   children_query: Query<&Children>,
+  mut tail_event_message: MessageWriter<TailEventMessage>,
 ) {
   for (transform, parent) in snake_head_query.iter() {
     let current_position = transform.translation.truncate() - (transform.rotation * Vec3::Y * 5.).truncate();
     let parent_entity = parent.get();
     if let Ok(children) = children_query.get(parent_entity) {
       for child in children.iter() {
-        if let Ok((snake_tail_entity, mut snake_tail)) = snake_tail_query.get_mut(child) {
+        if let Ok((snake_tail_entity, mut snake_tail, player_id)) = snake_tail_query.get_mut(child) {
           let gap_samples_remaining = snake_tail.gap_samples_remaining;
           let active_segment_index = snake_tail.segments.len() - 1;
           let is_active_segment_positions_empty = snake_tail.segments[active_segment_index].positions().is_empty();
@@ -136,17 +155,15 @@ fn update_snake_tail_segments_system(
           // gap samples remaining
           if is_active_segment_positions_empty && gap_samples_remaining == 0 {
             snake_tail.distance_since_last_sample = 0.0;
-            let snake_tail_colour = snake_tail.colour.clone();
-            let active_segment = &mut snake_tail.segments[active_segment_index];
-            active_segment.push_position(current_position);
-            create_segment_mesh_if_none_exist(
+            append_sample_to_active_segment(
               &mut commands,
               &mut meshes,
               &mut materials,
-              active_segment,
+              &mut snake_tail,
               snake_tail_entity,
-              snake_tail_colour,
+              current_position,
             );
+            tail_event_message.write(TailEventMessage::Sampled(*player_id, current_position));
             continue;
           }
 
@@ -160,8 +177,9 @@ fn update_snake_tail_segments_system(
             &mut materials,
             &mut snake_tail,
             snake_tail_entity,
-            active_segment_index,
+            *player_id,
             current_position,
+            &mut tail_event_message,
           );
         }
       }
@@ -211,13 +229,14 @@ fn update_distance_since_last_sample(
 
 /// Handles the logic for when the distance since the last sample exceeds the defined threshold.
 fn handle_sample_distance_reached(
-  mut commands: &mut Commands,
-  mut meshes: &mut ResMut<Assets<Mesh>>,
-  mut materials: &mut ResMut<Assets<ColorMaterial>>,
+  commands: &mut Commands,
+  meshes: &mut ResMut<Assets<Mesh>>,
+  materials: &mut ResMut<Assets<ColorMaterial>>,
   snake_tail: &mut Mut<SnakeTail>,
   snake_tail_entity: Entity,
-  active_segment_index: usize,
+  player_id: PlayerId,
   current_position: Vec2,
+  tail_event_message: &mut MessageWriter<TailEventMessage>,
 ) {
   if snake_tail.distance_since_last_sample < SNAKE_TAIL_POSITION_SAMPLE_DISTANCE {
     return;
@@ -232,18 +251,52 @@ fn handle_sample_distance_reached(
     if snake_tail.gap_samples_remaining == 0 {
       // Start a fresh segment after the gap
       snake_tail.segments.push(SnakeSegment::default());
+      tail_event_message.write(TailEventMessage::GapEnded(player_id));
     }
     return;
   }
 
-  // Add current position to active segment and create mesh if needed
+  // Add current position to active segment, creating the mesh and collider if needed
+  append_sample_to_active_segment(
+    commands,
+    meshes,
+    materials,
+    snake_tail,
+    snake_tail_entity,
+    current_position,
+  );
+  tail_event_message.write(TailEventMessage::Sampled(player_id, current_position));
+
+  // If this segment reached max continuous length, start gap samples
+  let is_active_segment_full = snake_tail
+    .segments
+    .last()
+    .is_some_and(|active_segment| active_segment.positions().len() >= SNAKE_LENGTH_MAX_CONTINUOUS);
+  if is_active_segment_full {
+    snake_tail.gap_samples_remaining = SNAKE_GAP_LENGTH;
+    tail_event_message.write(TailEventMessage::GapStarted(player_id));
+  }
+}
+
+/// Appends a sampled position to the active (last) segment of the [`SnakeTail`]. Creates the segment's mesh entity if
+/// none exists and its collider entity once the segment has enough positions.
+fn append_sample_to_active_segment(
+  commands: &mut Commands,
+  meshes: &mut ResMut<Assets<Mesh>>,
+  materials: &mut ResMut<Assets<ColorMaterial>>,
+  snake_tail: &mut Mut<SnakeTail>,
+  snake_tail_entity: Entity,
+  position: Vec2,
+) {
   let snake_tail_colour = snake_tail.colour.clone();
-  let active_segment = &mut snake_tail.segments[active_segment_index];
-  active_segment.push_position(current_position);
+  let Some(active_segment) = snake_tail.segments.last_mut() else {
+    return;
+  };
+  active_segment.push_position(position);
   create_segment_mesh_if_none_exist(
-    &mut commands,
-    &mut meshes,
-    &mut materials,
+    commands,
+    meshes,
+    materials,
     active_segment,
     snake_tail_entity,
     snake_tail_colour,
@@ -267,10 +320,51 @@ fn handle_sample_distance_reached(
       active_segment.set_collider_entity(collider_entity);
     }
   }
+}
 
-  // If this segment reached max continuous length, start gap samples
-  if active_segment.positions().len() >= SNAKE_LENGTH_MAX_CONTINUOUS {
-    snake_tail.gap_samples_remaining = SNAKE_GAP_LENGTH;
+/// Builds each player's [`SnakeTail`] from the authoritative [`TailEventMessage`]s received from the server instead of
+/// sampling the (interpolated) head position locally. This ensures that the visible segments and gaps of every player
+/// match the server's colliders exactly. Only runs on clients.
+fn apply_tail_event_messages_system(
+  mut commands: Commands,
+  mut tail_event_messages: MessageReader<TailEventMessage>,
+  mut snake_tail_query: Query<(Entity, &mut SnakeTail, &PlayerId), Without<SnakeHead>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  mesh_query: Query<&Mesh2d>,
+) {
+  for message in tail_event_messages.read() {
+    let Some((snake_tail_entity, mut snake_tail, _)) = snake_tail_query
+      .iter_mut()
+      .find(|(_, _, player_id)| **player_id == message.player_id())
+    else {
+      warn!("Received [{:?}] but no matching snake tail exists", message);
+      continue;
+    };
+    match message {
+      TailEventMessage::Sampled(_, position) => {
+        snake_tail.distance_since_last_sample = 0.0;
+        append_sample_to_active_segment(
+          &mut commands,
+          &mut meshes,
+          &mut materials,
+          &mut snake_tail,
+          snake_tail_entity,
+          *position,
+        );
+      }
+      TailEventMessage::GapStarted(_) => {
+        // The closed segment is no longer the active one, so make sure its mesh contains all of its positions
+        if let Some(closed_segment) = snake_tail.segments.last() {
+          refresh_segment_mesh(closed_segment, &mesh_query, &mut meshes);
+        }
+        snake_tail.gap_samples_remaining = SNAKE_GAP_LENGTH;
+      }
+      TailEventMessage::GapEnded(_) => {
+        snake_tail.gap_samples_remaining = 0;
+        snake_tail.segments.push(SnakeSegment::default());
+      }
+    }
   }
 }
 
@@ -315,7 +409,7 @@ fn compute_polyline_vertices(active_segment: &SnakeSegment) -> Option<Vec<Vector
 /// Updates the mesh of the active (last) [`SnakeSegment`] every time the [`SnakeTail`] changes.
 fn update_active_segment_mesh_system(
   snake_tail_query: Query<&SnakeTail, (Without<SnakeHead>, Changed<SnakeTail>)>,
-  mut mesh_query: Query<&mut Mesh2d>,
+  mesh_query: Query<&Mesh2d>,
   mut meshes: ResMut<Assets<Mesh>>,
 ) {
   for snake_tail in &snake_tail_query {
@@ -326,12 +420,17 @@ fn update_active_segment_mesh_system(
       if active_segment.positions().len() < 2 {
         continue;
       }
-      if let Some(mesh_entity) = active_segment.mesh_entity() {
-        if let Ok(mesh2d) = mesh_query.get_mut(mesh_entity) {
-          if let Some(mut m) = meshes.get_mut(&mesh2d.0) {
-            *m = create_snake_tail_mesh(&active_segment.positions());
-          }
-        }
+      refresh_segment_mesh(active_segment, &mesh_query, &mut meshes);
+    }
+  }
+}
+
+/// Replaces the mesh of the given [`SnakeSegment`] with one that covers all of its sampled positions.
+fn refresh_segment_mesh(segment: &SnakeSegment, mesh_query: &Query<&Mesh2d>, meshes: &mut ResMut<Assets<Mesh>>) {
+  if let Some(mesh_entity) = segment.mesh_entity() {
+    if let Ok(mesh2d) = mesh_query.get(mesh_entity) {
+      if let Some(mut m) = meshes.get_mut(&mesh2d.0) {
+        *m = create_snake_tail_mesh(&segment.positions());
       }
     }
   }
@@ -394,6 +493,59 @@ fn disable_eliminated_players_system(
   }
 }
 
+/// Spawns a [`DeathEffect`] in the colour of the eliminated player at the position of each elimination.
+fn spawn_death_effect_system(
+  mut commands: Commands,
+  mut player_eliminated_messages: MessageReader<PlayerEliminatedMessage>,
+  registered_players: Res<RegisteredPlayers>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  for message in player_eliminated_messages.read() {
+    let colour = registered_players
+      .players
+      .iter()
+      .find(|p| p.id == message.player_id)
+      .map(|p| p.colour)
+      .unwrap_or(Color::WHITE);
+    commands.spawn((
+      Name::new("Death Effect"),
+      DeathEffect(Timer::from_seconds(DEATH_EFFECT_DURATION_SECONDS, TimerMode::Once)),
+      Mesh2d(meshes.add(Annulus::new(SNAKE_HEAD_SIZE, SNAKE_HEAD_SIZE + 2.))),
+      MeshMaterial2d(materials.add(colour)),
+      Transform::from_translation(message.position.extend(10.)),
+      PIXEL_PERFECT_LAYER,
+    ));
+  }
+}
+
+/// Expands and fades out every [`DeathEffect`] over its lifetime and despawns it once it has finished.
+fn animate_death_effect_system(
+  mut commands: Commands,
+  time: Res<Time<Real>>,
+  mut death_effect_query: Query<(Entity, &mut DeathEffect, &mut Transform, &MeshMaterial2d<ColorMaterial>)>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+  for (entity, mut death_effect, mut transform, material) in &mut death_effect_query {
+    death_effect.0.tick(time.delta());
+    if death_effect.0.is_finished() {
+      commands.entity(entity).despawn();
+      continue;
+    }
+    let progress = death_effect.0.fraction();
+    transform.scale = Vec3::splat(1. + progress * (DEATH_EFFECT_MAX_SCALE - 1.));
+    if let Some(mut material) = materials.get_mut(&material.0) {
+      material.color.set_alpha(1. - progress);
+    }
+  }
+}
+
+fn despawn_death_effects_system(mut commands: Commands, death_effect_query: Query<Entity, With<DeathEffect>>) {
+  for entity in &death_effect_query {
+    commands.entity(entity).despawn();
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -113,3 +113,15 @@ pub(crate) const ERROR_COLOUR: Srgba = tailwind::RED_500;
 /// failed.
 #[allow(unused)]
 pub(crate) const CLIENT_HAND_SHAKE_TIMEOUT_SECS: u64 = 7;
+
+/// The maximum number of characters of a player's name.
+#[allow(unused)]
+pub(crate) const MAX_PLAYER_NAME_LENGTH: usize = 10;
+
+/// The maximum number of characters of a chat message.
+#[allow(unused)]
+pub(crate) const MAX_CHAT_MESSAGE_LENGTH: usize = 60;
+
+/// The maximum number of characters of a room password.
+#[allow(unused)]
+pub(crate) const MAX_ROOM_PASSWORD_LENGTH: usize = 64;
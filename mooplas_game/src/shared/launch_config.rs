@@ -0,0 +1,76 @@
+#![cfg(feature = "online")]
+
+use bevy::prelude::Resource;
+use clap::{Args, Parser};
+use mooplas_networking_matchbox::prelude::IceServerOptions;
+
+/// A resource with the command line arguments this app instance was launched with. Parsed once at startup, before any
+/// plugins are added, so that plugins can read the values they need from here.
+#[derive(Resource, Parser, Debug, Default, Clone, PartialEq, Eq)]
+#[command(name = "mooplas_game", about = "Runs the game")]
+pub struct LaunchConfig {
+  /// Host an online game straight away
+  #[arg(long)]
+  pub host: bool,
+
+  /// Join the online game with the given room ID straight away, takes precedence over --host
+  #[arg(long, value_name = "ROOM_ID")]
+  pub join: Option<String>,
+
+  /// The player name to use instead of prompting for one
+  #[arg(long)]
+  pub name: Option<String>,
+
+  #[command(flatten)]
+  pub networking: NetworkingArgs,
+}
+
+impl LaunchConfig {
+  /// Parses the command line arguments on native platforms, exiting with a usage message if they are invalid. In the
+  /// browser, there are no command line arguments and the page provides the launch options instead.
+  pub fn from_runtime_config() -> Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      Self::parse()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+      Self::default()
+    }
+  }
+}
+
+/// The networking arguments that both the game and the headless server accept.
+#[derive(Args, Debug, Default, Clone, PartialEq, Eq)]
+pub struct NetworkingArgs {
+  /// The signalling server to connect to [default: the config file or the URL baked in at build time]
+  #[arg(long, value_name = "URL")]
+  pub signalling_server_url: Option<String>,
+
+  /// A JSON file with the ICE servers to use instead of the signalling server's
+  #[arg(long, value_name = "PATH", env = "ICE_SERVER_CONFIG")]
+  pub ice_server_config: Option<String>,
+
+  /// Comma-separated STUN/TURN URLs to use instead of the signalling server's
+  #[arg(long, value_name = "URLS", env = "ICE_SERVER_URLS")]
+  pub ice_server_urls: Option<String>,
+
+  /// The username for the TURN servers
+  #[arg(long, value_name = "USERNAME", env = "ICE_SERVER_USERNAME")]
+  pub ice_server_username: Option<String>,
+
+  /// The credential for the TURN servers
+  #[arg(long, value_name = "SECRET", env = "ICE_SERVER_CREDENTIAL", hide_env_values = true)]
+  pub ice_server_credential: Option<String>,
+}
+
+impl NetworkingArgs {
+  pub fn ice_server_options(&self) -> IceServerOptions {
+    IceServerOptions {
+      config_path: self.ice_server_config.clone(),
+      urls: self.ice_server_urls.clone(),
+      username: self.ice_server_username.clone(),
+      credential: self.ice_server_credential.clone(),
+    }
+  }
+}
@@ -1,13 +1,16 @@
 use crate::shared::PlayerId;
 use avian2d::math::Scalar;
 use bevy::app::{App, Plugin};
+use bevy::math::Vec2;
 use bevy::prelude::Message;
 
-use crate::prelude::ControlSchemeId;
 #[cfg(feature = "online")]
 use crate::prelude::constants::{ERROR_COLOUR, INFO_COLOUR};
+use crate::prelude::{ControlSchemeId, EliminationCause};
 #[cfg(feature = "online")]
 use bevy::prelude::{Color, Srgba};
+#[cfg(feature = "online")]
+use mooplas_networking::prelude::ClientId;
 
 /// A plugin that registers all shared messages used across multiple plugins and systems.
 pub struct SharedMessagesPlugin;
@@ -21,13 +24,28 @@ impl Plugin for SharedMessagesPlugin {
       .add_message::<ContinueMessage>()
       .add_message::<ExitLobbyMessage>()
       .add_message::<TouchControlsToggledMessage>()
-      .add_message::<InputMessage>();
+      .add_message::<InputMessage>()
+      .add_message::<TailEventMessage>()
+      .add_message::<PlayerEliminatedMessage>()
+      .add_message::<ModeratePlayerMessage>()
+      .add_message::<LocalPlayerRegistrationRequestMessage>()
+      .add_message::<LocalPlayerReadyRequestMessage>();
 
     #[cfg(feature = "online")]
     app
-      .add_message::<LocalPlayerRegistrationRequestMessage>()
       .add_message::<ConnectionInfoMessage>()
-      .add_message::<UiNotification>();
+      .add_message::<UiNotification>()
+      .add_message::<SendChatMessage>()
+      .add_message::<ChatMessage>()
+      .add_message::<LocalSpectatingRequestMessage>()
+      .add_message::<ClientBannedMessage>()
+      .add_message::<RoomPasswordMessage>()
+      .add_message::<RoomPasswordRequiredMessage>()
+      .add_message::<RoomVisibilityMessage>()
+      .add_message::<RefreshPublicRoomsMessage>()
+      .add_message::<PublicRoomsMessage>()
+      .add_message::<RoomSelectedMessage>()
+      .add_message::<TestSignallingServerMessage>();
   }
 }
 
@@ -38,7 +56,7 @@ pub struct DebugStateMessage {
   pub display_player_gizmos: bool,
 }
 
-/// A message that communicates a change to a user's registration status in the lobby.
+/// A message that communicates a change to a user's registration status in the lobby, including whether they're ready.
 #[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct PlayerRegistrationMessage {
   pub player_id: PlayerId,
@@ -48,7 +66,6 @@ pub(crate) struct PlayerRegistrationMessage {
 }
 
 /// A local request to register or unregister a control scheme in online mode.
-#[cfg(feature = "online")]
 #[derive(Message, Debug, Copy, Clone)]
 pub(crate) struct LocalPlayerRegistrationRequestMessage {
   pub control_scheme_id: ControlSchemeId,
@@ -56,6 +73,13 @@ pub(crate) struct LocalPlayerRegistrationRequestMessage {
   pub has_registered: bool,
 }
 
+/// A local request to mark a locally registered player as ready (true) or not ready (false) in online mode.
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct LocalPlayerReadyRequestMessage {
+  pub player_id: PlayerId,
+  pub is_ready: bool,
+}
+
 /// A message that communicates a change to the touch controls setting.
 #[derive(Message)]
 pub struct TouchControlsToggledMessage {
@@ -92,6 +116,7 @@ pub enum MenuName {
   EnterNameMenu,
   HostGameMenu,
   JoinGameMenu,
+  BrowseGamesMenu,
 }
 
 /// A [`Message`] written for an input action by a player.
@@ -101,6 +126,49 @@ pub enum InputMessage {
   Action(PlayerId),
 }
 
+/// A [`Message`] describing an authoritative change to a player's [`crate::prelude::SnakeTail`]. Written by whoever
+/// simulates the tails (i.e. the server or a local game) and applied by clients in online multiplayer, so that their
+/// tails are built from exactly the same sample points as the server's colliders.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub enum TailEventMessage {
+  /// A position was sampled and appended to the active segment of the player's tail.
+  Sampled(PlayerId, Vec2),
+  /// The active segment has ended and a gap has started.
+  GapStarted(PlayerId),
+  /// The gap has ended and a new segment has been started.
+  GapEnded(PlayerId),
+}
+
+impl TailEventMessage {
+  pub fn player_id(&self) -> PlayerId {
+    match self {
+      TailEventMessage::Sampled(player_id, _)
+      | TailEventMessage::GapStarted(player_id)
+      | TailEventMessage::GapEnded(player_id) => *player_id,
+    }
+  }
+}
+
+/// A [`Message`] indicating that a player has been eliminated. Written by whoever runs the collision checks (i.e. the
+/// server or a local game) and replicated to clients in online multiplayer.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerEliminatedMessage {
+  pub player_id: PlayerId,
+  pub cause: EliminationCause,
+  /// The world position at which the player was eliminated.
+  pub position: Vec2,
+}
+
+impl PlayerEliminatedMessage {
+  pub fn new(player_id: PlayerId, cause: EliminationCause, position: Vec2) -> Self {
+    Self {
+      player_id,
+      cause,
+      position,
+    }
+  }
+}
+
 /// A [`Message`] indicating that the game should continue (e.g., start or restart). Used when an arbitrary player
 /// input is required.
 #[derive(Message)]
@@ -130,14 +198,132 @@ impl ExitLobbyMessage {
 #[derive(Message, Clone)]
 pub struct ConnectionInfoMessage {
   pub connection_string: String,
+  /// The password of the room to join, if the user has entered one.
+  pub password: Option<String>,
+  /// Whether to join as a spectator, which watches rounds without registering any players.
+  pub is_spectating: bool,
 }
 
 impl ConnectionInfoMessage {
   pub fn new(connection_string: String) -> Self {
-    Self { connection_string }
+    Self {
+      connection_string,
+      password: None,
+      is_spectating: false,
+    }
   }
 }
 
+/// A local request to send a chat message to all players in online mode.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone)]
+pub struct SendChatMessage {
+  pub text: String,
+}
+
+/// A local request to watch the following rounds as a spectator (true) or to play in them (false) in online mode.
+/// Switching is only possible between rounds.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LocalSpectatingRequestMessage {
+  pub is_spectating: bool,
+}
+
+/// A [`Message`] for a chat message that has been sent by any player, including the local player, and should be
+/// displayed in the UI.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+  pub sender_name: String,
+  pub text: String,
+}
+
+/// What the host does with the client that registered a player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModerationAction {
+  /// Disconnects the client. It may rejoin the room as a new client.
+  Kick,
+  /// Disconnects the client and refuses it for the rest of the session.
+  Ban,
+}
+
+/// A local request of the host to kick or ban the client that registered the given player, along with all of its
+/// players.
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ModeratePlayerMessage {
+  pub player_id: PlayerId,
+  pub action: ModerationAction,
+}
+
+/// A [`Message`] indicating that the host has banned a client, so that the networking implementation can refuse it
+/// too (e.g. at the signalling server).
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClientBannedMessage {
+  pub client_id: ClientId,
+}
+
+/// A [`Message`] indicating that the host wants to protect its room with a password, or to remove the protection if
+/// there is no password.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct RoomPasswordMessage {
+  pub password: Option<String>,
+}
+
+/// A [`Message`] indicating that the room the client tried to join is protected by a password, so that the UI can
+/// prompt for it.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RoomPasswordRequiredMessage;
+
+/// A [`Message`] indicating that the host wants to list its room in the public room list, or to remove it from the list.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RoomVisibilityMessage {
+  pub is_public: bool,
+}
+
+/// A [`Message`] requesting the public room list, which is then delivered via a [`PublicRoomsMessage`].
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RefreshPublicRoomsMessage;
+
+/// A room in the public room list.
+#[cfg(feature = "online")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicRoomInfo {
+  pub room_id: String,
+  pub name: String,
+  pub player_count: u8,
+  pub max_players: u8,
+  pub password_required: bool,
+}
+
+/// A [`Message`] with the public room list, or the reason why it couldn't be retrieved.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct PublicRoomsMessage {
+  pub rooms: Result<Vec<PublicRoomInfo>, String>,
+}
+
+/// A [`Message`] indicating that the user has picked a room from the public room list or opened an invite link, so that
+/// the join menu can connect to it.
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct RoomSelectedMessage {
+  pub room_id: String,
+  pub password_required: bool,
+}
+
+/// A [`Message`] requesting a connectivity test against the signalling server with the given base URL, the result of
+/// which is shown as a [`UiNotification`].
+#[cfg(feature = "online")]
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct TestSignallingServerMessage {
+  pub url: String,
+}
+
 /// A [`Message`] for displaying an error message in the UI.
 #[derive(Message, Clone)]
 pub struct UiNotification {
@@ -201,6 +387,8 @@ mod tests {
     assert!(app.world().contains_resource::<Messages<InputMessage>>());
     assert!(app.world().contains_resource::<Messages<ContinueMessage>>());
     assert!(app.world().contains_resource::<Messages<ExitLobbyMessage>>());
+    assert!(app.world().contains_resource::<Messages<TailEventMessage>>());
+    assert!(app.world().contains_resource::<Messages<PlayerEliminatedMessage>>());
   }
 
   #[test]
@@ -1,11 +1,13 @@
 mod components;
 pub mod constants;
+mod launch_config;
 mod messages;
 mod resources;
 mod structs;
 mod utils;
 
 pub use components::*;
+pub use launch_config::*;
 pub use messages::*;
 pub use resources::*;
 pub use structs::*;
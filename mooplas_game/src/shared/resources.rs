@@ -9,8 +9,12 @@ use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 use mooplas_networking::prelude::NetworkRole;
 use std::fmt::Display;
 
+#[cfg(feature = "online")]
+use crate::prelude::MAX_PLAYERS;
 #[cfg(feature = "online")]
 use crate::shared::utils::generate_random_name;
+#[cfg(feature = "online")]
+use mooplas_networking::prelude::ClientId;
 
 /// A plugin that registers and initialises shared resources used across the entire application such as [`Settings`].
 pub struct SharedResourcesPlugin;
@@ -31,7 +35,11 @@ impl Plugin for SharedResourcesPlugin {
       .init_resource::<NetworkRole>();
 
     #[cfg(feature = "online")]
-    app.init_resource::<PlayerName>();
+    app
+      .init_resource::<PlayerName>()
+      .init_resource::<Spectators>()
+      .init_resource::<GameRules>()
+      .register_type::<GameRules>();
   }
 }
 
@@ -95,6 +103,64 @@ pub struct GeneralSettings {
   pub enable_touch_controls: bool,
 }
 
+/// A resource that holds the rules of online games hosted by this application. Only relevant for the host.
+#[cfg(feature = "online")]
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+#[reflect(Resource)]
+pub struct GameRules {
+  /// Whether the game is paused while the players of a disconnected client are held for them. If not, their snakes
+  /// keep going straight.
+  pub pause_while_reconnecting: bool,
+  /// How long the players of a disconnected client are held for them before they are removed from the game.
+  pub reconnect_grace_period_seconds: f32,
+  /// The maximum number of players that can register for a round. Never exceeds [`MAX_PLAYERS`].
+  pub max_players: u8,
+  /// How long to wait once all registered players are ready before starting the round automatically. If `None`, the
+  /// host has to start the round.
+  pub ready_countdown_seconds: Option<f32>,
+}
+
+#[cfg(feature = "online")]
+impl Default for GameRules {
+  fn default() -> Self {
+    Self {
+      pause_while_reconnecting: false,
+      reconnect_grace_period_seconds: 30.,
+      max_players: MAX_PLAYERS,
+      ready_countdown_seconds: None,
+    }
+  }
+}
+
+#[cfg(feature = "online")]
+impl GameRules {
+  /// The names of all presets accepted by [`GameRules::from_preset`].
+  pub const PRESETS: [&'static str; 3] = ["standard", "casual", "competitive"];
+
+  /// Returns the rules of the preset with the given name, or `None` if no such preset exists.
+  /// - `standard`: the default rules
+  /// - `casual`: disconnected players are only held briefly and the game is never paused for them, and rounds start
+  ///   automatically shortly after all players are ready
+  /// - `competitive`: the game is paused while disconnected players are held for them, for longer
+  pub fn from_preset(name: &str) -> Option<Self> {
+    match name.trim().to_lowercase().as_str() {
+      "standard" => Some(Self::default()),
+      "casual" => Some(Self {
+        pause_while_reconnecting: false,
+        reconnect_grace_period_seconds: 10.,
+        ready_countdown_seconds: Some(5.),
+        ..Self::default()
+      }),
+      "competitive" => Some(Self {
+        pause_while_reconnecting: true,
+        reconnect_grace_period_seconds: 60.,
+        ..Self::default()
+      }),
+      _ => None,
+    }
+  }
+}
+
 /// A resource that holds all valid spawn points in the game world. Contains a list of (x, y, rotation) tuples.
 #[derive(Resource, Reflect, Clone, Default)]
 pub struct SpawnPoints {
@@ -137,6 +203,23 @@ impl RegisteredPlayers {
       .map(|player| player.id)
   }
 
+  /// Returns `true` if at least one player is registered and all registered players are ready.
+  pub fn are_all_ready(&self) -> bool {
+    !self.players.is_empty() && self.players.iter().all(|player| player.is_ready)
+  }
+
+  /// Sets whether the player with the given [`PlayerId`] is ready. Returns `true` if their readiness has changed.
+  #[cfg(feature = "online")]
+  pub fn set_ready(&mut self, player_id: PlayerId, is_ready: bool) -> bool {
+    match self.players.iter_mut().find(|player| player.id == player_id) {
+      Some(player) if player.is_ready != is_ready => {
+        player.is_ready = is_ready;
+        true
+      }
+      _ => false,
+    }
+  }
+
   /// Adds a new registered player.
   /// Returns `Ok` if the player was added, [`ErrorKind::PlayerAlreadyRegistered`] if a player with the same [`PlayerId`] already exists.
   pub fn register(&mut self, player: RegisteredPlayer) -> Result<(), ErrorKind> {
@@ -264,6 +347,31 @@ impl PlayerName {
   }
 }
 
+/// A resource that holds the clients that are spectating the online game this client is part of, as last reported by the
+/// server, and whether this client is one of them.
+#[cfg(feature = "online")]
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Spectators {
+  /// All spectating clients, in the order in which they started spectating.
+  pub clients: Vec<ClientId>,
+  /// Whether this client is spectating.
+  pub is_local_client_spectating: bool,
+}
+
+#[cfg(feature = "online")]
+impl Spectators {
+  /// Returns the number of spectating clients, including this client.
+  pub fn count(&self) -> usize {
+    self.clients.len()
+  }
+
+  /// Forgets all spectators, including this client. Use when leaving an online game.
+  pub fn clear(&mut self) {
+    self.clients.clear();
+    self.is_local_client_spectating = false;
+  }
+}
+
 /// A resource that holds information about the winner of the last round.
 #[derive(Resource, Default)]
 pub struct WinnerInfo {
@@ -520,6 +628,30 @@ mod tests {
     }
   }
 
+  #[cfg(feature = "online")]
+  #[test]
+  fn are_all_ready_requires_every_registered_player_to_be_ready() {
+    let mut registered_players = RegisteredPlayers::default();
+    assert!(!registered_players.are_all_ready());
+    for id in 0..2 {
+      registered_players
+        .register(RegisteredPlayer::new_mutable(
+          PlayerId(id),
+          format!("Player {id}"),
+          ControlScheme::test(id),
+          Color::default(),
+        ))
+        .expect("Failed to registered player");
+    }
+
+    assert!(registered_players.set_ready(PlayerId(0), true));
+    assert!(!registered_players.set_ready(PlayerId(0), true));
+    assert!(!registered_players.are_all_ready());
+    assert!(registered_players.set_ready(PlayerId(1), true));
+    assert!(registered_players.are_all_ready());
+    assert!(!registered_players.set_ready(PlayerId(7), false));
+  }
+
   #[test]
   fn player_already_registered_error_displays_correct_message() {
     let error = ErrorKind::PlayerAlreadyRegistered(PlayerId(1));
@@ -543,4 +675,16 @@ mod tests {
     let error = ErrorKind::RegistrationNotImmutable(PlayerId(4));
     assert_eq!(format!("{}", error), "[Player 4] is not immutably registered");
   }
+
+  #[cfg(feature = "online")]
+  #[test]
+  fn game_rules_from_preset_resolves_all_presets_case_insensitively() {
+    for preset in GameRules::PRESETS {
+      assert!(GameRules::from_preset(preset).is_some(), "Preset [{preset}] is missing");
+    }
+    let rules = GameRules::from_preset(" Competitive ").expect("Failed to resolve competitive preset");
+    assert!(rules.pause_while_reconnecting);
+    assert_eq!(rules.max_players, MAX_PLAYERS);
+    assert!(GameRules::from_preset("chaotic").is_none());
+  }
 }
@@ -3,6 +3,9 @@ use bevy::prelude::{Color, KeyCode};
 
 pub const MAX_PLAYERS: u8 = 8;
 
+/// The number of spectators an online room holds on top of its players. Spectators don't take a [`PlayerId`].
+pub const MAX_SPECTATORS: u8 = 8;
+
 /// A local-only identifier for a control scheme. Separates "which keys you're pressing" (local)
 /// from "which player you are" (global/network).
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -28,6 +31,19 @@ impl ControlScheme {
   }
 }
 
+/// The reason a player was eliminated from the current round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EliminationCause {
+  /// The player left the bounds of the play area.
+  Wall,
+  /// The player ran into their own tail.
+  OwnTail,
+  /// The player ran into the tail of the given player.
+  OtherTail(PlayerId),
+  /// The player collided head-on with the given player.
+  HeadOn(PlayerId),
+}
+
 /// Represents a player that has registered to play the game. Used during the game loop.
 #[derive(Clone)]
 pub struct RegisteredPlayer {
@@ -36,6 +52,8 @@ pub struct RegisteredPlayer {
   pub input: ControlScheme,
   pub colour: Color,
   pub alive: bool,
+  /// Whether the player is ready for the round to start. Only relevant in online multiplayer.
+  pub is_ready: bool,
   mutable: bool,
 }
 
@@ -47,6 +65,7 @@ impl RegisteredPlayer {
       input,
       colour,
       alive: true,
+      is_ready: false,
       mutable: true,
     }
   }
@@ -59,6 +78,7 @@ impl RegisteredPlayer {
       input,
       colour,
       alive: true,
+      is_ready: false,
       mutable: false,
     }
   }
@@ -0,0 +1,330 @@
+#![cfg(feature = "online")]
+
+use crate::app_state::AppState;
+use crate::prelude::constants::{DEFAULT_FONT, NORMAL_FONT, SMALL_FONT, TEXT_COLOUR};
+use crate::prelude::{
+  CustomInteraction, MenuName, PublicRoomInfo, PublicRoomsMessage, RefreshPublicRoomsMessage, RoomSelectedMessage,
+};
+use crate::shared::ToggleMenuMessage;
+use crate::ui::shared::{
+  BackgroundRoot, default_shadow, despawn_children, despawn_menu, menu_base_node, spawn_background_if_not_exists,
+  spawn_button, spawn_logo,
+};
+use bevy::app::{App, Plugin};
+use bevy::asset::{AssetServer, Assets, Handle};
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::image::TextureAtlasLayout;
+use bevy::log::debug;
+use bevy::prelude::{
+  AlignItems, Changed, ChildOf, Children, Commands, Component, Entity, FlexDirection, Font, FontSize,
+  IntoScheduleConfigs, JustifyContent, MessageReader, MessageWriter, Name, Node, OnExit, Query, Res, ResMut, Text,
+  TextFont, Update, With, default, in_state, percent, px,
+};
+
+/// A plugin to manage the menu UI that lists the public rooms of the signalling server, so that players can join a game
+/// without being given a room ID. Only included with the "online" feature.
+pub struct BrowseGamesMenuPlugin;
+
+impl Plugin for BrowseGamesMenuPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        Update,
+        (
+          handle_toggle_menu_message,
+          handle_button_interactions_system,
+          handle_public_rooms_message,
+        )
+          .chain()
+          .run_if(in_state(AppState::Preparing)),
+      )
+      .add_systems(OnExit(AppState::Preparing), despawn_menu_system);
+  }
+}
+
+/// The maximum number of public rooms shown at once, so that the menu fits on the screen.
+const MAX_LISTED_ROOMS: usize = 5;
+
+/// Marker component for the root of the menu. Used for despawning.
+#[derive(Component)]
+struct BrowseGamesMenuRoot;
+
+/// Marker component for the node that holds the list of public rooms.
+#[derive(Component)]
+struct PublicRoomList;
+
+/// Component for a button that joins a public room.
+#[derive(Component)]
+struct PublicRoomButton {
+  room_id: String,
+  password_required: bool,
+}
+
+/// Marker component for the refresh button.
+#[derive(Component)]
+struct RefreshButton;
+
+/// Marker component for the back button.
+#[derive(Component)]
+struct BackButton;
+
+/// System to handle toggling the browse games menu based on received messages. Requests the public room list whenever
+/// the menu is opened.
+fn handle_toggle_menu_message(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  mut messages: MessageReader<ToggleMenuMessage>,
+  menu_root_query: Query<Entity, With<BrowseGamesMenuRoot>>,
+  background_root_query: Query<Entity, With<BackgroundRoot>>,
+  mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+  mut refresh_public_rooms_message: MessageWriter<RefreshPublicRoomsMessage>,
+) {
+  for message in messages.read() {
+    match message.active {
+      MenuName::BrowseGamesMenu => {
+        spawn_menu(
+          &mut commands,
+          &asset_server,
+          &mut texture_atlas_layouts,
+          background_root_query,
+        );
+        refresh_public_rooms_message.write(RefreshPublicRoomsMessage);
+      }
+      _ => despawn_menu(&mut commands, &menu_root_query),
+    }
+  }
+}
+
+fn spawn_menu(
+  commands: &mut Commands,
+  asset_server: &AssetServer,
+  texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+  background_root_query: Query<Entity, With<BackgroundRoot>>,
+) {
+  let font = asset_server.load(DEFAULT_FONT);
+  let background_image = asset_server.load("images/background.png");
+  let logo_image = asset_server.load("images/logo_animated.png");
+
+  // Background & logo
+  spawn_background_if_not_exists(
+    commands,
+    BackgroundRoot,
+    background_image,
+    texture_atlas_layouts,
+    background_root_query,
+  );
+  spawn_logo(commands, BrowseGamesMenuRoot, logo_image, texture_atlas_layouts);
+
+  // Browse games UI
+  commands
+    .spawn(menu_base_node(BrowseGamesMenuRoot, "Browse Games Menu".to_string()))
+    .with_children(|parent| {
+      parent
+        .spawn(Node {
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          width: percent(75),
+          row_gap: px(20.),
+          ..default()
+        })
+        .with_children(|parent| {
+          // List of public rooms, filled once the signalling server has responded
+          parent
+            .spawn((
+              Name::new("Public Room List"),
+              PublicRoomList,
+              Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                width: percent(100),
+                row_gap: px(10.),
+                ..default()
+              },
+            ))
+            .with_children(|parent| {
+              spawn_list_text(parent, &font, "Looking for public games...");
+            });
+
+          // Button: Refresh
+          spawn_button(parent, asset_server, RefreshButton, "Refresh", 300, NORMAL_FONT);
+
+          // Button: Back
+          spawn_button(parent, asset_server, BackButton, "Back", 300, NORMAL_FONT);
+        });
+    });
+}
+
+/// Spawns a line of text in the list of public rooms e.g. to explain why it is empty.
+fn spawn_list_text(parent: &mut RelatedSpawnerCommands<ChildOf>, font: &Handle<Font>, text: &str) {
+  parent.spawn((
+    Text::new(text),
+    TextFont {
+      font: font.clone().into(),
+      font_size: FontSize::Px(SMALL_FONT),
+      ..default()
+    },
+    TEXT_COLOUR,
+    default_shadow(),
+  ));
+}
+
+/// Returns the label of a public room's button. Example: "Alice's game (2/5) - Password".
+fn public_room_label(room: &PublicRoomInfo) -> String {
+  format!(
+    "{} ({}/{}){}",
+    room.name,
+    room.player_count,
+    room.max_players,
+    if room.password_required { " - Password" } else { "" }
+  )
+}
+
+/// System to replace the list of public rooms whenever the signalling server has responded.
+fn handle_public_rooms_message(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  mut messages: MessageReader<PublicRoomsMessage>,
+  list_query: Query<(Entity, Option<&Children>), With<PublicRoomList>>,
+) {
+  let Some(message) = messages.read().last() else {
+    return;
+  };
+  let font = asset_server.load(DEFAULT_FONT);
+  for (entity, children) in &list_query {
+    if let Some(children) = children {
+      despawn_children(&mut commands, children);
+    }
+    commands.entity(entity).with_children(|parent| match &message.rooms {
+      Err(_) => spawn_list_text(parent, &font, "Unable to load public games - please try again later"),
+      Ok(rooms) if rooms.is_empty() => spawn_list_text(parent, &font, "No public games right now - why not host one?"),
+      Ok(rooms) => {
+        for room in rooms.iter().take(MAX_LISTED_ROOMS) {
+          let button = PublicRoomButton {
+            room_id: room.room_id.clone(),
+            password_required: room.password_required,
+          };
+          spawn_button(parent, &asset_server, button, &public_room_label(room), 500, SMALL_FONT);
+        }
+      }
+    });
+  }
+}
+
+/// A system to handle all browse games menu button interactions.
+fn handle_button_interactions_system(
+  public_room_button_query: Query<(&CustomInteraction, &PublicRoomButton), Changed<CustomInteraction>>,
+  refresh_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<RefreshButton>)>,
+  back_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<BackButton>)>,
+  mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
+  mut refresh_public_rooms_message: MessageWriter<RefreshPublicRoomsMessage>,
+  mut room_selected_message: MessageWriter<RoomSelectedMessage>,
+) {
+  for (interaction, button) in &public_room_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected public room [{}]", button.room_id);
+      toggle_menu_message.write(ToggleMenuMessage::set(MenuName::JoinGameMenu));
+      room_selected_message.write(RoomSelectedMessage {
+        room_id: button.room_id.clone(),
+        password_required: button.password_required,
+      });
+    }
+  }
+
+  for interaction in &refresh_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Refresh\"");
+      refresh_public_rooms_message.write(RefreshPublicRoomsMessage);
+    }
+  }
+
+  for interaction in &back_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Back\"");
+      toggle_menu_message.write(ToggleMenuMessage::set(MenuName::PlayOnlineMenu));
+    }
+  }
+}
+
+/// Despawns all elements with the [`BrowseGamesMenuRoot`] component.
+fn despawn_menu_system(mut commands: Commands, menu_root_query: Query<Entity, With<BrowseGamesMenuRoot>>) {
+  despawn_menu(&mut commands, &menu_root_query);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bevy::prelude::{App, Messages, MinimalPlugins, Update};
+
+  fn setup() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_message::<ToggleMenuMessage>();
+    app.add_message::<RefreshPublicRoomsMessage>();
+    app.add_message::<RoomSelectedMessage>();
+    app
+  }
+
+  #[test]
+  fn public_room_label_includes_player_count_and_password_requirement() {
+    let mut room = PublicRoomInfo {
+      room_id: "ABC123".to_string(),
+      name: "Alice's game".to_string(),
+      player_count: 2,
+      max_players: 5,
+      password_required: false,
+    };
+    assert_eq!(public_room_label(&room), "Alice's game (2/5)");
+
+    room.password_required = true;
+    assert_eq!(public_room_label(&room), "Alice's game (2/5) - Password");
+  }
+
+  #[test]
+  fn handle_button_interactions_system_opens_join_menu_for_selected_public_room() {
+    let mut app = setup();
+    app.add_systems(Update, handle_button_interactions_system);
+    app.world_mut().spawn((
+      PublicRoomButton {
+        room_id: "ABC123".to_string(),
+        password_required: true,
+      },
+      CustomInteraction::Released,
+    ));
+
+    app.update();
+
+    let toggle_menu_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<ToggleMenuMessage>>()
+      .iter_current_update_messages()
+      .map(|message| message.active)
+      .collect();
+    assert_eq!(toggle_menu_messages, vec![MenuName::JoinGameMenu]);
+    let room_selected_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<RoomSelectedMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(
+      room_selected_messages,
+      vec![RoomSelectedMessage {
+        room_id: "ABC123".to_string(),
+        password_required: true,
+      }]
+    );
+  }
+
+  #[test]
+  fn handle_button_interactions_system_refreshes_public_rooms_when_refresh_button_released() {
+    let mut app = setup();
+    app.add_systems(Update, handle_button_interactions_system);
+    app.world_mut().spawn((RefreshButton, CustomInteraction::Released));
+
+    app.update();
+
+    let refresh_messages = app.world().resource::<Messages<RefreshPublicRoomsMessage>>();
+    assert_eq!(refresh_messages.iter_current_update_messages().count(), 1);
+  }
+}
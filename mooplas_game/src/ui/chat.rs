@@ -0,0 +1,501 @@
+#![cfg(feature = "online")]
+
+use crate::app_state::AppState;
+use crate::prelude::constants::{
+  ACCENT_COLOUR, BUTTON_ALPHA_DEFAULT, DEFAULT_FONT, MAX_CHAT_MESSAGE_LENGTH, SMALL_FONT, TEXT_COLOUR,
+};
+use crate::prelude::{ChatMessage, CustomInteraction, SendChatMessage};
+use crate::ui::shared::{default_shadow, despawn_children, spawn_button};
+use bevy::app::{App, Plugin, PreUpdate, Update};
+use bevy::asset::AssetServer;
+use bevy::color::palettes::tailwind;
+use bevy::color::{Alpha, Color};
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::input::{ButtonInput, InputSystems};
+use bevy::input_focus::InputFocus;
+use bevy::input_focus::tab_navigation::TabIndex;
+use bevy::log::debug;
+use bevy::picking::Pickable;
+use bevy::prelude::{
+  AlignItems, BackgroundColor, BorderColor, BorderRadius, Changed, ChildOf, Children, Commands, Component, Entity,
+  FlexDirection, FlexWrap, Font, FontSize, Handle, IntoScheduleConfigs, KeyCode, MessageReader, MessageWriter, Name,
+  Node, OnEnter, OnExit, PositionType, Query, Real, Res, ResMut, Resource, Text, TextColor, TextFont, TextLayout, Time,
+  Timer, TimerMode, UiRect, With, default, in_state, percent, px, resource_changed,
+};
+use bevy::text::{EditableText, TextCursorStyle};
+use mooplas_networking::prelude::NetworkRole;
+use std::collections::VecDeque;
+
+/// A plugin that lets online players chat with each other. Adds a chat box with quick-chat presets to the lobby and
+/// shows chat messages as a fading overlay while playing.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<ChatLog>()
+      .add_systems(Update, record_chat_message_system)
+      .add_systems(
+        OnEnter(AppState::Registering),
+        spawn_lobby_chat_system.run_if(|network_role: Res<NetworkRole>| !network_role.is_none()),
+      )
+      .add_systems(
+        PreUpdate,
+        submit_chat_message_keyboard_system
+          .after(InputSystems)
+          .run_if(in_state(AppState::Registering)),
+      )
+      .add_systems(
+        Update,
+        (
+          handle_quick_chat_button_system,
+          update_lobby_chat_log_system.run_if(resource_changed::<ChatLog>),
+        )
+          .run_if(in_state(AppState::Registering)),
+      )
+      .add_systems(OnExit(AppState::Registering), despawn_lobby_chat_system)
+      .add_systems(
+        Update,
+        (spawn_chat_overlay_entries_system, fade_chat_overlay_entries_system).run_if(in_state(AppState::Playing)),
+      )
+      .add_systems(OnExit(AppState::Playing), despawn_chat_overlay_system)
+      .add_systems(OnEnter(AppState::Preparing), clear_chat_log_system);
+  }
+}
+
+/// Messages that can be sent with a single click or tap, without typing.
+const QUICK_CHAT_PRESETS: [&str; 4] = ["Good luck!", "Ready!", "Good game!", "One more?"];
+const CHAT_LOG_MAX_ENTRIES: usize = 6;
+const CHAT_OVERLAY_ENTRY_DURATION_SECONDS: f32 = 6.0;
+/// The time at the end of the lifetime of a chat overlay entry during which it fades out.
+const CHAT_OVERLAY_FADE_SECONDS: f32 = 1.5;
+
+/// The most recent chat messages, oldest first.
+#[derive(Resource, Default)]
+struct ChatLog(VecDeque<ChatMessage>);
+
+impl ChatLog {
+  fn push(&mut self, message: ChatMessage) {
+    if self.0.len() >= CHAT_LOG_MAX_ENTRIES {
+      self.0.pop_front();
+    }
+    self.0.push_back(message);
+  }
+}
+
+/// Marker component for the root of the chat box in the lobby. Used for despawning.
+#[derive(Component)]
+struct LobbyChatRoot;
+
+/// Marker component for the node that contains the chat log in the lobby.
+#[derive(Component)]
+struct LobbyChatLog;
+
+/// Marker component for the chat input field.
+#[derive(Component)]
+struct ChatInputField;
+
+/// A button that sends the quick-chat preset at the given index of [`QUICK_CHAT_PRESETS`].
+#[derive(Component)]
+struct QuickChatButton(usize);
+
+/// Marker component for the root of the chat overlay shown while playing. All chat overlay entries are children of
+/// this.
+#[derive(Component)]
+struct ChatOverlayRoot;
+
+/// Timer controlling the lifetime of a chat overlay entry. Uses real time so that entries also expire while the game is
+/// paused.
+#[derive(Component)]
+struct ChatOverlayEntry(Timer);
+
+/// Returns the alpha of a chat overlay entry with the given remaining lifetime: opaque at first, then fading out.
+fn chat_overlay_alpha(remaining_seconds: f32) -> f32 {
+  (remaining_seconds / CHAT_OVERLAY_FADE_SECONDS).clamp(0., 1.)
+}
+
+fn record_chat_message_system(mut messages: MessageReader<ChatMessage>, mut chat_log: ResMut<ChatLog>) {
+  for message in messages.read() {
+    debug!("[Chat] {}: {}", message.sender_name, message.text);
+    chat_log.push(message.clone());
+  }
+}
+
+fn clear_chat_log_system(mut chat_log: ResMut<ChatLog>) {
+  chat_log.0.clear();
+}
+
+/// Spawns a single line of the chat e.g. "Alice: Good luck!", with the name of the sender highlighted.
+fn spawn_chat_line(parent: &mut RelatedSpawnerCommands<ChildOf>, font: &Handle<Font>, message: &ChatMessage) {
+  let text_font = TextFont {
+    font: font.clone().into(),
+    font_size: FontSize::Px(SMALL_FONT),
+    ..default()
+  };
+  parent
+    .spawn((
+      Node {
+        flex_direction: FlexDirection::Row,
+        ..default()
+      },
+      Pickable::IGNORE,
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        Text::new(format!("{}: ", message.sender_name)),
+        text_font.clone(),
+        TextColor(Color::from(ACCENT_COLOUR)),
+        default_shadow(),
+      ));
+      parent.spawn((
+        Text::new(message.text.clone()),
+        text_font,
+        TEXT_COLOUR,
+        default_shadow(),
+      ));
+    });
+}
+
+/// Spawns the chat box in the bottom left corner of the lobby: the chat log, an input field and quick-chat buttons.
+fn spawn_lobby_chat_system(mut commands: Commands, asset_server: Res<AssetServer>, chat_log: Res<ChatLog>) {
+  let font = asset_server.load(DEFAULT_FONT);
+  let mut chat_input = EditableText::default();
+  chat_input.max_characters = Some(MAX_CHAT_MESSAGE_LENGTH);
+  chat_input.visible_width = Some(30.);
+
+  commands
+    .spawn((
+      LobbyChatRoot,
+      Name::new("Lobby Chat"),
+      Node {
+        position_type: PositionType::Absolute,
+        bottom: px(16),
+        left: px(16),
+        width: px(420),
+        flex_direction: FlexDirection::Column,
+        row_gap: px(8),
+        ..default()
+      },
+    ))
+    .with_children(|parent| {
+      parent
+        .spawn((
+          LobbyChatLog,
+          Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: px(2),
+            ..default()
+          },
+          Pickable::IGNORE,
+        ))
+        .with_children(|parent| {
+          for message in &chat_log.0 {
+            spawn_chat_line(parent, &font, message);
+          }
+        });
+      parent.spawn((
+        Name::new("Chat Input Field"),
+        ChatInputField,
+        chat_input,
+        TabIndex(0),
+        TextLayout::no_wrap(),
+        TextFont {
+          font: font.clone().into(),
+          font_size: FontSize::Px(SMALL_FONT),
+          ..default()
+        },
+        TextColor(Color::from(ACCENT_COLOUR)),
+        TextCursorStyle::default(),
+        BorderColor::all(Color::from(tailwind::SLATE_500)),
+        BackgroundColor(Color::from(tailwind::SLATE_500.with_alpha(BUTTON_ALPHA_DEFAULT))),
+        Node {
+          width: percent(100),
+          padding: UiRect::all(px(6.)),
+          align_items: AlignItems::Center,
+          border_radius: BorderRadius::all(px(6)),
+          ..default()
+        },
+      ));
+      parent
+        .spawn(Node {
+          flex_direction: FlexDirection::Row,
+          flex_wrap: FlexWrap::Wrap,
+          column_gap: px(8),
+          row_gap: px(8),
+          ..default()
+        })
+        .with_children(|parent| {
+          for (index, preset) in QUICK_CHAT_PRESETS.iter().enumerate() {
+            spawn_button(parent, &asset_server, QuickChatButton(index), preset, 200, SMALL_FONT);
+          }
+        });
+    });
+}
+
+/// Re-renders the chat log in the lobby whenever a chat message has been received.
+fn update_lobby_chat_log_system(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  chat_log: Res<ChatLog>,
+  chat_log_query: Query<(Entity, Option<&Children>), With<LobbyChatLog>>,
+) {
+  let font = asset_server.load(DEFAULT_FONT);
+  for (entity, children) in &chat_log_query {
+    if let Some(children) = children {
+      despawn_children(&mut commands, children);
+    }
+    commands.entity(entity).with_children(|parent| {
+      for message in &chat_log.0 {
+        spawn_chat_line(parent, &font, message);
+      }
+    });
+  }
+}
+
+/// Sends the content of the chat input field when [Enter] is pressed while it has focus, and leaves the input field on
+/// [Enter] and [Escape]. Runs right after the keyboard input has been updated and consumes these key presses, so that
+/// they don't also start the game.
+fn submit_chat_message_keyboard_system(
+  mut input_focus: ResMut<InputFocus>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  mut chat_input_query: Query<&mut EditableText, With<ChatInputField>>,
+  mut send_chat_message: MessageWriter<SendChatMessage>,
+) {
+  let Some(focused_entity) = input_focus.get() else {
+    return;
+  };
+  let Ok(mut chat_input) = chat_input_query.get_mut(focused_entity) else {
+    return;
+  };
+  if chat_input.is_composing() {
+    return;
+  }
+  if keyboard_input.just_pressed(KeyCode::Enter) {
+    let text = chat_input.value().to_string().trim().to_string();
+    if !text.is_empty() {
+      send_chat_message.write(SendChatMessage { text });
+    }
+    chat_input.clear();
+  } else if !keyboard_input.just_pressed(KeyCode::Escape) {
+    return;
+  }
+  keyboard_input.reset(KeyCode::Enter);
+  keyboard_input.reset(KeyCode::Escape);
+  input_focus.clear();
+}
+
+/// Sends the quick-chat preset of the quick-chat button that has been clicked or tapped.
+fn handle_quick_chat_button_system(
+  query: Query<(&CustomInteraction, &QuickChatButton), Changed<CustomInteraction>>,
+  mut send_chat_message: MessageWriter<SendChatMessage>,
+) {
+  for (interaction, button) in &query {
+    if *interaction == CustomInteraction::Released {
+      send_chat_message.write(SendChatMessage {
+        text: QUICK_CHAT_PRESETS[button.0].to_string(),
+      });
+    }
+  }
+}
+
+fn despawn_lobby_chat_system(mut commands: Commands, lobby_chat_query: Query<Entity, With<LobbyChatRoot>>) {
+  for entity in &lobby_chat_query {
+    commands.entity(entity).despawn();
+  }
+}
+
+/// Shows each chat message received while playing as a short-lived entry in the bottom left corner of the screen.
+fn spawn_chat_overlay_entries_system(
+  mut commands: Commands,
+  mut messages: MessageReader<ChatMessage>,
+  asset_server: Res<AssetServer>,
+  chat_overlay_root_query: Query<(Entity, Option<&Children>), With<ChatOverlayRoot>>,
+) {
+  if messages.is_empty() {
+    return;
+  }
+
+  let (root, mut entries) = match chat_overlay_root_query.single() {
+    Ok((root, children)) => (root, children.map(|c| c.iter().collect::<Vec<_>>()).unwrap_or_default()),
+    Err(_) => (spawn_chat_overlay_root(&mut commands), Vec::new()),
+  };
+  let font = asset_server.load(DEFAULT_FONT);
+
+  for message in messages.read() {
+    // Remove the oldest entry to make space for the new one
+    if entries.len() >= CHAT_LOG_MAX_ENTRIES {
+      commands.entity(entries.remove(0)).despawn();
+    }
+    let entry = commands
+      .spawn((
+        ChatOverlayEntry(Timer::from_seconds(
+          CHAT_OVERLAY_ENTRY_DURATION_SECONDS,
+          TimerMode::Once,
+        )),
+        Name::new("Chat Overlay Entry"),
+        Node::default(),
+        Pickable::IGNORE,
+      ))
+      .with_children(|parent| spawn_chat_line(parent, &font, message))
+      .id();
+    commands.entity(root).add_child(entry);
+    entries.push(entry);
+  }
+}
+
+fn spawn_chat_overlay_root(commands: &mut Commands) -> Entity {
+  commands
+    .spawn((
+      ChatOverlayRoot,
+      Name::new("Chat Overlay"),
+      Node {
+        position_type: PositionType::Absolute,
+        bottom: px(16),
+        left: px(16),
+        flex_direction: FlexDirection::Column,
+        row_gap: px(2),
+        ..default()
+      },
+      Pickable::IGNORE,
+    ))
+    .id()
+}
+
+/// Advances the timer of each chat overlay entry, fades it out towards the end of its lifetime and despawns it when the
+/// timer has expired.
+fn fade_chat_overlay_entries_system(
+  mut commands: Commands,
+  time: Res<Time<Real>>,
+  mut entry_query: Query<(Entity, &mut ChatOverlayEntry)>,
+  children_query: Query<&Children>,
+  mut text_colour_query: Query<&mut TextColor>,
+) {
+  for (entity, mut entry) in &mut entry_query {
+    entry.0.tick(time.delta());
+    if entry.0.just_finished() {
+      commands.entity(entity).despawn();
+      continue;
+    }
+    let alpha = chat_overlay_alpha(entry.0.remaining_secs());
+    for text in children_query.iter_descendants(entity) {
+      if let Ok(mut text_colour) = text_colour_query.get_mut(text) {
+        text_colour.0.set_alpha(alpha);
+      }
+    }
+  }
+}
+
+/// Despawns the chat overlay so that chat messages from the last round don't linger into the next one.
+fn despawn_chat_overlay_system(mut commands: Commands, chat_overlay_query: Query<Entity, With<ChatOverlayRoot>>) {
+  for entity in &chat_overlay_query {
+    commands.entity(entity).despawn();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bevy::prelude::{Messages, MinimalPlugins};
+
+  fn setup() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_message::<SendChatMessage>();
+    app
+  }
+
+  fn chat_message(text: &str) -> ChatMessage {
+    ChatMessage {
+      sender_name: "Alice".to_string(),
+      text: text.to_string(),
+    }
+  }
+
+  fn sent_chat_messages(app: &mut App) -> Vec<String> {
+    app
+      .world_mut()
+      .resource_mut::<Messages<SendChatMessage>>()
+      .drain()
+      .map(|message| message.text)
+      .collect()
+  }
+
+  #[test]
+  fn chat_log_keeps_only_the_most_recent_messages() {
+    let mut chat_log = ChatLog::default();
+
+    for index in 0..CHAT_LOG_MAX_ENTRIES + 2 {
+      chat_log.push(chat_message(&index.to_string()));
+    }
+
+    assert_eq!(chat_log.0.len(), CHAT_LOG_MAX_ENTRIES);
+    assert_eq!(chat_log.0.front(), Some(&chat_message("2")));
+  }
+
+  #[test]
+  fn chat_overlay_alpha_only_fades_at_the_end_of_the_lifetime() {
+    assert_eq!(chat_overlay_alpha(CHAT_OVERLAY_ENTRY_DURATION_SECONDS), 1.);
+    assert_eq!(chat_overlay_alpha(CHAT_OVERLAY_FADE_SECONDS / 2.), 0.5);
+    assert_eq!(chat_overlay_alpha(0.), 0.);
+  }
+
+  #[test]
+  fn submit_chat_message_keyboard_system_sends_trimmed_text_and_consumes_enter() {
+    let mut app = setup();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_systems(Update, submit_chat_message_keyboard_system);
+    let input_entity = app
+      .world_mut()
+      .spawn((ChatInputField, EditableText::new("  Good luck!  ")))
+      .id();
+    app.world_mut().insert_resource(InputFocus::from_entity(input_entity));
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .press(KeyCode::Enter);
+
+    app.update();
+
+    assert_eq!(sent_chat_messages(&mut app), vec!["Good luck!".to_string()]);
+    assert!(
+      app
+        .world()
+        .get::<EditableText>(input_entity)
+        .expect("Expected chat input field")
+        .value()
+        .is_empty()
+    );
+    assert!(app.world().resource::<InputFocus>().get().is_none());
+    assert!(!app.world().resource::<ButtonInput<KeyCode>>().pressed(KeyCode::Enter));
+  }
+
+  #[test]
+  fn submit_chat_message_keyboard_system_ignores_enter_without_focus() {
+    let mut app = setup();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<InputFocus>();
+    app.add_systems(Update, submit_chat_message_keyboard_system);
+    app.world_mut().spawn((ChatInputField, EditableText::new("Good luck!")));
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .press(KeyCode::Enter);
+
+    app.update();
+
+    assert!(sent_chat_messages(&mut app).is_empty());
+    assert!(app.world().resource::<ButtonInput<KeyCode>>().pressed(KeyCode::Enter));
+  }
+
+  #[test]
+  fn handle_quick_chat_button_system_sends_preset_when_released() {
+    let mut app = setup();
+    app.add_systems(Update, handle_quick_chat_button_system);
+    app.world_mut().spawn((QuickChatButton(1), CustomInteraction::Released));
+    app.world_mut().spawn((QuickChatButton(2), CustomInteraction::None));
+
+    app.update();
+
+    assert_eq!(sent_chat_messages(&mut app), vec![QUICK_CHAT_PRESETS[1].to_string()]);
+  }
+}
@@ -1,7 +1,9 @@
 #![cfg(feature = "online")]
 
 use crate::app_state::AppState;
-use crate::prelude::constants::{ACCENT_COLOUR, BUTTON_ALPHA_DEFAULT, DEFAULT_FONT, NORMAL_FONT, TEXT_COLOUR};
+use crate::prelude::constants::{
+  ACCENT_COLOUR, BUTTON_ALPHA_DEFAULT, DEFAULT_FONT, MAX_PLAYER_NAME_LENGTH, NORMAL_FONT, TEXT_COLOUR,
+};
 use crate::prelude::{CustomInteraction, MenuName, PlayerName};
 use crate::shared::ToggleMenuMessage;
 use crate::shared::constants::SMALL_FONT;
@@ -104,7 +106,7 @@ fn spawn_menu(
 
   let initial_name = player_name.get().to_string();
   let mut name_input = EditableText::new(initial_name);
-  name_input.max_characters = Some(10);
+  name_input.max_characters = Some(MAX_PLAYER_NAME_LENGTH);
   name_input.visible_width = Some(10.);
 
   // Enter name UI
@@ -123,7 +125,10 @@ fn spawn_menu(
         .with_children(|parent| {
           // Prompt text
           parent.spawn((
-            Text::new("Choose your name (max. 10 characters):"),
+            Text::new(format!(
+              "Choose your name (max. {} characters):",
+              MAX_PLAYER_NAME_LENGTH
+            )),
             TextFont {
               font: heading_font.clone().into(),
               font_size: FontSize::Px(SMALL_FONT),
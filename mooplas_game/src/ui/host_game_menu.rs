@@ -1,8 +1,11 @@
 use crate::app_state::AppState;
-use crate::prelude::constants::{ACCENT_COLOUR, BUTTON_ALPHA_DEFAULT, DEFAULT_FONT, NORMAL_FONT, TEXT_COLOUR};
-use crate::prelude::{ConnectionInfoMessage, CustomInteraction, MenuName};
+use crate::prelude::constants::{
+  ACCENT_COLOUR, BUTTON_ALPHA_DEFAULT, DEFAULT_FONT, MAX_ROOM_PASSWORD_LENGTH, NORMAL_FONT, TEXT_COLOUR,
+};
+use crate::prelude::{ConnectionInfoMessage, CustomInteraction, MenuName, RoomPasswordMessage, RoomVisibilityMessage};
 use crate::shared::ToggleMenuMessage;
 use crate::shared::constants::SMALL_FONT;
+use crate::ui::launch_options::invite_link;
 use crate::ui::shared::{
   BackgroundRoot, despawn_menu, menu_base_node, spawn_background_if_not_exists, spawn_button, spawn_logo,
 };
@@ -11,14 +14,14 @@ use bevy::asset::{AssetServer, Assets};
 use bevy::color::Color;
 use bevy::color::palettes::tailwind;
 use bevy::image::TextureAtlasLayout;
-use bevy::input_focus::AutoFocus;
 use bevy::input_focus::tab_navigation::TabIndex;
+use bevy::input_focus::{AutoFocus, InputFocus};
 use bevy::log::*;
 use bevy::prelude::{
-  AlignItems, Alpha, BackgroundColor, BorderColor, BorderRadius, Changed, Commands, Component, Entity, FlexDirection,
-  FontSize, IntoScheduleConfigs, Justify, JustifyContent, Local, MessageReader, MessageWriter, Name, Node, OnExit,
-  Query, Res, ResMut, Text, TextColor, TextFont, TextLayout, TextShadow, UiRect, Update, With, default, in_state,
-  percent, px,
+  AlignItems, Alpha, BackgroundColor, BorderColor, BorderRadius, ButtonInput, Changed, Children, Commands, Component,
+  Entity, FlexDirection, FontSize, IntoScheduleConfigs, Justify, JustifyContent, KeyCode, Local, MessageReader,
+  MessageWriter, Name, Node, OnExit, Query, Res, ResMut, Text, TextColor, TextFont, TextLayout, TextShadow, UiRect,
+  Update, With, Without, default, in_state, percent, px,
 };
 use bevy::text::{EditableText, LineHeight, TextCursorStyle, TextEdit};
 
@@ -35,6 +38,7 @@ impl Plugin for HostGameMenuPlugin {
           handle_toggle_menu_message,
           handle_button_interactions_system,
           handle_connection_info_updated_message,
+          handle_submit_password_keyboard_system,
         )
           .chain()
           .run_if(in_state(AppState::Preparing)),
@@ -51,6 +55,20 @@ struct HostGameMenuRoot;
 #[derive(Component)]
 struct HostRoomInputField;
 
+/// Marker component for the invite link input field.
+#[derive(Component)]
+struct HostInviteLinkInputField;
+
+/// Marker component for the optional room password input field.
+#[derive(Component)]
+struct HostPasswordInputField;
+
+/// Component for the button that toggles whether the room is listed in the public room browser.
+#[derive(Component, Default)]
+struct RoomVisibilityButton {
+  is_public: bool,
+}
+
 /// Marker component for the back button.
 #[derive(Component)]
 struct BackButton;
@@ -90,6 +108,12 @@ fn spawn_menu(
   let mut room_input = EditableText::new("Generating room ID...");
   room_input.max_characters = Some(10);
   room_input.visible_width = Some(10.);
+  let mut invite_link_input = EditableText::default();
+  invite_link_input.max_characters = Some(300);
+  invite_link_input.visible_width = Some(30.);
+  let mut password_input = EditableText::default();
+  password_input.max_characters = Some(MAX_ROOM_PASSWORD_LENGTH);
+  password_input.visible_width = Some(20.);
 
   // Background & logo
   spawn_background_if_not_exists(
@@ -135,6 +159,80 @@ fn spawn_menu(
             AutoFocus,
             TabIndex(0),
             TextLayout::no_wrap().with_justify(Justify::Center),
+            TextFont {
+              font: font.clone().into(),
+              font_size: FontSize::Px(NORMAL_FONT),
+              ..Default::default()
+            },
+            TextColor(Color::from(ACCENT_COLOUR)),
+            TextCursorStyle::default(),
+            BorderColor::all(Color::from(tailwind::SLATE_500)),
+            BackgroundColor(Color::from(tailwind::SLATE_500.with_alpha(BUTTON_ALPHA_DEFAULT))),
+            Node {
+              width: percent(100),
+              height: px(55.),
+              padding: UiRect::all(px(10.)),
+              align_items: AlignItems::Center,
+              justify_content: JustifyContent::Center,
+              border_radius: BorderRadius::all(px(10)),
+              ..default()
+            },
+          ));
+
+          // Text input field to copy the invite link from, which joins the room directly
+          parent.spawn((
+            Text::new("Or send them this invite link:"),
+            TextFont {
+              font: heading_font.clone().into(),
+              font_size: FontSize::Px(SMALL_FONT),
+              ..default()
+            },
+            TEXT_COLOUR,
+            TextShadow::default(),
+          ));
+          parent.spawn((
+            Name::new("Invite Link Input Field"),
+            HostInviteLinkInputField,
+            invite_link_input,
+            TabIndex(1),
+            TextLayout::no_wrap().with_justify(Justify::Center),
+            TextFont {
+              font: font.clone().into(),
+              font_size: FontSize::Px(SMALL_FONT),
+              ..Default::default()
+            },
+            TextColor(Color::from(ACCENT_COLOUR)),
+            TextCursorStyle::default(),
+            BorderColor::all(Color::from(tailwind::SLATE_500)),
+            BackgroundColor(Color::from(tailwind::SLATE_500.with_alpha(BUTTON_ALPHA_DEFAULT))),
+            Node {
+              width: percent(100),
+              height: px(45.),
+              padding: UiRect::all(px(10.)),
+              align_items: AlignItems::Center,
+              justify_content: JustifyContent::Center,
+              border_radius: BorderRadius::all(px(10)),
+              ..default()
+            },
+          ));
+
+          // Optional password to keep strangers out
+          parent.spawn((
+            Text::new("Optionally, protect the room with a password (press Enter to apply):"),
+            TextFont {
+              font: heading_font.clone().into(),
+              font_size: FontSize::Px(SMALL_FONT),
+              ..default()
+            },
+            TEXT_COLOUR,
+            TextShadow::default(),
+          ));
+          parent.spawn((
+            Name::new("Password Input Field"),
+            HostPasswordInputField,
+            password_input,
+            TabIndex(2),
+            TextLayout::no_wrap().with_justify(Justify::Center),
             TextFont {
               font: font.into(),
               font_size: FontSize::Px(NORMAL_FONT),
@@ -168,16 +266,28 @@ fn spawn_menu(
             TextShadow::default(),
           ));
 
+          // Room visibility button
+          let label = room_visibility_button_label(false);
+          spawn_button(
+            parent,
+            asset_server,
+            RoomVisibilityButton::default(),
+            label,
+            300,
+            NORMAL_FONT,
+          );
+
           // Back button
           spawn_button(parent, asset_server, BackButton, "Back", 300, NORMAL_FONT);
         });
     });
 }
 
-/// System to handle updating the host address text when the connection info is updated.
+/// System to handle updating the host address text and the invite link when the connection info is updated.
 fn handle_connection_info_updated_message(
   mut messages: MessageReader<ConnectionInfoMessage>,
-  mut room_input_query: Query<&mut EditableText, With<HostRoomInputField>>,
+  mut room_input_query: Query<&mut EditableText, (With<HostRoomInputField>, Without<HostInviteLinkInputField>)>,
+  mut invite_link_input_query: Query<&mut EditableText, (With<HostInviteLinkInputField>, Without<HostRoomInputField>)>,
   mut retryable_connection_info_message: Local<Option<(ConnectionInfoMessage, bool)>>,
 ) {
   for message in messages.read() {
@@ -194,6 +304,13 @@ fn handle_connection_info_updated_message(
         trace!("Successfully updated text input field with connection info");
         *is_processed = true;
       }
+      for mut invite_link_input in &mut invite_link_input_query {
+        invite_link_input.clear();
+        invite_link_input
+          .editor_mut()
+          .set_text(&invite_link(&message.connection_string));
+        invite_link_input.queue_edit(TextEdit::TextEnd(false));
+      }
     }
     if *is_processed {
       *retryable_connection_info_message = None;
@@ -201,12 +318,66 @@ fn handle_connection_info_updated_message(
   }
 }
 
+/// System to apply the room password when Enter is pressed in the password input field. An empty password removes the
+/// protection again.
+fn handle_submit_password_keyboard_system(
+  input_focus: Res<InputFocus>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  password_input_query: Query<&EditableText, With<HostPasswordInputField>>,
+  mut room_password_message: MessageWriter<RoomPasswordMessage>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::Enter) {
+    return;
+  }
+  let Some(focused_entity) = input_focus.get() else {
+    return;
+  };
+  let Ok(password_input) = password_input_query.get(focused_entity) else {
+    return;
+  };
+  if password_input.is_composing() {
+    return;
+  }
+  let password = Some(password_input.value().to_string()).filter(|password| !password.is_empty());
+  debug!("[Menu] Submitted room password (set: {})", password.is_some());
+  room_password_message.write(RoomPasswordMessage { password });
+}
+
+/// Returns the label of the [`RoomVisibilityButton`] i.e. the action that pressing it performs.
+fn room_visibility_button_label(is_public: bool) -> &'static str {
+  if is_public { "Make Private" } else { "Make Public" }
+}
+
 /// System to handle all host game menu button interactions.
 //noinspection DuplicatedCode
 fn handle_button_interactions_system(
+  mut visibility_button_query: Query<
+    (&CustomInteraction, &mut RoomVisibilityButton, &Children),
+    Changed<CustomInteraction>,
+  >,
   mut back_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<BackButton>)>,
+  mut text_query: Query<&mut Text>,
   mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
+  mut room_visibility_message: MessageWriter<RoomVisibilityMessage>,
 ) {
+  for (interaction, mut button, children) in &mut visibility_button_query {
+    if *interaction == CustomInteraction::Released {
+      button.is_public = !button.is_public;
+      debug!(
+        "[Menu] Selected \"{}\"",
+        room_visibility_button_label(!button.is_public)
+      );
+      room_visibility_message.write(RoomVisibilityMessage {
+        is_public: button.is_public,
+      });
+      for child in children.iter() {
+        if let Ok(mut text) = text_query.get_mut(child) {
+          **text = room_visibility_button_label(button.is_public).to_string();
+        }
+      }
+    }
+  }
+
   for interaction in &mut back_button_query {
     if *interaction == CustomInteraction::Released {
       debug!("[Menu] Selected \"Back\"");
@@ -254,6 +425,31 @@ mod tests {
     assert_eq!(input.value().to_string(), "abc123");
   }
 
+  #[test]
+  fn handle_connection_info_updated_message_updates_invite_link_editable_text() {
+    let mut app = setup();
+    app.add_systems(Update, handle_connection_info_updated_message);
+    app
+      .world_mut()
+      .spawn((HostRoomInputField, EditableText::new("Generating room ID...")));
+    app
+      .world_mut()
+      .spawn((HostInviteLinkInputField, EditableText::default()));
+    app
+      .world_mut()
+      .write_message(ConnectionInfoMessage::new("abc123".to_string()))
+      .expect("Failed to write ConnectionInfoMessage");
+
+    app.update();
+
+    let mut input_query = app
+      .world_mut()
+      .query_filtered::<&EditableText, With<HostInviteLinkInputField>>();
+    let input = input_query.single(app.world()).expect("Expected invite link input");
+    assert_eq!(input.value().to_string(), invite_link("abc123"));
+    assert!(input.value().to_string().contains("abc123"));
+  }
+
   #[test]
   fn handle_connection_info_updated_message_retries_until_host_room_input_exists() {
     let mut app = setup();
@@ -292,4 +488,84 @@ mod tests {
     assert_eq!(toggle_menu_messages.len(), 1);
     assert_eq!(toggle_menu_messages[0].active, MenuName::PlayOnlineMenu);
   }
+
+  #[test]
+  fn handle_button_interactions_system_toggles_room_visibility_when_visibility_button_released() {
+    let mut app = setup();
+    app.add_message::<RoomVisibilityMessage>();
+    app.add_systems(Update, handle_button_interactions_system);
+    let button_entity = app
+      .world_mut()
+      .spawn((RoomVisibilityButton::default(), CustomInteraction::Released))
+      .with_child(Text::new(room_visibility_button_label(false)))
+      .id();
+
+    app.update();
+
+    let room_visibility_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<RoomVisibilityMessage>>()
+      .iter_current_update_messages()
+      .copied()
+      .collect();
+    assert_eq!(
+      room_visibility_messages,
+      vec![RoomVisibilityMessage { is_public: true }]
+    );
+    let button = app
+      .world()
+      .get::<RoomVisibilityButton>(button_entity)
+      .expect("Expected room visibility button");
+    assert!(button.is_public);
+    let mut text_query = app.world_mut().query::<&Text>();
+    let text = text_query.single(app.world()).expect("Expected button text");
+    assert_eq!(text.as_str(), "Make Private");
+  }
+
+  #[test]
+  fn handle_submit_password_keyboard_system_sends_password_and_removes_it_when_empty() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordMessage>();
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_systems(Update, handle_submit_password_keyboard_system);
+    let input_entity = app
+      .world_mut()
+      .spawn((HostPasswordInputField, EditableText::new("hunter2")))
+      .id();
+    app.world_mut().insert_resource(InputFocus::from_entity(input_entity));
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .press(KeyCode::Enter);
+
+    app.update();
+
+    let room_password_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<RoomPasswordMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(
+      room_password_messages,
+      vec![RoomPasswordMessage {
+        password: Some("hunter2".to_string())
+      }]
+    );
+
+    app.world_mut().entity_mut(input_entity).insert(EditableText::default());
+    let mut keyboard_input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keyboard_input.release(KeyCode::Enter);
+    keyboard_input.clear();
+    keyboard_input.press(KeyCode::Enter);
+    app.update();
+
+    let room_password_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<RoomPasswordMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(room_password_messages, vec![RoomPasswordMessage { password: None }]);
+  }
 }
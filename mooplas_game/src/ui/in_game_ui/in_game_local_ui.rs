@@ -81,6 +81,7 @@ fn handle_local_player_registration_message(
     update_call_to_action_to_start(
       &mut commands,
       message.is_anyone_registered,
+      true,
       &cta_query,
       &asset_server,
       &settings,
@@ -1,13 +1,15 @@
 use crate::app_state::AppState;
-use crate::prelude::constants::{ACCENT_COLOUR, DEFAULT_FONT, TEXT_COLOUR};
+use crate::prelude::constants::{ACCENT_COLOUR, DEFAULT_FONT, SMALL_FONT, TEXT_COLOUR};
 use crate::prelude::{
-  AvailableControlSchemes, ControlScheme, ControlSchemeId, MAX_PLAYERS, PlayerId, RegisteredPlayers, Settings,
-  colour_for_player_id,
+  AvailableControlSchemes, ControlScheme, ControlSchemeId, CustomInteraction, LocalPlayerReadyRequestMessage,
+  LocalPlayerRegistrationRequestMessage, MAX_PLAYERS, ModeratePlayerMessage, ModerationAction, PlayerId,
+  RegisteredPlayers, Settings, colour_for_player_id,
 };
 use crate::shared::PlayerRegistrationMessage;
 use crate::ui::in_game_ui::in_game_ui;
 use crate::ui::shared::{
-  LobbyUiCta, default_font, default_shadow, despawn_children, player_display_name, player_slot_label,
+  LobbyUiCta, default_font, default_shadow, despawn_children, format_round_trip_time, player_display_name,
+  player_slot_label, spawn_button,
 };
 use bevy::app::{App, Plugin, Update};
 use bevy::asset::AssetServer;
@@ -15,25 +17,42 @@ use bevy::ecs::children;
 use bevy::ecs::relationship::RelatedSpawnerCommands;
 use bevy::ecs::spawn::{Spawn, SpawnRelatedBundle};
 use bevy::prelude::{
-  AlignItems, Alpha, ChildOf, Children, Color, Commands, Component, Entity, FlexDirection, Font, Handle,
-  IntoScheduleConfigs, Justify, JustifyContent, LineBreak, MessageReader, Node, Pickable, Query, Res, Text, TextColor,
-  TextFont, TextLayout, TextShadow, With, default, in_state,
+  AlignItems, Alpha, Changed, ChildOf, Children, Color, Commands, Component, Entity, FlexDirection, Font, Handle,
+  IntoScheduleConfigs, Justify, JustifyContent, LineBreak, MessageReader, MessageWriter, Node, Pickable, Query, Res,
+  Text, TextColor, TextFont, TextLayout, TextShadow, UiRect, With, default, in_state,
 };
 use bevy::text::LineHeight;
-use bevy::ui::{BackgroundColor, percent};
-use mooplas_networking::prelude::NetworkRole;
+use bevy::ui::{BackgroundColor, percent, px};
+use mooplas_networking::prelude::{NetworkRole, NetworkStats};
 
 /// A plugin that manages the online-only in-game lobby UI, including the player registration slots and the join prompt.
 pub struct InGameOnlineUiPlugin;
 
 impl Plugin for InGameOnlineUiPlugin {
   fn build(&self, app: &mut App) {
-    app.add_systems(
-      Update,
-      handle_online_player_registration_message
-        .run_if(in_state(AppState::Registering))
-        .run_if(|network_role: Res<NetworkRole>| !network_role.is_none()),
-    );
+    app
+      .add_systems(
+        Update,
+        (
+          handle_online_player_registration_message,
+          update_player_ping_labels_system,
+        )
+          .chain()
+          .run_if(in_state(AppState::Registering))
+          .run_if(|network_role: Res<NetworkRole>| !network_role.is_none()),
+      )
+      .add_systems(
+        Update,
+        handle_local_player_button_system
+          .run_if(in_state(AppState::Registering))
+          .run_if(|network_role: Res<NetworkRole>| !network_role.is_none()),
+      )
+      .add_systems(
+        Update,
+        handle_moderation_button_system
+          .run_if(in_state(AppState::Registering))
+          .run_if(|network_role: Res<NetworkRole>| network_role.is_server()),
+      );
   }
 }
 
@@ -43,6 +62,29 @@ struct OnlineLobbyUiEntry {
   player_id: PlayerId,
 }
 
+/// The component for the round-trip time of the client that registered a player, shown next to their status in the
+/// lobby UI.
+#[derive(Component)]
+struct PlayerPingLabel {
+  player_id: PlayerId,
+}
+
+/// The component for the buttons with which the host kicks or bans the client that registered a player.
+#[derive(Component)]
+struct ModerationButton {
+  player_id: PlayerId,
+  action: ModerationAction,
+}
+
+/// The component for the buttons with which a local player becomes ready or leaves the lobby.
+#[derive(Component)]
+enum LocalPlayerButton {
+  /// Marks the player as ready (true) or not ready (false).
+  SetReady { player_id: PlayerId, is_ready: bool },
+  /// Unregisters the player.
+  Leave { control_scheme_id: ControlSchemeId },
+}
+
 /// Marker component for the prompt to join by pressing an available action key.
 #[derive(Component)]
 struct JoinPromptNode;
@@ -57,9 +99,11 @@ enum PlayerEntryState {
 pub(crate) fn spawn_online_lobby_ui(
   commands: &mut Commands,
   root: Entity,
+  asset_server: &AssetServer,
   font: &Handle<Font>,
   available_control_schemes: &AvailableControlSchemes,
   registered_players: &RegisteredPlayers,
+  is_host: bool,
 ) {
   for player_index in 0..MAX_PLAYERS {
     let player_id = PlayerId(player_index);
@@ -81,10 +125,12 @@ pub(crate) fn spawn_online_lobby_ui(
     spawn_online_lobby_ui_entry_children(
       commands,
       entry,
+      asset_server,
       font,
       player_id,
       available_control_schemes,
       registered_players,
+      is_host,
     );
   }
 
@@ -132,10 +178,12 @@ fn handle_online_player_registration_message(
       spawn_online_lobby_ui_entry_children(
         &mut commands,
         entity,
+        &asset_server,
         &font,
         entry.player_id,
         &available_control_schemes,
         &registered_players,
+        network_role.is_server(),
       );
     }
 
@@ -152,6 +200,7 @@ fn handle_online_player_registration_message(
     in_game_ui::update_call_to_action_to_start(
       &mut commands,
       message.is_anyone_registered,
+      registered_players.are_all_ready(),
       &cta_query,
       &asset_server,
       &settings,
@@ -160,21 +209,29 @@ fn handle_online_player_registration_message(
   }
 }
 
-/// Spawns a single row for an online player in the lobby UI, showing the player slot, whether they're registered, and
-/// their control scheme if registered. Examples:
-/// - "Player 1: Registered remotely"
-/// - "Player 2: Play with \[Z] and \[C]"
+/// Spawns a single row for an online player in the lobby UI, showing the player slot, whether they're registered,
+/// their control scheme and whether they're ready if registered, and the round-trip time of the client that registered
+/// them, if known. Locally registered players get buttons to become ready and to leave. On the host, remotely
+/// registered players also get buttons to kick or ban their client. Examples:
+/// - "Player 1: Registered remotely - Ready (42 ms) \[Kick] \[Ban]"
+/// - "Player 2: Play with \[Z] and \[C] - Not ready \[Ready] \[Leave]"
 /// - "Player 3: Not registered"
 fn spawn_online_lobby_ui_entry_children(
   commands: &mut Commands,
   entity: Entity,
+  asset_server: &AssetServer,
   font: &Handle<Font>,
   player_id: PlayerId,
   available_control_schemes: &AvailableControlSchemes,
   registered_players: &RegisteredPlayers,
+  is_host: bool,
 ) {
   let entry_state = online_lobby_ui_entry_state(player_id, registered_players);
   let display_name = player_display_name(player_id, registered_players);
+  let is_ready = registered_players
+    .players
+    .iter()
+    .any(|player| player.id == player_id && player.is_ready);
   commands.entity(entity).with_children(|parent| {
     parent.spawn(player_slot_label(font, &display_name, colour_for_player_id(player_id)));
     match entry_state {
@@ -191,14 +248,166 @@ fn spawn_online_lobby_ui_entry_children(
             )
           });
         spawn_player_registered_with_keys_label(parent, control_scheme, font);
+        spawn_player_readiness_label(parent, font, is_ready);
       }
       PlayerEntryState::RegisteredRemotely => {
         parent.spawn(player_registered_remotely_prompt(font));
+        spawn_player_readiness_label(parent, font, is_ready);
       }
     }
+    parent.spawn((
+      PlayerPingLabel { player_id },
+      Text::default(),
+      default_font(font),
+      TEXT_COLOUR,
+      default_shadow(),
+    ));
+    if let PlayerEntryState::RegisteredLocally { control_scheme_id } = entry_state {
+      spawn_local_player_buttons(parent, asset_server, player_id, control_scheme_id, is_ready);
+    }
+    if is_host && entry_state == PlayerEntryState::RegisteredRemotely {
+      spawn_moderation_buttons(parent, asset_server, player_id);
+    }
   });
 }
 
+/// Spawns the text " - Ready" or " - Not ready" - intended to follow the registration status of a player.
+fn spawn_player_readiness_label(parent: &mut RelatedSpawnerCommands<ChildOf>, font: &Handle<Font>, is_ready: bool) {
+  let (text, colour) = if is_ready {
+    (" - Ready", TextColor(Color::from(ACCENT_COLOUR)))
+  } else {
+    (" - Not ready", TEXT_COLOUR)
+  };
+  parent.spawn((
+    Text::new(text),
+    default_font(font),
+    TextLayout::new(Justify::Center, LineBreak::WordBoundary),
+    colour,
+    default_shadow(),
+  ));
+}
+
+/// Spawns the buttons with which a locally registered player becomes ready (or no longer ready) and leaves the lobby.
+fn spawn_local_player_buttons(
+  parent: &mut RelatedSpawnerCommands<ChildOf>,
+  asset_server: &AssetServer,
+  player_id: PlayerId,
+  control_scheme_id: ControlSchemeId,
+  is_ready: bool,
+) {
+  parent
+    .spawn(Node {
+      flex_direction: FlexDirection::Row,
+      align_items: AlignItems::Center,
+      column_gap: px(8),
+      margin: UiRect::left(px(16)),
+      ..default()
+    })
+    .with_children(|parent| {
+      let ready_label = if is_ready { "Unready" } else { "Ready" };
+      let ready_button = LocalPlayerButton::SetReady {
+        player_id,
+        is_ready: !is_ready,
+      };
+      spawn_button(parent, asset_server, ready_button, ready_label, 110, SMALL_FONT);
+      let leave_button = LocalPlayerButton::Leave { control_scheme_id };
+      spawn_button(parent, asset_server, leave_button, "Leave", 80, SMALL_FONT);
+    });
+}
+
+/// Requests to mark a local player as ready or to unregister them when the respective button is clicked or tapped.
+fn handle_local_player_button_system(
+  query: Query<(&CustomInteraction, &LocalPlayerButton), Changed<CustomInteraction>>,
+  mut local_player_ready_request_message: MessageWriter<LocalPlayerReadyRequestMessage>,
+  mut local_player_registration_request_message: MessageWriter<LocalPlayerRegistrationRequestMessage>,
+) {
+  for (interaction, button) in &query {
+    if *interaction != CustomInteraction::Released {
+      continue;
+    }
+    match *button {
+      LocalPlayerButton::SetReady { player_id, is_ready } => {
+        local_player_ready_request_message.write(LocalPlayerReadyRequestMessage { player_id, is_ready });
+      }
+      LocalPlayerButton::Leave { control_scheme_id } => {
+        local_player_registration_request_message.write(LocalPlayerRegistrationRequestMessage {
+          control_scheme_id,
+          has_registered: false,
+        });
+      }
+    }
+  }
+}
+
+/// Spawns the buttons with which the host kicks or bans the client that registered the given player.
+fn spawn_moderation_buttons(
+  parent: &mut RelatedSpawnerCommands<ChildOf>,
+  asset_server: &AssetServer,
+  player_id: PlayerId,
+) {
+  parent
+    .spawn(Node {
+      flex_direction: FlexDirection::Row,
+      align_items: AlignItems::Center,
+      column_gap: px(8),
+      margin: UiRect::left(px(16)),
+      ..default()
+    })
+    .with_children(|parent| {
+      for (action, label) in [(ModerationAction::Kick, "Kick"), (ModerationAction::Ban, "Ban")] {
+        spawn_button(
+          parent,
+          asset_server,
+          ModerationButton { player_id, action },
+          label,
+          80,
+          SMALL_FONT,
+        );
+      }
+    });
+}
+
+/// Asks the server to kick or ban the client that registered a player when the host clicks or taps the respective
+/// button.
+fn handle_moderation_button_system(
+  query: Query<(&CustomInteraction, &ModerationButton), Changed<CustomInteraction>>,
+  mut moderate_player_message: MessageWriter<ModeratePlayerMessage>,
+) {
+  for (interaction, button) in &query {
+    if *interaction == CustomInteraction::Released {
+      moderate_player_message.write(ModeratePlayerMessage {
+        player_id: button.player_id,
+        action: button.action,
+      });
+    }
+  }
+}
+
+/// A system that keeps the round-trip time shown next to each player in the lobby UI up to date.
+fn update_player_ping_labels_system(
+  network_stats: Option<Res<NetworkStats>>,
+  mut ping_label_query: Query<(&PlayerPingLabel, &mut Text)>,
+) {
+  let Some(network_stats) = network_stats else {
+    return;
+  };
+  for (label, mut text) in &mut ping_label_query {
+    let ping_text = player_ping_text(label.player_id, &network_stats);
+    if text.0 != ping_text {
+      text.0 = ping_text;
+    }
+  }
+}
+
+/// Returns the round-trip time of the client that registered the player, e.g. " (42 ms)". Empty for players without a
+/// measured round-trip time, such as those registered on the host.
+fn player_ping_text(player_id: PlayerId, network_stats: &NetworkStats) -> String {
+  network_stats
+    .for_player(mooplas_networking::prelude::PlayerId(player_id.0))
+    .map(|peer| format!(" ({})", format_round_trip_time(peer.round_trip_time)))
+    .unwrap_or_default()
+}
+
 fn online_lobby_ui_entry_state(player_id: PlayerId, registered_players: &RegisteredPlayers) -> PlayerEntryState {
   match registered_players.players.iter().find(|player| player.id == player_id) {
     Some(player) if player.is_local() => PlayerEntryState::RegisteredLocally {
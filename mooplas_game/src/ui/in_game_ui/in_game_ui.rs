@@ -156,11 +156,20 @@ fn spawn_lobby_ui(
       root,
     );
   } else {
-    in_game_online_ui::spawn_online_lobby_ui(commands, root, &font, available_control_schemes, registered_players);
+    in_game_online_ui::spawn_online_lobby_ui(
+      commands,
+      root,
+      asset_server,
+      &font,
+      available_control_schemes,
+      registered_players,
+      network_role.is_server(),
+    );
   }
 
   // Call to action
   let has_any_registered = registered_players.count() > 0;
+  let is_everyone_ready = network_role.is_none() || registered_players.are_all_ready();
   let cta = commands
     .spawn((
       LobbyUiCta,
@@ -178,6 +187,7 @@ fn spawn_lobby_ui(
         default_shadow,
         is_touch_controlled,
         has_any_registered,
+        is_everyone_ready,
         parent,
         is_permitted_action,
       );
@@ -193,6 +203,7 @@ fn spawn_call_to_action_to_start(
   default_shadow: TextShadow,
   is_touch_controlled: bool,
   has_any_registered: bool,
+  is_everyone_ready: bool,
   parent: &mut RelatedSpawnerCommands<ChildOf>,
   is_permitted_action: bool,
 ) {
@@ -205,6 +216,15 @@ fn spawn_call_to_action_to_start(
       TextLayout::new(Justify::Center, LineBreak::WordBoundary),
       default_shadow,
     ));
+  } else if !is_everyone_ready {
+    parent.spawn((
+      Text::new("Waiting for all players to be ready..."),
+      default_font.clone(),
+      LineHeight::RelativeToFont(3.),
+      TEXT_COLOUR,
+      TextLayout::new(Justify::Center, LineBreak::WordBoundary),
+      default_shadow,
+    ));
   } else if !is_permitted_action {
     parent.spawn((
       Text::new("Waiting for host to start..."),
@@ -249,6 +269,7 @@ fn spawn_call_to_action_to_start(
 pub(crate) fn update_call_to_action_to_start(
   commands: &mut Commands,
   has_any_players: bool,
+  is_everyone_ready: bool,
   cta_query: &Query<(Entity, &Children), With<LobbyUiCta>>,
   asset_server: &Res<AssetServer>,
   settings: &Res<Settings>,
@@ -268,6 +289,7 @@ pub(crate) fn update_call_to_action_to_start(
         default_shadow,
         is_touch_controlled,
         has_any_players,
+        is_everyone_ready,
         parent,
         is_permitted_action,
       );
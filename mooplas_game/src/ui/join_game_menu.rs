@@ -1,8 +1,13 @@
 #![cfg(feature = "online")]
 
 use crate::app_state::AppState;
-use crate::prelude::constants::{ACCENT_COLOUR, CLIENT_HAND_SHAKE_TIMEOUT_SECS, DEFAULT_FONT, NORMAL_FONT};
-use crate::prelude::{ConnectionInfoMessage, CustomInteraction, MenuName, UiNotification};
+use crate::prelude::constants::{
+  ACCENT_COLOUR, CLIENT_HAND_SHAKE_TIMEOUT_SECS, DEFAULT_FONT, MAX_ROOM_PASSWORD_LENGTH, NORMAL_FONT, SMALL_FONT,
+  TEXT_COLOUR,
+};
+use crate::prelude::{
+  ConnectionInfoMessage, CustomInteraction, MenuName, RoomPasswordRequiredMessage, RoomSelectedMessage, UiNotification,
+};
 use crate::shared::ToggleMenuMessage;
 use crate::shared::constants::BUTTON_ALPHA_DEFAULT;
 use crate::ui::shared::{
@@ -17,12 +22,12 @@ use bevy::input_focus::InputFocus;
 use bevy::input_focus::tab_navigation::TabIndex;
 use bevy::log::debug;
 use bevy::prelude::{
-  AlignItems, Alpha, BackgroundColor, BorderColor, BorderRadius, ButtonInput, Changed, Commands, Component,
-  DetectChangesMut, Entity, FlexDirection, FontSize, IntoScheduleConfigs, Justify, JustifyContent, KeyCode,
-  MessageReader, MessageWriter, Name, Node, OnExit, Query, Res, ResMut, TextColor, TextFont, TextLayout, UiRect,
-  Update, With, Without, default, in_state, percent, px,
+  AlignItems, Alpha, BackgroundColor, BorderColor, BorderRadius, ButtonInput, Changed, Children, Commands, Component,
+  DetectChangesMut, Display, Entity, FlexDirection, FontSize, IntoScheduleConfigs, Justify, JustifyContent, KeyCode,
+  Local, MessageReader, MessageWriter, Name, Node, OnExit, Or, Query, Res, ResMut, Text, TextColor, TextFont,
+  TextLayout, TextShadow, UiRect, Update, With, Without, default, in_state, percent, px,
 };
-use bevy::text::{EditableText, TextCursorStyle};
+use bevy::text::{EditableText, TextCursorStyle, TextEdit};
 
 /// A plugin to manage the game menu UI used to joining an online multiplayer game. Only included with the "online"
 /// feature.
@@ -38,6 +43,8 @@ impl Plugin for JoinGameMenuPlugin {
           handle_button_interactions_system,
           handle_submit_connection_button_system,
           handle_submit_connection_keyboard_system,
+          handle_room_password_required_message,
+          handle_room_selected_message,
           handle_ui_notification_messages,
         )
           .chain()
@@ -55,6 +62,21 @@ struct JoinGameMenuRoot;
 #[derive(Component)]
 struct JoinRoomInputField;
 
+/// Marker component for the password prompt, which is only shown once the signalling server has reported that the room
+/// is protected by a password.
+#[derive(Component)]
+struct JoinPasswordPrompt;
+
+/// Marker component for the password input field.
+#[derive(Component)]
+struct JoinPasswordInputField;
+
+/// Component for the button that toggles whether to join as a player or as a spectator.
+#[derive(Component, Default)]
+struct JoinRoleButton {
+  is_spectating: bool,
+}
+
 /// Marker component for the connect button.
 #[derive(Component)]
 struct ConnectButton;
@@ -97,6 +119,9 @@ fn spawn_menu(
   let mut room_input = EditableText::default();
   room_input.max_characters = Some(200);
   room_input.visible_width = Some(45.);
+  let mut password_input = EditableText::default();
+  password_input.max_characters = Some(MAX_ROOM_PASSWORD_LENGTH);
+  password_input.visible_width = Some(20.);
 
   // Background & logo
   spawn_background_if_not_exists(
@@ -149,6 +174,58 @@ fn spawn_menu(
             },
           ));
 
+          // Password prompt, hidden until the room turns out to be protected by a password
+          parent
+            .spawn((
+              Name::new("Password Prompt"),
+              JoinPasswordPrompt,
+              Node {
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                width: percent(100),
+                row_gap: px(20.),
+                ..default()
+              },
+            ))
+            .with_children(|parent| {
+              parent.spawn((
+                Text::new("This room is protected by a password:"),
+                TextFont {
+                  font: font.clone().into(),
+                  font_size: FontSize::Px(SMALL_FONT),
+                  ..default()
+                },
+                TEXT_COLOUR,
+                TextShadow::default(),
+              ));
+              parent.spawn((
+                Name::new("Password Input Field"),
+                JoinPasswordInputField,
+                password_input,
+                TextLayout::no_wrap().with_justify(Justify::Center),
+                TextFont {
+                  font: font.clone().into(),
+                  font_size: FontSize::Px(NORMAL_FONT),
+                  ..Default::default()
+                },
+                TextColor(Color::from(ACCENT_COLOUR)),
+                TextCursorStyle::default(),
+                TabIndex(1),
+                BorderColor::all(Color::from(tailwind::SLATE_500)),
+                BackgroundColor(Color::from(tailwind::SLATE_500.with_alpha(BUTTON_ALPHA_DEFAULT))),
+                Node {
+                  width: percent(100),
+                  height: px(55.),
+                  padding: UiRect::all(px(10.)),
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  border_radius: BorderRadius::all(px(10)),
+                  ..default()
+                },
+              ));
+            });
+
           // Spacer to make sure the UI has the same height as all other menus
           parent.spawn((
             Name::new("Spacer"),
@@ -159,6 +236,10 @@ fn spawn_menu(
             },
           ));
 
+          // Button: Join as player or spectator
+          let label = join_role_button_label(false);
+          spawn_button(parent, asset_server, JoinRoleButton::default(), label, 300, NORMAL_FONT);
+
           // Button: Connect
           spawn_button(parent, asset_server, ConnectButton, "Connect", 300, NORMAL_FONT);
 
@@ -168,12 +249,35 @@ fn spawn_menu(
     });
 }
 
-/// A system to handle all back button interactions.
+/// Returns the label of the [`JoinRoleButton`] i.e. the role with which the user joins.
+fn join_role_button_label(is_spectating: bool) -> &'static str {
+  if is_spectating {
+    "Role: Spectator"
+  } else {
+    "Role: Player"
+  }
+}
+
+/// A system to handle all join role and back button interactions.
 //noinspection DuplicatedCode
 fn handle_button_interactions_system(
+  mut join_role_button_query: Query<(&CustomInteraction, &mut JoinRoleButton, &Children), Changed<CustomInteraction>>,
   mut back_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<BackButton>)>,
+  mut text_query: Query<&mut Text>,
   mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
 ) {
+  for (interaction, mut button, children) in &mut join_role_button_query {
+    if *interaction == CustomInteraction::Released {
+      button.is_spectating = !button.is_spectating;
+      debug!("[Menu] Selected \"{}\"", join_role_button_label(button.is_spectating));
+      for child in children.iter() {
+        if let Ok(mut text) = text_query.get_mut(child) {
+          **text = join_role_button_label(button.is_spectating).to_string();
+        }
+      }
+    }
+  }
+
   for interaction in &mut back_button_query {
     if *interaction == CustomInteraction::Released {
       debug!("[Menu] Selected \"Back\"");
@@ -187,6 +291,8 @@ fn handle_submit_connection_button_system(
   mut connect_button_query: Query<&mut CustomInteraction, (Changed<CustomInteraction>, With<ConnectButton>)>,
   mut back_button_query: Query<&mut CustomInteraction, (With<BackButton>, Without<ConnectButton>)>,
   input_query: Query<&EditableText, With<JoinRoomInputField>>,
+  password_input_query: Query<&EditableText, With<JoinPasswordInputField>>,
+  join_role_button_query: Query<&JoinRoleButton>,
   mut connection_info_message: MessageWriter<ConnectionInfoMessage>,
   mut ui_message: MessageWriter<UiNotification>,
 ) {
@@ -204,6 +310,8 @@ fn handle_submit_connection_button_system(
 
     if submit_connection_string(
       input.value().to_string(),
+      entered_password(&password_input_query),
+      is_joining_as_spectator(&join_role_button_query),
       &mut connect_button_interaction,
       &mut back_button_interaction,
       &mut connection_info_message,
@@ -219,7 +327,10 @@ fn handle_submit_connection_button_system(
 fn handle_submit_connection_keyboard_system(
   input_focus: Res<InputFocus>,
   keyboard_input: Res<ButtonInput<KeyCode>>,
-  input_query: Query<&EditableText, With<JoinRoomInputField>>,
+  input_query: Query<&EditableText, Or<(With<JoinRoomInputField>, With<JoinPasswordInputField>)>>,
+  room_input_query: Query<&EditableText, With<JoinRoomInputField>>,
+  password_input_query: Query<&EditableText, With<JoinPasswordInputField>>,
+  join_role_button_query: Query<&JoinRoleButton>,
   mut connect_button_query: Query<&mut CustomInteraction, (With<ConnectButton>, Without<BackButton>)>,
   mut back_button_query: Query<&mut CustomInteraction, (With<BackButton>, Without<ConnectButton>)>,
   mut connection_info_message: MessageWriter<ConnectionInfoMessage>,
@@ -237,6 +348,9 @@ fn handle_submit_connection_keyboard_system(
   if input.is_composing() {
     return;
   }
+  let Ok(room_input) = room_input_query.single() else {
+    return;
+  };
   let Ok(mut connect_button_interaction) = connect_button_query.single_mut() else {
     return;
   };
@@ -245,7 +359,9 @@ fn handle_submit_connection_keyboard_system(
   };
 
   if submit_connection_string(
-    input.value().to_string(),
+    room_input.value().to_string(),
+    entered_password(&password_input_query),
+    is_joining_as_spectator(&join_role_button_query),
     &mut connect_button_interaction,
     &mut back_button_interaction,
     &mut connection_info_message,
@@ -256,8 +372,26 @@ fn handle_submit_connection_keyboard_system(
   }
 }
 
+/// Returns whether the user has chosen to join as a spectator.
+fn is_joining_as_spectator(join_role_button_query: &Query<&JoinRoleButton>) -> bool {
+  join_role_button_query
+    .single()
+    .is_ok_and(|join_role_button| join_role_button.is_spectating)
+}
+
+/// Returns the password that the user has entered, if any.
+fn entered_password(password_input_query: &Query<&EditableText, With<JoinPasswordInputField>>) -> Option<String> {
+  password_input_query
+    .single()
+    .ok()
+    .map(|input| input.value().to_string())
+    .filter(|password| !password.is_empty())
+}
+
 fn submit_connection_string(
   connection_string: String,
+  password: Option<String>,
+  is_spectating: bool,
   connect_button_interaction: &mut CustomInteraction,
   back_button_interaction: &mut CustomInteraction,
   connection_info_message: &mut MessageWriter<ConnectionInfoMessage>,
@@ -289,11 +423,92 @@ fn submit_connection_string(
 
   // Send connection info message for networking systems to process
   debug!("Sending [ConnectionInfoMessage] with text: {:?}", connection_string);
-  connection_info_message.write(ConnectionInfoMessage { connection_string });
+  connection_info_message.write(ConnectionInfoMessage {
+    connection_string,
+    password,
+    is_spectating,
+  });
 
   true
 }
 
+/// A system to reveal and focus the password prompt once the signalling server has reported that the room is protected
+/// by a password.
+fn handle_room_password_required_message(
+  mut messages: MessageReader<RoomPasswordRequiredMessage>,
+  mut input_focus: ResMut<InputFocus>,
+  mut password_prompt_query: Query<&mut Node, With<JoinPasswordPrompt>>,
+  password_input_query: Query<Entity, With<JoinPasswordInputField>>,
+) {
+  if messages.read().count() == 0 {
+    return;
+  }
+  for mut node in &mut password_prompt_query {
+    node.display = Display::Flex;
+  }
+  if let Ok(password_input) = password_input_query.single() {
+    input_focus.set(password_input);
+  }
+}
+
+/// A system to fill in the room ID of a room that was selected in the public room browser or via an invite link.
+/// Connects straight away unless the room is protected by a password, in which case the password prompt is revealed and
+/// focused instead. Retries until the menu has been spawned, since the message is written in the same frame the menu
+/// is opened.
+fn handle_room_selected_message(
+  mut messages: MessageReader<RoomSelectedMessage>,
+  mut pending_selection: Local<Option<RoomSelectedMessage>>,
+  mut input_focus: ResMut<InputFocus>,
+  mut room_input_query: Query<&mut EditableText, With<JoinRoomInputField>>,
+  mut password_prompt_query: Query<&mut Node, With<JoinPasswordPrompt>>,
+  password_input_query: Query<Entity, With<JoinPasswordInputField>>,
+  join_role_button_query: Query<&JoinRoleButton>,
+  mut connect_button_query: Query<&mut CustomInteraction, (With<ConnectButton>, Without<BackButton>)>,
+  mut back_button_query: Query<&mut CustomInteraction, (With<BackButton>, Without<ConnectButton>)>,
+  mut connection_info_message: MessageWriter<ConnectionInfoMessage>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  if let Some(message) = messages.read().last() {
+    *pending_selection = Some(message.clone());
+  }
+  let Some(selection) = &*pending_selection else {
+    return;
+  };
+  let Ok(mut room_input) = room_input_query.single_mut() else {
+    return;
+  };
+  let Ok(mut connect_button_interaction) = connect_button_query.single_mut() else {
+    return;
+  };
+  let Ok(mut back_button_interaction) = back_button_query.single_mut() else {
+    return;
+  };
+
+  room_input.clear();
+  room_input.editor_mut().set_text(&selection.room_id);
+  room_input.queue_edit(TextEdit::TextEnd(false));
+  if selection.password_required {
+    for mut node in &mut password_prompt_query {
+      node.display = Display::Flex;
+    }
+    if let Ok(password_input) = password_input_query.single() {
+      input_focus.set(password_input);
+    }
+  } else if submit_connection_string(
+    selection.room_id.clone(),
+    None,
+    is_joining_as_spectator(&join_role_button_query),
+    &mut connect_button_interaction,
+    &mut back_button_interaction,
+    &mut connection_info_message,
+    &mut ui_message,
+  ) {
+    connect_button_interaction.set_changed();
+    back_button_interaction.set_changed();
+  }
+  *pending_selection = None;
+}
+
 /// A system to handle UI error messages and display them in the menu. Also re-enables the connect and back buttons.
 fn handle_ui_notification_messages(
   mut messages: MessageReader<UiNotification>,
@@ -373,6 +588,66 @@ mod tests {
     );
   }
 
+  #[test]
+  fn handle_submit_connection_button_system_sends_entered_password() {
+    let mut app = setup();
+    app.add_systems(Update, handle_submit_connection_button_system);
+    app
+      .world_mut()
+      .spawn((JoinRoomInputField, EditableText::new("room-42")));
+    app
+      .world_mut()
+      .spawn((JoinPasswordInputField, EditableText::new("hunter2")));
+    app.world_mut().spawn((ConnectButton, CustomInteraction::Released));
+    app.world_mut().spawn((BackButton, CustomInteraction::None));
+
+    app.update();
+
+    let connection_info_messages = app
+      .world_mut()
+      .get_resource_mut::<Messages<ConnectionInfoMessage>>()
+      .expect("Messages<ConnectionInfoMessage> missing");
+    let connection_info_messages: Vec<_> = connection_info_messages.iter_current_update_messages().collect();
+    assert_eq!(connection_info_messages.len(), 1);
+    assert_eq!(connection_info_messages[0].password.as_deref(), Some("hunter2"));
+  }
+
+  #[test]
+  fn handle_submit_connection_button_system_joins_as_spectator_once_role_is_toggled() {
+    let mut app = setup();
+    app.add_systems(
+      Update,
+      (
+        handle_button_interactions_system,
+        handle_submit_connection_button_system,
+      )
+        .chain(),
+    );
+    app
+      .world_mut()
+      .spawn((JoinRoomInputField, EditableText::new("room-42")));
+    app
+      .world_mut()
+      .spawn((JoinRoleButton::default(), CustomInteraction::Released))
+      .with_child(Text::new(join_role_button_label(false)));
+    app.world_mut().spawn((ConnectButton, CustomInteraction::Released));
+    app.world_mut().spawn((BackButton, CustomInteraction::None));
+
+    app.update();
+
+    let connection_info_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<ConnectionInfoMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(connection_info_messages.len(), 1);
+    assert!(connection_info_messages[0].is_spectating);
+    let mut text_query = app.world_mut().query::<&Text>();
+    let text = text_query.single(app.world()).expect("Expected button text");
+    assert_eq!(text.as_str(), "Role: Spectator");
+  }
+
   #[test]
   fn handle_submit_connection_button_system_ignores_empty_connection_string() {
     let mut app = setup();
@@ -427,6 +702,124 @@ mod tests {
     assert_eq!(connection_info_messages[0].connection_string, "room-from-keyboard");
   }
 
+  #[test]
+  fn handle_room_password_required_message_reveals_and_focuses_password_prompt() {
+    let mut app = setup();
+    app.add_message::<RoomPasswordRequiredMessage>();
+    app.init_resource::<InputFocus>();
+    app.add_systems(Update, handle_room_password_required_message);
+    let prompt_entity = app
+      .world_mut()
+      .spawn((
+        JoinPasswordPrompt,
+        Node {
+          display: Display::None,
+          ..default()
+        },
+      ))
+      .id();
+    let password_input_entity = app
+      .world_mut()
+      .spawn((JoinPasswordInputField, EditableText::default()))
+      .id();
+
+    app.update();
+    assert_eq!(
+      app.world().get::<Node>(prompt_entity).expect("Expected prompt").display,
+      Display::None
+    );
+
+    app
+      .world_mut()
+      .write_message(RoomPasswordRequiredMessage)
+      .expect("Failed to write RoomPasswordRequiredMessage");
+    app.update();
+
+    assert_eq!(
+      app.world().get::<Node>(prompt_entity).expect("Expected prompt").display,
+      Display::Flex
+    );
+    assert_eq!(app.world().resource::<InputFocus>().get(), Some(password_input_entity));
+  }
+
+  #[test]
+  fn handle_room_selected_message_connects_once_menu_exists() {
+    let mut app = setup();
+    app.add_message::<RoomSelectedMessage>();
+    app.init_resource::<InputFocus>();
+    app.add_systems(Update, handle_room_selected_message);
+    app
+      .world_mut()
+      .write_message(RoomSelectedMessage {
+        room_id: "ABC123".to_string(),
+        password_required: false,
+      })
+      .expect("Failed to write RoomSelectedMessage");
+
+    app.update();
+    app.world_mut().spawn((JoinRoomInputField, EditableText::default()));
+    app.world_mut().spawn((ConnectButton, CustomInteraction::None));
+    app.world_mut().spawn((BackButton, CustomInteraction::None));
+    app.update();
+
+    let mut input_query = app
+      .world_mut()
+      .query_filtered::<&EditableText, With<JoinRoomInputField>>();
+    let input = input_query.single(app.world()).expect("Expected room input");
+    assert_eq!(input.value().to_string(), "ABC123");
+    let connection_info_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<ConnectionInfoMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(connection_info_messages.len(), 1);
+    assert_eq!(connection_info_messages[0].connection_string, "ABC123");
+    assert_eq!(connection_info_messages[0].password, None);
+  }
+
+  #[test]
+  fn handle_room_selected_message_asks_for_password_when_room_requires_one() {
+    let mut app = setup();
+    app.add_message::<RoomSelectedMessage>();
+    app.init_resource::<InputFocus>();
+    app.add_systems(Update, handle_room_selected_message);
+    app.world_mut().spawn((JoinRoomInputField, EditableText::default()));
+    app.world_mut().spawn((ConnectButton, CustomInteraction::None));
+    app.world_mut().spawn((BackButton, CustomInteraction::None));
+    let prompt_entity = app
+      .world_mut()
+      .spawn((
+        JoinPasswordPrompt,
+        Node {
+          display: Display::None,
+          ..default()
+        },
+      ))
+      .id();
+    let password_input_entity = app
+      .world_mut()
+      .spawn((JoinPasswordInputField, EditableText::default()))
+      .id();
+    app
+      .world_mut()
+      .write_message(RoomSelectedMessage {
+        room_id: "ABC123".to_string(),
+        password_required: true,
+      })
+      .expect("Failed to write RoomSelectedMessage");
+
+    app.update();
+
+    assert_eq!(
+      app.world().get::<Node>(prompt_entity).expect("Expected prompt").display,
+      Display::Flex
+    );
+    assert_eq!(app.world().resource::<InputFocus>().get(), Some(password_input_entity));
+    let connection_info_messages = app.world().resource::<Messages<ConnectionInfoMessage>>();
+    assert_eq!(connection_info_messages.iter_current_update_messages().count(), 0);
+  }
+
   #[test]
   fn handle_ui_notification_messages_resets_disabled_join_buttons_when_notification_requires_reset() {
     let mut app = setup();
@@ -0,0 +1,187 @@
+use crate::app_state::AppState;
+use crate::prelude::constants::{DEFAULT_COLOUR, DEFAULT_FONT, SMALL_FONT, TEXT_COLOUR};
+use crate::prelude::{EliminationCause, PlayerEliminatedMessage, PlayerId, RegisteredPlayers};
+use crate::ui::shared::{default_shadow, player_display_name};
+use bevy::app::{App, Plugin, Update};
+use bevy::asset::AssetServer;
+use bevy::color::{Alpha, Color};
+use bevy::picking::Pickable;
+use bevy::prelude::{
+  AlignItems, BackgroundColor, Children, Commands, Component, Entity, FlexDirection, FontSize, MessageReader, Name,
+  Node, OnExit, PositionType, Query, Real, Res, Text, TextColor, TextFont, Time, Timer, TimerMode, With, default, px,
+};
+
+/// A plugin that shows a short-lived feed of eliminations (who died and why) in the top left corner of the screen.
+pub struct KillFeedPlugin;
+
+impl Plugin for KillFeedPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        Update,
+        (handle_player_eliminated_message, clear_kill_feed_entries_system),
+      )
+      .add_systems(OnExit(AppState::GameOver), despawn_kill_feed_system);
+  }
+}
+
+const KILL_FEED_ENTRY_DURATION_SECONDS: f32 = 5.0;
+const KILL_FEED_MAX_ENTRIES: usize = 5;
+
+/// Marker component for the root of the kill feed. All kill feed entries are children of this.
+#[derive(Component)]
+struct KillFeedRoot;
+
+/// Timer controlling the lifetime of a kill feed entry. Uses real time so that entries also expire while the game is
+/// paused e.g. after the final elimination of a round.
+#[derive(Component)]
+struct KillFeedEntry(Timer);
+
+/// Returns the text describing the elimination and, if another player was involved, that player's ID. The text is
+/// meant to be displayed between the name of the eliminated player and the name of the other player.
+fn describe_elimination(cause: EliminationCause) -> (&'static str, Option<PlayerId>) {
+  match cause {
+    EliminationCause::Wall => (" hit the wall", None),
+    EliminationCause::OwnTail => (" ran into their own tail", None),
+    EliminationCause::OtherTail(killer_id) => (" ran into the tail of ", Some(killer_id)),
+    EliminationCause::HeadOn(other_player_id) => (" collided head-on with ", Some(other_player_id)),
+  }
+}
+
+fn player_name_text(
+  player_id: PlayerId,
+  registered_players: &RegisteredPlayers,
+  text_font: &TextFont,
+) -> (Text, TextFont, TextColor) {
+  let colour = registered_players
+    .players
+    .iter()
+    .find(|p| p.id == player_id)
+    .map(|p| p.colour)
+    .unwrap_or(DEFAULT_COLOUR);
+  (
+    Text::new(player_display_name(player_id, registered_players)),
+    text_font.clone(),
+    TextColor(colour),
+  )
+}
+
+fn handle_player_eliminated_message(
+  mut commands: Commands,
+  mut messages: MessageReader<PlayerEliminatedMessage>,
+  asset_server: Res<AssetServer>,
+  registered_players: Res<RegisteredPlayers>,
+  kill_feed_root_query: Query<(Entity, Option<&Children>), With<KillFeedRoot>>,
+) {
+  if messages.is_empty() {
+    return;
+  }
+
+  let (root, mut entries) = match kill_feed_root_query.single() {
+    Ok((root, children)) => (root, children.map(|c| c.iter().collect::<Vec<_>>()).unwrap_or_default()),
+    Err(_) => (spawn_kill_feed_root(&mut commands), Vec::new()),
+  };
+  let text_font = TextFont {
+    font: asset_server.load(DEFAULT_FONT).into(),
+    font_size: FontSize::Px(SMALL_FONT),
+    ..default()
+  };
+
+  for message in messages.read() {
+    // Remove the oldest entry to make space for the new one
+    if entries.len() >= KILL_FEED_MAX_ENTRIES {
+      commands.entity(entries.remove(0)).despawn();
+    }
+
+    let (description, other_player_id) = describe_elimination(message.cause);
+    let entry = commands
+      .spawn((
+        KillFeedEntry(Timer::from_seconds(KILL_FEED_ENTRY_DURATION_SECONDS, TimerMode::Once)),
+        Name::new("Kill Feed Entry"),
+        Node {
+          flex_direction: FlexDirection::Row,
+          align_items: AlignItems::Center,
+          ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.5)),
+        Pickable::IGNORE,
+      ))
+      .with_children(|parent| {
+        parent.spawn((
+          player_name_text(message.player_id, &registered_players, &text_font),
+          default_shadow(),
+        ));
+        parent.spawn((Text::new(description), text_font.clone(), TEXT_COLOUR, default_shadow()));
+        if let Some(other_player_id) = other_player_id {
+          parent.spawn((
+            player_name_text(other_player_id, &registered_players, &text_font),
+            default_shadow(),
+          ));
+        }
+      })
+      .id();
+    commands.entity(root).add_child(entry);
+    entries.push(entry);
+  }
+}
+
+fn spawn_kill_feed_root(commands: &mut Commands) -> Entity {
+  commands
+    .spawn((
+      KillFeedRoot,
+      Name::new("Kill Feed"),
+      Node {
+        position_type: PositionType::Absolute,
+        top: px(16),
+        left: px(16),
+        flex_direction: FlexDirection::Column,
+        row_gap: px(4),
+        ..default()
+      },
+      Pickable::IGNORE,
+    ))
+    .id()
+}
+
+/// Advances the timer of each kill feed entry and despawns it when the timer has expired.
+fn clear_kill_feed_entries_system(
+  mut commands: Commands,
+  time: Res<Time<Real>>,
+  mut entry_query: Query<(Entity, &mut KillFeedEntry)>,
+) {
+  for (entity, mut entry) in &mut entry_query {
+    entry.0.tick(time.delta());
+    if entry.0.just_finished() {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+/// Despawns the kill feed so that entries from the last round don't linger into the next one.
+fn despawn_kill_feed_system(mut commands: Commands, kill_feed_root_query: Query<Entity, With<KillFeedRoot>>) {
+  for entity in &kill_feed_root_query {
+    commands.entity(entity).despawn();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn describe_elimination_only_references_other_player_when_involved() {
+    assert_eq!(describe_elimination(EliminationCause::Wall), (" hit the wall", None));
+    assert_eq!(
+      describe_elimination(EliminationCause::OwnTail),
+      (" ran into their own tail", None)
+    );
+    assert_eq!(
+      describe_elimination(EliminationCause::OtherTail(PlayerId(3))),
+      (" ran into the tail of ", Some(PlayerId(3)))
+    );
+    assert_eq!(
+      describe_elimination(EliminationCause::HeadOn(PlayerId(1))),
+      (" collided head-on with ", Some(PlayerId(1)))
+    );
+  }
+}
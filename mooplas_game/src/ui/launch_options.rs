@@ -0,0 +1,317 @@
+#![cfg(feature = "online")]
+
+use crate::app_state::AppState;
+use crate::prelude::constants::MAX_PLAYER_NAME_LENGTH;
+use crate::prelude::{LaunchConfig, MenuName, PlayerName, RoomSelectedMessage, ToggleMenuMessage};
+use bevy::app::{App, Plugin};
+use bevy::log::{info, warn};
+use bevy::prelude::{MessageWriter, OnEnter, ResMut, Resource};
+use url::Url;
+
+const JOIN_ARG: &str = "--join";
+const ROOM_QUERY_KEY: &str = "room";
+const NAME_QUERY_KEY: &str = "name";
+
+/// A plugin that lets players skip the menus and go straight to hosting or joining an online game, using command line
+/// arguments on native platforms or an invite link in the browser. Only included with the "online" feature.
+pub struct LaunchOptionsPlugin;
+
+impl Plugin for LaunchOptionsPlugin {
+  fn build(&self, app: &mut App) {
+    let launch_options = LaunchOptions::from_runtime_config(app.world().get_resource::<LaunchConfig>());
+    app
+      .insert_resource(launch_options)
+      .add_systems(OnEnter(AppState::Preparing), apply_launch_options_system);
+  }
+}
+
+/// What to do straight away once the menus are shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LaunchAction {
+  Host,
+  Join(String),
+}
+
+/// A resource with the options this app instance was launched with. Consumed the first time the menus are shown.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+struct LaunchOptions {
+  action: Option<LaunchAction>,
+  name: Option<String>,
+}
+
+impl LaunchOptions {
+  /// Reads the launch options of this app instance:
+  /// - On native platforms, `--host` or `--join <room>`, plus `--name <name>`, from the [`LaunchConfig`]
+  /// - In the browser, the `room` and `name` query parameters of the page hosting the game e.g. `?room=ABCDEF`
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  fn from_runtime_config(launch_config: Option<&LaunchConfig>) -> Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      launch_config.map(Self::from_launch_config).unwrap_or_default()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+      page_url()
+        .map(|page_url| Self::from_page_url(&page_url))
+        .unwrap_or_default()
+    }
+  }
+
+  #[cfg(any(not(target_arch = "wasm32"), test))]
+  fn from_launch_config(launch_config: &LaunchConfig) -> Self {
+    let action = match &launch_config.join {
+      Some(room_id) => {
+        if launch_config.host {
+          warn!("Ignoring [--host] since [{}] was provided as well", JOIN_ARG);
+        }
+        Some(LaunchAction::Join(room_id.clone()))
+      }
+      None if launch_config.host => Some(LaunchAction::Host),
+      None => None,
+    };
+    Self::new(action, launch_config.name.clone())
+  }
+
+  #[cfg(any(target_arch = "wasm32", test))]
+  fn from_page_url(page_url: &str) -> Self {
+    let Ok(page_url) = Url::parse(page_url) else {
+      return Self::default();
+    };
+    let query_value = |key: &str| {
+      page_url
+        .query_pairs()
+        .find(|(query_key, _)| query_key == key)
+        .map(|(_, value)| value.into_owned())
+    };
+    Self::new(
+      query_value(ROOM_QUERY_KEY).map(LaunchAction::Join),
+      query_value(NAME_QUERY_KEY),
+    )
+  }
+
+  /// Creates launch options, ignoring an empty room ID or name and shortening names that are too long.
+  fn new(action: Option<LaunchAction>, name: Option<String>) -> Self {
+    let action = match action {
+      Some(LaunchAction::Join(room_id)) => {
+        let room_id = room_id.trim().to_string();
+        if room_id.is_empty() {
+          warn!("Ignoring request to join a room without a room ID");
+          None
+        } else {
+          Some(LaunchAction::Join(room_id))
+        }
+      }
+      action => action,
+    };
+    let name = name
+      .map(|name| name.trim().chars().take(MAX_PLAYER_NAME_LENGTH).collect::<String>())
+      .filter(|name| !name.is_empty());
+    Self { action, name }
+  }
+}
+
+/// A system to apply the launch options the first time the menus are shown. Sets and confirms the player name, if one
+/// was provided, and then opens the host game menu or joins the requested room. Later visits to the menus e.g. after
+/// leaving a game aren't affected.
+fn apply_launch_options_system(
+  mut launch_options: ResMut<LaunchOptions>,
+  mut player_name: ResMut<PlayerName>,
+  mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
+  mut room_selected_message: MessageWriter<RoomSelectedMessage>,
+) {
+  if let Some(name) = launch_options.name.take() {
+    info!("Using player name \"{}\" from launch options", name);
+    player_name.set(name);
+    player_name.confirm();
+  }
+  match launch_options.action.take() {
+    Some(LaunchAction::Host) => {
+      info!("Hosting a game as requested by launch options");
+      toggle_menu_message.write(ToggleMenuMessage::set(MenuName::HostGameMenu));
+    }
+    Some(LaunchAction::Join(room_id)) => {
+      info!("Joining room [{}] as requested by launch options", room_id);
+      toggle_menu_message.write(ToggleMenuMessage::set(MenuName::JoinGameMenu));
+      room_selected_message.write(RoomSelectedMessage {
+        room_id,
+        password_required: false,
+      });
+    }
+    None => {}
+  }
+}
+
+/// Returns an invite link that lets others join the given room. In the browser, this is the page hosting the game
+/// with the room as query parameter. On native platforms, it is based on `INVITE_BASE_URL` at build time (e.g. the URL
+/// of the web build), falling back to the command line arguments that join the room if it wasn't set.
+pub(crate) fn invite_link(room_id: &str) -> String {
+  #[cfg(target_arch = "wasm32")]
+  let base_url = page_url();
+  #[cfg(not(target_arch = "wasm32"))]
+  let base_url = option_env!("INVITE_BASE_URL").map(str::to_string);
+  invite_link_from(base_url.as_deref(), room_id)
+}
+
+/// Builds an invite link from the given base URL, replacing any room or name query parameters it already has, but
+/// keeping others such as `signalling_server_url` so that the invitee connects to the same signalling server.
+fn invite_link_from(base_url: Option<&str>, room_id: &str) -> String {
+  let Some(mut url) = base_url.and_then(|base_url| Url::parse(base_url.trim()).ok()) else {
+    return format!("mooplas_game {JOIN_ARG} {room_id}");
+  };
+  let query_pairs: Vec<(String, String)> = url
+    .query_pairs()
+    .filter(|(key, _)| key != ROOM_QUERY_KEY && key != NAME_QUERY_KEY)
+    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+    .collect();
+  url.set_fragment(None);
+  url
+    .query_pairs_mut()
+    .clear()
+    .extend_pairs(query_pairs)
+    .append_pair(ROOM_QUERY_KEY, room_id);
+  url.to_string()
+}
+
+/// Returns the URL of the page hosting the game.
+#[cfg(target_arch = "wasm32")]
+fn page_url() -> Option<String> {
+  web_sys::window()?.location().href().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bevy::prelude::{Messages, MinimalPlugins, Update};
+  use clap::Parser;
+
+  fn from_args(args: &[&str]) -> LaunchOptions {
+    let launch_config = LaunchConfig::try_parse_from(std::iter::once("mooplas_game").chain(args.iter().copied()))
+      .expect("Failed to parse arguments");
+    LaunchOptions::from_launch_config(&launch_config)
+  }
+
+  #[test]
+  fn from_launch_config_reads_join_and_name_arguments() {
+    let launch_options = from_args(&["--name", " Moop ", "--join", "ABCDEF"]);
+
+    assert_eq!(
+      launch_options,
+      LaunchOptions {
+        action: Some(LaunchAction::Join("ABCDEF".to_string())),
+        name: Some("Moop".to_string()),
+      }
+    );
+  }
+
+  #[test]
+  fn from_launch_config_prefers_join_over_host_and_ignores_networking_arguments() {
+    assert_eq!(
+      from_args(&["--host", "--signalling-server-url", "wss://signal.example.com"]).action,
+      Some(LaunchAction::Host)
+    );
+    assert_eq!(
+      from_args(&["--host", "--join", "ABCDEF"]).action,
+      Some(LaunchAction::Join("ABCDEF".to_string()))
+    );
+    assert_eq!(from_args(&[]), LaunchOptions::default());
+  }
+
+  #[test]
+  fn launch_config_rejects_invalid_arguments() {
+    for invalid in [vec!["--join"], vec!["--name"], vec!["--verbose"]] {
+      assert!(
+        LaunchConfig::try_parse_from(std::iter::once("mooplas_game").chain(invalid.iter().copied())).is_err(),
+        "Expected {invalid:?} to be rejected"
+      );
+    }
+  }
+
+  #[test]
+  fn from_launch_config_ignores_empty_room_and_shortens_long_names() {
+    let launch_options = from_args(&["--join", " ", "--name", "Mooplas Champion"]);
+
+    assert_eq!(launch_options.action, None);
+    assert_eq!(launch_options.name, Some("Mooplas Ch".to_string()));
+  }
+
+  #[test]
+  fn from_page_url_reads_room_and_name_query_parameters() {
+    assert_eq!(
+      LaunchOptions::from_page_url("https://example.com/mooplas/?signalling_server_url=wss%3A%2F%2Fs.com&room=ABCDEF"),
+      LaunchOptions {
+        action: Some(LaunchAction::Join("ABCDEF".to_string())),
+        name: None,
+      }
+    );
+    assert_eq!(
+      LaunchOptions::from_page_url("https://example.com/mooplas/?name=Moop").name,
+      Some("Moop".to_string())
+    );
+    assert_eq!(LaunchOptions::from_page_url("not a url"), LaunchOptions::default());
+  }
+
+  #[test]
+  fn invite_link_from_adds_room_to_base_url_and_keeps_other_query_parameters() {
+    assert_eq!(
+      invite_link_from(Some("https://example.com/mooplas/"), "ABCDEF"),
+      "https://example.com/mooplas/?room=ABCDEF"
+    );
+    assert_eq!(
+      invite_link_from(
+        Some("https://example.com/?room=OLD&name=Moop&signalling_server_url=wss%3A%2F%2Fs.com#top"),
+        "ABCDEF"
+      ),
+      "https://example.com/?signalling_server_url=wss%3A%2F%2Fs.com&room=ABCDEF"
+    );
+  }
+
+  #[test]
+  fn invite_link_from_falls_back_to_command_line_arguments_without_base_url() {
+    assert_eq!(invite_link_from(None, "ABCDEF"), "mooplas_game --join ABCDEF");
+    assert_eq!(
+      invite_link_from(Some("not a url"), "ABCDEF"),
+      "mooplas_game --join ABCDEF"
+    );
+  }
+
+  #[test]
+  fn apply_launch_options_system_confirms_name_and_joins_room_only_once() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_message::<ToggleMenuMessage>();
+    app.add_message::<RoomSelectedMessage>();
+    app.init_resource::<PlayerName>();
+    app.insert_resource(LaunchOptions {
+      action: Some(LaunchAction::Join("ABCDEF".to_string())),
+      name: Some("Moop".to_string()),
+    });
+    app.add_systems(Update, apply_launch_options_system);
+
+    app.update();
+
+    let player_name = app.world().resource::<PlayerName>();
+    assert_eq!(player_name.get(), "Moop");
+    assert!(player_name.is_confirmed());
+    let toggle_menu_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<ToggleMenuMessage>>()
+      .iter_current_update_messages()
+      .map(|message| message.active)
+      .collect();
+    assert_eq!(toggle_menu_messages, vec![MenuName::JoinGameMenu]);
+    let room_selected_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<RoomSelectedMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(
+      room_selected_messages,
+      vec![RoomSelectedMessage {
+        room_id: "ABCDEF".to_string(),
+        password_required: false,
+      }]
+    );
+    assert_eq!(*app.world().resource::<LaunchOptions>(), LaunchOptions::default());
+  }
+}
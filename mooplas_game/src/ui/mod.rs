@@ -1,5 +1,10 @@
 use crate::ui::shared::ButtonAnimation;
 
+#[cfg(feature = "online")]
+mod browse_games_menu;
+#[cfg(feature = "online")]
+mod chat;
+
 #[cfg(feature = "online")]
 mod join_game_menu;
 
@@ -10,10 +15,19 @@ mod enter_name_menu;
 mod host_game_menu;
 
 mod in_game_ui;
+mod kill_feed;
+#[cfg(feature = "online")]
+mod launch_options;
 mod main_menu;
+#[cfg(feature = "online")]
+mod network_stats_overlay;
 mod notification;
 mod play_online_menu;
 mod shared;
+#[cfg(feature = "online")]
+mod signalling_server_settings;
+#[cfg(feature = "online")]
+mod spectator_hud;
 mod touch_controls_ui;
 mod ui;
 
@@ -0,0 +1,199 @@
+use crate::prelude::constants::{DEFAULT_FONT, SMALL_FONT, TEXT_COLOUR};
+use crate::prelude::{PlayerId, RegisteredPlayers};
+use crate::ui::shared::{default_shadow, format_round_trip_time, player_display_name};
+use bevy::app::{App, Plugin, Update};
+use bevy::asset::AssetServer;
+use bevy::color::{Alpha, Color};
+use bevy::input::ButtonInput;
+use bevy::log::info;
+use bevy::picking::Pickable;
+use bevy::prelude::{
+  BackgroundColor, Commands, Component, Entity, FontSize, IntoScheduleConfigs, KeyCode, Name, Node, PositionType,
+  Query, Res, Text, TextFont, With, default, px,
+};
+use mooplas_networking::prelude::{NetworkStats, PacketCounters, PeerStats};
+
+/// A plugin that shows the round-trip time, jitter and packet loss of each client, as well as the number of packets
+/// sent and received, in the top right corner of the screen. Toggled with [F8].
+pub struct NetworkStatsOverlayPlugin;
+
+impl Plugin for NetworkStatsOverlayPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      Update,
+      (toggle_network_stats_overlay_system, update_network_stats_overlay_system).chain(),
+    );
+  }
+}
+
+/// Marker component for the network stats overlay.
+#[derive(Component)]
+struct NetworkStatsOverlay;
+
+/// Describes the connection of a single client, e.g. "Alice, Bob: 42 ms (±3 ms), 5% loss".
+fn describe_peer_stats(peer: &PeerStats, registered_players: &RegisteredPlayers) -> String {
+  let label = if peer.player_ids.is_empty() {
+    let client_id = peer.client_id.to_string();
+    format!("Client {}", &client_id[client_id.len().saturating_sub(8)..])
+  } else {
+    peer
+      .player_ids
+      .iter()
+      .map(|player_id| player_display_name(PlayerId(*player_id), registered_players))
+      .collect::<Vec<_>>()
+      .join(", ")
+  };
+  format!(
+    "{}: {} (±{}), {:.0}% loss",
+    label,
+    format_round_trip_time(peer.round_trip_time),
+    format_round_trip_time(peer.jitter),
+    peer.packet_loss * 100.
+  )
+}
+
+fn describe_network_stats(network_stats: &NetworkStats, registered_players: &RegisteredPlayers) -> String {
+  if network_stats.peers().is_empty() {
+    return "Network stats: no connected clients".to_string();
+  }
+  let mut lines = vec!["Network stats:".to_string()];
+  lines.extend(
+    network_stats
+      .peers()
+      .iter()
+      .map(|peer| describe_peer_stats(peer, registered_players)),
+  );
+  lines.join("\n")
+}
+
+/// Describes how many messages and packets were exchanged, e.g. "Sent 300 messages in 100 packets (3.0 per packet),
+/// received 40 in 25".
+fn describe_packet_counters(packet_counters: &PacketCounters) -> String {
+  format!(
+    "Sent {} messages in {} packets ({:.1} per packet), received {} in {}",
+    packet_counters.messages_sent,
+    packet_counters.packets_sent,
+    packet_counters.messages_per_packet_sent().unwrap_or_default(),
+    packet_counters.messages_received,
+    packet_counters.packets_received
+  )
+}
+
+/// Spawns or despawns the network stats overlay when [F8] is pressed.
+fn toggle_network_stats_overlay_system(
+  mut commands: Commands,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  asset_server: Res<AssetServer>,
+  overlay_query: Query<Entity, With<NetworkStatsOverlay>>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::F8) {
+    return;
+  }
+  if overlay_query.is_empty() {
+    info!("[F8] Showing network stats overlay");
+    commands.spawn((
+      NetworkStatsOverlay,
+      Name::new("Network Stats Overlay"),
+      Node {
+        position_type: PositionType::Absolute,
+        top: px(16),
+        right: px(16),
+        ..default()
+      },
+      Text::default(),
+      TextFont {
+        font: asset_server.load(DEFAULT_FONT).into(),
+        font_size: FontSize::Px(SMALL_FONT),
+        ..default()
+      },
+      TEXT_COLOUR,
+      default_shadow(),
+      BackgroundColor(Color::BLACK.with_alpha(0.5)),
+      Pickable::IGNORE,
+    ));
+  } else {
+    info!("[F8] Hiding network stats overlay");
+    for entity in &overlay_query {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+/// Keeps the text of the network stats overlay up to date.
+fn update_network_stats_overlay_system(
+  network_stats: Option<Res<NetworkStats>>,
+  packet_counters: Option<Res<PacketCounters>>,
+  registered_players: Res<RegisteredPlayers>,
+  mut overlay_query: Query<&mut Text, With<NetworkStatsOverlay>>,
+) {
+  let Some(network_stats) = network_stats else {
+    return;
+  };
+  for mut text in &mut overlay_query {
+    let mut description = describe_network_stats(&network_stats, &registered_players);
+    if let Some(packet_counters) = &packet_counters {
+      description = format!("{}\n{}", description, describe_packet_counters(packet_counters));
+    }
+    if text.0 != description {
+      text.0 = description;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use mooplas_networking::prelude::ClientId;
+  use std::time::Duration;
+
+  #[test]
+  fn describe_peer_stats_falls_back_to_client_id_without_players() {
+    let peer = PeerStats {
+      client_id: ClientId::from_u64(0x1234abcd),
+      player_ids: Vec::new(),
+      round_trip_time: Duration::from_millis(42),
+      jitter: Duration::from_millis(3),
+      packet_loss: 0.05,
+    };
+
+    assert_eq!(
+      describe_peer_stats(&peer, &RegisteredPlayers::default()),
+      "Client 1234abcd: 42 ms (±3 ms), 5% loss"
+    );
+  }
+
+  #[test]
+  fn describe_peer_stats_lists_players_of_client() {
+    let peer = PeerStats {
+      client_id: ClientId::from_u64(1),
+      player_ids: vec![0, 2],
+      round_trip_time: Duration::from_millis(120),
+      jitter: Duration::ZERO,
+      packet_loss: 0.,
+    };
+
+    assert_eq!(
+      describe_peer_stats(&peer, &RegisteredPlayers::default()),
+      "Player 0, Player 2: 120 ms (±0 ms), 0% loss"
+    );
+  }
+
+  #[test]
+  fn describe_packet_counters_includes_messages_per_packet() {
+    let packet_counters = PacketCounters {
+      messages_sent: 300,
+      packets_sent: 100,
+      messages_received: 40,
+      packets_received: 25,
+    };
+
+    assert_eq!(
+      describe_packet_counters(&packet_counters),
+      "Sent 300 messages in 100 packets (3.0 per packet), received 40 in 25"
+    );
+    assert_eq!(
+      describe_packet_counters(&PacketCounters::default()),
+      "Sent 0 messages in 0 packets (0.0 per packet), received 0 in 0"
+    );
+  }
+}
@@ -14,6 +14,9 @@ use bevy::prelude::{
   MessageWriter, Node, Query, Res, ResMut, Update, With, default, in_state, px,
 };
 
+#[cfg(feature = "online")]
+use crate::ui::signalling_server_settings::spawn_signalling_server_settings;
+
 /// A plugin to manage the play online menu UI. Players can choose to host or join an online game from this menu.
 pub struct PlayOnlineMenuPlugin;
 
@@ -42,6 +45,10 @@ struct HostGameButton;
 #[derive(Component)]
 struct JoinGameButton;
 
+/// Marker component for the browse games button in the play online menu.
+#[derive(Component)]
+struct BrowseGamesButton;
+
 /// System to handle toggling the play online menu based on received messages.
 fn handle_toggle_menu_message(
   mut commands: Commands,
@@ -98,6 +105,16 @@ fn spawn_menu(
         .with_children(|parent| {
           spawn_button(parent, &asset_server, HostGameButton, "Host Game", 300, NORMAL_FONT);
           spawn_button(parent, &asset_server, JoinGameButton, "Join Game", 300, NORMAL_FONT);
+          spawn_button(
+            parent,
+            &asset_server,
+            BrowseGamesButton,
+            "Browse Games",
+            300,
+            NORMAL_FONT,
+          );
+          #[cfg(feature = "online")]
+          spawn_signalling_server_settings(parent, asset_server);
           spawn_button(parent, &asset_server, BackButton, "Back", 300, NORMAL_FONT);
         });
     });
@@ -106,6 +123,7 @@ fn spawn_menu(
 fn handle_button_interactions_system(
   mut host_game_button: Query<&CustomInteraction, (Changed<CustomInteraction>, With<HostGameButton>)>,
   mut join_game_button: Query<&CustomInteraction, (Changed<CustomInteraction>, With<JoinGameButton>)>,
+  mut browse_games_button: Query<&CustomInteraction, (Changed<CustomInteraction>, With<BrowseGamesButton>)>,
   mut back_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<BackButton>)>,
   mut toggle_menu_message: MessageWriter<ToggleMenuMessage>,
 ) {
@@ -123,6 +141,13 @@ fn handle_button_interactions_system(
     }
   }
 
+  for interaction in &mut browse_games_button {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Browse Games\"");
+      toggle_menu_message.write(ToggleMenuMessage::set(MenuName::BrowseGamesMenu));
+    }
+  }
+
   for interaction in &mut back_button_query {
     if *interaction == CustomInteraction::Released {
       debug!("[Menu] Selected \"Back\"");
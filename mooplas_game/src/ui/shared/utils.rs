@@ -1,4 +1,5 @@
 use bevy::prelude::{Children, Commands, Component, Entity, Query, With};
+use std::time::Duration;
 
 /// Despawns the provided children.
 pub(crate) fn despawn_children(commands: &mut Commands, children: &Children) {
@@ -13,3 +14,8 @@ pub(crate) fn despawn_menu(commands: &mut Commands, marker_component_query: &Que
     commands.entity(root).despawn();
   }
 }
+
+/// Returns the round-trip time in milliseconds, e.g. "42 ms".
+pub(crate) fn format_round_trip_time(round_trip_time: Duration) -> String {
+  format!("{} ms", round_trip_time.as_millis())
+}
@@ -0,0 +1,297 @@
+#![cfg(feature = "online")]
+
+use crate::app_state::AppState;
+use crate::prelude::constants::{ACCENT_COLOUR, DEFAULT_FONT, NORMAL_FONT, SMALL_FONT, TEXT_COLOUR};
+use crate::prelude::{CustomInteraction, TestSignallingServerMessage, UiNotification};
+use crate::shared::constants::BUTTON_ALPHA_DEFAULT;
+use crate::ui::shared::{default_shadow, spawn_button};
+use bevy::app::{App, Plugin};
+use bevy::asset::AssetServer;
+use bevy::color::Color;
+use bevy::color::palettes::tailwind;
+use bevy::ecs::relationship::RelatedSpawnerCommands;
+use bevy::input_focus::tab_navigation::TabIndex;
+use bevy::log::debug;
+use bevy::prelude::{
+  Added, AlignItems, Alpha, BackgroundColor, BorderColor, BorderRadius, Changed, ChildOf, Component, DetectChangesMut,
+  Display, FlexDirection, FontSize, IntoScheduleConfigs, Justify, JustifyContent, MessageWriter, Name, Node, Query,
+  Res, ResMut, Text, TextColor, TextFont, TextLayout, UiRect, Update, With, default, in_state, px,
+};
+use bevy::text::{EditableText, TextCursorStyle, TextEdit};
+use mooplas_networking::prelude::SignallingServerUrl;
+
+/// A plugin to manage the advanced settings of the play online menu, which let players switch to another signalling
+/// server at runtime and test whether it can be reached. Only included with the "online" feature.
+pub struct SignallingServerSettingsPlugin;
+
+impl Plugin for SignallingServerSettingsPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      Update,
+      (fill_signalling_server_input_system, handle_button_interactions_system)
+        .chain()
+        .run_if(in_state(AppState::Preparing)),
+    );
+  }
+}
+
+/// Marker component for the button that shows or hides the advanced settings.
+#[derive(Component)]
+struct AdvancedButton;
+
+/// Marker component for the advanced settings, which are hidden until the advanced button is pressed.
+#[derive(Component)]
+struct AdvancedSettings;
+
+/// Marker component for the input field with the URL of the signalling server.
+#[derive(Component)]
+struct SignallingServerInputField;
+
+/// Marker component for the button that switches to the signalling server that has been entered.
+#[derive(Component)]
+struct SaveButton;
+
+/// Marker component for the button that checks whether the signalling server that has been entered can be reached.
+#[derive(Component)]
+struct TestButton;
+
+/// Spawns the advanced button and the advanced settings it reveals into the given menu.
+pub(crate) fn spawn_signalling_server_settings(
+  parent: &mut RelatedSpawnerCommands<ChildOf>,
+  asset_server: &AssetServer,
+) {
+  let font = asset_server.load(DEFAULT_FONT);
+  let mut url_input = EditableText::default();
+  url_input.max_characters = Some(200);
+  url_input.visible_width = Some(30.);
+
+  // Button: Advanced
+  spawn_button(parent, asset_server, AdvancedButton, "Advanced", 300, NORMAL_FONT);
+
+  // Advanced settings, hidden until the advanced button is pressed
+  parent
+    .spawn((
+      Name::new("Advanced Settings"),
+      AdvancedSettings,
+      Node {
+        display: Display::None,
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: px(20.),
+        ..default()
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        Text::new("Signalling server:"),
+        TextFont {
+          font: font.clone().into(),
+          font_size: FontSize::Px(SMALL_FONT),
+          ..default()
+        },
+        TEXT_COLOUR,
+        default_shadow(),
+      ));
+      parent.spawn((
+        Name::new("Signalling Server Input Field"),
+        SignallingServerInputField,
+        url_input,
+        TextLayout::no_wrap().with_justify(Justify::Center),
+        TextFont {
+          font: font.clone().into(),
+          font_size: FontSize::Px(SMALL_FONT),
+          ..default()
+        },
+        TextColor(Color::from(ACCENT_COLOUR)),
+        TextCursorStyle::default(),
+        TabIndex(0),
+        BorderColor::all(Color::from(tailwind::SLATE_500)),
+        BackgroundColor(Color::from(tailwind::SLATE_500.with_alpha(BUTTON_ALPHA_DEFAULT))),
+        Node {
+          width: px(600.),
+          height: px(45.),
+          padding: UiRect::all(px(10.)),
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          border_radius: BorderRadius::all(px(10)),
+          ..default()
+        },
+      ));
+      parent
+        .spawn(Node {
+          flex_direction: FlexDirection::Row,
+          column_gap: px(20.),
+          ..default()
+        })
+        .with_children(|parent| {
+          spawn_button(parent, asset_server, SaveButton, "Save", 140, SMALL_FONT);
+          spawn_button(parent, asset_server, TestButton, "Test", 140, SMALL_FONT);
+        });
+    });
+}
+
+/// A system to fill in the URL of the current signalling server whenever the input field has been spawned.
+fn fill_signalling_server_input_system(
+  signalling_server_url: Res<SignallingServerUrl>,
+  mut input_query: Query<&mut EditableText, Added<SignallingServerInputField>>,
+) {
+  for mut input in &mut input_query {
+    input.clear();
+    input.editor_mut().set_text(signalling_server_url.as_str());
+    input.queue_edit(TextEdit::TextEnd(false));
+  }
+}
+
+/// A system to handle all advanced settings button interactions. The entered URL is validated before it is used or
+/// tested.
+fn handle_button_interactions_system(
+  advanced_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<AdvancedButton>)>,
+  save_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<SaveButton>)>,
+  test_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<TestButton>)>,
+  mut advanced_settings_query: Query<&mut Node, With<AdvancedSettings>>,
+  input_query: Query<&EditableText, With<SignallingServerInputField>>,
+  mut signalling_server_url: ResMut<SignallingServerUrl>,
+  mut test_signalling_server_message: MessageWriter<TestSignallingServerMessage>,
+  mut ui_message: MessageWriter<UiNotification>,
+) {
+  for interaction in &advanced_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Advanced\"");
+      for mut node in &mut advanced_settings_query {
+        node.display = match node.display {
+          Display::None => Display::Flex,
+          _ => Display::None,
+        };
+      }
+    }
+  }
+
+  let Ok(input) = input_query.single() else {
+    return;
+  };
+  let entered_url = || {
+    SignallingServerUrl::try_new(input.value().to_string().trim())
+      .map_err(|error| UiNotification::error(format!("Signalling server URL is not valid: {error}")))
+  };
+
+  for interaction in &save_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Save\"");
+      match entered_url() {
+        Ok(url) => {
+          ui_message.write(UiNotification::info(format!(
+            "Using signalling server [{}]",
+            url.as_str()
+          )));
+          signalling_server_url.set_if_neq(url);
+        }
+        Err(notification) => {
+          ui_message.write(notification);
+        }
+      }
+    }
+  }
+
+  for interaction in &test_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Test\"");
+      match entered_url() {
+        Ok(url) => {
+          test_signalling_server_message.write(TestSignallingServerMessage {
+            url: url.as_str().to_string(),
+          });
+        }
+        Err(notification) => {
+          ui_message.write(notification);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bevy::prelude::{App, Messages, MinimalPlugins, Update};
+
+  fn setup(entered_url: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_message::<TestSignallingServerMessage>();
+    app.add_message::<UiNotification>();
+    app.insert_resource(SignallingServerUrl::new("ws://localhost:3536"));
+    app.add_systems(Update, handle_button_interactions_system);
+    let mut input = EditableText::default();
+    input.editor_mut().set_text(entered_url);
+    app.world_mut().spawn((SignallingServerInputField, input));
+    app
+  }
+
+  fn notification_texts(app: &App) -> Vec<String> {
+    app
+      .world()
+      .resource::<Messages<UiNotification>>()
+      .iter_current_update_messages()
+      .map(|notification| notification.text.clone())
+      .collect()
+  }
+
+  #[test]
+  fn handle_button_interactions_system_saves_valid_signalling_server_url() {
+    let mut app = setup(" wss://signal.example.com/ ");
+    app.world_mut().spawn((SaveButton, CustomInteraction::Released));
+
+    app.update();
+
+    assert_eq!(
+      app.world().resource::<SignallingServerUrl>().as_str(),
+      "wss://signal.example.com"
+    );
+    assert_eq!(
+      notification_texts(&app),
+      vec!["Using signalling server [wss://signal.example.com]".to_string()]
+    );
+  }
+
+  #[test]
+  fn handle_button_interactions_system_rejects_invalid_signalling_server_url() {
+    let mut app = setup("https://signal.example.com");
+    app.world_mut().spawn((SaveButton, CustomInteraction::Released));
+    app.world_mut().spawn((TestButton, CustomInteraction::Released));
+
+    app.update();
+
+    assert_eq!(
+      app.world().resource::<SignallingServerUrl>().as_str(),
+      "ws://localhost:3536"
+    );
+    assert_eq!(notification_texts(&app).len(), 2);
+    let test_messages = app.world().resource::<Messages<TestSignallingServerMessage>>();
+    assert_eq!(test_messages.iter_current_update_messages().count(), 0);
+  }
+
+  #[test]
+  fn handle_button_interactions_system_tests_entered_signalling_server_without_saving_it() {
+    let mut app = setup("wss://signal.example.com");
+    app.world_mut().spawn((TestButton, CustomInteraction::Released));
+
+    app.update();
+
+    let test_messages: Vec<_> = app
+      .world()
+      .resource::<Messages<TestSignallingServerMessage>>()
+      .iter_current_update_messages()
+      .cloned()
+      .collect();
+    assert_eq!(
+      test_messages,
+      vec![TestSignallingServerMessage {
+        url: "wss://signal.example.com".to_string()
+      }]
+    );
+    assert_eq!(
+      app.world().resource::<SignallingServerUrl>().as_str(),
+      "ws://localhost:3536"
+    );
+  }
+}
@@ -0,0 +1,219 @@
+#![cfg(feature = "online")]
+
+use crate::app_state::AppState;
+use crate::prelude::constants::{DEFAULT_FONT, NORMAL_FONT, SMALL_FONT, TEXT_COLOUR};
+use crate::prelude::{CustomInteraction, LocalSpectatingRequestMessage, RegisteredPlayers, Spectators};
+use crate::ui::shared::{default_shadow, spawn_button};
+use bevy::app::{App, Plugin, Update};
+use bevy::asset::AssetServer;
+use bevy::color::{Alpha, Color};
+use bevy::log::debug;
+use bevy::picking::Pickable;
+use bevy::prelude::{
+  AlignItems, BackgroundColor, Changed, Commands, Component, Display, Entity, FlexDirection, FontSize,
+  IntoScheduleConfigs, MessageWriter, Name, Node, PositionType, Query, Res, State, Text, TextFont, UiRect, With,
+  default, px,
+};
+use mooplas_networking::prelude::NetworkRole;
+
+/// A plugin that shows spectating clients the players of the current game, including their status, in the bottom left
+/// corner of the screen and lets them switch to playing between rounds.
+pub struct SpectatorHudPlugin;
+
+impl Plugin for SpectatorHudPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Update, (update_spectator_hud_system, handle_play_button_system).chain());
+  }
+}
+
+/// Marker component for the root of the spectator HUD.
+#[derive(Component)]
+struct SpectatorHudRoot;
+
+/// Marker component for the text listing the players of the current game.
+#[derive(Component)]
+struct SpectatorHudText;
+
+/// Marker component for the button that turns the spectator into a player. Hidden while a round is being played.
+#[derive(Component)]
+struct PlayButton;
+
+/// Describes the players of the current game and their status, e.g. "Spectating (2 watching)\nAlice: alive".
+fn describe_game(registered_players: &RegisteredPlayers, spectators: &Spectators, state: AppState) -> String {
+  let mut lines = vec![format!("Spectating ({} watching)", spectators.count())];
+  if registered_players.players.is_empty() {
+    lines.push("No players yet".to_string());
+  }
+  for player in &registered_players.players {
+    let status = match (state, player.is_ready, player.alive) {
+      (AppState::Registering, true, _) => "ready",
+      (AppState::Registering, false, _) => "not ready",
+      (_, _, true) => "alive",
+      (_, _, false) => "eliminated",
+    };
+    lines.push(format!("{}: {}", player.name, status));
+  }
+  lines.join("\n")
+}
+
+fn is_showing_spectator_hud(spectators: &Spectators, network_role: &NetworkRole, state: AppState) -> bool {
+  spectators.is_local_client_spectating
+    && network_role.is_client()
+    && matches!(state, AppState::Registering | AppState::Playing | AppState::GameOver)
+}
+
+/// Spawns the spectator HUD while the local client is spectating, keeps it up to date, and despawns it otherwise.
+fn update_spectator_hud_system(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  spectators: Res<Spectators>,
+  registered_players: Res<RegisteredPlayers>,
+  network_role: Res<NetworkRole>,
+  current_state: Res<State<AppState>>,
+  hud_query: Query<Entity, With<SpectatorHudRoot>>,
+  mut text_query: Query<&mut Text, With<SpectatorHudText>>,
+  mut play_button_query: Query<&mut Node, With<PlayButton>>,
+) {
+  let state = *current_state.get();
+  if !is_showing_spectator_hud(&spectators, &network_role, state) {
+    for entity in &hud_query {
+      commands.entity(entity).despawn();
+    }
+    return;
+  }
+
+  let description = describe_game(&registered_players, &spectators, state);
+  if hud_query.is_empty() {
+    spawn_spectator_hud(&mut commands, &asset_server, description);
+    return;
+  }
+  for mut text in &mut text_query {
+    if text.0 != description {
+      text.0 = description.clone();
+    }
+  }
+  let display = if state == AppState::Playing {
+    Display::None
+  } else {
+    Display::Flex
+  };
+  for mut node in &mut play_button_query {
+    if node.display != display {
+      node.display = display;
+    }
+  }
+}
+
+fn spawn_spectator_hud(commands: &mut Commands, asset_server: &AssetServer, description: String) {
+  commands
+    .spawn((
+      SpectatorHudRoot,
+      Name::new("Spectator HUD"),
+      Node {
+        position_type: PositionType::Absolute,
+        bottom: px(16),
+        left: px(16),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Start,
+        row_gap: px(8),
+        padding: UiRect::all(px(8)),
+        ..default()
+      },
+      BackgroundColor(Color::BLACK.with_alpha(0.5)),
+      Pickable::IGNORE,
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        SpectatorHudText,
+        Text::new(description),
+        TextFont {
+          font: asset_server.load(DEFAULT_FONT).into(),
+          font_size: FontSize::Px(SMALL_FONT),
+          ..default()
+        },
+        TEXT_COLOUR,
+        default_shadow(),
+        Pickable::IGNORE,
+      ));
+      spawn_button(parent, asset_server, PlayButton, "Play", 200, NORMAL_FONT);
+    });
+}
+
+/// Asks the server to turn the local client from a spectator into a player when the "Play" button is released.
+fn handle_play_button_system(
+  play_button_query: Query<&CustomInteraction, (Changed<CustomInteraction>, With<PlayButton>)>,
+  mut local_spectating_request_message: MessageWriter<LocalSpectatingRequestMessage>,
+) {
+  for interaction in &play_button_query {
+    if *interaction == CustomInteraction::Released {
+      debug!("[Menu] Selected \"Play\"");
+      local_spectating_request_message.write(LocalSpectatingRequestMessage { is_spectating: false });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::{ControlScheme, PlayerId, RegisteredPlayer};
+
+  fn registered_players() -> RegisteredPlayers {
+    let mut alice =
+      RegisteredPlayer::new_mutable(PlayerId(0), "Alice".to_string(), ControlScheme::test(0), Color::WHITE);
+    alice.is_ready = true;
+    let mut bob = RegisteredPlayer::new_mutable(PlayerId(1), "Bob".to_string(), ControlScheme::test(1), Color::WHITE);
+    bob.alive = false;
+    RegisteredPlayers {
+      players: vec![alice, bob],
+    }
+  }
+
+  #[test]
+  fn describe_game_lists_readiness_while_registering_and_survival_otherwise() {
+    let spectators = Spectators {
+      clients: vec![mooplas_networking::prelude::ClientId::from_u64(7)],
+      is_local_client_spectating: true,
+    };
+
+    assert_eq!(
+      describe_game(&registered_players(), &spectators, AppState::Registering),
+      "Spectating (1 watching)\nAlice: ready\nBob: not ready"
+    );
+    assert_eq!(
+      describe_game(&registered_players(), &spectators, AppState::Playing),
+      "Spectating (1 watching)\nAlice: alive\nBob: eliminated"
+    );
+    assert_eq!(
+      describe_game(&RegisteredPlayers::default(), &spectators, AppState::GameOver),
+      "Spectating (1 watching)\nNo players yet"
+    );
+  }
+
+  #[test]
+  fn is_showing_spectator_hud_requires_spectating_client_in_game() {
+    let mut spectators = Spectators::default();
+    spectators.is_local_client_spectating = true;
+
+    assert!(is_showing_spectator_hud(
+      &spectators,
+      &NetworkRole::Client,
+      AppState::Playing
+    ));
+    assert!(!is_showing_spectator_hud(
+      &spectators,
+      &NetworkRole::Client,
+      AppState::Preparing
+    ));
+    assert!(!is_showing_spectator_hud(
+      &spectators,
+      &NetworkRole::Server,
+      AppState::Playing
+    ));
+    spectators.is_local_client_spectating = false;
+    assert!(!is_showing_spectator_hud(
+      &spectators,
+      &NetworkRole::Client,
+      AppState::Registering
+    ));
+  }
+}
@@ -2,17 +2,30 @@ use crate::app_state::AppState;
 use crate::prelude::constants::{BUTTON_ALPHA_DEFAULT, BUTTON_ALPHA_PRESSED};
 use crate::prelude::{CustomInteraction, RegularButton, Settings, TouchControlButton};
 #[cfg(feature = "online")]
+use crate::ui::browse_games_menu::BrowseGamesMenuPlugin;
+#[cfg(feature = "online")]
+use crate::ui::chat::ChatPlugin;
+#[cfg(feature = "online")]
 use crate::ui::enter_name_menu::EnterNameMenuPlugin;
 #[cfg(feature = "online")]
 use crate::ui::host_game_menu::HostGameMenuPlugin;
 use crate::ui::in_game_ui::InGameUiPlugin;
 #[cfg(feature = "online")]
 use crate::ui::join_game_menu::JoinGameMenuPlugin;
+use crate::ui::kill_feed::KillFeedPlugin;
+#[cfg(feature = "online")]
+use crate::ui::launch_options::LaunchOptionsPlugin;
 use crate::ui::main_menu::MainMenuPlugin;
+#[cfg(feature = "online")]
+use crate::ui::network_stats_overlay::NetworkStatsOverlayPlugin;
 use crate::ui::notification::NotificationPlugin;
 use crate::ui::play_online_menu::PlayOnlineMenuPlugin;
 use crate::ui::shared;
 use crate::ui::shared::{BackgroundRoot, ButtonAnimation};
+#[cfg(feature = "online")]
+use crate::ui::signalling_server_settings::SignallingServerSettingsPlugin;
+#[cfg(feature = "online")]
+use crate::ui::spectator_hud::SpectatorHudPlugin;
 use crate::ui::touch_controls_ui::TouchControlsUiPlugin;
 use bevy::app::{App, Plugin, PostUpdate, Update};
 use bevy::color::palettes::tailwind;
@@ -35,6 +48,7 @@ impl Plugin for UiPlugin {
         MainMenuPlugin,
         PlayOnlineMenuPlugin,
         InGameUiPlugin,
+        KillFeedPlugin,
         TouchControlsUiPlugin,
       ))
       .add_systems(
@@ -50,8 +64,14 @@ impl Plugin for UiPlugin {
       EnterNameMenuPlugin,
       HostGameMenuPlugin,
       JoinGameMenuPlugin,
+      BrowseGamesMenuPlugin,
+      SignallingServerSettingsPlugin,
+      LaunchOptionsPlugin,
       TabNavigationPlugin,
       NotificationPlugin,
+      NetworkStatsOverlayPlugin,
+      ChatPlugin,
+      SpectatorHudPlugin,
     ));
   }
 }
@@ -1,27 +1,37 @@
-use bevy::app::{App, Plugin, Update};
+use crate::matchbox::utils::{SOCKET_CHANNEL_SEMANTICS, network_error_from_channel_error};
+use bevy::app::{App, Plugin};
 use bevy::log::*;
-use bevy::prelude::{Commands, IntoScheduleConfigs, MessageReader, MessageWriter, ResMut, Resource, resource_exists};
+use bevy::prelude::Resource;
 use bevy_matchbox::MatchboxSocket;
-use bevy_matchbox::matchbox_socket::{ChannelError, Packet, PeerId, PeerState};
+use bevy_matchbox::matchbox_socket::{Packet, PeerId, PeerState};
 use mooplas_networking::prelude::{
-  ChannelType, ClientNetworkingActive, InboundServerMessage, NetworkErrorEvent, OutboundClientMessage,
-  decode_from_bytes,
+  ChannelSemantics, ChannelType, ClientTransport, ClientTransportPlugin, NetworkErrorEvent,
 };
 
-/// A Bevy plugin that adds client-side online multiplayer capabilities.
+/// A Bevy plugin that adds client-side online multiplayer capabilities using Matchbox, by driving the
+/// [`MatchboxClientTransport`] through the [`ClientTransportPlugin`].
 pub struct MatchboxClientPlugin;
 
 impl Plugin for MatchboxClientPlugin {
   fn build(&self, app: &mut App) {
-    app
-      .add_systems(
-        Update,
-        receive_server_messages_system.run_if(resource_exists::<ClientNetworkingActive>),
-      )
-      .add_systems(
-        Update,
-        handle_outbound_client_message.run_if(resource_exists::<ClientNetworkingActive>),
-      );
+    app.add_plugins(ClientTransportPlugin::<MatchboxClientTransport>::default());
+  }
+}
+
+/// The [`ClientTransport`] of a client that connects to its host via a [`MatchboxSocket`]. Insert it with
+/// [`crate::prelude::start_client_socket`].
+#[derive(Resource)]
+pub struct MatchboxClientTransport {
+  socket: MatchboxSocket,
+  has_reported_disconnect: bool,
+}
+
+impl MatchboxClientTransport {
+  pub(crate) fn new(socket: MatchboxSocket) -> Self {
+    Self {
+      socket,
+      has_reported_disconnect: false,
+    }
   }
 }
 
@@ -37,68 +47,50 @@ fn network_error_from_peer_state(peer_state: PeerState) -> Option<NetworkErrorEv
   }
 }
 
-fn network_error_from_channel_error(channel_error: ChannelError) -> NetworkErrorEvent {
-  match channel_error {
-    ChannelError::Closed => NetworkErrorEvent::Disconnect("Connection closed".to_string()),
-    _ => NetworkErrorEvent::OtherError(channel_error.to_string()),
-  }
-}
+impl ClientTransport for MatchboxClientTransport {
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics] = &SOCKET_CHANNEL_SEMANTICS;
 
-fn receive_server_messages_system(
-  mut socket: ResMut<MatchboxSocket>,
-  mut commands: Commands,
-  mut inbound_server_message: MessageWriter<InboundServerMessage>,
-) {
-  match socket.try_update_peers() {
-    Ok(result) => {
-      for (peer_id, state) in result {
+  fn poll_connection(&mut self) -> Result<(), NetworkErrorEvent> {
+    if self.has_reported_disconnect {
+      return Ok(());
+    }
+    let error = match self.socket.try_update_peers() {
+      Ok(peers) => peers.into_iter().find_map(|(peer_id, state)| {
         info!("[{peer_id}]: {state:?}");
-        if let Some(error) = network_error_from_peer_state(state) {
-          commands.trigger(error);
-        }
+        network_error_from_peer_state(state)
+      }),
+      Err(channel_error) => Some(network_error_from_channel_error(channel_error)),
+    };
+    match error {
+      Some(error) => {
+        self.has_reported_disconnect = true;
+        Err(error)
       }
-    }
-    Err(channel_error) => {
-      commands.trigger(network_error_from_channel_error(channel_error));
+      None => Ok(()),
     }
   }
 
-  for (_id, message) in socket.channel_mut(ChannelType::ReliableOrdered.into()).receive() {
-    let server_message: InboundServerMessage =
-      decode_from_bytes(&message).expect("Failed to deserialise server message");
-    debug!(
-      "Received [{:?}] server message: {:?}",
-      ChannelType::ReliableOrdered,
-      server_message
-    );
-    inbound_server_message.write(server_message);
+  fn receive(&mut self, channel: ChannelType) -> Vec<Vec<u8>> {
+    self
+      .socket
+      .channel_mut(channel.index())
+      .receive()
+      .into_iter()
+      .map(|(_peer_id, packet)| packet.into_vec())
+      .collect()
   }
 
-  for (_id, message) in socket.channel_mut(ChannelType::Unreliable.into()).receive() {
-    let server_message: InboundServerMessage =
-      decode_from_bytes(&message).expect("Failed to deserialise server message");
-    inbound_server_message.write(server_message);
+  /// Sends the payload to the host, which is the only peer of a client.
+  fn send(&mut self, channel: ChannelType, payload: &[u8]) {
+    let peers: Vec<PeerId> = self.socket.connected_peers().collect();
+    let packet = Packet::from(payload);
+    for peer_id in peers {
+      self.socket.channel_mut(channel.index()).send(packet.clone(), peer_id);
+    }
   }
-}
 
-/// A system that applies outgoing send/disconnect requests via [`MatchboxSocket`].
-fn handle_outbound_client_message(
-  mut messages: MessageReader<OutboundClientMessage>,
-  mut socket: ResMut<MatchboxSocket>,
-) {
-  for message in messages.read() {
-    match message {
-      OutboundClientMessage::Send { channel, payload } => {
-        let packet = Packet::from(payload.as_slice());
-        let peers: Vec<_> = socket.connected_peers().collect();
-        for peer_id in peers {
-          socket.channel_mut((*channel).into()).send(packet.clone(), peer_id);
-        }
-      }
-      OutboundClientMessage::Disconnect => {
-        socket.close();
-      }
-    }
+  fn disconnect(&mut self) {
+    self.socket.close();
   }
 }
 
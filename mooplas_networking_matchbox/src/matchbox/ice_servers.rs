@@ -0,0 +1,258 @@
+use bevy::prelude::Resource;
+use bevy_matchbox::matchbox_socket::RtcIceServerConfig;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_STUN_SERVER_URL: &str = "stun:stun.l.google.com:19302";
+const ICE_SERVER_URL_SCHEMES: [&str; 4] = ["stun:", "stuns:", "turn:", "turns:"];
+
+/// A resource with the STUN and TURN servers that WebRTC uses to establish connections between peers. TURN servers
+/// relay the traffic of peers that can't reach each other directly, e.g. behind symmetric NATs, and usually require the
+/// username and credential, which STUN servers ignore. Uses Google's public STUN server by default.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IceServerConfig {
+  pub urls: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub username: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub credential: Option<String>,
+}
+
+impl Default for IceServerConfig {
+  fn default() -> Self {
+    Self {
+      urls: vec![DEFAULT_STUN_SERVER_URL.to_string()],
+      username: None,
+      credential: None,
+    }
+  }
+}
+
+/// The ICE server options this app instance was launched with, e.g. via command line arguments or environment
+/// variables. See [`IceServerConfig::from_runtime_config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IceServerOptions {
+  /// The path of a JSON settings file with the ICE servers.
+  pub config_path: Option<String>,
+  /// Comma-separated STUN/TURN URLs, overriding those of the settings file.
+  pub urls: Option<String>,
+  /// The username for the TURN servers, overriding the one of the settings file.
+  pub username: Option<String>,
+  /// The credential for the TURN servers, overriding the one of the settings file.
+  pub credential: Option<String>,
+}
+
+impl From<&IceServerConfig> for RtcIceServerConfig {
+  fn from(config: &IceServerConfig) -> Self {
+    Self {
+      urls: config.urls.clone(),
+      username: config.username.clone(),
+      credential: config.credential.clone(),
+    }
+  }
+}
+
+impl IceServerConfig {
+  /// Loads the ICE servers configured for this app instance, if any.
+  ///
+  /// On native platforms, [`IceServerOptions::config_path`] points to a JSON settings file such as
+  /// `{"urls": ["turn:turn.example.com:3478"], "username": "user", "credential": "secret"}`, and the other options
+  /// override its values.
+  ///
+  /// In the browser, the options are ignored and the page can provide the same JSON object as
+  /// `window.mooplasConfig.iceServers`.
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  pub fn from_runtime_config(options: &IceServerOptions) -> Result<Option<Self>, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      Self::from_options(options, |path| {
+        std::fs::read_to_string(path).map_err(|error| format!("Unable to read [{path}]: {error}"))
+      })
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+      Self::from_page_config()
+    }
+  }
+
+  /// Parses and validates ICE servers from JSON, e.g. a settings file or the response of the signalling server.
+  pub fn from_json(json: &str) -> Result<Self, String> {
+    serde_json::from_str::<Self>(json)
+      .map_err(|error| format!("ICE server config is not valid: {error}"))?
+      .validated()
+  }
+
+  #[cfg(any(not(target_arch = "wasm32"), test))]
+  fn from_options(
+    options: &IceServerOptions,
+    read_file: impl Fn(&str) -> Result<String, String>,
+  ) -> Result<Option<Self>, String> {
+    let mut config = match &options.config_path {
+      Some(path) => Some(Self::from_json(&read_file(path)?)?),
+      None => None,
+    };
+    if let Some(urls) = &options.urls {
+      let urls = urls
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect();
+      config = Some(Self {
+        urls,
+        ..config.unwrap_or_default()
+      });
+    }
+    if options.username.is_some() || options.credential.is_some() {
+      let Some(config) = config.as_mut() else {
+        return Err("An ICE server username or credential requires ICE server URLs or a config file".to_string());
+      };
+      if let Some(username) = &options.username {
+        config.username = Some(username.clone());
+      }
+      if let Some(credential) = &options.credential {
+        config.credential = Some(credential.clone());
+      }
+    }
+    config.map(Self::validated).transpose()
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn from_page_config() -> Result<Option<Self>, String> {
+    use js_sys::{JSON, JsString, Reflect};
+
+    let Some(window) = web_sys::window() else {
+      return Ok(None);
+    };
+    let Ok(page_config) = Reflect::get(window.as_ref(), &JsString::from("mooplasConfig")) else {
+      return Ok(None);
+    };
+    if page_config.is_undefined() || page_config.is_null() {
+      return Ok(None);
+    }
+    let ice_servers = Reflect::get(&page_config, &JsString::from("iceServers"))
+      .map_err(|_| "Unable to read [mooplasConfig.iceServers] of the page".to_string())?;
+    if ice_servers.is_undefined() || ice_servers.is_null() {
+      return Ok(None);
+    }
+    let json = JSON::stringify(&ice_servers)
+      .map_err(|_| "Unable to serialise [mooplasConfig.iceServers] of the page".to_string())?;
+    Self::from_json(&String::from(json)).map(Some)
+  }
+
+  fn validated(self) -> Result<Self, String> {
+    if self.urls.is_empty() {
+      return Err("ICE server config must include at least one URL".to_string());
+    }
+    if let Some(url) = self
+      .urls
+      .iter()
+      .find(|url| !ICE_SERVER_URL_SCHEMES.iter().any(|scheme| url.starts_with(scheme)))
+    {
+      return Err(format!(
+        "ICE server URL [{url}] must start with one of [{}]",
+        ICE_SERVER_URL_SCHEMES.join(", ")
+      ));
+    }
+    Ok(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn no_file(path: &str) -> Result<String, String> {
+    panic!("Unexpected attempt to read [{path}]")
+  }
+
+  #[test]
+  fn ice_server_config_uses_public_google_stun_server_by_default() {
+    let config = RtcIceServerConfig::from(&IceServerConfig::default());
+    assert_eq!(config.urls, vec!["stun:stun.l.google.com:19302".to_string()]);
+    assert_eq!(config.username, None);
+    assert_eq!(config.credential, None);
+  }
+
+  #[test]
+  fn from_options_returns_none_when_nothing_is_configured() {
+    assert_eq!(
+      IceServerConfig::from_options(&IceServerOptions::default(), no_file),
+      Ok(None)
+    );
+  }
+
+  #[test]
+  fn from_options_reads_comma_separated_urls_and_credentials() {
+    let config = IceServerConfig::from_options(
+      &IceServerOptions {
+        urls: Some("stun:stun.example.com:3478, turn:turn.example.com:3478".to_string()),
+        username: Some("alice".to_string()),
+        credential: Some("secret".to_string()),
+        ..IceServerOptions::default()
+      },
+      no_file,
+    );
+
+    assert_eq!(
+      config,
+      Ok(Some(IceServerConfig {
+        urls: vec![
+          "stun:stun.example.com:3478".to_string(),
+          "turn:turn.example.com:3478".to_string()
+        ],
+        username: Some("alice".to_string()),
+        credential: Some("secret".to_string()),
+      }))
+    );
+  }
+
+  #[test]
+  fn from_options_reads_settings_file_and_lets_other_values_override_it() {
+    let config = IceServerConfig::from_options(
+      &IceServerOptions {
+        config_path: Some("ice.json".to_string()),
+        credential: Some("new-secret".to_string()),
+        ..IceServerOptions::default()
+      },
+      |path| {
+        assert_eq!(path, "ice.json");
+        Ok(r#"{"urls": ["turns:turn.example.com:5349"], "username": "bob", "credential": "old-secret"}"#.to_string())
+      },
+    );
+
+    assert_eq!(
+      config,
+      Ok(Some(IceServerConfig {
+        urls: vec!["turns:turn.example.com:5349".to_string()],
+        username: Some("bob".to_string()),
+        credential: Some("new-secret".to_string()),
+      }))
+    );
+  }
+
+  #[test]
+  fn from_options_rejects_credentials_without_urls() {
+    let options = IceServerOptions {
+      username: Some("alice".to_string()),
+      ..IceServerOptions::default()
+    };
+    let error =
+      IceServerConfig::from_options(&options, no_file).expect_err("Expected a username without URLs to be rejected");
+    assert!(error.contains("ICE server URLs"));
+  }
+
+  #[test]
+  fn from_json_rejects_missing_or_invalid_urls() {
+    assert!(IceServerConfig::from_json(r#"{"urls": []}"#).is_err());
+    assert!(IceServerConfig::from_json(r#"{"urls": ["https://turn.example.com"]}"#).is_err());
+    assert!(IceServerConfig::from_json(r#"{"username": "alice"}"#).is_err());
+    assert_eq!(
+      IceServerConfig::from_json(r#"{"urls": ["stun:stun.example.com:3478"]}"#),
+      Ok(IceServerConfig {
+        urls: vec!["stun:stun.example.com:3478".to_string()],
+        username: None,
+        credential: None,
+      })
+    );
+  }
+}
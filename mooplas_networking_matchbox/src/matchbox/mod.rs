@@ -1,7 +1,9 @@
 mod client;
+mod ice_servers;
 mod server;
 mod utils;
 
 pub use client::*;
+pub use ice_servers::*;
 pub use server::*;
 pub use utils::*;
@@ -1,33 +1,42 @@
+use crate::matchbox::utils::{SOCKET_CHANNEL_SEMANTICS, network_error_from_channel_error};
 use crate::prelude::client_id_from_peer_id;
 use bevy::prelude::*;
-use bevy_matchbox::matchbox_socket::{ChannelError, Packet};
+use bevy_matchbox::matchbox_socket::Packet;
 use bevy_matchbox::prelude::*;
 #[cfg(not(target_arch = "wasm32"))]
 use bevy_matchbox::{MatchboxServer, matchbox_signaling::SignalingServer};
 use mooplas_networking::prelude::{
-  ChannelType, ClientMessage, InboundClientMessage, InboundServerMessage, Lobby, NetworkErrorEvent,
-  OutboundServerMessage, ServerNetworkingActive, decode_from_bytes,
+  ChannelSemantics, ChannelType, ClientId, NetworkErrorEvent, ServerTransport, ServerTransportPlugin, TransportEvent,
 };
+use std::collections::HashSet;
 #[cfg(not(target_arch = "wasm32"))]
 use std::net::{Ipv4Addr, SocketAddrV4};
 
-/// A Bevy plugin that adds server-side online multiplayer capabilities using Matchbox.
+/// A Bevy plugin that adds server-side online multiplayer capabilities using Matchbox, by driving the
+/// [`MatchboxServerTransport`] through the [`ServerTransportPlugin`].
 pub struct ServerMatchboxPlugin;
 
 impl Plugin for ServerMatchboxPlugin {
   fn build(&self, app: &mut App) {
-    app
-      .add_systems(
-        Update,
-        receive_messages.run_if(resource_exists::<ServerNetworkingActive>),
-      )
-      .add_systems(
-        Update,
-        handle_outbound_server_message.run_if(resource_exists::<ServerNetworkingActive>),
-      );
+    app.add_plugins(ServerTransportPlugin::<MatchboxServerTransport>::default());
   }
 }
 
+/// The [`ServerTransport`] of a host whose clients connect via a [`MatchboxSocket`]. Insert it with
+/// [`crate::prelude::start_server_socket`].
+#[derive(Resource)]
+pub struct MatchboxServerTransport {
+  socket: MatchboxSocket,
+  /// The clients that the server has disconnected, but whose connection hasn't been closed yet. Matchbox can't close
+  /// the connection to a single peer, so the server stops exchanging messages with them instead, until they leave e.g.
+  /// because the signalling server has removed them from the room, see [`crate::prelude::request_peer_removal`].
+  disconnected_clients: HashSet<ClientId>,
+  /// The disconnects that haven't been reported by [`ServerTransport::poll_events`] yet.
+  pending_events: Vec<TransportEvent>,
+  /// Whether the signalling server has assigned the host a peer ID, i.e. has accepted it into its room.
+  has_joined_room: bool,
+}
+
 /// Starts the Matchbox signalling server and inserts the [`MatchboxServer`] resource. This cannot run on WASM targets
 /// as the server cannot run in the browser. It's provided here for local development.
 #[cfg(not(target_arch = "wasm32"))]
@@ -41,7 +50,10 @@ pub fn start_signaling_server(commands: &mut Commands) {
   let matchbox_server = MatchboxServer::from(
     SignalingServer::client_server_builder(addr)
       .on_connection_request(|connection| {
-        info!("Connecting: {connection:?}");
+        info!(
+          "Connecting to room [{}]",
+          connection.path.as_deref().unwrap_or_default()
+        );
         Ok(true)
       })
       .on_id_assignment(|(socket, id)| info!("Socket [{socket}] received ID [{id}]"))
@@ -57,108 +69,99 @@ pub fn start_signaling_server(commands: &mut Commands) {
   commands.insert_resource(matchbox_server);
 }
 
-/// A system that receives incoming messages and connection events from the [`MatchboxSocket`] and triggers
-/// corresponding events for the application to react to. Also updates the [`Lobby`] resource with connected clients.
-fn receive_messages(
-  mut socket: ResMut<MatchboxSocket>,
-  mut commands: Commands,
-  mut lobby: ResMut<Lobby>,
-  mut inbound_client_message: MessageWriter<InboundClientMessage>,
-  mut inbound_server_message: MessageWriter<InboundServerMessage>,
-) {
-  match socket.try_update_peers() {
-    Ok(result) => {
-      for (peer_id, state) in result {
-        let client_id = client_id_from_peer_id(peer_id);
-        let server_event: InboundServerMessage = match state {
-          PeerState::Connected => {
-            trace!("Client with ID [{client_id}] connected");
-            lobby.connected.push(client_id);
-            InboundServerMessage::ClientConnected { client_id }
-          }
-          PeerState::Disconnected => {
-            trace!("Client with ID [{client_id}] disconnected");
-            lobby.connected.retain(|&id| id != client_id);
-            InboundServerMessage::ClientDisconnected { client_id }
-          }
-        };
-
-        // Trigger an event for an application to react to
-        inbound_server_message.write(server_event);
-      }
-    }
-    Err(channel_error) => {
-      let error = match channel_error {
-        ChannelError::Closed => NetworkErrorEvent::Disconnect("Connection closed".to_string()),
-        _ => NetworkErrorEvent::OtherError(channel_error.to_string()),
-      };
-
-      // Trigger an error event for an application to react to
-      commands.trigger(error);
+impl MatchboxServerTransport {
+  pub(crate) fn new(socket: MatchboxSocket) -> Self {
+    Self {
+      socket,
+      disconnected_clients: HashSet::new(),
+      pending_events: Vec::new(),
+      has_joined_room: false,
     }
   }
 
-  for (peer_id, message) in socket.channel_mut(ChannelType::ReliableOrdered.into()).receive() {
-    let client_id = client_id_from_peer_id(peer_id);
-    let client_message: ClientMessage = decode_from_bytes(&message).expect("Failed to deserialise client message");
-    trace!(
-      "Received [{:?}] message from client [{client_id}]: {:?}",
-      ChannelType::ReliableOrdered,
-      client_message
-    );
-    inbound_client_message.write(client_message.to_inbound_message(client_id));
+  /// Returns whether the signalling server has accepted the host into its room, which is when the room can be managed
+  /// via the signalling server's HTTP endpoints, e.g. listed publicly.
+  pub fn has_joined_room(&self) -> bool {
+    self.has_joined_room
   }
 
-  for (peer_id, message) in socket.channel_mut(ChannelType::Unreliable.into()).receive() {
-    let client_id = client_id_from_peer_id(peer_id);
-    let client_message: ClientMessage = decode_from_bytes(&message).expect("Failed to deserialise client message");
-    inbound_client_message.write(client_message.to_inbound_message(client_id));
+  fn peer_id(&self, client_id: ClientId) -> Option<PeerId> {
+    self
+      .socket
+      .connected_peers()
+      .find(|&peer_id| client_id_from_peer_id(peer_id) == client_id)
+      .filter(|_| !self.disconnected_clients.contains(&client_id))
   }
 }
 
-/// A system that applies outgoing send/broadcast/disconnect requests using the [`MatchboxSocket`].
-fn handle_outbound_server_message(
-  mut messages: MessageReader<OutboundServerMessage>,
-  mut socket: ResMut<MatchboxSocket>,
-) {
-  for message in messages.read() {
-    match message {
-      OutboundServerMessage::Broadcast { channel, payload } => {
-        let packet = Packet::from(payload.as_slice());
-        let peers: Vec<PeerId> = socket.connected_peers().collect();
-        for peer_id in peers {
-          socket.channel_mut((*channel).into()).send(packet.clone(), peer_id);
-        }
-      }
-      OutboundServerMessage::BroadcastExcept {
-        except_client_id,
-        channel,
-        payload,
-      } => {
-        let packet = Packet::from(payload.as_slice());
-        let peers: Vec<PeerId> = socket
-          .connected_peers()
-          .filter(|&peer_id| client_id_from_peer_id(peer_id) != *except_client_id)
-          .collect();
-        for peer_id in peers {
-          socket.channel_mut((*channel).into()).send(packet.clone(), peer_id);
+impl ServerTransport for MatchboxServerTransport {
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics] = &SOCKET_CHANNEL_SEMANTICS;
+
+  fn poll_events(&mut self) -> Result<Vec<TransportEvent>, NetworkErrorEvent> {
+    let peers = self
+      .socket
+      .try_update_peers()
+      .map_err(network_error_from_channel_error)?;
+    if !self.has_joined_room {
+      self.has_joined_room = self.socket.id().is_some();
+    }
+    let mut events = std::mem::take(&mut self.pending_events);
+    for (peer_id, state) in peers {
+      let client_id = client_id_from_peer_id(peer_id);
+      match state {
+        PeerState::Connected => events.push(TransportEvent::ClientConnected(client_id)),
+        PeerState::Disconnected if self.disconnected_clients.remove(&client_id) => {
+          trace!("Previously disconnected client with ID [{client_id}] has left");
         }
-      }
-      OutboundServerMessage::Send {
-        client_id,
-        channel,
-        payload,
-      } => {
-        let packet = Packet::from(payload.as_slice());
-        let peer: PeerId = socket
-          .connected_peers()
-          .find(|&peer_id| client_id_from_peer_id(peer_id) == *client_id)
-          .expect("Client ID not found among connected peers");
-        socket.channel_mut((*channel).into()).send(packet, peer);
-      }
-      OutboundServerMessage::DisconnectAll => {
-        socket.close();
+        PeerState::Disconnected => events.push(TransportEvent::ClientDisconnected(client_id)),
       }
     }
+    Ok(events)
+  }
+
+  fn receive(&mut self, channel: ChannelType) -> Vec<(ClientId, Vec<u8>)> {
+    self
+      .socket
+      .channel_mut(channel.index())
+      .receive()
+      .into_iter()
+      .map(|(peer_id, packet)| (client_id_from_peer_id(peer_id), packet.into_vec()))
+      .filter(|(client_id, _)| !self.disconnected_clients.contains(client_id))
+      .collect()
+  }
+
+  fn send(&mut self, client_id: ClientId, channel: ChannelType, payload: &[u8]) {
+    // The client may have disconnected in the meantime, especially if the link conditioner delayed the message
+    let Some(peer_id) = self.peer_id(client_id) else {
+      warn!("Dropping message for client [{client_id}] as it is not among the connected peers");
+      return;
+    };
+    self
+      .socket
+      .channel_mut(channel.index())
+      .send(Packet::from(payload), peer_id);
+  }
+
+  fn connected_clients(&self) -> Vec<ClientId> {
+    self
+      .socket
+      .connected_peers()
+      .map(client_id_from_peer_id)
+      .filter(|client_id| !self.disconnected_clients.contains(client_id))
+      .collect()
+  }
+
+  fn disconnect(&mut self, client_id: ClientId) {
+    if self.peer_id(client_id).is_none() {
+      return;
+    }
+    trace!("Disconnecting client with ID [{client_id}]");
+    self.disconnected_clients.insert(client_id);
+    self.pending_events.push(TransportEvent::ClientDisconnected(client_id));
+  }
+
+  fn disconnect_all(&mut self) {
+    self.disconnected_clients.clear();
+    self.socket.close();
   }
 }
@@ -1,14 +1,19 @@
+use crate::prelude::{IceServerConfig, MatchboxClientTransport, MatchboxServerTransport};
+use bevy::log::{info, warn};
 use bevy::prelude::Commands;
-use bevy_matchbox::matchbox_socket::{PeerId, RtcIceServerConfig, WebRtcSocket};
+use bevy_matchbox::matchbox_socket::{ChannelError, PeerId, WebRtcSocket};
 use bevy_matchbox::prelude::ChannelConfig;
 use bevy_matchbox::{MatchboxServer, MatchboxSocket};
-use mooplas_networking::prelude::{ClientId, ClientNetworkingActive, ServerNetworkingActive};
+use mooplas_networking::prelude::{
+  CHANNELS, ChannelSemantics, ChannelType, ClientId, ClientNetworkingActive, NetworkErrorEvent, ServerNetworkingActive,
+};
 use rand::RngExt;
 use rand::distr::Alphanumeric;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 const ROOM_NAME_LENGTH: usize = 6;
-const STUN_ICE_SERVER_URL: &str = "stun:stun.l.google.com:19302";
+const SECRET_LENGTH: usize = 24;
 
 /// Generates a WebSocket room ID such as 'PSD7AH'.
 pub fn generate_room_id() -> String {
@@ -20,6 +25,416 @@ pub fn generate_room_id() -> String {
     .collect()
 }
 
+/// Generates a random secret such as a host key or a client identity for the signalling server.
+pub fn generate_secret() -> String {
+  rand::rng()
+    .sample_iter(&Alphanumeric)
+    .take(SECRET_LENGTH)
+    .map(char::from)
+    .collect()
+}
+
+/// Returns the URL with which a client connects to a room, including its identity and the room's password, if any. The
+/// signalling server uses the identity to refuse clients that have been banned from the room, even if they reconnect
+/// with a new peer ID.
+pub fn client_room_url(room_url: &str, identity: &str, password: Option<&str>) -> Result<String, String> {
+  let mut url = Url::parse(room_url).map_err(|error| format!("URL is not valid: {error}"))?;
+  url.query_pairs_mut().append_pair("identity", identity);
+  if let Some(password) = password {
+    url.query_pairs_mut().append_pair("password", password);
+  }
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint with which the host of the given room bans a client.
+pub fn ban_url(room_url: &str, host_key: &str, client_id: ClientId) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, &format!("bans/{}", peer_id_from_client_id(client_id)))?;
+  url.query_pairs_mut().append_pair("key", host_key);
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint with which the host of the given room removes a client from
+/// it.
+pub fn peer_url(room_url: &str, host_key: &str, client_id: ClientId) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, &format!("peers/{}", peer_id_from_client_id(client_id)))?;
+  url.query_pairs_mut().append_pair("key", host_key);
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint with which the host of the given room designates the client
+/// that takes over as host if it leaves.
+pub fn successor_url(room_url: &str, host_key: &str, client_id: ClientId) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, &format!("successor/{}", peer_id_from_client_id(client_id)))?;
+  url.query_pairs_mut().append_pair("key", host_key);
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint with which the host of the given room changes how many
+/// clients it can hold.
+pub fn capacity_url(room_url: &str, host_key: &str, capacity: u16) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, &format!("capacity/{capacity}"))?;
+  url.query_pairs_mut().append_pair("key", host_key);
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint that tells a client whether it may join the given room
+/// with the given password, if any.
+pub fn room_access_url(room_url: &str, password: Option<&str>) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, "access")?;
+  if let Some(password) = password {
+    url.query_pairs_mut().append_pair("password", password);
+  }
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint with which the host of the given room lists it publicly.
+pub fn room_listing_url(room_url: &str, host_key: &str) -> Result<String, String> {
+  let mut url = room_endpoint_url(room_url, "listing")?;
+  url.query_pairs_mut().append_pair("key", host_key);
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint that lists all public rooms.
+pub fn public_rooms_url(signalling_server_base_url: &str) -> Result<String, String> {
+  let mut url = Url::parse(signalling_server_base_url).map_err(|error| format!("URL is not valid: {error}"))?;
+  use_http_scheme(&mut url)?;
+  url.set_query(None);
+  url.set_path("rooms");
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint that reports whether it is running.
+pub fn health_url(signalling_server_base_url: &str) -> Result<String, String> {
+  let mut url = Url::parse(signalling_server_base_url).map_err(|error| format!("URL is not valid: {error}"))?;
+  use_http_scheme(&mut url)?;
+  url.set_query(None);
+  url.set_path("health");
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of the signalling server endpoint that provides the ICE servers to use.
+pub fn ice_servers_url(signalling_server_base_url: &str) -> Result<String, String> {
+  let mut url = Url::parse(signalling_server_base_url).map_err(|error| format!("URL is not valid: {error}"))?;
+  use_http_scheme(&mut url)?;
+  url.set_query(None);
+  url.set_path("ice-servers");
+  Ok(url.to_string())
+}
+
+/// Returns the HTTP(S) URL of an endpoint of the signalling server that relates to the room of the given room URL.
+fn room_endpoint_url(room_url: &str, endpoint: &str) -> Result<Url, String> {
+  let mut url = Url::parse(room_url).map_err(|error| format!("URL is not valid: {error}"))?;
+  let room = url
+    .path_segments()
+    .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+    .ok_or_else(|| "URL must include a room identifier path (e.g., /room-id)".to_string())?
+    .to_string();
+  use_http_scheme(&mut url)?;
+  url.set_query(None);
+  url.set_path(&format!("rooms/{room}/{endpoint}"));
+  Ok(url)
+}
+
+/// Replaces the WebSocket scheme of a signalling server URL with the matching HTTP(S) scheme.
+fn use_http_scheme(url: &mut Url) -> Result<(), String> {
+  let scheme = if url.scheme() == "wss" { "https" } else { "http" };
+  url
+    .set_scheme(scheme)
+    .map_err(|()| format!("Unable to use [{scheme}] for the signalling server"))
+}
+
+/// Asks the signalling server to refuse the given client for the rest of the room's lifetime. Only works with the
+/// standalone signalling server and clients that provided an identity, so failures are logged but otherwise ignored.
+pub fn request_ban(room_url: &str, host_key: &str, client_id: ClientId) {
+  let url = match ban_url(room_url, host_key, client_id) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!(
+        "Unable to ban client [{}] at the signalling server: {}",
+        client_id, error
+      );
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::post(url, Vec::new()), move |result| match result {
+    Ok(response) if response.ok => info!("Banned client [{}] at the signalling server", client_id),
+    Ok(response) => warn!(
+      "Signalling server refused to ban client [{}]: [{}] {}",
+      client_id, response.status, response.status_text
+    ),
+    Err(error) => warn!(
+      "Unable to ban client [{}] at the signalling server: {}",
+      client_id, error
+    ),
+  });
+}
+
+/// Asks the signalling server to remove the given client from the room and to close its connection, so that its
+/// WebRTC connection to the host is closed as well. Only works with the standalone signalling server, so failures are
+/// logged but otherwise ignored.
+pub fn request_peer_removal(room_url: &str, host_key: &str, client_id: ClientId) {
+  let url = match peer_url(room_url, host_key, client_id) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!(
+        "Unable to remove client [{}] at the signalling server: {}",
+        client_id, error
+      );
+      return;
+    }
+  };
+  let request = ehttp::Request {
+    method: "DELETE".to_string(),
+    ..ehttp::Request::get(url)
+  };
+  ehttp::fetch(request, move |result| match result {
+    Ok(response) if response.ok => info!("Removed client [{}] at the signalling server", client_id),
+    Ok(response) => warn!(
+      "Signalling server refused to remove client [{}]: [{}] {}",
+      client_id, response.status, response.status_text
+    ),
+    Err(error) => warn!(
+      "Unable to remove client [{}] at the signalling server: {}",
+      client_id, error
+    ),
+  });
+}
+
+/// Tells the signalling server which client takes over as host if the host leaves, so that no other client can take
+/// over the room in its place. Only works with the standalone signalling server, so failures are logged but otherwise
+/// ignored.
+pub fn request_successor(room_url: &str, host_key: &str, client_id: ClientId) {
+  let url = match successor_url(room_url, host_key, client_id) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!(
+        "Unable to designate client [{}] as successor at the signalling server: {}",
+        client_id, error
+      );
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::post(url, Vec::new()), move |result| match result {
+    Ok(response) if response.ok => info!(
+      "Designated client [{}] as successor at the signalling server",
+      client_id
+    ),
+    Ok(response) => warn!(
+      "Signalling server refused to designate client [{}] as successor: [{}] {}",
+      client_id, response.status, response.status_text
+    ),
+    Err(error) => warn!(
+      "Unable to designate client [{}] as successor at the signalling server: {}",
+      client_id, error
+    ),
+  });
+}
+
+/// Asks the signalling server to change how many clients the given room can hold, authorised by the host key the host
+/// connected with. Failures are only logged, since the room keeps its previous capacity.
+pub fn request_capacity(room_url: &str, host_key: &str, capacity: u16) {
+  let url = match capacity_url(room_url, host_key, capacity) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!(
+        "Unable to change the capacity of the room at the signalling server: {}",
+        error
+      );
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::post(url, Vec::new()), move |result| match result {
+    Ok(response) if response.ok => info!(
+      "Changed the capacity of the room to [{}] clients at the signalling server",
+      capacity
+    ),
+    Ok(response) => warn!(
+      "Signalling server refused to change the capacity of the room: [{}] {}",
+      response.status, response.status_text
+    ),
+    Err(error) => warn!(
+      "Unable to change the capacity of the room at the signalling server: {}",
+      error
+    ),
+  });
+}
+
+/// How the host describes its room in the signalling server's public room list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoomListing {
+  pub name: String,
+  pub player_count: u8,
+  pub max_players: u8,
+}
+
+/// A room in the signalling server's public room list.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PublicRoom {
+  pub room_id: String,
+  pub name: String,
+  pub player_count: u8,
+  pub max_players: u8,
+  pub password_required: bool,
+}
+
+/// Asks the signalling server to list the room publicly with the given listing, or to remove it from the public room
+/// list if there is none. Only works with the standalone signalling server, so failures are logged but otherwise
+/// ignored.
+pub fn request_room_listing(room_url: &str, host_key: &str, listing: Option<&RoomListing>) {
+  let url = match room_listing_url(room_url, host_key) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!(
+        "Unable to update the public room list at the signalling server: {}",
+        error
+      );
+      return;
+    }
+  };
+  let request = match listing {
+    Some(listing) => match ehttp::Request::json(url, listing) {
+      Ok(request) => request,
+      Err(error) => {
+        warn!("Unable to serialise the room listing: {}", error);
+        return;
+      }
+    },
+    None => ehttp::Request {
+      method: "DELETE".to_string(),
+      ..ehttp::Request::get(url)
+    },
+  };
+  ehttp::fetch(request, move |result| match result {
+    Ok(response) if response.ok => info!("Updated the public room list at the signalling server"),
+    Ok(response) => warn!(
+      "Signalling server refused to update the public room list: [{}] {}",
+      response.status, response.status_text
+    ),
+    Err(error) => warn!(
+      "Unable to update the public room list at the signalling server: {}",
+      error
+    ),
+  });
+}
+
+/// Asks the signalling server for its public room list and passes it, or the reason why it couldn't be retrieved, to
+/// `on_response`.
+pub fn request_public_rooms(
+  signalling_server_base_url: &str,
+  on_response: impl 'static + Send + FnOnce(Result<Vec<PublicRoom>, String>),
+) {
+  let url = match public_rooms_url(signalling_server_base_url) {
+    Ok(url) => url,
+    Err(error) => {
+      on_response(Err(error));
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::get(url), move |result| {
+    on_response(match result {
+      Ok(response) if response.ok => response
+        .json::<Vec<PublicRoom>>()
+        .map_err(|error| format!("Public room list is not valid: {error}")),
+      Ok(response) => Err(format!("[{}] {}", response.status, response.status_text)),
+      Err(error) => Err(error),
+    });
+  });
+}
+
+/// Asks the signalling server for the ICE servers to use, including short-lived TURN credentials if it has any TURN
+/// servers, and passes them, or the reason why they couldn't be retrieved, to `on_response`.
+pub fn request_ice_servers(
+  signalling_server_base_url: &str,
+  on_response: impl 'static + Send + FnOnce(Result<IceServerConfig, String>),
+) {
+  let url = match ice_servers_url(signalling_server_base_url) {
+    Ok(url) => url,
+    Err(error) => {
+      on_response(Err(error));
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::get(url), move |result| {
+    on_response(match result {
+      Ok(response) if response.ok => match response.text() {
+        Some(json) => IceServerConfig::from_json(json),
+        None => Err("ICE server config is not valid UTF-8".to_string()),
+      },
+      Ok(response) => Err(format!("[{}] {}", response.status, response.status_text)),
+      Err(error) => Err(error),
+    });
+  });
+}
+
+/// Checks whether the signalling server can be reached and passes the result, or the reason why it couldn't be reached,
+/// to `on_response`. Only the standalone signalling server provides the endpoint for this.
+pub fn request_health_check(
+  signalling_server_base_url: &str,
+  on_response: impl 'static + Send + FnOnce(Result<(), String>),
+) {
+  let url = match health_url(signalling_server_base_url) {
+    Ok(url) => url,
+    Err(error) => {
+      on_response(Err(error));
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::get(url), move |result| {
+    on_response(match result {
+      Ok(response) if response.ok => Ok(()),
+      Ok(response) => Err(format!("[{}] {}", response.status, response.status_text)),
+      Err(error) => Err(error),
+    });
+  });
+}
+
+/// Whether a client may join a room, as reported by the signalling server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomAccess {
+  Granted,
+  PasswordRequired,
+  PasswordInvalid,
+  RoomFull,
+}
+
+impl RoomAccess {
+  /// Interprets the status code with which the signalling server answered an access request. Any status other than the
+  /// ones for a missing or wrong password or a full room grants access, so that the connection attempt itself reports
+  /// other problems e.g. a room that doesn't exist or a signalling server that doesn't support passwords.
+  pub fn from_status(status: u16) -> Self {
+    match status {
+      401 => Self::PasswordRequired,
+      403 => Self::PasswordInvalid,
+      503 => Self::RoomFull,
+      _ => Self::Granted,
+    }
+  }
+}
+
+/// Asks the signalling server whether the client may join the given room with the given password, if any, and passes
+/// the answer to `on_response`. Access is granted if the signalling server can't be asked.
+pub fn request_room_access(
+  room_url: &str,
+  password: Option<&str>,
+  on_response: impl 'static + Send + FnOnce(RoomAccess),
+) {
+  let url = match room_access_url(room_url, password) {
+    Ok(url) => url,
+    Err(error) => {
+      warn!("Unable to check access to the room at the signalling server: {}", error);
+      on_response(RoomAccess::Granted);
+      return;
+    }
+  };
+  ehttp::fetch(ehttp::Request::get(url), move |result| match result {
+    Ok(response) => on_response(RoomAccess::from_status(response.status)),
+    Err(error) => {
+      warn!("Unable to check access to the room at the signalling server: {}", error);
+      on_response(RoomAccess::Granted);
+    }
+  });
+}
+
 /// Give it the signalling server base URL and a connection string (either a full room URL or just a room ID) and it
 /// resolves it to a full room URL.
 ///
@@ -75,26 +490,83 @@ pub fn validate_websocket_url(url: &str) -> Result<(), String> {
   Ok(())
 }
 
-pub fn start_socket(commands: &mut Commands, room_url: &str) -> Result<(), String> {
-  validate_websocket_url(room_url)?;
-  let web_rtc_socket_builder = WebRtcSocket::builder(room_url)
-    .ice_server(signalling_ice_server_config())
-    .add_unreliable_channel()
-    .add_reliable_channel()
-    .add_channel(ChannelConfig {
+/// Returns the channels of every socket, in the order of their [`ChannelType::index`].
+pub fn socket_channels() -> [ChannelConfig; CHANNELS.len()] {
+  CHANNELS.map(|channel| match channel {
+    ChannelType::Unreliable => ChannelConfig::unreliable(),
+    ChannelType::ReliableUnordered => ChannelConfig {
       ordered: false,
       max_retransmits: None,
-    });
-  let socket = MatchboxSocket::from(web_rtc_socket_builder);
-  commands.insert_resource(socket);
+    },
+    ChannelType::ReliableOrdered => ChannelConfig::reliable(),
+  })
+}
+
+/// Returns the guarantees with which a socket channel with the given configuration delivers payloads.
+pub fn channel_semantics(config: &ChannelConfig) -> ChannelSemantics {
+  ChannelSemantics {
+    reliable: config.max_retransmits.is_none(),
+    ordered: config.ordered,
+  }
+}
+
+/// The guarantees with which the [`socket_channels`] deliver payloads, by [`ChannelType::index`].
+pub(crate) const SOCKET_CHANNEL_SEMANTICS: [ChannelSemantics; CHANNELS.len()] = [
+  ChannelSemantics {
+    reliable: false,
+    ordered: false,
+  },
+  ChannelSemantics {
+    reliable: true,
+    ordered: false,
+  },
+  ChannelSemantics {
+    reliable: true,
+    ordered: true,
+  },
+];
+
+/// Opens a socket to the given room that establishes WebRTC connections via the given ICE servers.
+fn open_socket(room_url: &str, ice_servers: &IceServerConfig) -> Result<MatchboxSocket, String> {
+  validate_websocket_url(room_url)?;
+  let [first_channel, second_channel, third_channel] = socket_channels();
+  let web_rtc_socket_builder = WebRtcSocket::builder(room_url)
+    .ice_server(ice_servers.into())
+    .add_channel(first_channel)
+    .add_channel(second_channel)
+    .add_channel(third_channel);
+  Ok(MatchboxSocket::from(web_rtc_socket_builder))
+}
+
+/// Opens a socket with which the host accepts clients in the given room and inserts it as a
+/// [`MatchboxServerTransport`].
+pub fn start_server_socket(
+  commands: &mut Commands,
+  room_url: &str,
+  ice_servers: &IceServerConfig,
+) -> Result<(), String> {
+  let socket = open_socket(room_url, ice_servers)?;
+  commands.insert_resource(MatchboxServerTransport::new(socket));
   Ok(())
 }
 
-pub fn signalling_ice_server_config() -> RtcIceServerConfig {
-  RtcIceServerConfig {
-    urls: vec![STUN_ICE_SERVER_URL.to_string()],
-    username: None,
-    credential: None,
+/// Opens a socket with which a client connects to the host of the given room and inserts it as a
+/// [`MatchboxClientTransport`].
+pub fn start_client_socket(
+  commands: &mut Commands,
+  room_url: &str,
+  ice_servers: &IceServerConfig,
+) -> Result<(), String> {
+  let socket = open_socket(room_url, ice_servers)?;
+  commands.insert_resource(MatchboxClientTransport::new(socket));
+  Ok(())
+}
+
+/// Converts an error of the [`MatchboxSocket`] into the [`NetworkErrorEvent`] with which the game is informed of it.
+pub(crate) fn network_error_from_channel_error(channel_error: ChannelError) -> NetworkErrorEvent {
+  match channel_error {
+    ChannelError::Closed => NetworkErrorEvent::Disconnect("Connection closed".to_string()),
+    _ => NetworkErrorEvent::OtherError(channel_error.to_string()),
   }
 }
 
@@ -112,7 +584,8 @@ pub fn peer_id_from_client_id(client_id: ClientId) -> PeerId {
 pub fn remove_all_matchbox_resources(commands: &mut Commands) {
   commands.remove_resource::<ClientNetworkingActive>();
   commands.remove_resource::<ServerNetworkingActive>();
-  commands.remove_resource::<MatchboxSocket>();
+  commands.remove_resource::<MatchboxServerTransport>();
+  commands.remove_resource::<MatchboxClientTransport>();
 
   #[cfg(not(target_arch = "wasm32"))]
   commands.remove_resource::<MatchboxServer>();
@@ -121,6 +594,20 @@ pub fn remove_all_matchbox_resources(commands: &mut Commands) {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use mooplas_networking::prelude::{ClientMessage, InboundServerMessage, MessageRegistry};
+
+  #[test]
+  fn socket_channels_match_declared_channel_semantics() {
+    let mut registry = MessageRegistry::default();
+    registry.register::<ClientMessage>().register::<InboundServerMessage>();
+    let channels: Vec<ChannelSemantics> = socket_channels().iter().map(channel_semantics).collect();
+
+    assert_eq!(channels, SOCKET_CHANNEL_SEMANTICS);
+    assert_eq!(registry.validate(&channels), Ok(()));
+    for channel in CHANNELS {
+      assert_eq!(channels[channel.index()], channel.semantics());
+    }
+  }
 
   #[test]
   fn generate_room_id_returns_lowercase_alphanumeric_room_id() {
@@ -204,10 +691,143 @@ mod tests {
   }
 
   #[test]
-  fn signalling_ice_server_config_uses_public_google_stun_server() {
-    let config = signalling_ice_server_config();
-    assert_eq!(config.urls, vec!["stun:stun.l.google.com:19302".to_string()]);
-    assert_eq!(config.username, None);
-    assert_eq!(config.credential, None);
+  fn generate_secret_returns_unique_alphanumeric_secrets() {
+    let secret = generate_secret();
+
+    assert_eq!(secret.len(), SECRET_LENGTH);
+    assert!(secret.chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_ne!(secret, generate_secret());
+  }
+
+  #[test]
+  fn client_room_url_appends_identity_to_room_url() {
+    assert_eq!(
+      client_room_url("wss://signal.example.com/room-456", "alice", None).expect("Expected valid room URL"),
+      "wss://signal.example.com/room-456?identity=alice"
+    );
+  }
+
+  #[test]
+  fn client_room_url_appends_encoded_password_to_room_url() {
+    assert_eq!(
+      client_room_url("wss://signal.example.com/room-456", "alice", Some("open sesame&")).expect("Expected valid URL"),
+      "wss://signal.example.com/room-456?identity=alice&password=open+sesame%26"
+    );
+  }
+
+  #[test]
+  fn room_access_url_uses_http_endpoint_of_room() {
+    assert_eq!(
+      room_access_url("ws://localhost:3536/room-456", Some("hunter2")).expect("Expected valid room URL"),
+      "http://localhost:3536/rooms/room-456/access?password=hunter2"
+    );
+    assert_eq!(
+      room_access_url("ws://localhost:3536/room-456", None).expect("Expected valid room URL"),
+      "http://localhost:3536/rooms/room-456/access"
+    );
+  }
+
+  #[test]
+  fn room_listing_url_and_public_rooms_url_use_http_endpoints() {
+    assert_eq!(
+      room_listing_url("wss://signal.example.com/room-456?role=host", "secret").expect("Expected valid room URL"),
+      "https://signal.example.com/rooms/room-456/listing?key=secret"
+    );
+    assert_eq!(
+      public_rooms_url("wss://signal.example.com").expect("Expected valid URL"),
+      "https://signal.example.com/rooms"
+    );
+    assert_eq!(
+      public_rooms_url("ws://localhost:3536/").expect("Expected valid URL"),
+      "http://localhost:3536/rooms"
+    );
+  }
+
+  #[test]
+  fn health_url_uses_http_endpoint() {
+    assert_eq!(
+      health_url("wss://signal.example.com").expect("Expected valid URL"),
+      "https://signal.example.com/health"
+    );
+    assert_eq!(
+      health_url("ws://localhost:3536/").expect("Expected valid URL"),
+      "http://localhost:3536/health"
+    );
+  }
+
+  #[test]
+  fn ice_servers_url_uses_http_endpoint() {
+    assert_eq!(
+      ice_servers_url("wss://signal.example.com").expect("Expected valid URL"),
+      "https://signal.example.com/ice-servers"
+    );
+    assert_eq!(
+      ice_servers_url("ws://localhost:3536/").expect("Expected valid URL"),
+      "http://localhost:3536/ice-servers"
+    );
+  }
+
+  #[test]
+  fn room_access_from_status_only_refuses_missing_or_wrong_passwords() {
+    assert_eq!(RoomAccess::from_status(204), RoomAccess::Granted);
+    assert_eq!(RoomAccess::from_status(401), RoomAccess::PasswordRequired);
+    assert_eq!(RoomAccess::from_status(403), RoomAccess::PasswordInvalid);
+    assert_eq!(RoomAccess::from_status(503), RoomAccess::RoomFull);
+    assert_eq!(RoomAccess::from_status(404), RoomAccess::Granted);
+  }
+
+  #[test]
+  fn ban_url_uses_http_endpoint_of_room() {
+    let client_id = ClientId::from_u64(7);
+
+    assert_eq!(
+      ban_url("wss://signal.example.com/room-456", "secret", client_id).expect("Expected valid room URL"),
+      format!(
+        "https://signal.example.com/rooms/room-456/bans/{}?key=secret",
+        peer_id_from_client_id(client_id)
+      )
+    );
+    assert_eq!(
+      ban_url("ws://localhost:3536/room-456?role=host", "secret", client_id).expect("Expected valid room URL"),
+      format!(
+        "http://localhost:3536/rooms/room-456/bans/{}?key=secret",
+        peer_id_from_client_id(client_id)
+      )
+    );
+  }
+
+  #[test]
+  fn successor_url_uses_http_endpoint_of_room() {
+    let client_id = ClientId::from_u64(7);
+
+    assert_eq!(
+      successor_url("wss://signal.example.com/room-456?role=host", "secret", client_id)
+        .expect("Expected valid room URL"),
+      format!(
+        "https://signal.example.com/rooms/room-456/successor/{}?key=secret",
+        peer_id_from_client_id(client_id)
+      )
+    );
+  }
+
+  #[test]
+  fn capacity_url_uses_http_endpoint_of_room() {
+    assert_eq!(
+      capacity_url("wss://signal.example.com/room-456?role=host", "secret", 9).expect("Expected valid room URL"),
+      "https://signal.example.com/rooms/room-456/capacity/9?key=secret"
+    );
+  }
+
+  #[test]
+  fn peer_url_uses_http_endpoint_of_room() {
+    let client_id = ClientId::from_u64(7);
+
+    assert_eq!(
+      peer_url("wss://signal.example.com/room-456?role=host", "secret", client_id).expect("Expected valid room URL"),
+      format!(
+        "https://signal.example.com/rooms/room-456/peers/{}?key=secret",
+        peer_id_from_client_id(client_id)
+      )
+    );
   }
 }
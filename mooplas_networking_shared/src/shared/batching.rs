@@ -0,0 +1,319 @@
+use crate::prelude::{ChannelType, decode_from_bytes};
+use bevy::prelude::Resource;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+/// The size up to which payloads are packed into the same packet. Browsers reliably deliver WebRTC data channel
+/// messages of up to 16 KiB; a payload that is larger on its own is still sent, but in a packet of its own.
+pub const MAX_BATCH_BYTES: usize = 16 * 1024;
+
+/// Collects the payloads sent within a frame and packs all payloads for the same recipient and channel into as few
+/// packets as possible. Each payload is prefixed with its length as a LEB128 varint; see [`unbatch`] for the reverse.
+/// Payloads keep their order within each recipient and channel.
+#[derive(Debug)]
+pub struct PacketBatcher<R> {
+  packets: Vec<(R, ChannelType, Vec<u8>)>,
+  /// The index of the packet in `packets` that is still being filled for each recipient and channel.
+  open_packets: HashMap<(R, ChannelType), usize>,
+  payloads: usize,
+}
+
+impl<R> Default for PacketBatcher<R> {
+  fn default() -> Self {
+    Self {
+      packets: Vec::new(),
+      open_packets: HashMap::new(),
+      payloads: 0,
+    }
+  }
+}
+
+impl<R: Copy + Eq + Hash> PacketBatcher<R> {
+  /// Adds a payload to the packet for the recipient and channel, starting a new packet if it would grow beyond
+  /// [`MAX_BATCH_BYTES`].
+  pub fn push(&mut self, recipient: R, channel: ChannelType, payload: &[u8]) {
+    let framed_size = varint_size(payload.len()) + payload.len();
+    let open_packet = self
+      .open_packets
+      .get(&(recipient, channel))
+      .copied()
+      .filter(|&index| self.packets[index].2.len() + framed_size <= MAX_BATCH_BYTES);
+    let index = match open_packet {
+      Some(index) => index,
+      None => {
+        self.packets.push((recipient, channel, Vec::with_capacity(framed_size)));
+        self.open_packets.insert((recipient, channel), self.packets.len() - 1);
+        self.packets.len() - 1
+      }
+    };
+    let packet = &mut self.packets[index].2;
+    write_varint(packet, payload.len());
+    packet.extend_from_slice(payload);
+    self.payloads += 1;
+  }
+
+  /// Returns the number of payloads added since the last call of [`PacketBatcher::drain`].
+  pub fn payloads(&self) -> usize {
+    self.payloads
+  }
+
+  /// Returns all packets in the order in which they were started and resets the batcher.
+  pub fn drain(&mut self) -> Vec<(R, ChannelType, Vec<u8>)> {
+    self.open_packets.clear();
+    self.payloads = 0;
+    std::mem::take(&mut self.packets)
+  }
+}
+
+/// The reason a packet couldn't be split into its payloads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+  /// The packet ended within the length prefix of a payload.
+  TruncatedLength,
+  /// The packet ended before the payload was complete.
+  TruncatedPayload { expected: usize, remaining: usize },
+}
+
+impl Display for BatchError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BatchError::TruncatedLength => write!(f, "Batch ended within a length prefix"),
+      BatchError::TruncatedPayload { expected, remaining } => write!(
+        f,
+        "Batch ended within a payload of {expected} bytes, with only {remaining} bytes left"
+      ),
+    }
+  }
+}
+
+/// Splits a packet created by [`PacketBatcher`] into its payloads. Fails without returning any payload if the packet is
+/// truncated, since nothing after the first inconsistency can be trusted.
+pub fn unbatch(packet: &[u8]) -> Result<Vec<&[u8]>, BatchError> {
+  let mut payloads = Vec::new();
+  let mut remaining = packet;
+  while !remaining.is_empty() {
+    let (length, rest) = read_varint(remaining).ok_or(BatchError::TruncatedLength)?;
+    if rest.len() < length {
+      return Err(BatchError::TruncatedPayload {
+        expected: length,
+        remaining: rest.len(),
+      });
+    }
+    let (payload, rest) = rest.split_at(length);
+    payloads.push(payload);
+    remaining = rest;
+  }
+  Ok(payloads)
+}
+
+/// A payload that couldn't be decoded, or a packet that couldn't be split into payloads in the first place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndecodablePayload {
+  /// The size of the payload, or of the whole packet if it couldn't be split.
+  pub size: usize,
+  pub reason: String,
+}
+
+impl Display for UndecodablePayload {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.reason)
+  }
+}
+
+/// Splits a packet into its payloads and decodes each of them. A payload that can't be decoded is returned as an error
+/// in its place without affecting the others, while a packet that can't be split results in a single error.
+pub fn decode_packet<T: DeserializeOwned>(packet: &[u8]) -> Vec<Result<T, UndecodablePayload>> {
+  match unbatch(packet) {
+    Ok(payloads) => payloads
+      .into_iter()
+      .map(|payload| {
+        decode_from_bytes(payload).map_err(|error| UndecodablePayload {
+          size: payload.len(),
+          reason: error.to_string(),
+        })
+      })
+      .collect(),
+    Err(error) => vec![Err(UndecodablePayload {
+      size: packet.len(),
+      reason: error.to_string(),
+    })],
+  }
+}
+
+fn varint_size(mut value: usize) -> usize {
+  let mut size = 1;
+  while value >= 0x80 {
+    value >>= 7;
+    size += 1;
+  }
+  size
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: usize) {
+  while value >= 0x80 {
+    buffer.push((value as u8 & 0x7f) | 0x80);
+    value >>= 7;
+  }
+  buffer.push(value as u8);
+}
+
+/// Reads a varint from the start of the bytes, returning it together with the remaining bytes. Returns `None` if the
+/// bytes end within the varint or if it doesn't fit into a `usize`.
+fn read_varint(bytes: &[u8]) -> Option<(usize, &[u8])> {
+  let mut value: usize = 0;
+  for (index, byte) in bytes.iter().enumerate() {
+    let shift = 7 * index as u32;
+    if shift >= usize::BITS {
+      return None;
+    }
+    value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+    if byte & 0x80 == 0 {
+      return Some((value, &bytes[index + 1..]));
+    }
+  }
+  None
+}
+
+/// A resource counting the messages and packets this peer has sent and received, which shows how much batching saves.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PacketCounters {
+  pub messages_sent: u64,
+  pub packets_sent: u64,
+  pub messages_received: u64,
+  pub packets_received: u64,
+}
+
+impl PacketCounters {
+  pub fn record_sent(&mut self, messages: usize, packets: usize) {
+    self.messages_sent += messages as u64;
+    self.packets_sent += packets as u64;
+  }
+
+  pub fn record_received(&mut self, messages: usize) {
+    self.messages_received += messages as u64;
+    self.packets_received += 1;
+  }
+
+  /// Returns the average number of messages per sent packet, or `None` if nothing has been sent yet.
+  pub fn messages_per_packet_sent(&self) -> Option<f32> {
+    (self.packets_sent > 0).then(|| self.messages_sent as f32 / self.packets_sent as f32)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::{ClientMessage, encode_to_bytes};
+
+  #[test]
+  fn batcher_packs_payloads_per_recipient_and_channel_in_order() {
+    let mut batcher = PacketBatcher::default();
+    batcher.push(1, ChannelType::ReliableOrdered, &[1]);
+    batcher.push(2, ChannelType::ReliableOrdered, &[2]);
+    batcher.push(1, ChannelType::Unreliable, &[3]);
+    batcher.push(1, ChannelType::ReliableOrdered, &[4, 5]);
+    assert_eq!(batcher.payloads(), 4);
+
+    let packets = batcher.drain();
+
+    assert_eq!(
+      packets,
+      vec![
+        (1, ChannelType::ReliableOrdered, vec![1, 1, 2, 4, 5]),
+        (2, ChannelType::ReliableOrdered, vec![1, 2]),
+        (1, ChannelType::Unreliable, vec![1, 3]),
+      ]
+    );
+    assert_eq!(unbatch(&packets[0].2), Ok(vec![&[1][..], &[4, 5][..]]));
+    assert_eq!(batcher.payloads(), 0);
+    assert!(batcher.drain().is_empty());
+  }
+
+  #[test]
+  fn batcher_starts_new_packet_once_full() {
+    let mut batcher = PacketBatcher::default();
+    let payload = vec![7; MAX_BATCH_BYTES / 2];
+    batcher.push((), ChannelType::ReliableOrdered, &payload);
+    batcher.push((), ChannelType::ReliableOrdered, &payload);
+    batcher.push((), ChannelType::ReliableOrdered, &vec![8; MAX_BATCH_BYTES * 2]);
+
+    let packets = batcher.drain();
+
+    assert_eq!(packets.len(), 3);
+    assert_eq!(
+      unbatch(&packets[0].2).expect("Expected valid batch"),
+      vec![payload.as_slice()]
+    );
+    assert_eq!(
+      unbatch(&packets[2].2).expect("Expected valid batch")[0].len(),
+      MAX_BATCH_BYTES * 2
+    );
+  }
+
+  #[test]
+  fn unbatch_rejects_truncated_packets() {
+    assert_eq!(unbatch(&[]), Ok(Vec::new()));
+    assert_eq!(unbatch(&[255]), Err(BatchError::TruncatedLength));
+    assert_eq!(
+      unbatch(&[1, 9, 3, 1]),
+      Err(BatchError::TruncatedPayload {
+        expected: 3,
+        remaining: 1,
+      })
+    );
+    assert_eq!(unbatch(&[0xff; 11]), Err(BatchError::TruncatedLength));
+  }
+
+  #[test]
+  fn decode_packet_drops_only_undecodable_payloads() {
+    let mut batcher = PacketBatcher::default();
+    batcher.push(
+      (),
+      ChannelType::ReliableOrdered,
+      &encode_to_bytes(&ClientMessage::Pong(1)).expect("Encode failed"),
+    );
+    batcher.push((), ChannelType::ReliableOrdered, &[255]);
+    batcher.push(
+      (),
+      ChannelType::ReliableOrdered,
+      &encode_to_bytes(&ClientMessage::Pong(2)).expect("Encode failed"),
+    );
+    let packet = batcher.drain().remove(0).2;
+
+    let messages = decode_packet::<ClientMessage>(&packet);
+
+    assert_eq!(messages.len(), 3);
+    assert!(matches!(messages[0], Ok(ClientMessage::Pong(1))));
+    assert!(matches!(&messages[1], Err(UndecodablePayload { size: 1, .. })));
+    assert!(matches!(messages[2], Ok(ClientMessage::Pong(2))));
+    assert!(matches!(
+      decode_packet::<ClientMessage>(&[255]).as_slice(),
+      [Err(UndecodablePayload { size: 1, .. })]
+    ));
+  }
+
+  #[test]
+  fn varints_round_trip() {
+    for value in [0, 1, 127, 128, 300, MAX_BATCH_BYTES, usize::MAX] {
+      let mut buffer = Vec::new();
+      write_varint(&mut buffer, value);
+      assert_eq!(buffer.len(), varint_size(value));
+      assert_eq!(read_varint(&buffer), Some((value, &[][..])));
+    }
+  }
+
+  #[test]
+  fn packet_counters_report_messages_per_packet() {
+    let mut packet_counters = PacketCounters::default();
+    assert_eq!(packet_counters.messages_per_packet_sent(), None);
+
+    packet_counters.record_sent(6, 2);
+    packet_counters.record_received(4);
+
+    assert_eq!(packet_counters.messages_per_packet_sent(), Some(3.));
+    assert_eq!(packet_counters.packets_received, 1);
+    assert_eq!(packet_counters.messages_received, 4);
+  }
+}
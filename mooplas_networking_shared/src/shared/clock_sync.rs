@@ -0,0 +1,324 @@
+use crate::prelude::{
+  ClientMessage, ClientNetworkingActive, InboundClientMessage, InboundServerMessage, OutboundClientMessage,
+  OutboundServerMessage, ServerNetworkingActive,
+};
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::{
+  IntoScheduleConfigs, MessageReader, MessageWriter, Real, Res, ResMut, Resource, Time, Timer, TimerMode, not,
+  resource_exists,
+};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A plugin that synchronises the clock of each client with the clock of the server, so that the server can schedule
+/// something to happen at the same time on all clients. Clients periodically send a
+/// [`ClientMessage::TimeSyncRequest`] with their local time, which the server answers with its own time; like NTP,
+/// each answer yields an estimate of the offset between both clocks. The result is available in the [`ServerClock`]
+/// resource on both the server and the clients.
+pub struct ClockSyncPlugin;
+
+impl Plugin for ClockSyncPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<ServerClock>()
+      .init_resource::<TimeSyncTracker>()
+      .add_systems(
+        Update,
+        (synchronise_host_clock_system, respond_to_time_sync_request_system)
+          .run_if(resource_exists::<ServerNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        (handle_time_sync_response_system, send_time_sync_request_system)
+          .chain()
+          .run_if(resource_exists::<ClientNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        reset_server_clock_system
+          .run_if(not(resource_exists::<ServerNetworkingActive>))
+          .run_if(not(resource_exists::<ClientNetworkingActive>)),
+      );
+  }
+}
+
+/// The rate at which the server clock advances by one tick.
+pub const SERVER_TICKS_PER_SECOND: u64 = 60;
+/// The interval between time sync requests once the first [`SAMPLE_WINDOW`] requests have been sent.
+const SYNC_INTERVAL_SECONDS: f32 = 1.;
+/// The interval between the first [`SAMPLE_WINDOW`] time sync requests, so that the clock converges quickly.
+const INITIAL_SYNC_INTERVAL_SECONDS: f32 = 0.1;
+/// The number of most recent samples the offset is estimated from.
+const SAMPLE_WINDOW: usize = 8;
+/// Answers that took longer than this are ignored, as half their round-trip time is too poor an estimate of the delay.
+const MAX_ROUND_TRIP_TIME: Duration = Duration::from_secs(2);
+
+/// A single measurement of the offset between the server clock and the local clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClockSample {
+  round_trip_time: Duration,
+  offset_micros: i64,
+}
+
+/// A resource with the estimated offset between the clock of the server and the local clock, i.e. the elapsed
+/// [`Time<Real>`] of each app. Converts between local time and server time or ticks. On the server, the offset is
+/// always zero; on a client, it is unknown until the first answer of the server has arrived.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServerClock {
+  /// The smoothed offset of the server clock from the local clock in microseconds.
+  offset_micros: Option<i64>,
+  /// The most recent samples, oldest first.
+  samples: VecDeque<ClockSample>,
+}
+
+impl ServerClock {
+  pub fn is_synchronised(&self) -> bool {
+    self.offset_micros.is_some()
+  }
+
+  /// Returns how far the server clock is ahead of the local clock in microseconds, which is negative if it is behind.
+  pub fn offset_micros(&self) -> Option<i64> {
+    self.offset_micros
+  }
+
+  /// Converts a local time into the corresponding server time.
+  pub fn server_time(&self, local_time: Duration) -> Option<Duration> {
+    Some(shift(local_time, self.offset_micros?))
+  }
+
+  /// Converts a server time into the corresponding local time, e.g. to find out when something the server scheduled
+  /// happens locally. Server times from before the start of the local clock are clamped to zero.
+  pub fn local_time(&self, server_time: Duration) -> Option<Duration> {
+    Some(shift(server_time, -self.offset_micros?))
+  }
+
+  /// Returns the server tick at the given local time.
+  pub fn server_tick(&self, local_time: Duration) -> Option<u64> {
+    let server_time = self.server_time(local_time)?;
+    Some((server_time.as_micros() * SERVER_TICKS_PER_SECOND as u128 / 1_000_000) as u64)
+  }
+
+  /// Returns the local time at which the given server tick starts.
+  pub fn local_time_of_tick(&self, tick: u64) -> Option<Duration> {
+    let server_time = Duration::from_micros((tick as u128 * 1_000_000 / SERVER_TICKS_PER_SECOND as u128) as u64);
+    self.local_time(server_time)
+  }
+
+  /// Records the answer to a time sync request sent at `client_time`, received at `now`. Like NTP, the server is
+  /// assumed to have answered halfway through the round trip. The offset follows the sample with the shortest
+  /// round-trip time among the most recent ones, since it is the least affected by queueing delays, and is smoothed to
+  /// avoid jumps.
+  fn record_sample(&mut self, client_time: Duration, server_time: Duration, now: Duration) {
+    let Some(round_trip_time) = now.checked_sub(client_time) else {
+      return;
+    };
+    if round_trip_time > MAX_ROUND_TRIP_TIME {
+      return;
+    }
+    let offset_micros = micros(server_time) + micros(round_trip_time) / 2 - micros(now);
+    if self.samples.len() == SAMPLE_WINDOW {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(ClockSample {
+      round_trip_time,
+      offset_micros,
+    });
+    let Some(best_sample) = self.samples.iter().min_by_key(|sample| sample.round_trip_time) else {
+      return;
+    };
+    self.offset_micros = Some(match self.offset_micros {
+      Some(current) => current + (best_sample.offset_micros - current) / 4,
+      None => best_sample.offset_micros,
+    });
+  }
+
+  fn reset(&mut self) {
+    self.offset_micros = None;
+    self.samples.clear();
+  }
+}
+
+fn micros(duration: Duration) -> i64 {
+  duration.as_micros().min(i64::MAX as u128) as i64
+}
+
+/// Shifts a time by a signed number of microseconds, clamping it to zero.
+fn shift(time: Duration, offset_micros: i64) -> Duration {
+  let offset = Duration::from_micros(offset_micros.unsigned_abs());
+  if offset_micros >= 0 {
+    time.saturating_add(offset)
+  } else {
+    time.saturating_sub(offset)
+  }
+}
+
+/// A client-side resource that schedules the time sync requests.
+#[derive(Resource)]
+struct TimeSyncTracker {
+  timer: Timer,
+  requests_sent: usize,
+}
+
+impl Default for TimeSyncTracker {
+  fn default() -> Self {
+    Self {
+      timer: Timer::from_seconds(INITIAL_SYNC_INTERVAL_SECONDS, TimerMode::Repeating),
+      requests_sent: 0,
+    }
+  }
+}
+
+/// Sends a time sync request straight away, then in quick succession until the first [`SAMPLE_WINDOW`] samples have
+/// been collected, and periodically after that.
+fn send_time_sync_request_system(
+  time: Res<Time<Real>>,
+  mut tracker: ResMut<TimeSyncTracker>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  tracker.timer.tick(time.delta());
+  if tracker.requests_sent > 0 && !tracker.timer.just_finished() {
+    return;
+  }
+  tracker.requests_sent += 1;
+  if tracker.requests_sent == SAMPLE_WINDOW {
+    tracker.timer = Timer::from_seconds(SYNC_INTERVAL_SECONDS, TimerMode::Repeating);
+  }
+  outbound_client_message.write(OutboundClientMessage::send(&ClientMessage::TimeSyncRequest(
+    time.elapsed(),
+  )));
+}
+
+/// Records the answers of the server in the [`ServerClock`]. Starts over when the client is initialised by a server,
+/// since a new server e.g. after a host migration has a different clock.
+fn handle_time_sync_response_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  time: Res<Time<Real>>,
+  mut server_clock: ResMut<ServerClock>,
+  mut tracker: ResMut<TimeSyncTracker>,
+) {
+  for message in messages.read() {
+    match message {
+      InboundServerMessage::ClientInitialised { .. } => {
+        server_clock.reset();
+        *tracker = TimeSyncTracker::default();
+      }
+      InboundServerMessage::TimeSyncResponse {
+        client_time,
+        server_time,
+      } => server_clock.record_sample(*client_time, *server_time, time.elapsed()),
+      _ => {}
+    }
+  }
+}
+
+/// Answers each time sync request with the current server time.
+fn respond_to_time_sync_request_system(
+  mut messages: MessageReader<InboundClientMessage>,
+  time: Res<Time<Real>>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+) {
+  for message in messages.read() {
+    if let InboundClientMessage::TimeSyncRequest(client_time, client_id) = message {
+      let time_sync_response = InboundServerMessage::TimeSyncResponse {
+        client_time: *client_time,
+        server_time: time.elapsed(),
+      };
+      outbound_server_message.write(OutboundServerMessage::send(*client_id, &time_sync_response));
+    }
+  }
+}
+
+/// The server's own clock is the server clock, so its offset is zero.
+fn synchronise_host_clock_system(mut server_clock: ResMut<ServerClock>) {
+  if server_clock.offset_micros != Some(0) {
+    server_clock.reset();
+    server_clock.offset_micros = Some(0);
+  }
+}
+
+/// Forgets the offset of the last session once networking is no longer active.
+fn reset_server_clock_system(mut server_clock: ResMut<ServerClock>, mut tracker: ResMut<TimeSyncTracker>) {
+  if server_clock.is_synchronised() || tracker.requests_sent > 0 {
+    server_clock.reset();
+    *tracker = TimeSyncTracker::default();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn millis(value: u64) -> Duration {
+    Duration::from_millis(value)
+  }
+
+  #[test]
+  fn record_sample_estimates_offset_from_round_trip() {
+    let mut server_clock = ServerClock::default();
+
+    // Sent at 1000 ms local time, answered at 5040 ms server time, received at 1100 ms local time
+    server_clock.record_sample(millis(1000), millis(5040), millis(1100));
+
+    assert_eq!(server_clock.offset_micros(), Some(3_990_000));
+    assert_eq!(server_clock.server_time(millis(2000)), Some(millis(5990)));
+    assert_eq!(server_clock.local_time(millis(5990)), Some(millis(2000)));
+  }
+
+  #[test]
+  fn record_sample_follows_sample_with_shortest_round_trip_time() {
+    let mut server_clock = ServerClock::default();
+    server_clock.record_sample(millis(0), millis(1025), millis(50));
+    assert_eq!(server_clock.offset_micros(), Some(1_000_000));
+
+    // A sample delayed on the way back suggests a smaller offset, but is outweighed by the faster first sample
+    server_clock.record_sample(millis(1000), millis(2025), millis(1450));
+    assert_eq!(server_clock.offset_micros(), Some(1_000_000));
+
+    // A faster sample is followed gradually
+    server_clock.record_sample(millis(2000), millis(3010), millis(2010));
+    assert_eq!(server_clock.offset_micros(), Some(1_001_250));
+  }
+
+  #[test]
+  fn record_sample_ignores_slow_or_impossible_answers() {
+    let mut server_clock = ServerClock::default();
+
+    server_clock.record_sample(millis(0), millis(5000), millis(3000));
+    server_clock.record_sample(millis(2000), millis(5000), millis(1000));
+
+    assert!(!server_clock.is_synchronised());
+    assert!(server_clock.samples.is_empty());
+  }
+
+  #[test]
+  fn record_sample_only_considers_most_recent_samples() {
+    let mut server_clock = ServerClock::default();
+    server_clock.record_sample(millis(0), millis(1000), millis(0));
+    for _ in 0..SAMPLE_WINDOW {
+      server_clock.record_sample(millis(0), millis(2010), millis(20));
+    }
+
+    assert_eq!(server_clock.samples.len(), SAMPLE_WINDOW);
+    assert!(
+      server_clock
+        .samples
+        .iter()
+        .all(|sample| sample.round_trip_time == millis(20))
+    );
+  }
+
+  #[test]
+  fn server_clock_converts_between_local_time_and_ticks() {
+    let mut server_clock = ServerClock::default();
+    assert_eq!(server_clock.server_tick(millis(1000)), None);
+
+    server_clock.offset_micros = Some(-500_000);
+
+    assert_eq!(server_clock.server_tick(millis(1500)), Some(SERVER_TICKS_PER_SECOND));
+    assert_eq!(
+      server_clock.local_time_of_tick(SERVER_TICKS_PER_SECOND),
+      Some(millis(1500))
+    );
+    assert_eq!(server_clock.server_time(millis(100)), Some(Duration::ZERO));
+  }
+}
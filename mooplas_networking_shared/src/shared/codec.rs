@@ -23,7 +23,7 @@ mod tests {
 
   mod online {
     use super::*;
-    use crate::prelude::{ClientId, SerialisableRegisteredPlayer};
+    use crate::prelude::{ClientId, ReconnectToken, SerialisableRegisteredPlayer, SerialisableTailEvent};
     use crate::shared::messages::InboundServerMessage;
     use crate::shared::structs::{ClientMessage, SerialisableRegistrationRequest};
 
@@ -47,6 +47,7 @@ mod tests {
 
     #[test]
     fn client_initialised_round_trip_carries_seed_without_spawn_points() {
+      let token = ReconnectToken::new_random();
       let original = InboundServerMessage::ClientInitialised {
         seed: 42,
         client_id: ClientId::from_u64(7),
@@ -56,8 +57,10 @@ mod tests {
           player_id: 0,
           control_scheme_id: 0,
           name: "Host".to_string(),
+          is_ready: false,
         }],
         winner_info: Some(0),
+        reconnect_token: token,
       };
       let bytes = encode_to_bytes(&original).expect("Encode should succeed");
       let decoded: InboundServerMessage = decode_from_bytes(&bytes).expect("Decode should succeed");
@@ -68,6 +71,7 @@ mod tests {
         current_state,
         registered_players,
         winner_info,
+        reconnect_token,
       } = decoded
       else {
         panic!("Expected ClientInitialised");
@@ -77,6 +81,40 @@ mod tests {
       assert_eq!(current_state, "Playing");
       assert_eq!(registered_players.len(), 1);
       assert_eq!(winner_info, Some(0));
+      assert_eq!(reconnect_token, token);
+    }
+
+    #[test]
+    fn update_tails_round_trip_preserves_event_order() {
+      let original = InboundServerMessage::UpdateTails {
+        events: vec![
+          SerialisableTailEvent::Sampled {
+            player_id: 1,
+            x: 10.5,
+            y: -3.25,
+          },
+          SerialisableTailEvent::GapStarted { player_id: 1 },
+          SerialisableTailEvent::GapEnded { player_id: 2 },
+        ],
+      };
+      let bytes = encode_to_bytes(&original).expect("Encode should succeed");
+      let decoded: InboundServerMessage = decode_from_bytes(&bytes).expect("Decode should succeed");
+
+      let InboundServerMessage::UpdateTails { events } = decoded else {
+        panic!("Expected UpdateTails");
+      };
+      assert_eq!(
+        events,
+        vec![
+          SerialisableTailEvent::Sampled {
+            player_id: 1,
+            x: 10.5,
+            y: -3.25,
+          },
+          SerialisableTailEvent::GapStarted { player_id: 1 },
+          SerialisableTailEvent::GapEnded { player_id: 2 },
+        ]
+      );
     }
   }
 }
@@ -0,0 +1,393 @@
+use crate::prelude::{ChannelType, OutboundClientMessage, OutboundServerMessage};
+use bevy::ecs::system::SystemParam;
+use bevy::log::info;
+use bevy::prelude::{Local, Message, MessageReader, Real, Reflect, ReflectResource, Res, Resource, Time};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const LINK_CONDITIONER_ENV_VAR_PREFIX: &str = "LINK_CONDITIONER";
+/// The additional delay of a message that is held back to be reordered.
+const REORDERING_DELAY: Duration = Duration::from_millis(50);
+
+/// The network conditions simulated for a single channel. Probabilities are between `0.0` and `1.0`.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelConditions {
+  /// The delay added to every message.
+  pub latency_ms: f32,
+  /// The maximum random deviation from the latency, in either direction.
+  pub jitter_ms: f32,
+  /// The probability of a message being dropped. Ignored for reliable channels.
+  pub packet_loss: f32,
+  /// The probability of a message being sent twice. Ignored for reliable channels.
+  pub duplication: f32,
+  /// The probability of a message being held back, so that later messages overtake it. Ignored for
+  /// [`ChannelType::ReliableOrdered`].
+  pub reordering: f32,
+}
+
+impl ChannelConditions {
+  fn from_lookup(channel_name: &str, lookup: &impl Fn(&str) -> Option<String>) -> Self {
+    let value = |setting: &str| {
+      lookup(&format!("{LINK_CONDITIONER_ENV_VAR_PREFIX}_{channel_name}_{setting}"))
+        .or_else(|| lookup(&format!("{LINK_CONDITIONER_ENV_VAR_PREFIX}_{setting}")))
+        .and_then(|value| value.trim().parse::<f32>().ok())
+        .unwrap_or_default()
+        .max(0.)
+    };
+    Self {
+      latency_ms: value("LATENCY_MS"),
+      jitter_ms: value("JITTER_MS"),
+      packet_loss: value("PACKET_LOSS").min(1.),
+      duplication: value("DUPLICATION").min(1.),
+      reordering: value("REORDERING").min(1.),
+    }
+  }
+
+  /// Returns the latency with a random amount of jitter applied.
+  fn sample_delay(&self) -> Duration {
+    let jitter = (rand::random::<f32>() * 2. - 1.) * self.jitter_ms;
+    Duration::from_micros(((self.latency_ms + jitter).max(0.) * 1000.) as u64)
+  }
+}
+
+/// A resource that configures the link conditioner, which simulates poor network conditions by delaying, dropping,
+/// duplicating and reordering outbound messages before they reach the transport. Reliable channels are never dropped
+/// or duplicated, and [`ChannelType::ReliableOrdered`] messages are never reordered.
+///
+/// Loaded from environment variables of the form `LINK_CONDITIONER_{SETTING}`, which apply to all channels, or
+/// `LINK_CONDITIONER_{CHANNEL}_{SETTING}`, which apply to a single channel and take precedence. `{CHANNEL}` is one of
+/// `UNRELIABLE`, `RELIABLE_ORDERED` or `RELIABLE_UNORDERED` and `{SETTING}` is one of `LATENCY_MS`, `JITTER_MS`,
+/// `PACKET_LOSS`, `DUPLICATION` or `REORDERING`. Enabled if any of them is set. Can also be changed at runtime, e.g.
+/// via the game's link conditioner menu in dev builds.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct LinkConditioner {
+  pub enabled: bool,
+  pub unreliable: ChannelConditions,
+  pub reliable_ordered: ChannelConditions,
+  pub reliable_unordered: ChannelConditions,
+}
+
+impl LinkConditioner {
+  pub fn from_env() -> Self {
+    Self::from_lookup(|key| std::env::var(key).ok())
+  }
+
+  fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+    let link_conditioner = Self {
+      enabled: false,
+      unreliable: ChannelConditions::from_lookup("UNRELIABLE", &lookup),
+      reliable_ordered: ChannelConditions::from_lookup("RELIABLE_ORDERED", &lookup),
+      reliable_unordered: ChannelConditions::from_lookup("RELIABLE_UNORDERED", &lookup),
+    };
+    let is_configured = [
+      link_conditioner.unreliable,
+      link_conditioner.reliable_ordered,
+      link_conditioner.reliable_unordered,
+    ]
+    .iter()
+    .any(|conditions| *conditions != ChannelConditions::default());
+    if is_configured {
+      info!("Link conditioner enabled: {:?}", link_conditioner);
+    }
+    Self {
+      enabled: is_configured,
+      ..link_conditioner
+    }
+  }
+
+  pub fn for_channel(&self, channel: ChannelType) -> &ChannelConditions {
+    match channel {
+      ChannelType::Unreliable => &self.unreliable,
+      ChannelType::ReliableOrdered => &self.reliable_ordered,
+      ChannelType::ReliableUnordered => &self.reliable_unordered,
+    }
+  }
+}
+
+/// An outbound message that can be held back by the link conditioner.
+pub trait ConditionedMessage: Message + Clone {
+  /// Returns the channel the message is sent on, or `None` for control messages such as disconnecting, which are
+  /// delivered after all messages that are still held back.
+  fn channel(&self) -> Option<ChannelType>;
+}
+
+impl ConditionedMessage for OutboundServerMessage {
+  fn channel(&self) -> Option<ChannelType> {
+    match self {
+      OutboundServerMessage::Broadcast { channel, .. }
+      | OutboundServerMessage::BroadcastExcept { channel, .. }
+      | OutboundServerMessage::Send { channel, .. } => Some(*channel),
+      OutboundServerMessage::Disconnect { .. } | OutboundServerMessage::DisconnectAll => None,
+    }
+  }
+}
+
+impl ConditionedMessage for OutboundClientMessage {
+  fn channel(&self) -> Option<ChannelType> {
+    match self {
+      OutboundClientMessage::Send { channel, .. } => Some(*channel),
+      OutboundClientMessage::Disconnect => None,
+    }
+  }
+}
+
+/// The messages held back by the link conditioner, in the order in which they were written.
+struct LinkConditionerQueue<T> {
+  pending: VecDeque<(Duration, T)>,
+  /// The time at which the last [`ChannelType::ReliableOrdered`] message is delivered, which no later message on the
+  /// same channel may overtake.
+  last_ordered_delivery: Duration,
+}
+
+impl<T> Default for LinkConditionerQueue<T> {
+  fn default() -> Self {
+    Self {
+      pending: VecDeque::new(),
+      last_ordered_delivery: Duration::ZERO,
+    }
+  }
+}
+
+impl<T: ConditionedMessage> LinkConditionerQueue<T> {
+  fn push(&mut self, link_conditioner: &LinkConditioner, message: T, now: Duration) {
+    let Some(channel) = message.channel() else {
+      let last_delivery = self.pending.iter().map(|(delivery, _)| *delivery).max().unwrap_or(now);
+      self.pending.push_back((last_delivery.max(now), message));
+      return;
+    };
+    let conditions = link_conditioner.for_channel(channel);
+    let is_reordered = rand::random::<f32>() < conditions.reordering;
+    match channel {
+      ChannelType::Unreliable => {
+        if rand::random::<f32>() < conditions.packet_loss {
+          return;
+        }
+        if rand::random::<f32>() < conditions.duplication {
+          self
+            .pending
+            .push_back((now + conditions.sample_delay(), message.clone()));
+        }
+        let reordering_delay = if is_reordered { REORDERING_DELAY } else { Duration::ZERO };
+        self
+          .pending
+          .push_back((now + conditions.sample_delay() + reordering_delay, message));
+      }
+      ChannelType::ReliableUnordered => {
+        let reordering_delay = if is_reordered { REORDERING_DELAY } else { Duration::ZERO };
+        self
+          .pending
+          .push_back((now + conditions.sample_delay() + reordering_delay, message));
+      }
+      ChannelType::ReliableOrdered => {
+        let delivery = (now + conditions.sample_delay()).max(self.last_ordered_delivery);
+        self.last_ordered_delivery = delivery;
+        self.pending.push_back((delivery, message));
+      }
+    }
+  }
+
+  /// Removes and returns all messages that are due, ordered by delivery time and then by the order in which they were
+  /// written.
+  fn pop_due(&mut self, now: Duration) -> Vec<T> {
+    let mut due = Vec::new();
+    let mut remaining = VecDeque::new();
+    for (delivery, message) in self.pending.drain(..) {
+      if delivery <= now {
+        due.push((delivery, message));
+      } else {
+        remaining.push_back((delivery, message));
+      }
+    }
+    self.pending = remaining;
+    due.sort_by_key(|(delivery, _)| *delivery);
+    due.into_iter().map(|(_, message)| message).collect()
+  }
+
+  fn pop_all(&mut self) -> Vec<T> {
+    self.pop_due(Duration::MAX)
+  }
+}
+
+/// A system parameter that transports use instead of a [`MessageReader`] to read outbound messages. Passes messages
+/// through unchanged, unless the [`LinkConditioner`] is enabled.
+#[derive(SystemParam)]
+pub struct ConditionedMessageReader<'w, 's, T: ConditionedMessage> {
+  messages: MessageReader<'w, 's, T>,
+  link_conditioner: Option<Res<'w, LinkConditioner>>,
+  time: Res<'w, Time<Real>>,
+  queue: Local<'s, LinkConditionerQueue<T>>,
+}
+
+impl<T: ConditionedMessage> ConditionedMessageReader<'_, '_, T> {
+  /// Returns the messages that are due to be sent now.
+  pub fn read(&mut self) -> Vec<T> {
+    let now = self.time.elapsed();
+    match self
+      .link_conditioner
+      .as_deref()
+      .filter(|link_conditioner| link_conditioner.enabled)
+    {
+      Some(link_conditioner) => {
+        for message in self.messages.read() {
+          self.queue.push(link_conditioner, message.clone(), now);
+        }
+        self.queue.pop_due(now)
+      }
+      None => {
+        let mut messages = self.queue.pop_all();
+        messages.extend(self.messages.read().cloned());
+        messages
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn millis(value: u64) -> Duration {
+    Duration::from_millis(value)
+  }
+
+  fn send(channel: ChannelType, payload: u8) -> OutboundClientMessage {
+    OutboundClientMessage::Send {
+      channel,
+      payload: vec![payload],
+    }
+  }
+
+  fn payloads(messages: Vec<OutboundClientMessage>) -> Vec<u8> {
+    messages
+      .into_iter()
+      .filter_map(|message| match message {
+        OutboundClientMessage::Send { payload, .. } => Some(payload[0]),
+        OutboundClientMessage::Disconnect => None,
+      })
+      .collect()
+  }
+
+  fn link_conditioner(conditions: ChannelConditions) -> LinkConditioner {
+    LinkConditioner {
+      enabled: true,
+      unreliable: conditions,
+      reliable_ordered: conditions,
+      reliable_unordered: conditions,
+    }
+  }
+
+  #[test]
+  fn from_lookup_prefers_channel_specific_settings_and_is_disabled_when_unset() {
+    assert!(!LinkConditioner::from_lookup(|_| None).enabled);
+
+    let link_conditioner = LinkConditioner::from_lookup(|key| match key {
+      "LINK_CONDITIONER_LATENCY_MS" => Some("100".to_string()),
+      "LINK_CONDITIONER_UNRELIABLE_LATENCY_MS" => Some("20".to_string()),
+      "LINK_CONDITIONER_PACKET_LOSS" => Some("2".to_string()),
+      _ => None,
+    });
+
+    assert!(link_conditioner.enabled);
+    assert_eq!(link_conditioner.unreliable.latency_ms, 20.);
+    assert_eq!(link_conditioner.reliable_ordered.latency_ms, 100.);
+    assert_eq!(link_conditioner.reliable_unordered.packet_loss, 1.);
+  }
+
+  #[test]
+  fn push_delays_messages_by_latency() {
+    let link_conditioner = link_conditioner(ChannelConditions {
+      latency_ms: 100.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    queue.push(&link_conditioner, send(ChannelType::Unreliable, 1), millis(0));
+
+    assert!(queue.pop_due(millis(99)).is_empty());
+    assert_eq!(payloads(queue.pop_due(millis(100))), vec![1]);
+  }
+
+  #[test]
+  fn push_never_drops_or_duplicates_reliable_messages() {
+    let link_conditioner = link_conditioner(ChannelConditions {
+      packet_loss: 1.,
+      duplication: 1.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    queue.push(&link_conditioner, send(ChannelType::Unreliable, 1), millis(0));
+    queue.push(&link_conditioner, send(ChannelType::ReliableOrdered, 2), millis(0));
+    queue.push(&link_conditioner, send(ChannelType::ReliableUnordered, 3), millis(0));
+
+    assert_eq!(payloads(queue.pop_due(millis(0))), vec![2, 3]);
+  }
+
+  #[test]
+  fn push_duplicates_unreliable_messages() {
+    let link_conditioner = link_conditioner(ChannelConditions {
+      duplication: 1.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    queue.push(&link_conditioner, send(ChannelType::Unreliable, 1), millis(0));
+
+    assert_eq!(payloads(queue.pop_due(millis(0))), vec![1, 1]);
+  }
+
+  #[test]
+  fn push_never_reorders_reliable_ordered_messages() {
+    let link_conditioner = link_conditioner(ChannelConditions {
+      latency_ms: 100.,
+      jitter_ms: 100.,
+      reordering: 1.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    for payload in 0..20 {
+      queue.push(
+        &link_conditioner,
+        send(ChannelType::ReliableOrdered, payload),
+        millis(payload as u64),
+      );
+    }
+
+    assert_eq!(payloads(queue.pop_all()), (0..20).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn push_lets_later_messages_overtake_reordered_messages() {
+    let mut link_conditioner = link_conditioner(ChannelConditions {
+      reordering: 1.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    queue.push(&link_conditioner, send(ChannelType::Unreliable, 1), millis(0));
+    link_conditioner.unreliable.reordering = 0.;
+    queue.push(&link_conditioner, send(ChannelType::Unreliable, 2), millis(0));
+
+    assert_eq!(payloads(queue.pop_due(millis(0))), vec![2]);
+    assert_eq!(payloads(queue.pop_due(REORDERING_DELAY)), vec![1]);
+  }
+
+  #[test]
+  fn push_delivers_control_messages_after_held_back_messages() {
+    let link_conditioner = link_conditioner(ChannelConditions {
+      latency_ms: 100.,
+      ..ChannelConditions::default()
+    });
+    let mut queue = LinkConditionerQueue::default();
+
+    queue.push(&link_conditioner, send(ChannelType::ReliableOrdered, 1), millis(0));
+    queue.push(&link_conditioner, OutboundClientMessage::Disconnect, millis(0));
+
+    assert!(queue.pop_due(millis(50)).is_empty());
+    let messages = queue.pop_due(millis(100));
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[1], OutboundClientMessage::Disconnect));
+  }
+}
@@ -0,0 +1,475 @@
+use crate::prelude::{
+  CHANNELS, ChannelSemantics, ChannelType, ClientId, ClientTransport, NetworkErrorEvent, ServerTransport,
+  TransportEvent,
+};
+use bevy::prelude::Resource;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// An in-memory network that connects a server and any number of clients in the same process, e.g. multiple Bevy
+/// `App`s in a test. Payloads are delivered in order and without loss on every channel, which satisfies the guarantees
+/// of every [`ChannelType`]. Combine with the [`crate::prelude::LinkConditioner`] to simulate worse conditions.
+///
+/// Cloning a [`LoopbackNetwork`] returns a handle to the same network.
+#[derive(Clone, Default)]
+pub struct LoopbackNetwork {
+  state: Arc<Mutex<LoopbackState>>,
+}
+
+#[derive(Default)]
+struct LoopbackState {
+  server_events: VecDeque<TransportEvent>,
+  links: Vec<LoopbackLink>,
+  next_client_id: u64,
+}
+
+/// Every channel of a [`LoopbackNetwork`] is reliable and ordered.
+const LOOPBACK_CHANNEL_SEMANTICS: [ChannelSemantics; CHANNELS.len()] = [ChannelSemantics {
+  reliable: true,
+  ordered: true,
+}; CHANNELS.len()];
+
+/// The connection between the server and a single client.
+struct LoopbackLink {
+  client_id: ClientId,
+  to_server: [VecDeque<Vec<u8>>; CHANNELS.len()],
+  to_client: [VecDeque<Vec<u8>>; CHANNELS.len()],
+  is_connected: bool,
+}
+
+impl LoopbackState {
+  fn link_mut(&mut self, client_id: ClientId) -> Option<&mut LoopbackLink> {
+    self.links.iter_mut().find(|link| link.client_id == client_id)
+  }
+}
+
+impl LoopbackNetwork {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, LoopbackState> {
+    self.state.lock().expect("Loopback network mutex was poisoned")
+  }
+
+  /// Returns the server side of the network. Insert it as a resource into the host's `App`.
+  pub fn server(&self) -> LoopbackServerTransport {
+    LoopbackServerTransport { network: self.clone() }
+  }
+
+  /// Connects a new client to the network and returns its side of the connection. Insert it as a resource into the
+  /// client's `App`. The server is informed of the new client the next time it polls for events.
+  pub fn connect_client(&self) -> LoopbackClientTransport {
+    let mut state = self.lock();
+    // The nil client ID is reserved for the host's own players
+    state.next_client_id += 1;
+    let client_id = ClientId::from_u64(state.next_client_id);
+    state.links.push(LoopbackLink {
+      client_id,
+      to_server: Default::default(),
+      to_client: Default::default(),
+      is_connected: true,
+    });
+    state
+      .server_events
+      .push_back(TransportEvent::ClientConnected(client_id));
+    LoopbackClientTransport {
+      network: self.clone(),
+      client_id,
+      has_reported_disconnect: false,
+    }
+  }
+}
+
+/// The server side of a [`LoopbackNetwork`].
+#[derive(Resource)]
+pub struct LoopbackServerTransport {
+  network: LoopbackNetwork,
+}
+
+impl ServerTransport for LoopbackServerTransport {
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics] = &LOOPBACK_CHANNEL_SEMANTICS;
+
+  fn poll_events(&mut self) -> Result<Vec<TransportEvent>, NetworkErrorEvent> {
+    Ok(self.network.lock().server_events.drain(..).collect())
+  }
+
+  fn receive(&mut self, channel: ChannelType) -> Vec<(ClientId, Vec<u8>)> {
+    let mut state = self.network.lock();
+    let mut received = Vec::new();
+    for link in &mut state.links {
+      let client_id = link.client_id;
+      received.extend(
+        link.to_server[channel.index()]
+          .drain(..)
+          .map(|payload| (client_id, payload)),
+      );
+    }
+    received
+  }
+
+  fn send(&mut self, client_id: ClientId, channel: ChannelType, payload: &[u8]) {
+    if let Some(link) = self.network.lock().link_mut(client_id)
+      && link.is_connected
+    {
+      link.to_client[channel.index()].push_back(payload.to_vec());
+    }
+  }
+
+  fn connected_clients(&self) -> Vec<ClientId> {
+    self
+      .network
+      .lock()
+      .links
+      .iter()
+      .filter(|link| link.is_connected)
+      .map(|link| link.client_id)
+      .collect()
+  }
+
+  fn disconnect(&mut self, client_id: ClientId) {
+    let mut state = self.network.lock();
+    let was_connected = state
+      .link_mut(client_id)
+      .is_some_and(|link| std::mem::replace(&mut link.is_connected, false));
+    if was_connected {
+      state
+        .server_events
+        .push_back(TransportEvent::ClientDisconnected(client_id));
+    }
+  }
+
+  fn disconnect_all(&mut self) {
+    for link in &mut self.network.lock().links {
+      link.is_connected = false;
+    }
+  }
+}
+
+/// The client side of a [`LoopbackNetwork`].
+#[derive(Resource)]
+pub struct LoopbackClientTransport {
+  network: LoopbackNetwork,
+  client_id: ClientId,
+  has_reported_disconnect: bool,
+}
+
+impl LoopbackClientTransport {
+  /// Returns the [`ClientId`] under which the server knows this client.
+  pub fn client_id(&self) -> ClientId {
+    self.client_id
+  }
+
+  fn is_connected(&self) -> bool {
+    let mut state = self.network.lock();
+    state.link_mut(self.client_id).is_some_and(|link| link.is_connected)
+  }
+}
+
+impl ClientTransport for LoopbackClientTransport {
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics] = &LOOPBACK_CHANNEL_SEMANTICS;
+
+  fn poll_connection(&mut self) -> Result<(), NetworkErrorEvent> {
+    if self.is_connected() || self.has_reported_disconnect {
+      return Ok(());
+    }
+    self.has_reported_disconnect = true;
+    Err(NetworkErrorEvent::Disconnect("Host disconnected".to_string()))
+  }
+
+  fn receive(&mut self, channel: ChannelType) -> Vec<Vec<u8>> {
+    let mut state = self.network.lock();
+    match state.link_mut(self.client_id) {
+      Some(link) => link.to_client[channel.index()].drain(..).collect(),
+      None => Vec::new(),
+    }
+  }
+
+  fn send(&mut self, channel: ChannelType, payload: &[u8]) {
+    if let Some(link) = self.network.lock().link_mut(self.client_id)
+      && link.is_connected
+    {
+      link.to_server[channel.index()].push_back(payload.to_vec());
+    }
+  }
+
+  fn disconnect(&mut self) {
+    let mut state = self.network.lock();
+    let was_connected = state
+      .link_mut(self.client_id)
+      .is_some_and(|link| std::mem::replace(&mut link.is_connected, false));
+    if was_connected {
+      state
+        .server_events
+        .push_back(TransportEvent::ClientDisconnected(self.client_id));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::{
+    ClientMessage, ClientNetworkingActive, ClientTransportPlugin, InboundClientMessage, InboundServerMessage, Lobby,
+    MalformedPackets, NetworkingMessagesPlugin, NetworkingResourcesPlugin, OutboundClientMessage,
+    OutboundServerMessage, PacketCounters, ReconnectToken, ServerNetworkingActive, ServerTransportPlugin,
+    TOO_MANY_MALFORMED_PACKETS, encode_to_bytes,
+  };
+  use bevy::prelude::*;
+
+  fn setup_server(network: &LoopbackNetwork) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      NetworkingMessagesPlugin,
+      NetworkingResourcesPlugin,
+      ServerTransportPlugin::<LoopbackServerTransport>::default(),
+    ));
+    app.insert_resource(network.server());
+    app.insert_resource(ServerNetworkingActive);
+    app
+  }
+
+  fn setup_client(network: &LoopbackNetwork) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      NetworkingMessagesPlugin,
+      NetworkingResourcesPlugin,
+      ClientTransportPlugin::<LoopbackClientTransport>::default(),
+    ));
+    app.insert_resource(network.connect_client());
+    app.insert_resource(ClientNetworkingActive);
+    app
+  }
+
+  fn read_messages<T: Message>(app: &mut App) -> Vec<T> {
+    app.world_mut().resource_mut::<Messages<T>>().drain().collect()
+  }
+
+  /// The [`NetworkErrorEvent`]s triggered in an `App`, as text.
+  #[derive(Resource, Default)]
+  struct NetworkErrors(Vec<String>);
+
+  fn record_network_errors(app: &mut App) {
+    app.init_resource::<NetworkErrors>();
+    app.add_observer(|error: On<NetworkErrorEvent>, mut errors: ResMut<NetworkErrors>| {
+      errors.0.push(error.event().to_string());
+    });
+  }
+
+  #[test]
+  fn server_is_informed_of_connecting_and_disconnecting_clients() {
+    let network = LoopbackNetwork::new();
+    let mut server = setup_server(&network);
+    let mut client = setup_client(&network);
+    let client_id = client.world().resource::<LoopbackClientTransport>().client_id();
+
+    server.update();
+    assert_eq!(server.world().resource::<Lobby>().connected, vec![client_id]);
+    assert!(matches!(
+      read_messages::<InboundServerMessage>(&mut server).as_slice(),
+      [InboundServerMessage::ClientConnected { client_id: id }] if *id == client_id
+    ));
+
+    client
+      .world_mut()
+      .write_message(OutboundClientMessage::Disconnect)
+      .expect("Failed to write OutboundClientMessage");
+    client.update();
+    server.update();
+    assert!(server.world().resource::<Lobby>().connected.is_empty());
+    assert!(matches!(
+      read_messages::<InboundServerMessage>(&mut server).as_slice(),
+      [InboundServerMessage::ClientDisconnected { client_id: id, is_forced: false }] if *id == client_id
+    ));
+  }
+
+  #[test]
+  fn messages_are_delivered_in_order_in_both_directions() {
+    let network = LoopbackNetwork::new();
+    let mut server = setup_server(&network);
+    let mut client = setup_client(&network);
+    server.update();
+
+    for sequence in 0..3 {
+      let payload = encode_to_bytes(&ClientMessage::Pong(sequence)).expect("Failed to serialise pong");
+      client
+        .world_mut()
+        .write_message(OutboundClientMessage::Send {
+          channel: ChannelType::ReliableOrdered,
+          payload,
+        })
+        .expect("Failed to write OutboundClientMessage");
+    }
+    client.update();
+    server.update();
+    let sequences: Vec<u32> = read_messages::<InboundClientMessage>(&mut server)
+      .into_iter()
+      .filter_map(|message| match message {
+        InboundClientMessage::Pong(sequence, _) => Some(sequence),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(sequences, vec![0, 1, 2]);
+
+    let payload = encode_to_bytes(&InboundServerMessage::ShutdownServer).expect("Failed to serialise shutdown");
+    server
+      .world_mut()
+      .write_message(OutboundServerMessage::Broadcast {
+        channel: ChannelType::ReliableOrdered,
+        payload,
+      })
+      .expect("Failed to write OutboundServerMessage");
+    server.update();
+    client.update();
+    assert!(matches!(
+      read_messages::<InboundServerMessage>(&mut client).as_slice(),
+      [InboundServerMessage::ShutdownServer]
+    ));
+  }
+
+  #[test]
+  fn messages_for_the_same_peer_and_channel_are_sent_in_one_packet() {
+    let network = LoopbackNetwork::new();
+    let mut server = setup_server(&network);
+    let mut client = setup_client(&network);
+    server.update();
+
+    for sequence in 0..3 {
+      client
+        .world_mut()
+        .write_message(OutboundClientMessage::send(&ClientMessage::Pong(sequence)))
+        .expect("Failed to write OutboundClientMessage");
+    }
+    client
+      .world_mut()
+      .write_message(OutboundClientMessage::send(&ClientMessage::Reconnect(
+        ReconnectToken::new_random(),
+      )))
+      .expect("Failed to write OutboundClientMessage");
+    client.update();
+    server.update();
+
+    assert_eq!(read_messages::<InboundClientMessage>(&mut server).len(), 4);
+    let client_counters = *client.world().resource::<PacketCounters>();
+    assert_eq!(client_counters.messages_sent, 4);
+    assert_eq!(client_counters.packets_sent, 2);
+    let server_counters = *server.world().resource::<PacketCounters>();
+    assert_eq!(server_counters.messages_received, 4);
+    assert_eq!(server_counters.packets_received, 2);
+  }
+
+  #[test]
+  fn server_disconnects_client_that_sends_too_many_malformed_packets() {
+    let network = LoopbackNetwork::new();
+    let mut server = setup_server(&network);
+    server.insert_resource(MalformedPackets::new(2));
+    record_network_errors(&mut server);
+    let mut client_transport = network.connect_client();
+    let client_id = client_transport.client_id();
+    server.update();
+    read_messages::<InboundServerMessage>(&mut server);
+
+    client_transport.send(ChannelType::Unreliable, &[255]);
+    server.update();
+    assert_eq!(server.world().resource::<NetworkErrors>().0.len(), 1);
+    assert_eq!(server.world().resource::<Lobby>().connected, vec![client_id]);
+
+    client_transport.send(ChannelType::ReliableOrdered, &[255]);
+    server.update();
+    server.update();
+    let errors = &server.world().resource::<NetworkErrors>().0;
+    assert_eq!(errors.len(), 2);
+    assert!(errors[1].contains("[ReliableOrdered] packet of 1 bytes"));
+    assert!(server.world().resource::<Lobby>().connected.is_empty());
+    assert!(matches!(
+      read_messages::<InboundServerMessage>(&mut server).as_slice(),
+      [InboundServerMessage::ClientDisconnected { client_id: id, is_forced: true }] if *id == client_id
+    ));
+  }
+
+  #[test]
+  fn server_reports_client_it_has_disconnected_as_forced() {
+    let network = LoopbackNetwork::new();
+    let mut server = setup_server(&network);
+    let mut client = setup_client(&network);
+    let client_id = client.world().resource::<LoopbackClientTransport>().client_id();
+    server.update();
+    read_messages::<InboundServerMessage>(&mut server);
+
+    server
+      .world_mut()
+      .write_message(OutboundServerMessage::Disconnect { client_id })
+      .expect("Failed to write OutboundServerMessage");
+    server.update();
+    server.update();
+    client.update();
+
+    assert!(server.world().resource::<Lobby>().connected.is_empty());
+    assert!(matches!(
+      read_messages::<InboundServerMessage>(&mut server).as_slice(),
+      [InboundServerMessage::ClientDisconnected { client_id: id, is_forced: true }] if *id == client_id
+    ));
+  }
+
+  #[test]
+  fn client_disconnects_from_server_that_sends_too_many_malformed_packets() {
+    let network = LoopbackNetwork::new();
+    let mut server_transport = network.server();
+    let mut client = setup_client(&network);
+    client.insert_resource(MalformedPackets::new(1));
+    record_network_errors(&mut client);
+    let client_id = client.world().resource::<LoopbackClientTransport>().client_id();
+    server_transport.poll_events().expect("Failed to poll events");
+
+    server_transport.send(client_id, ChannelType::Unreliable, &[255]);
+    client.update();
+
+    let errors = &client.world().resource::<NetworkErrors>().0;
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("from the server"));
+    assert_eq!(errors[1], TOO_MANY_MALFORMED_PACKETS);
+    assert!(server_transport.connected_clients().is_empty());
+  }
+
+  #[test]
+  fn client_reports_disconnect_once_after_server_disconnects_all() {
+    let network = LoopbackNetwork::new();
+    let mut server_transport = network.server();
+    let mut client_transport = network.connect_client();
+    assert!(client_transport.poll_connection().is_ok());
+
+    server_transport.disconnect_all();
+    server_transport.send(client_transport.client_id(), ChannelType::ReliableOrdered, &[1]);
+
+    assert!(matches!(
+      client_transport.poll_connection(),
+      Err(NetworkErrorEvent::Disconnect(_))
+    ));
+    assert!(client_transport.poll_connection().is_ok());
+    assert!(client_transport.receive(ChannelType::ReliableOrdered).is_empty());
+    assert!(server_transport.connected_clients().is_empty());
+  }
+
+  #[test]
+  fn server_can_disconnect_a_single_client_after_sending_a_final_message() {
+    let network = LoopbackNetwork::new();
+    let mut server_transport = network.server();
+    let mut kicked_client = network.connect_client();
+    let other_client = network.connect_client();
+    server_transport.poll_events().expect("Failed to poll events");
+
+    server_transport.send(kicked_client.client_id(), ChannelType::ReliableOrdered, &[1]);
+    server_transport.disconnect(kicked_client.client_id());
+    server_transport.disconnect(kicked_client.client_id());
+
+    assert_eq!(
+      server_transport.poll_events().expect("Failed to poll events"),
+      vec![TransportEvent::ClientDisconnected(kicked_client.client_id())]
+    );
+    assert_eq!(server_transport.connected_clients(), vec![other_client.client_id()]);
+    assert_eq!(kicked_client.receive(ChannelType::ReliableOrdered), vec![vec![1]]);
+    assert!(kicked_client.poll_connection().is_err());
+  }
+}
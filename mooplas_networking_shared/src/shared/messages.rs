@@ -1,10 +1,17 @@
-use crate::prelude::{ChannelType, ClientId, SerialisableRegisteredPlayer};
-use crate::shared::structs::{SerialisableInput, SerialisableRegistrationRequest, SerialisableUnregistrationRequest};
+use crate::prelude::{
+  ChannelType, ClientId, ClientMessage, MessageRegistry, PeerStats, ReconnectToken, SerialisableRegisteredPlayer,
+};
+use crate::shared::structs::{
+  DisconnectReason, SerialisableChatMessage, SerialisableEliminationCause, SerialisableInputFrame,
+  SerialisablePlayerSnapshot, SerialisableReadyRequest, SerialisableRegistrationRequest, SerialisableTailEvent,
+  SerialisableUnregistrationRequest,
+};
 use bevy::app::{App, Plugin};
 use bevy::prelude::{Component, Message};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::time::Duration;
 
 pub struct NetworkingMessagesPlugin;
 
@@ -15,7 +22,13 @@ impl Plugin for NetworkingMessagesPlugin {
       .add_message::<InboundClientMessage>()
       .add_message::<OutboundClientMessage>()
       .add_message::<InboundServerMessage>()
-      .add_message::<OutboundServerMessage>();
+      .add_message::<OutboundServerMessage>()
+      .init_resource::<MessageRegistry>();
+    app
+      .world_mut()
+      .resource_mut::<MessageRegistry>()
+      .register::<ClientMessage>()
+      .register::<InboundServerMessage>();
   }
 }
 
@@ -47,7 +60,30 @@ impl PlayerStateUpdateMessage {
 pub enum InboundClientMessage {
   RegistrationRequest(SerialisableRegistrationRequest, ClientId),
   UnregistrationRequest(SerialisableUnregistrationRequest, ClientId),
-  Input(SerialisableInput, ClientId),
+  Input(Vec<SerialisableInputFrame>, ClientId),
+  Reconnect(ReconnectToken, ClientId),
+  Pong(u32, ClientId),
+  TimeSyncRequest(Duration, ClientId),
+  Chat(SerialisableChatMessage, ClientId),
+  ReadyRequest(SerialisableReadyRequest, ClientId),
+  SetSpectating(bool, ClientId),
+}
+
+impl InboundClientMessage {
+  /// Returns the ID of the client that sent this message.
+  pub fn client_id(&self) -> ClientId {
+    match self {
+      InboundClientMessage::RegistrationRequest(_, client_id)
+      | InboundClientMessage::UnregistrationRequest(_, client_id)
+      | InboundClientMessage::Input(_, client_id)
+      | InboundClientMessage::Reconnect(_, client_id)
+      | InboundClientMessage::Pong(_, client_id)
+      | InboundClientMessage::TimeSyncRequest(_, client_id)
+      | InboundClientMessage::Chat(_, client_id)
+      | InboundClientMessage::ReadyRequest(_, client_id)
+      | InboundClientMessage::SetSpectating(_, client_id) => *client_id,
+    }
+  }
 }
 
 impl Debug for InboundClientMessage {
@@ -67,8 +103,43 @@ impl Debug for InboundClientMessage {
           message.player_id, client_id
         )
       }
-      InboundClientMessage::Input(action, client_id) => {
-        write!(f, "ClientMessage::{:?} for client with ID {}", action, client_id)
+      InboundClientMessage::Input(frames, client_id) => {
+        write!(
+          f,
+          "ClientMessage::Input with {} frame(s) for client with ID {}",
+          frames.len(),
+          client_id
+        )
+      }
+      InboundClientMessage::Reconnect(_, client_id) => {
+        write!(f, "ClientMessage::Reconnect for client with ID {}", client_id)
+      }
+      InboundClientMessage::Pong(sequence, client_id) => {
+        write!(f, "ClientMessage::Pong {} for client with ID {}", sequence, client_id)
+      }
+      InboundClientMessage::TimeSyncRequest(client_time, client_id) => {
+        write!(
+          f,
+          "ClientMessage::TimeSyncRequest at {:?} for client with ID {}",
+          client_time, client_id
+        )
+      }
+      InboundClientMessage::Chat(_, client_id) => {
+        write!(f, "ClientMessage::Chat for client with ID {}", client_id)
+      }
+      InboundClientMessage::ReadyRequest(message, client_id) => {
+        write!(
+          f,
+          "ClientMessage::ReadyRequest for {} (ready: {}) with ID {}",
+          message.player_id, message.is_ready, client_id
+        )
+      }
+      InboundClientMessage::SetSpectating(is_spectating, client_id) => {
+        write!(
+          f,
+          "ClientMessage::SetSpectating {} for client with ID {}",
+          is_spectating, client_id
+        )
       }
     }
   }
@@ -100,6 +171,9 @@ pub enum OutboundServerMessage {
     channel: ChannelType,
     payload: Vec<u8>,
   },
+  /// Disconnect a specific client. Transports that can't close a single connection stop exchanging messages with the
+  /// client instead.
+  Disconnect { client_id: ClientId },
   /// Disconnect all connected clients.
   DisconnectAll,
 }
@@ -110,7 +184,9 @@ pub enum InboundServerMessage {
   /// Sent by the server to all clients (except the one that just connected) when a new client has connected.
   ClientConnected { client_id: ClientId },
   /// Sent by the server to all clients (except the one that just disconnected) when a client has disconnected.
-  ClientDisconnected { client_id: ClientId },
+  /// `is_forced` is `true` if the server has disconnected the client, e.g. for sending too many malformed packets or
+  /// because it was kicked, and `false` if the client left or lost its connection.
+  ClientDisconnected { client_id: ClientId, is_forced: bool },
   /// Sent to a client when they have successfully initialised their connection to the server. Sent by the server in
   /// response to a [`InboundServerMessage::ClientConnected`] to the client that just connected.
   ClientInitialised {
@@ -119,6 +195,8 @@ pub enum InboundServerMessage {
     current_state: String,
     registered_players: Vec<SerialisableRegisteredPlayer>,
     winner_info: Option<u8>,
+    /// The token the client can use to reclaim its players if its connection drops.
+    reconnect_token: ReconnectToken,
   },
   /// Indicates that the app state has changed on the server.
   StateChanged { new_state: String, winner_info: Option<u8> },
@@ -129,10 +207,60 @@ pub enum InboundServerMessage {
     control_scheme_id: u8,
     name: String,
   },
+  /// Sent to a client that reclaimed its reserved slot with a [`ReconnectToken`]. Contains the players it had
+  /// registered before its connection dropped, now associated with its new [`ClientId`].
+  ReconnectAccepted {
+    registered_players: Vec<SerialisableRegisteredPlayer>,
+  },
+  /// Informs a client which client takes over as host if the host leaves. The [`ReconnectToken`]s of all clients are
+  /// only sent to the successor itself, which needs them to let the other clients reclaim their players after the
+  /// migration. They are empty for every other client.
+  HostSuccessionChanged {
+    successor: Option<ClientId>,
+    reconnect_tokens: Vec<(ClientId, ReconnectToken)>,
+  },
+  /// Informs clients that a player in the lobby has become ready or is no longer ready for the round to start.
+  PlayerReadyChanged { player_id: u8, is_ready: bool },
+  /// Informs clients that all players are ready and the round starts in the given number of seconds, or that the
+  /// countdown has been cancelled (`None`) because a player is no longer ready.
+  ReadyCountdownChanged { seconds: Option<f32> },
   /// Informs clients that a player has unregistered from the lobby.
   PlayerUnregistered { client_id: ClientId, player_id: u8 },
+  /// Informs clients which clients are currently spectating, in the order in which they started spectating. Sent to all
+  /// clients whenever the list changes and to each client that connects.
+  SpectatorsChanged { spectators: Vec<ClientId> },
   /// Contains authoritative player state updates in a vec of (player_id, x, y, rotation).
   UpdatePlayerStates { states: Vec<(u8, f32, f32, f32)> },
+  /// Contains all authoritative tail changes (samples and gap boundaries) since the last update, in the order in which
+  /// they occurred on the server.
+  UpdateTails { events: Vec<SerialisableTailEvent> },
+  /// Sent to a client that connected while a round is in progress, directly after
+  /// [`InboundServerMessage::ClientInitialised`]. Contains everything needed to render the round. Any
+  /// [`InboundServerMessage::UpdateTails`] received before this message is already included in it.
+  WorldSnapshot { players: Vec<SerialisablePlayerSnapshot> },
+  /// Informs clients that a player has been eliminated, why, and where (x, y) it happened.
+  PlayerEliminated {
+    player_id: u8,
+    cause: SerialisableEliminationCause,
+    x: f32,
+    y: f32,
+  },
+  /// Sent periodically on the [`ChannelType::Unreliable`] channel to measure the connection quality of each client,
+  /// which answers with a [`crate::prelude::ClientMessage::Pong`]. Contains the latest [`PeerStats`] of all clients.
+  Ping { sequence: u32, peers: Vec<PeerStats> },
+  /// The answer to a [`crate::prelude::ClientMessage::TimeSyncRequest`], containing the time at which the client sent
+  /// it and the time at which the server answered, see [`crate::prelude::ClockSyncPlugin`].
+  TimeSyncResponse {
+    client_time: Duration,
+    server_time: Duration,
+  },
+  /// Acknowledges that the server has applied all input frames of the receiving client up to and including the given
+  /// sequence number, so that the client stops resending them.
+  InputAck { sequence: u32 },
+  /// Informs a client that the server is about to disconnect it, and why.
+  Kicked { reason: DisconnectReason },
+  /// A chat message from a player, broadcast to all clients on the [`ChannelType::ReliableOrdered`] channel.
+  Chat { sender_name: String, text: String },
   /// Informs the clients that the server is about to shut down. Gives clients time to prepare before being
   /// disconnected.
   ShutdownServer,
@@ -1,9 +1,23 @@
+mod batching;
+mod clock_sync;
 mod codec;
+mod link_conditioner;
+mod loopback;
 mod messages;
+mod network_stats;
+mod registry;
 mod resources;
 mod structs;
+mod transport;
 
 pub use crate::shared::codec::*;
+pub use batching::*;
+pub use clock_sync::*;
+pub use link_conditioner::*;
+pub use loopback::*;
 pub use messages::*;
+pub use network_stats::*;
+pub use registry::*;
 pub use resources::*;
 pub use structs::*;
+pub use transport::*;
@@ -0,0 +1,344 @@
+use crate::prelude::{
+  ClientId, ClientMessage, ClientNetworkingActive, InboundClientMessage, InboundServerMessage, Lobby,
+  OutboundClientMessage, OutboundServerMessage, PlayerId, ServerNetworkingActive,
+};
+use bevy::app::{App, Plugin, Update};
+use bevy::prelude::{
+  IntoScheduleConfigs, MessageReader, MessageWriter, Real, Res, ResMut, Resource, Time, Timer, TimerMode, not,
+  resource_exists,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// A plugin that measures the round-trip time, jitter and packet loss between the server and each client by exchanging
+/// pings and pongs on the [`crate::prelude::ChannelType::Unreliable`] channel. The results are available in the
+/// [`NetworkStats`] resource on both the server and the clients.
+pub struct NetworkStatsPlugin;
+
+impl Plugin for NetworkStatsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<NetworkStats>()
+      .init_resource::<PingTracker>()
+      .add_systems(
+        Update,
+        (handle_pong_system, send_ping_system)
+          .chain()
+          .run_if(resource_exists::<ServerNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        respond_to_ping_system.run_if(resource_exists::<ClientNetworkingActive>),
+      )
+      .add_systems(
+        Update,
+        clear_network_stats_system
+          .run_if(not(resource_exists::<ServerNetworkingActive>))
+          .run_if(not(resource_exists::<ClientNetworkingActive>)),
+      );
+  }
+}
+
+const PING_INTERVAL_SECONDS: f32 = 1.;
+/// Pings that haven't been answered within this time are considered lost.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// The number of most recent pings the packet loss is calculated over.
+const PACKET_LOSS_WINDOW: usize = 20;
+
+/// The connection quality between the server and a client, as measured by the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeerStats {
+  pub client_id: ClientId,
+  /// The raw [`PlayerId`]s of the players registered by the client.
+  pub player_ids: Vec<u8>,
+  /// The smoothed round-trip time.
+  pub round_trip_time: Duration,
+  /// The mean deviation between consecutive round-trip time samples.
+  pub jitter: Duration,
+  /// The fraction of recent pings that weren't answered in time, between `0.0` and `1.0`.
+  pub packet_loss: f32,
+}
+
+/// A resource containing the latest [`PeerStats`] of each client. On the server, it is updated with every pong; on a
+/// client, it is replaced with the stats the server sends along with every ping.
+#[derive(Resource, Debug, Default)]
+pub struct NetworkStats {
+  peers: Vec<PeerStats>,
+}
+
+impl NetworkStats {
+  pub fn peers(&self) -> &[PeerStats] {
+    &self.peers
+  }
+
+  pub fn for_client(&self, client_id: &ClientId) -> Option<&PeerStats> {
+    self.peers.iter().find(|peer| peer.client_id == *client_id)
+  }
+
+  /// Returns the stats of the client that registered the player, if the player was registered by a client.
+  pub fn for_player(&self, player_id: PlayerId) -> Option<&PeerStats> {
+    self.peers.iter().find(|peer| peer.player_ids.contains(&player_id.0))
+  }
+
+  pub fn clear(&mut self) {
+    self.peers.clear();
+  }
+}
+
+/// The state the server needs to estimate the connection quality of a single client.
+#[derive(Debug, Default)]
+struct PeerEstimator {
+  /// The time at which each unanswered ping was sent, by sequence number.
+  pending: HashMap<u32, Duration>,
+  round_trip_time: Option<Duration>,
+  last_sample: Option<Duration>,
+  jitter: Duration,
+  /// Whether each of the most recent pings was answered in time, oldest first.
+  outcomes: VecDeque<bool>,
+}
+
+impl PeerEstimator {
+  /// Records the round-trip time of an answered ping. Uses the smoothing factors of TCP (RFC 6298) for the round-trip
+  /// time and RTP (RFC 3550) for the jitter. Ignores pongs for unknown or already expired pings.
+  fn record_pong(&mut self, sequence: u32, now: Duration) {
+    let Some(sent_at) = self.pending.remove(&sequence) else {
+      return;
+    };
+    let sample = now.saturating_sub(sent_at);
+    self.round_trip_time = Some(match self.round_trip_time {
+      Some(round_trip_time) => (round_trip_time * 7 + sample) / 8,
+      None => sample,
+    });
+    if let Some(last_sample) = self.last_sample {
+      let deviation = sample.abs_diff(last_sample);
+      self.jitter = (self.jitter * 15 + deviation) / 16;
+    }
+    self.last_sample = Some(sample);
+    self.record_outcome(true);
+  }
+
+  /// Forgets pings that haven't been answered in time and records them as lost.
+  fn expire_pending(&mut self, now: Duration) {
+    let expired: Vec<u32> = self
+      .pending
+      .iter()
+      .filter(|(_, sent_at)| now.saturating_sub(**sent_at) > PING_TIMEOUT)
+      .map(|(sequence, _)| *sequence)
+      .collect();
+    for sequence in expired {
+      self.pending.remove(&sequence);
+      self.record_outcome(false);
+    }
+  }
+
+  fn record_outcome(&mut self, is_answered: bool) {
+    if self.outcomes.len() == PACKET_LOSS_WINDOW {
+      self.outcomes.pop_front();
+    }
+    self.outcomes.push_back(is_answered);
+  }
+
+  fn packet_loss(&self) -> f32 {
+    if self.outcomes.is_empty() {
+      return 0.;
+    }
+    let lost = self.outcomes.iter().filter(|is_answered| !**is_answered).count();
+    lost as f32 / self.outcomes.len() as f32
+  }
+
+  /// Returns the stats of the client, or `None` if no ping has been answered yet.
+  fn to_peer_stats(&self, client_id: ClientId, player_ids: Vec<u8>) -> Option<PeerStats> {
+    Some(PeerStats {
+      client_id,
+      player_ids,
+      round_trip_time: self.round_trip_time?,
+      jitter: self.jitter,
+      packet_loss: self.packet_loss(),
+    })
+  }
+}
+
+/// A server-side resource that keeps track of the pings sent to each client.
+#[derive(Resource)]
+struct PingTracker {
+  timer: Timer,
+  next_sequence: u32,
+  peers: HashMap<ClientId, PeerEstimator>,
+}
+
+impl Default for PingTracker {
+  fn default() -> Self {
+    Self {
+      timer: Timer::from_seconds(PING_INTERVAL_SECONDS, TimerMode::Repeating),
+      next_sequence: 0,
+      peers: HashMap::new(),
+    }
+  }
+}
+
+/// Records the pongs sent by clients in response to a ping.
+fn handle_pong_system(
+  mut messages: MessageReader<InboundClientMessage>,
+  time: Res<Time<Real>>,
+  mut ping_tracker: ResMut<PingTracker>,
+) {
+  for message in messages.read() {
+    if let InboundClientMessage::Pong(sequence, client_id) = message
+      && let Some(peer) = ping_tracker.peers.get_mut(client_id)
+    {
+      peer.record_pong(*sequence, time.elapsed());
+    }
+  }
+}
+
+/// Periodically updates the [`NetworkStats`] and broadcasts a ping, which also contains the latest [`NetworkStats`], to
+/// all connected clients.
+fn send_ping_system(
+  time: Res<Time<Real>>,
+  lobby: Res<Lobby>,
+  mut ping_tracker: ResMut<PingTracker>,
+  mut network_stats: ResMut<NetworkStats>,
+  mut outbound_server_message: MessageWriter<OutboundServerMessage>,
+) {
+  ping_tracker.timer.tick(time.delta());
+  if !ping_tracker.timer.just_finished() {
+    return;
+  }
+
+  let now = time.elapsed();
+  let sequence = ping_tracker.next_sequence;
+  ping_tracker.next_sequence = sequence.wrapping_add(1);
+  ping_tracker
+    .peers
+    .retain(|client_id, _| lobby.connected.contains(client_id));
+  let mut peers = Vec::new();
+  for client_id in &lobby.connected {
+    let peer = ping_tracker.peers.entry(*client_id).or_default();
+    peer.expire_pending(now);
+    let player_ids = lobby
+      .get_registered_players_cloned(client_id)
+      .into_iter()
+      .map(|player_id| player_id.0)
+      .collect();
+    if let Some(peer_stats) = peer.to_peer_stats(*client_id, player_ids) {
+      peers.push(peer_stats);
+    }
+    peer.pending.insert(sequence, now);
+  }
+  network_stats.peers = peers.clone();
+
+  outbound_server_message.write(OutboundServerMessage::broadcast(&InboundServerMessage::Ping {
+    sequence,
+    peers,
+  }));
+}
+
+/// Answers each ping from the server with a pong and stores the stats the server sent along with it.
+fn respond_to_ping_system(
+  mut messages: MessageReader<InboundServerMessage>,
+  mut network_stats: ResMut<NetworkStats>,
+  mut outbound_client_message: MessageWriter<OutboundClientMessage>,
+) {
+  for message in messages.read() {
+    if let InboundServerMessage::Ping { sequence, peers } = message {
+      network_stats.peers = peers.clone();
+      outbound_client_message.write(OutboundClientMessage::send(&ClientMessage::Pong(*sequence)));
+    }
+  }
+}
+
+/// Forgets the stats of the last session once networking is no longer active.
+fn clear_network_stats_system(mut network_stats: ResMut<NetworkStats>) {
+  if !network_stats.peers.is_empty() {
+    network_stats.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn millis(value: u64) -> Duration {
+    Duration::from_millis(value)
+  }
+
+  #[test]
+  fn record_pong_smooths_round_trip_time_and_measures_jitter() {
+    let mut peer = PeerEstimator::default();
+    peer.pending.insert(0, millis(0));
+    peer.pending.insert(1, millis(1000));
+
+    peer.record_pong(0, millis(80));
+    peer.record_pong(1, millis(1160));
+
+    let stats = peer
+      .to_peer_stats(ClientId::from_u64(1), vec![2])
+      .expect("Expected stats after pongs");
+    assert_eq!(stats.round_trip_time, millis(90));
+    assert_eq!(stats.jitter, millis(5));
+    assert_eq!(stats.packet_loss, 0.);
+    assert_eq!(stats.player_ids, vec![2]);
+  }
+
+  #[test]
+  fn record_pong_ignores_unknown_sequence() {
+    let mut peer = PeerEstimator::default();
+
+    peer.record_pong(7, millis(100));
+
+    assert!(peer.to_peer_stats(ClientId::from_u64(1), Vec::new()).is_none());
+    assert!(peer.outcomes.is_empty());
+  }
+
+  #[test]
+  fn expire_pending_counts_unanswered_pings_as_lost() {
+    let mut peer = PeerEstimator::default();
+    peer.pending.insert(0, millis(0));
+    peer.pending.insert(1, millis(1000));
+    peer.pending.insert(2, millis(2000));
+    peer.record_pong(2, millis(2050));
+
+    peer.expire_pending(millis(2500));
+    assert_eq!(peer.pending.len(), 1);
+    assert_eq!(peer.packet_loss(), 0.5);
+
+    // A late pong for an expired ping is ignored
+    peer.record_pong(0, millis(2600));
+    assert_eq!(peer.packet_loss(), 0.5);
+  }
+
+  #[test]
+  fn packet_loss_only_considers_most_recent_pings() {
+    let mut peer = PeerEstimator::default();
+    for _ in 0..PACKET_LOSS_WINDOW {
+      peer.record_outcome(false);
+    }
+    for _ in 0..PACKET_LOSS_WINDOW / 2 {
+      peer.record_outcome(true);
+    }
+
+    assert_eq!(peer.packet_loss(), 0.5);
+  }
+
+  #[test]
+  fn network_stats_finds_stats_by_player() {
+    let client_id = ClientId::from_u64(3);
+    let network_stats = NetworkStats {
+      peers: vec![PeerStats {
+        client_id,
+        player_ids: vec![1, 4],
+        round_trip_time: millis(40),
+        jitter: millis(2),
+        packet_loss: 0.,
+      }],
+    };
+
+    assert_eq!(
+      network_stats.for_player(PlayerId(4)).map(|peer| peer.client_id),
+      Some(client_id)
+    );
+    assert!(network_stats.for_player(PlayerId(2)).is_none());
+    assert!(network_stats.for_client(&client_id).is_some());
+  }
+}
@@ -0,0 +1,341 @@
+use crate::prelude::{
+  CHANNELS, ChannelType, ClientId, ClientMessage, InboundServerMessage, OutboundClientMessage, OutboundServerMessage,
+  encode_to_bytes,
+};
+use bevy::prelude::Resource;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::any::type_name;
+use std::fmt::{Display, Formatter};
+
+/// The delivery guarantees of a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelSemantics {
+  /// Whether every payload is delivered.
+  pub reliable: bool,
+  /// Whether payloads are delivered in the order in which they were sent.
+  pub ordered: bool,
+}
+
+impl ChannelSemantics {
+  /// Returns whether a channel with these semantics provides at least the guarantees of the given semantics.
+  pub fn satisfies(self, required: ChannelSemantics) -> bool {
+    (self.reliable || !required.reliable) && (self.ordered || !required.ordered)
+  }
+}
+
+impl Display for ChannelSemantics {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let reliability = if self.reliable { "reliable" } else { "unreliable" };
+    let order = if self.ordered { "ordered" } else { "unordered" };
+    write!(f, "{reliability}, {order}")
+  }
+}
+
+impl ChannelType {
+  /// Returns the guarantees with which a transport must deliver payloads sent on this channel.
+  pub fn semantics(self) -> ChannelSemantics {
+    match self {
+      ChannelType::Unreliable => ChannelSemantics {
+        reliable: false,
+        ordered: false,
+      },
+      ChannelType::ReliableUnordered => ChannelSemantics {
+        reliable: true,
+        ordered: false,
+      },
+      ChannelType::ReliableOrdered => ChannelSemantics {
+        reliable: true,
+        ordered: true,
+      },
+    }
+  }
+
+  /// Returns the index of this channel, i.e. its position in [`CHANNELS`]. Transports that address their channels by
+  /// index must configure them in this order, see [`MessageRegistry::validate`].
+  pub fn index(self) -> usize {
+    CHANNELS
+      .iter()
+      .position(|&channel| channel == self)
+      .expect("All channel types are included in CHANNELS")
+  }
+}
+
+/// A message type that is sent over the network. Each message declares the channel it is sent on, so that call sites
+/// don't have to choose one. Send messages with the typed helpers such as [`OutboundServerMessage::broadcast`], which
+/// take care of encoding them.
+pub trait NetworkMessage: Serialize + DeserializeOwned + 'static {
+  /// All channels on which messages of this type are sent.
+  const CHANNELS: &'static [ChannelType];
+
+  /// Returns the channel on which this message is sent. Must be one of [`NetworkMessage::CHANNELS`].
+  fn channel(&self) -> ChannelType;
+}
+
+/// A [`NetworkMessage`] that the server sends to clients.
+pub trait ServerToClientMessage: NetworkMessage {}
+
+/// A [`NetworkMessage`] that a client sends to the server.
+pub trait ClientToServerMessage: NetworkMessage {}
+
+impl NetworkMessage for InboundServerMessage {
+  const CHANNELS: &'static [ChannelType] = &[ChannelType::Unreliable, ChannelType::ReliableOrdered];
+
+  fn channel(&self) -> ChannelType {
+    match self {
+      InboundServerMessage::UpdatePlayerStates { .. }
+      | InboundServerMessage::Ping { .. }
+      | InboundServerMessage::TimeSyncResponse { .. }
+      | InboundServerMessage::InputAck { .. } => ChannelType::Unreliable,
+      InboundServerMessage::ClientConnected { .. }
+      | InboundServerMessage::ClientDisconnected { .. }
+      | InboundServerMessage::ClientInitialised { .. }
+      | InboundServerMessage::StateChanged { .. }
+      | InboundServerMessage::PlayerRegistered { .. }
+      | InboundServerMessage::ReconnectAccepted { .. }
+      | InboundServerMessage::HostSuccessionChanged { .. }
+      | InboundServerMessage::PlayerReadyChanged { .. }
+      | InboundServerMessage::ReadyCountdownChanged { .. }
+      | InboundServerMessage::PlayerUnregistered { .. }
+      | InboundServerMessage::SpectatorsChanged { .. }
+      | InboundServerMessage::UpdateTails { .. }
+      | InboundServerMessage::WorldSnapshot { .. }
+      | InboundServerMessage::PlayerEliminated { .. }
+      | InboundServerMessage::Kicked { .. }
+      | InboundServerMessage::Chat { .. }
+      | InboundServerMessage::ShutdownServer => ChannelType::ReliableOrdered,
+    }
+  }
+}
+
+impl ServerToClientMessage for InboundServerMessage {}
+
+impl NetworkMessage for ClientMessage {
+  const CHANNELS: &'static [ChannelType] = &[ChannelType::Unreliable, ChannelType::ReliableOrdered];
+
+  fn channel(&self) -> ChannelType {
+    match self {
+      ClientMessage::Input(_) | ClientMessage::Pong(_) | ClientMessage::TimeSyncRequest(_) => ChannelType::Unreliable,
+      ClientMessage::RegistrationRequest(_)
+      | ClientMessage::UnregistrationRequest(_)
+      | ClientMessage::Reconnect(_)
+      | ClientMessage::Chat(_)
+      | ClientMessage::ReadyRequest(_)
+      | ClientMessage::SetSpectating(_) => ChannelType::ReliableOrdered,
+    }
+  }
+}
+
+impl ClientToServerMessage for ClientMessage {}
+
+/// Encodes a message and returns it together with the channel it is sent on. Panics if the message can't be encoded,
+/// which can only happen if its type uses serde features that postcard doesn't support.
+fn encode<T: NetworkMessage>(message: &T) -> (ChannelType, Vec<u8>) {
+  let channel = message.channel();
+  debug_assert!(
+    T::CHANNELS.contains(&channel),
+    "[{}] is sent on [{channel:?}], which isn't among its declared channels",
+    type_name::<T>()
+  );
+  let payload =
+    encode_to_bytes(message).unwrap_or_else(|error| panic!("Failed to encode [{}]: {error}", type_name::<T>()));
+  (channel, payload)
+}
+
+impl OutboundServerMessage {
+  /// Creates a request to send the given message to a specific client, on the channel the message declares.
+  pub fn send<T: ServerToClientMessage>(client_id: ClientId, message: &T) -> Self {
+    let (channel, payload) = encode(message);
+    OutboundServerMessage::Send {
+      client_id,
+      channel,
+      payload,
+    }
+  }
+
+  /// Creates a request to send the given message to all connected clients, on the channel the message declares.
+  pub fn broadcast<T: ServerToClientMessage>(message: &T) -> Self {
+    let (channel, payload) = encode(message);
+    OutboundServerMessage::Broadcast { channel, payload }
+  }
+
+  /// Creates a request to send the given message to all connected clients except the given one, on the channel the
+  /// message declares.
+  pub fn broadcast_except<T: ServerToClientMessage>(except_client_id: ClientId, message: &T) -> Self {
+    let (channel, payload) = encode(message);
+    OutboundServerMessage::BroadcastExcept {
+      except_client_id,
+      channel,
+      payload,
+    }
+  }
+}
+
+impl OutboundClientMessage {
+  /// Creates a request to send the given message to the server, on the channel the message declares.
+  pub fn send<T: ClientToServerMessage>(message: &T) -> Self {
+    let (channel, payload) = encode(message);
+    OutboundClientMessage::Send { channel, payload }
+  }
+}
+
+/// A message type registered with the [`MessageRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RegisteredMessage {
+  name: &'static str,
+  channels: &'static [ChannelType],
+}
+
+/// A resource with all [`NetworkMessage`] types an application sends, and the channels they are sent on. Transports
+/// check their channel configuration against it at startup, see [`MessageRegistry::validate`].
+#[derive(Resource, Debug, Default, Clone)]
+pub struct MessageRegistry {
+  messages: Vec<RegisteredMessage>,
+}
+
+impl MessageRegistry {
+  /// Registers a message type. Registering the same type more than once has no effect.
+  pub fn register<T: NetworkMessage>(&mut self) -> &mut Self {
+    let message = RegisteredMessage {
+      name: type_name::<T>(),
+      channels: T::CHANNELS,
+    };
+    if !self.messages.contains(&message) {
+      self.messages.push(message);
+    }
+    self
+  }
+
+  /// Checks that a transport with the given channels, listed by [`ChannelType::index`], delivers every registered
+  /// message with at least the guarantees of the channel the message declares. Returns a description of every
+  /// mismatch otherwise.
+  pub fn validate(&self, transport_channels: &[ChannelSemantics]) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for message in &self.messages {
+      for &channel in message.channels {
+        let required = channel.semantics();
+        match transport_channels.get(channel.index()) {
+          Some(&provided) if provided.satisfies(required) => {}
+          Some(provided) => errors.push(format!(
+            "[{}] is sent on [{channel:?}] ({required}), but channel {} of the transport is {provided}",
+            message.name,
+            channel.index()
+          )),
+          None => errors.push(format!(
+            "[{}] is sent on [{channel:?}] ({required}), but the transport has no channel {}",
+            message.name,
+            channel.index()
+          )),
+        }
+      }
+    }
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors.join("; "))
+    }
+  }
+}
+
+/// Panics unless a transport with the given channels, listed by [`ChannelType::index`], is compatible with the
+/// [`MessageRegistry`]. Run by transports at startup, so that a misconfigured transport is noticed straight away rather
+/// than through messages that are lost or arrive out of order.
+pub fn assert_transport_channels(registry: &MessageRegistry, transport: &str, channels: &[ChannelSemantics]) {
+  if let Err(error) = registry.validate(channels) {
+    panic!("The channels of [{transport}] don't match the message registry: {error}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::{SerialisableChatMessage, decode_from_bytes};
+
+  fn registry() -> MessageRegistry {
+    let mut registry = MessageRegistry::default();
+    registry.register::<InboundServerMessage>().register::<ClientMessage>();
+    registry
+  }
+
+  #[test]
+  fn channel_index_follows_order_of_channels() {
+    for (index, channel) in CHANNELS.into_iter().enumerate() {
+      assert_eq!(channel.index(), index);
+    }
+  }
+
+  #[test]
+  fn channel_semantics_satisfies_weaker_or_equal_guarantees_only() {
+    let reliable_ordered = ChannelType::ReliableOrdered.semantics();
+    let reliable_unordered = ChannelType::ReliableUnordered.semantics();
+    let unreliable = ChannelType::Unreliable.semantics();
+
+    assert!(reliable_ordered.satisfies(reliable_unordered));
+    assert!(reliable_ordered.satisfies(unreliable));
+    assert!(reliable_unordered.satisfies(unreliable));
+    assert!(!reliable_unordered.satisfies(reliable_ordered));
+    assert!(!unreliable.satisfies(reliable_unordered));
+  }
+
+  #[test]
+  fn validate_accepts_transport_that_provides_declared_channels() {
+    let transport_channels: Vec<_> = CHANNELS.into_iter().map(ChannelType::semantics).collect();
+
+    assert_eq!(registry().validate(&transport_channels), Ok(()));
+  }
+
+  #[test]
+  fn validate_rejects_transport_whose_channel_order_differs() {
+    let transport_channels = [
+      ChannelType::Unreliable.semantics(),
+      ChannelType::ReliableOrdered.semantics(),
+      ChannelType::ReliableUnordered.semantics(),
+    ];
+
+    let error = registry()
+      .validate(&transport_channels)
+      .expect_err("Expected channels in the wrong order to be rejected");
+    assert!(
+      error.contains("[ReliableOrdered] (reliable, ordered), but channel 2 of the transport is reliable, unordered"),
+      "Unexpected error: {error}"
+    );
+  }
+
+  #[test]
+  fn validate_rejects_transport_with_missing_channels() {
+    let error = registry()
+      .validate(&[ChannelType::Unreliable.semantics()])
+      .expect_err("Expected missing channels to be rejected");
+    assert!(error.contains("the transport has no channel 2"));
+  }
+
+  #[test]
+  fn register_ignores_duplicate_registrations() {
+    let mut registry = registry();
+    registry.register::<ClientMessage>();
+
+    assert_eq!(registry.messages.len(), 2);
+  }
+
+  #[test]
+  fn outbound_messages_use_channel_declared_by_message() {
+    let ping = InboundServerMessage::Ping {
+      sequence: 3,
+      peers: Vec::new(),
+    };
+    let OutboundServerMessage::Broadcast { channel, payload } = OutboundServerMessage::broadcast(&ping) else {
+      panic!("Expected broadcast");
+    };
+    assert_eq!(channel, ChannelType::Unreliable);
+    assert!(matches!(
+      decode_from_bytes::<InboundServerMessage>(&payload),
+      Ok(InboundServerMessage::Ping { sequence: 3, .. })
+    ));
+
+    let chat = ClientMessage::Chat(SerialisableChatMessage { text: "Hi".to_string() });
+    let OutboundClientMessage::Send { channel, .. } = OutboundClientMessage::send(&chat) else {
+      panic!("Expected send");
+    };
+    assert_eq!(channel, ChannelType::ReliableOrdered);
+  }
+}
@@ -1,9 +1,12 @@
-use crate::prelude::{ClientId, PlayerId, PlayerInLobby};
+use crate::prelude::{
+  ChannelType, ClientId, LinkConditioner, MalformedPacket, NetworkErrorEvent, PlayerId, PlayerInLobby, ReconnectToken,
+};
 use bevy::app::{App, Plugin};
-use bevy::log::debug;
+use bevy::log::{debug, info, warn};
 use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::Display;
 use url::Url;
 
 /// A plugin that registers and initialises shared resources used in either the client or server, or both.
@@ -11,12 +14,24 @@ pub struct NetworkingResourcesPlugin;
 
 impl Plugin for NetworkingResourcesPlugin {
   fn build(&self, app: &mut App) {
-    app.init_resource::<Lobby>().init_resource::<SignallingServerUrl>();
+    // Apps that have been launched with a signalling server URL resolve it themselves before adding this plugin
+    if !app.world().contains_resource::<SignallingServerUrl>() {
+      app.insert_resource(SignallingServerUrl::from_runtime_config(None));
+    }
+    app
+      .init_resource::<Lobby>()
+      .init_resource::<MalformedPackets>()
+      .insert_resource(LinkConditioner::from_env())
+      .register_type::<LinkConditioner>();
   }
 }
 
 const DEFAULT_SIGNALLING_SERVER_URL: &str = "ws://localhost:3536";
 const SIGNALLING_SERVER_URL_ENV_VAR: &str = "SIGNALLING_SERVER_URL";
+#[cfg(any(target_arch = "wasm32", test))]
+const SIGNALLING_SERVER_URL_QUERY_KEY: &str = "signalling_server_url";
+#[cfg(not(target_arch = "wasm32"))]
+const CONFIG_FILE_PATH: &str = "mooplas.json";
 
 /// A resource that indicates the current network role of this application instance. Only relevant in online
 /// multiplayer mode.
@@ -54,10 +69,18 @@ pub struct ServerNetworkingActive;
 #[derive(Resource, Default)]
 pub struct ClientNetworkingActive;
 
-/// A resource containing the URL of the signalling server. Loaded from the environment by default.
+/// A resource containing the URL of the signalling server. Resolved at startup, see
+/// [`SignallingServerUrl::from_runtime_config`], and can be changed at runtime e.g. from the play online menu.
 #[derive(Resource, Debug, Clone, PartialEq, Eq)]
 pub struct SignallingServerUrl(String);
 
+/// The settings file that is read at startup on native platforms, if it exists.
+#[cfg(any(not(target_arch = "wasm32"), test))]
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+struct ConfigFile {
+  signalling_server_url: Option<String>,
+}
+
 impl Default for SignallingServerUrl {
   fn default() -> Self {
     Self::from_build_time_env(option_env!("SIGNALLING_SERVER_URL"))
@@ -65,6 +88,40 @@ impl Default for SignallingServerUrl {
 }
 
 impl SignallingServerUrl {
+  /// Resolves the URL of the signalling server, using the first valid one of:
+  /// 1. The given URL the app was launched with, e.g. the `--signalling-server-url <url>` command line argument
+  /// 2. `signalling_server_url` in the `mooplas.json` config file in the working directory (native only)
+  /// 3. The `signalling_server_url` query parameter of the page hosting the game (WASM only)
+  /// 4. `SIGNALLING_SERVER_URL` at build time, or `ws://localhost:3536` if it wasn't set
+  ///
+  /// Invalid URLs are logged and skipped.
+  pub fn from_runtime_config(launch_url: Option<String>) -> Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    let sources = [
+      ("command line argument", launch_url),
+      ("config file", url_from_config_file()),
+    ];
+    #[cfg(target_arch = "wasm32")]
+    let sources = [("launch options", launch_url), ("page query string", url_from_page())];
+    Self::resolve(sources, option_env!("SIGNALLING_SERVER_URL"))
+  }
+
+  fn resolve(sources: impl IntoIterator<Item = (&'static str, Option<String>)>, build_time_url: Option<&str>) -> Self {
+    for (source, url) in sources {
+      let Some(url) = url else {
+        continue;
+      };
+      match Self::try_new(url.trim()) {
+        Ok(signalling_server_url) => {
+          info!("Using signalling server [{}] from {}", signalling_server_url.0, source);
+          return signalling_server_url;
+        }
+        Err(error) => warn!("Ignoring signalling server URL [{}] from {}: {}", url, source, error),
+      }
+    }
+    Self::from_build_time_env(build_time_url)
+  }
+
   pub fn new(url: impl Into<String>) -> Self {
     Self::try_new(url).unwrap_or_else(|error| panic!("Invalid signalling server URL: {error}"))
   }
@@ -96,6 +153,45 @@ impl SignallingServerUrl {
   }
 }
 
+/// Returns the signalling server URL from the config file, if it exists and contains one.
+#[cfg(not(target_arch = "wasm32"))]
+fn url_from_config_file() -> Option<String> {
+  match std::fs::read_to_string(CONFIG_FILE_PATH) {
+    Ok(json) => url_from_config(&json)
+      .inspect_err(|error| warn!("Ignoring [{}]: {}", CONFIG_FILE_PATH, error))
+      .ok()
+      .flatten(),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+    Err(error) => {
+      warn!("Unable to read [{}]: {}", CONFIG_FILE_PATH, error);
+      None
+    }
+  }
+}
+
+#[cfg(any(not(target_arch = "wasm32"), test))]
+fn url_from_config(json: &str) -> Result<Option<String>, String> {
+  serde_json::from_str::<ConfigFile>(json)
+    .map(|config_file| config_file.signalling_server_url)
+    .map_err(|error| format!("Config file is not valid: {error}"))
+}
+
+/// Returns the `signalling_server_url` query parameter of the page hosting the game, if any.
+#[cfg(target_arch = "wasm32")]
+fn url_from_page() -> Option<String> {
+  let page_url = web_sys::window()?.location().href().ok()?;
+  url_from_query(&page_url)
+}
+
+#[cfg(any(target_arch = "wasm32", test))]
+fn url_from_query(page_url: &str) -> Option<String> {
+  Url::parse(page_url)
+    .ok()?
+    .query_pairs()
+    .find(|(key, _)| key == SIGNALLING_SERVER_URL_QUERY_KEY)
+    .map(|(_, value)| value.into_owned())
+}
+
 fn validate_signalling_server_base_url(url: &str) -> Result<(), String> {
   let parsed_url = Url::parse(url).map_err(|error| format!("URL is not valid: {error}"))?;
   if !matches!(parsed_url.scheme(), "ws" | "wss") {
@@ -118,6 +214,13 @@ fn validate_signalling_server_base_url(url: &str) -> Result<(), String> {
 pub struct Lobby {
   pub connected: Vec<ClientId>,
   pub registered: HashMap<ClientId, Vec<PlayerInLobby>>,
+  /// The [`ReconnectToken`] issued to each connected client.
+  reconnect_tokens: HashMap<ClientId, ReconnectToken>,
+  /// The registrations of disconnected clients that are held until they reconnect, keyed by their token. The
+  /// registrations themselves stay in `registered` under the previous [`ClientId`].
+  reserved: HashMap<ReconnectToken, ClientId>,
+  /// The clients that watch rounds without registering players, in the order in which they started spectating.
+  spectators: Vec<ClientId>,
 }
 
 impl Lobby {
@@ -197,10 +300,194 @@ impl Lobby {
     }
   }
 
+  /// Issues a new [`ReconnectToken`] for the given client ID, replacing any previous one.
+  pub fn issue_reconnect_token(&mut self, client_id: ClientId) -> ReconnectToken {
+    let token = ReconnectToken::new_random();
+    self.reconnect_tokens.insert(client_id, token);
+    token
+  }
+
+  /// Reserves the registrations of a disconnected client so that they can be reclaimed with its [`ReconnectToken`].
+  /// Returns the token if the client had registered any players, `None` otherwise.
+  pub fn reserve_slot(&mut self, client_id: ClientId) -> Option<ReconnectToken> {
+    let token = self.reconnect_tokens.remove(&client_id)?;
+    if !self
+      .registered
+      .get(&client_id)
+      .is_some_and(|players| !players.is_empty())
+    {
+      return None;
+    }
+    self.reserved.insert(token, client_id);
+    Some(token)
+  }
+
+  /// Moves the registrations reserved for the given [`ReconnectToken`] to the new client ID. Returns the reclaimed
+  /// registrations, or `None` if there is no reservation for the token (e.g. because it has expired).
+  pub fn reclaim_slot(&mut self, token: &ReconnectToken, client_id: ClientId) -> Option<Vec<PlayerInLobby>> {
+    let previous_client_id = self.reserved.remove(token)?;
+    let players = self.registered.remove(&previous_client_id).unwrap_or_default();
+    if !players.is_empty() {
+      self
+        .registered
+        .entry(client_id)
+        .or_default()
+        .extend(players.iter().copied());
+    }
+    Some(players)
+  }
+
+  /// Removes the reservation for the given [`ReconnectToken`] and returns the client ID that the registrations are
+  /// still stored under, so that they can be unregistered.
+  pub fn release_reserved_slot(&mut self, token: &ReconnectToken) -> Option<ClientId> {
+    self.reserved.remove(token)
+  }
+
+  /// Revokes the [`ReconnectToken`] of the given client, including any reservation held for it, so that its
+  /// registrations can no longer be reclaimed. Returns the revoked token, if any.
+  pub fn revoke_reconnect_token(&mut self, client_id: ClientId) -> Option<ReconnectToken> {
+    if let Some(token) = self.reconnect_tokens.remove(&client_id) {
+      return Some(token);
+    }
+    let token = self
+      .reserved
+      .iter()
+      .find_map(|(token, reserved_client_id)| (*reserved_client_id == client_id).then_some(*token))?;
+    self.reserved.remove(&token);
+    Some(token)
+  }
+
+  /// Reserves the registrations of a client that were carried over from a previous host, so that they can be reclaimed
+  /// with the [`ReconnectToken`] that the previous host had issued. Returns `false` if the client has no registrations.
+  pub fn reserve_migrated_slot(&mut self, client_id: ClientId, token: ReconnectToken) -> bool {
+    if !self
+      .registered
+      .get(&client_id)
+      .is_some_and(|players| !players.is_empty())
+    {
+      return false;
+    }
+    self.reserved.insert(token, client_id);
+    true
+  }
+
+  /// Returns every [`ReconnectToken`] that can currently be used to reclaim registrations, including those of
+  /// disconnected clients whose registrations are being held.
+  pub fn reconnect_tokens(&self) -> Vec<(ClientId, ReconnectToken)> {
+    self
+      .reconnect_tokens
+      .iter()
+      .map(|(client_id, token)| (*client_id, *token))
+      .chain(self.reserved.iter().map(|(token, client_id)| (*client_id, *token)))
+      .collect()
+  }
+
+  /// Returns the client that takes over as host if the host leaves, which is the longest-connected client.
+  pub fn successor(&self) -> Option<ClientId> {
+    self.connected.first().copied()
+  }
+
+  /// Marks the given client as a spectator (true) or as a player (false). Returns `true` if this changed anything.
+  pub fn set_spectating(&mut self, client_id: ClientId, is_spectating: bool) -> bool {
+    if self.is_spectating(&client_id) == is_spectating {
+      return false;
+    }
+    if is_spectating {
+      self.spectators.push(client_id);
+    } else {
+      self.spectators.retain(|spectator| *spectator != client_id);
+    }
+    true
+  }
+
+  /// Returns `true` if the given client is a spectator, `false` otherwise.
+  pub fn is_spectating(&self, client_id: &ClientId) -> bool {
+    self.spectators.contains(client_id)
+  }
+
+  /// Returns all spectating clients, in the order in which they started spectating.
+  pub fn spectators(&self) -> &[ClientId] {
+    &self.spectators
+  }
+
+  /// Returns `true` if the registrations of any disconnected client are being held, `false` otherwise.
+  pub fn has_reserved_slots(&self) -> bool {
+    !self.reserved.is_empty()
+  }
+
   /// Clears all connected clients and registrations. Use when exiting an online game, not when starting a new round.
   pub fn clear(&mut self) {
     self.connected.clear();
     self.registered.clear();
+    self.reconnect_tokens.clear();
+    self.reserved.clear();
+    self.spectators.clear();
+  }
+}
+
+/// The number of malformed packets after which a peer is dropped by default.
+pub const DEFAULT_MAX_MALFORMED_PACKETS: u32 = 5;
+
+/// The reason with which a client disconnects from a server that has sent it too many malformed packets.
+pub const TOO_MANY_MALFORMED_PACKETS: &str = "Received too many malformed packets from the server";
+
+/// A resource that counts the packets that couldn't be decoded per peer, so that a peer sending corrupt or incompatible
+/// packets is dropped rather than crashing the app. Insert it with a different [`MalformedPackets::max_failures`] to
+/// change how many malformed packets are tolerated.
+#[derive(Resource, Debug)]
+pub struct MalformedPackets {
+  /// The number of malformed packets after which a peer is dropped.
+  pub max_failures: u32,
+  /// The number of malformed packets per client, or from the server if `None`.
+  failures: HashMap<Option<ClientId>, u32>,
+}
+
+impl Default for MalformedPackets {
+  fn default() -> Self {
+    Self::new(DEFAULT_MAX_MALFORMED_PACKETS)
+  }
+}
+
+impl MalformedPackets {
+  pub fn new(max_failures: u32) -> Self {
+    Self {
+      max_failures,
+      failures: HashMap::new(),
+    }
+  }
+
+  /// Records a packet that couldn't be decoded, sent by the given client or by the server if `None`, and returns the
+  /// [`NetworkErrorEvent`] describing it.
+  pub fn record(
+    &mut self,
+    client_id: Option<ClientId>,
+    channel: ChannelType,
+    payload_size: usize,
+    error: impl Display,
+  ) -> NetworkErrorEvent {
+    let failures = self.failures.entry(client_id).or_default();
+    *failures = failures.saturating_add(1);
+    NetworkErrorEvent::MalformedPacket(MalformedPacket {
+      client_id,
+      channel,
+      payload_size,
+      failures: *failures,
+      error: error.to_string(),
+    })
+  }
+
+  /// Returns `true` if the given client, or the server if `None`, has sent too many malformed packets and must be
+  /// dropped, `false` otherwise.
+  pub fn is_over_limit(&self, client_id: Option<ClientId>) -> bool {
+    self
+      .failures
+      .get(&client_id)
+      .is_some_and(|failures| *failures >= self.max_failures)
+  }
+
+  /// Forgets the malformed packets of a client, or of the server if `None`, e.g. once it has disconnected.
+  pub fn forget(&mut self, client_id: Option<ClientId>) {
+    self.failures.remove(&client_id);
   }
 }
 
@@ -337,6 +624,110 @@ mod tests {
     assert!(lobby.validate_registration(&client_id, &player_id_2));
   }
 
+  #[test]
+  fn reserve_slot_and_reclaim_slot_move_registrations_to_new_client_id() {
+    let mut lobby = Lobby::default();
+    let previous_client_id = test_client_id(1);
+    let new_client_id = test_client_id(2);
+    let token = lobby.issue_reconnect_token(previous_client_id);
+    lobby.register_player(previous_client_id, PlayerId(3), 1);
+
+    assert_eq!(lobby.reserve_slot(previous_client_id), Some(token));
+    assert!(lobby.has_reserved_slots());
+    assert!(lobby.validate_registration(&previous_client_id, &PlayerId(3)));
+
+    let reclaimed = lobby
+      .reclaim_slot(&token, new_client_id)
+      .expect("Expected reserved slot");
+    assert_eq!(
+      reclaimed,
+      vec![PlayerInLobby {
+        player_id: PlayerId(3),
+        control_scheme_id: 1,
+      }]
+    );
+    assert!(!lobby.has_reserved_slots());
+    assert!(!lobby.validate_registration(&previous_client_id, &PlayerId(3)));
+    assert!(lobby.validate_registration(&new_client_id, &PlayerId(3)));
+    assert!(lobby.reclaim_slot(&token, new_client_id).is_none());
+  }
+
+  #[test]
+  fn reserve_slot_returns_none_for_client_without_registrations() {
+    let mut lobby = Lobby::default();
+    let client_id = test_client_id(1);
+    lobby.issue_reconnect_token(client_id);
+
+    assert_eq!(lobby.reserve_slot(client_id), None);
+    assert!(!lobby.has_reserved_slots());
+  }
+
+  #[test]
+  fn successor_is_longest_connected_client() {
+    let mut lobby = Lobby::default();
+    assert_eq!(lobby.successor(), None);
+
+    lobby.connected.push(test_client_id(1));
+    lobby.connected.push(test_client_id(2));
+    assert_eq!(lobby.successor(), Some(test_client_id(1)));
+
+    lobby.connected.retain(|client_id| *client_id != test_client_id(1));
+    assert_eq!(lobby.successor(), Some(test_client_id(2)));
+  }
+
+  #[test]
+  fn set_spectating_tracks_spectators_in_order_and_reports_changes() {
+    let mut lobby = Lobby::default();
+
+    assert!(lobby.set_spectating(test_client_id(2), true));
+    assert!(lobby.set_spectating(test_client_id(1), true));
+    assert!(!lobby.set_spectating(test_client_id(1), true));
+    assert_eq!(lobby.spectators(), &[test_client_id(2), test_client_id(1)]);
+
+    assert!(lobby.set_spectating(test_client_id(2), false));
+    assert!(!lobby.set_spectating(test_client_id(3), false));
+    assert!(!lobby.is_spectating(&test_client_id(2)));
+    assert!(lobby.is_spectating(&test_client_id(1)));
+
+    lobby.clear();
+    assert!(lobby.spectators().is_empty());
+  }
+
+  #[test]
+  fn reserve_migrated_slot_allows_reclaiming_with_previous_token() {
+    let mut lobby = Lobby::default();
+    let previous_client_id = test_client_id(1);
+    let new_client_id = test_client_id(2);
+    let token = ReconnectToken::new_random();
+    lobby.register_player(previous_client_id, PlayerId(1), 0);
+
+    assert!(lobby.reserve_migrated_slot(previous_client_id, token));
+    assert!(!lobby.reserve_migrated_slot(test_client_id(3), ReconnectToken::new_random()));
+    assert_eq!(lobby.reconnect_tokens(), vec![(previous_client_id, token)]);
+    assert!(lobby.reclaim_slot(&token, new_client_id).is_some());
+    assert!(lobby.validate_registration(&new_client_id, &PlayerId(1)));
+  }
+
+  #[test]
+  fn revoke_reconnect_token_prevents_reclaiming_connected_and_reserved_slots() {
+    let mut lobby = Lobby::default();
+    let connected_client_id = test_client_id(1);
+    let disconnected_client_id = test_client_id(2);
+    let connected_token = lobby.issue_reconnect_token(connected_client_id);
+    let disconnected_token = lobby.issue_reconnect_token(disconnected_client_id);
+    lobby.register_player(disconnected_client_id, PlayerId(2), 0);
+    assert_eq!(lobby.reserve_slot(disconnected_client_id), Some(disconnected_token));
+
+    assert_eq!(lobby.revoke_reconnect_token(connected_client_id), Some(connected_token));
+    assert_eq!(
+      lobby.revoke_reconnect_token(disconnected_client_id),
+      Some(disconnected_token)
+    );
+    assert_eq!(lobby.revoke_reconnect_token(disconnected_client_id), None);
+    assert!(lobby.reconnect_tokens().is_empty());
+    assert!(lobby.reclaim_slot(&disconnected_token, test_client_id(3)).is_none());
+  }
+
   #[test]
   fn networking_resources_plugin_initialises_signalling_server_url() {
     let mut app = App::new();
@@ -348,6 +739,17 @@ mod tests {
     assert_eq!(signalling_server_url.as_str(), DEFAULT_SIGNALLING_SERVER_URL);
   }
 
+  #[test]
+  fn networking_resources_plugin_keeps_signalling_server_url_resolved_by_app() {
+    let mut app = App::new();
+    app.insert_resource(SignallingServerUrl::new("wss://signal.example.com"));
+    app.add_plugins((MinimalPlugins, NetworkingResourcesPlugin));
+    assert_eq!(
+      app.world().resource::<SignallingServerUrl>().as_str(),
+      "wss://signal.example.com"
+    );
+  }
+
   #[test]
   fn signalling_server_url_try_new_accepts_wss_url_without_port() {
     let signalling_server_url = SignallingServerUrl::try_new("wss://signal.example.com")
@@ -381,12 +783,77 @@ mod tests {
     assert_eq!(signalling_server_url.as_str(), "wss://signal.example.com");
   }
 
+  #[test]
+  fn signalling_server_url_resolve_uses_first_valid_source() {
+    let signalling_server_url = SignallingServerUrl::resolve(
+      [
+        ("command line argument", None),
+        ("config file", Some("https://not-a-websocket.example.com".to_string())),
+        ("page query string", Some(" wss://signal.example.com ".to_string())),
+      ],
+      Some("wss://build-time.example.com"),
+    );
+    assert_eq!(signalling_server_url.as_str(), "wss://signal.example.com");
+  }
+
+  #[test]
+  fn signalling_server_url_resolve_falls_back_to_build_time_url() {
+    let signalling_server_url = SignallingServerUrl::resolve([("command line argument", None)], None);
+    assert_eq!(signalling_server_url.as_str(), DEFAULT_SIGNALLING_SERVER_URL);
+  }
+
+  #[test]
+  fn url_from_config_reads_optional_signalling_server_url() {
+    assert_eq!(
+      url_from_config(r#"{"signalling_server_url": "wss://signal.example.com"}"#),
+      Ok(Some("wss://signal.example.com".to_string()))
+    );
+    assert_eq!(url_from_config("{}"), Ok(None));
+    assert!(url_from_config("not json").is_err());
+  }
+
+  #[test]
+  fn url_from_query_reads_decoded_query_parameter() {
+    assert_eq!(
+      url_from_query("https://example.com/mooplas/?signalling_server_url=wss%3A%2F%2Fsignal.example.com"),
+      Some("wss://signal.example.com".to_string())
+    );
+    assert_eq!(url_from_query("https://example.com/mooplas/?other=1"), None);
+  }
+
   #[test]
   #[should_panic(expected = "SIGNALLING_SERVER_URL must not be empty when set")]
   fn signalling_server_url_from_build_time_env_rejects_empty_url() {
     let _ = SignallingServerUrl::from_build_time_env(Some("   "));
   }
 
+  #[test]
+  fn malformed_packets_counts_failures_per_peer_until_limit_is_reached() {
+    let mut malformed_packets = MalformedPackets::new(2);
+    let client_id = test_client_id(1);
+
+    let error = malformed_packets.record(Some(client_id), ChannelType::Unreliable, 3, "Hit the end of buffer");
+    assert!(matches!(
+      error,
+      NetworkErrorEvent::MalformedPacket(MalformedPacket {
+        failures: 1,
+        payload_size: 3,
+        ..
+      })
+    ));
+    assert!(!malformed_packets.is_over_limit(Some(client_id)));
+
+    malformed_packets.record(None, ChannelType::ReliableOrdered, 5, "Hit the end of buffer");
+    assert!(!malformed_packets.is_over_limit(Some(client_id)));
+    assert!(!malformed_packets.is_over_limit(None));
+
+    malformed_packets.record(Some(client_id), ChannelType::Unreliable, 3, "Hit the end of buffer");
+    assert!(malformed_packets.is_over_limit(Some(client_id)));
+
+    malformed_packets.forget(Some(client_id));
+    assert!(!malformed_packets.is_over_limit(Some(client_id)));
+  }
+
   fn test_client_id(value: u128) -> ClientId {
     ClientId::from_u64(value as u64)
   }
@@ -4,36 +4,18 @@ use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::time::Duration;
 use uuid::Uuid;
 
-/// An enum representing the different types of channels that can be used for sending messages.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// An enum representing the different types of channels that can be used for sending messages. See
+/// [`crate::prelude::NetworkMessage`] for which message is sent on which channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ChannelType {
   Unreliable,
   ReliableOrdered,
   ReliableUnordered,
 }
 
-impl From<ChannelType> for u8 {
-  fn from(channel: ChannelType) -> Self {
-    match channel {
-      ChannelType::Unreliable => 0,
-      ChannelType::ReliableUnordered => 1,
-      ChannelType::ReliableOrdered => 2,
-    }
-  }
-}
-
-impl From<ChannelType> for usize {
-  fn from(channel: ChannelType) -> Self {
-    match channel {
-      ChannelType::Unreliable => 0,
-      ChannelType::ReliableUnordered => 1,
-      ChannelType::ReliableOrdered => 2,
-    }
-  }
-}
-
 /// A component identifying a player. Used to link player entities together.
 #[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PlayerId(pub u8);
@@ -103,6 +85,19 @@ impl Display for ClientId {
   }
 }
 
+/// A secret issued by the server to each client on connection. A client whose connection dropped can present it after
+/// reconnecting to reclaim the players it had registered before.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Hash)]
+#[serde(transparent)]
+pub struct ReconnectToken(Uuid);
+
+impl ReconnectToken {
+  /// Creates a new, random [`ReconnectToken`].
+  pub fn new_random() -> Self {
+    Self(Uuid::new_v4())
+  }
+}
+
 /// This is how the networking code communicates errors to the application code.
 #[derive(Event, Debug)]
 pub enum NetworkErrorEvent {
@@ -110,6 +105,9 @@ pub enum NetworkErrorEvent {
   NetcodeTransportError(String),
   IoError(String),
   OtherError(String),
+  /// A packet couldn't be decoded. Not fatal: the packet is dropped, and so is the peer once it has sent too many, see
+  /// [`crate::prelude::MalformedPackets`].
+  MalformedPacket(MalformedPacket),
 }
 
 impl Error for NetworkErrorEvent {}
@@ -121,18 +119,80 @@ impl Display for NetworkErrorEvent {
       | NetworkErrorEvent::NetcodeTransportError(message)
       | NetworkErrorEvent::IoError(message)
       | NetworkErrorEvent::OtherError(message) => message,
+      NetworkErrorEvent::MalformedPacket(packet) => return write!(fmt, "{packet}"),
     };
     write!(fmt, "{message}")
   }
 }
 
+/// Diagnostics for a packet that couldn't be decoded, e.g. because it is corrupt or was sent by an incompatible version
+/// of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedPacket {
+  /// The client that sent the packet, or `None` if it was sent by the server.
+  pub client_id: Option<ClientId>,
+  pub channel: ChannelType,
+  pub payload_size: usize,
+  /// How many malformed packets the sender has sent so far, including this one.
+  pub failures: u32,
+  pub error: String,
+}
+
+impl Display for MalformedPacket {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+    let sender = match self.client_id {
+      Some(client_id) => format!("client [{client_id}]"),
+      None => "the server".to_string(),
+    };
+    write!(
+      fmt,
+      "Received malformed [{:?}] packet of {} bytes from {} ({} so far): {}",
+      self.channel, self.payload_size, sender, self.failures, self.error
+    )
+  }
+}
+
+/// The reason why the server disconnects a client, sent with [`crate::prelude::InboundServerMessage::Kicked`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisconnectReason {
+  /// The client has sent too many invalid messages.
+  TooManyViolations,
+  /// The host has removed the client from the room.
+  KickedByHost,
+  /// The host has removed the client from the room and won't let it rejoin for the rest of the session.
+  BannedByHost,
+}
+
+impl Display for DisconnectReason {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let message = match self {
+      DisconnectReason::TooManyViolations => "You have been disconnected for sending invalid messages",
+      DisconnectReason::KickedByHost => "You have been kicked by the host",
+      DisconnectReason::BannedByHost => "You have been banned by the host",
+    };
+    write!(f, "{message}")
+  }
+}
+
 // A type that is serialisable and communicates an input action.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum SerialisableInput {
   Move(u8, f32),
   Action(u8),
 }
 
+/// The maximum number of [`SerialisableInputFrame`]s a client sends in a single [`ClientMessage::Input`], i.e. how many
+/// consecutive packets may be lost before an input is lost.
+pub const MAX_INPUT_FRAMES_PER_PACKET: usize = 5;
+
+/// All input actions of the local players of a client during a single tick. Frames are numbered consecutively by the
+/// client, which lets the server apply each frame exactly once and in order, no matter how often it was sent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SerialisableInputFrame {
+  pub sequence: u32,
+  pub inputs: Vec<SerialisableInput>,
+}
+
 /// A type that communicates a local control scheme registration request.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SerialisableRegistrationRequest {
@@ -146,6 +206,20 @@ pub struct SerialisableUnregistrationRequest {
   pub player_id: PlayerId,
 }
 
+/// A type that communicates whether a registered player is ready for the round to start.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct SerialisableReadyRequest {
+  pub player_id: PlayerId,
+  pub is_ready: bool,
+}
+
+/// A chat message sent by a client. The server broadcasts it with the name of the sender, which it derives from the
+/// players the sender has registered, so that clients can't impersonate anyone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SerialisableChatMessage {
+  pub text: String,
+}
+
 /// A player registration included in the authoritative client bootstrap.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SerialisableRegisteredPlayer {
@@ -153,6 +227,45 @@ pub struct SerialisableRegisteredPlayer {
   pub player_id: u8,
   pub control_scheme_id: u8,
   pub name: String,
+  pub is_ready: bool,
+}
+
+/// An authoritative change to a player's snake tail. The server replicates these so that clients build their tails from
+/// the exact sample points that the server's colliders are made of.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SerialisableTailEvent {
+  /// A sample point (x, y) was appended to the active segment of the player's tail.
+  Sampled { player_id: u8, x: f32, y: f32 },
+  /// The active segment of the player's tail has ended and a gap has started.
+  GapStarted { player_id: u8 },
+  /// The gap has ended and a new, empty segment has been started.
+  GapEnded { player_id: u8 },
+}
+
+/// The complete state of a player in a round that is in progress. Sent to clients that join mid-round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SerialisablePlayerSnapshot {
+  pub player_id: u8,
+  /// Position (x, y) and rotation in radians around the Z axis of the player's snake head
+  pub head: (f32, f32, f32),
+  pub alive: bool,
+  /// The sample points (x, y) of each tail segment, oldest segment first
+  pub segments: Vec<Vec<(f32, f32)>>,
+  /// Whether the last segment has been closed and the tail is currently in a gap
+  pub is_in_gap: bool,
+}
+
+/// The reason a player was eliminated, as replicated by the server. Player IDs are raw [`PlayerId`] values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SerialisableEliminationCause {
+  /// The player left the bounds of the play area.
+  Wall,
+  /// The player ran into their own tail.
+  OwnTail,
+  /// The player ran into the tail of another player.
+  OtherTail { killer_id: u8 },
+  /// The player collided head-on with another player.
+  HeadOn { other_player_id: u8 },
 }
 
 /// A player in an online game. Only used by the [`prelude::Lobby`] resource.
@@ -170,7 +283,19 @@ pub struct PlayerInLobby {
 pub enum ClientMessage {
   RegistrationRequest(SerialisableRegistrationRequest),
   UnregistrationRequest(SerialisableUnregistrationRequest),
-  Input(SerialisableInput),
+  /// The latest input frame together with the previous frames that the server hasn't acknowledged yet, oldest first.
+  /// Sent on the [`crate::prelude::ChannelType::Unreliable`] channel, so any frame may arrive more than once.
+  Input(Vec<SerialisableInputFrame>),
+  Reconnect(ReconnectToken),
+  /// The answer to an [`crate::prelude::InboundServerMessage::Ping`] with the same sequence number.
+  Pong(u32),
+  /// Asks the server for its current time, containing the local time of the client at which the request was sent.
+  TimeSyncRequest(Duration),
+  Chat(SerialisableChatMessage),
+  ReadyRequest(SerialisableReadyRequest),
+  /// Asks the server to treat this client as a spectator (true), which watches rounds without registering players, or
+  /// as a player (false).
+  SetSpectating(bool),
 }
 
 impl ClientMessage {
@@ -178,7 +303,13 @@ impl ClientMessage {
     match self {
       ClientMessage::RegistrationRequest(message) => InboundClientMessage::RegistrationRequest(message, client_id),
       ClientMessage::UnregistrationRequest(message) => InboundClientMessage::UnregistrationRequest(message, client_id),
-      ClientMessage::Input(action) => InboundClientMessage::Input(action, client_id),
+      ClientMessage::Input(frames) => InboundClientMessage::Input(frames, client_id),
+      ClientMessage::Reconnect(token) => InboundClientMessage::Reconnect(token, client_id),
+      ClientMessage::Pong(sequence) => InboundClientMessage::Pong(sequence, client_id),
+      ClientMessage::TimeSyncRequest(client_time) => InboundClientMessage::TimeSyncRequest(client_time, client_id),
+      ClientMessage::Chat(message) => InboundClientMessage::Chat(message, client_id),
+      ClientMessage::ReadyRequest(message) => InboundClientMessage::ReadyRequest(message, client_id),
+      ClientMessage::SetSpectating(is_spectating) => InboundClientMessage::SetSpectating(is_spectating, client_id),
     }
   }
 }
@@ -196,8 +327,30 @@ impl Debug for ClientMessage {
       ClientMessage::UnregistrationRequest(message) => {
         write!(f, "ClientMessage::UnregistrationRequest for {}", message.player_id)
       }
-      ClientMessage::Input(action) => {
-        write!(f, "ClientMessage::{:?}", action)
+      ClientMessage::Input(frames) => {
+        write!(f, "ClientMessage::Input with {} frame(s)", frames.len())
+      }
+      ClientMessage::Reconnect(_) => {
+        write!(f, "ClientMessage::Reconnect")
+      }
+      ClientMessage::Pong(sequence) => {
+        write!(f, "ClientMessage::Pong {}", sequence)
+      }
+      ClientMessage::TimeSyncRequest(client_time) => {
+        write!(f, "ClientMessage::TimeSyncRequest at {:?}", client_time)
+      }
+      ClientMessage::Chat(_) => {
+        write!(f, "ClientMessage::Chat")
+      }
+      ClientMessage::ReadyRequest(message) => {
+        write!(
+          f,
+          "ClientMessage::ReadyRequest for {} (ready: {})",
+          message.player_id, message.is_ready
+        )
+      }
+      ClientMessage::SetSpectating(is_spectating) => {
+        write!(f, "ClientMessage::SetSpectating {}", is_spectating)
       }
     }
   }
@@ -226,4 +379,30 @@ mod tests {
       "Other error"
     );
   }
+
+  #[test]
+  fn network_error_event_display_describes_malformed_packet() {
+    let packet = MalformedPacket {
+      client_id: Some(ClientId::from_u64(7)),
+      channel: ChannelType::Unreliable,
+      payload_size: 3,
+      failures: 2,
+      error: "Hit the end of buffer".to_string(),
+    };
+    assert_eq!(
+      NetworkErrorEvent::MalformedPacket(packet.clone()).to_string(),
+      format!(
+        "Received malformed [Unreliable] packet of 3 bytes from client [{}] (2 so far): Hit the end of buffer",
+        ClientId::from_u64(7)
+      )
+    );
+    assert_eq!(
+      NetworkErrorEvent::MalformedPacket(MalformedPacket {
+        client_id: None,
+        ..packet
+      })
+      .to_string(),
+      "Received malformed [Unreliable] packet of 3 bytes from the server (2 so far): Hit the end of buffer"
+    );
+  }
 }
@@ -0,0 +1,356 @@
+use crate::prelude::{
+  ChannelSemantics, ChannelType, ClientId, ClientMessage, ClientNetworkingActive, ConditionedMessageReader,
+  InboundClientMessage, InboundServerMessage, Lobby, MalformedPackets, MessageRegistry, NetworkErrorEvent,
+  OutboundClientMessage, OutboundServerMessage, PacketBatcher, PacketCounters, ServerNetworkingActive,
+  TOO_MANY_MALFORMED_PACKETS, assert_transport_channels, decode_packet,
+};
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::log::{trace, warn};
+use bevy::prelude::{Commands, IntoScheduleConfigs, MessageWriter, Res, ResMut, Resource, resource_exists};
+use std::any::type_name;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+/// All channels a transport must provide, in the order of their [`ChannelType::index`].
+pub const CHANNELS: [ChannelType; 3] = [
+  ChannelType::Unreliable,
+  ChannelType::ReliableUnordered,
+  ChannelType::ReliableOrdered,
+];
+
+/// A change in the connection of a client, as observed by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportEvent {
+  ClientConnected(ClientId),
+  ClientDisconnected(ClientId),
+}
+
+/// The clients that the [`ServerTransportPlugin`] has asked the transport to disconnect, but which the transport hasn't
+/// reported as disconnected yet. Used to tell forced disconnects apart from clients that left on their own.
+#[derive(Resource, Default)]
+struct ForcedDisconnects(HashSet<ClientId>);
+
+/// The contract of the server side of a transport backend. A backend inserts its implementation as a resource, together
+/// with [`ServerNetworkingActive`], and adds [`ServerTransportPlugin`], which takes care of the rest: keeping
+/// [`Lobby::connected`] up to date, decoding [`ClientMessage`]s into [`InboundClientMessage`]s, writing
+/// [`InboundServerMessage::ClientConnected`] and [`InboundServerMessage::ClientDisconnected`] (marked as forced if the
+/// server disconnected the client), and sending
+/// [`OutboundServerMessage`]s. All messages for the same client and channel within a frame are sent as a single
+/// payload, see [`PacketBatcher`].
+///
+/// Payloads must be delivered with the guarantees of the [`ChannelType`] they were sent on: anything may be lost on
+/// [`ChannelType::Unreliable`], nothing may be lost on [`ChannelType::ReliableUnordered`], and nothing may be lost or
+/// reordered on [`ChannelType::ReliableOrdered`].
+pub trait ServerTransport: Resource {
+  /// The guarantees with which this transport delivers payloads on each channel, by [`ChannelType::index`]. Checked
+  /// against the [`MessageRegistry`] at startup.
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics];
+
+  /// Returns the clients that connected or disconnected since the last call, in the order in which it happened.
+  /// Returns an error if the transport itself failed, e.g. because the connection to the signalling server was lost.
+  fn poll_events(&mut self) -> Result<Vec<TransportEvent>, NetworkErrorEvent>;
+
+  /// Returns all payloads received on the given channel since the last call, together with their sender.
+  fn receive(&mut self, channel: ChannelType) -> Vec<(ClientId, Vec<u8>)>;
+
+  /// Sends a payload to a connected client. Payloads for clients that aren't connected are dropped.
+  fn send(&mut self, client_id: ClientId, channel: ChannelType, payload: &[u8]);
+
+  /// Returns all currently connected clients.
+  fn connected_clients(&self) -> Vec<ClientId>;
+
+  /// Disconnects a single client. The client must be reported as disconnected by the next call of
+  /// [`ServerTransport::poll_events`] and must not be among the [`ServerTransport::connected_clients`] anymore.
+  fn disconnect(&mut self, client_id: ClientId);
+
+  /// Disconnects all clients and stops accepting new ones.
+  fn disconnect_all(&mut self);
+}
+
+/// The contract of the client side of a transport backend. A backend inserts its implementation as a resource, together
+/// with [`ClientNetworkingActive`], and adds [`ClientTransportPlugin`], which takes care of decoding payloads into
+/// [`InboundServerMessage`]s and sending [`OutboundClientMessage`]s. Payloads must be delivered with the guarantees of
+/// their [`ChannelType`], see [`ServerTransport`].
+pub trait ClientTransport: Resource {
+  /// The guarantees with which this transport delivers payloads on each channel, see
+  /// [`ServerTransport::CHANNEL_SEMANTICS`].
+  const CHANNEL_SEMANTICS: &'static [ChannelSemantics];
+
+  /// Returns an error once the connection to the server has been lost, which is then triggered as a
+  /// [`NetworkErrorEvent`].
+  fn poll_connection(&mut self) -> Result<(), NetworkErrorEvent>;
+
+  /// Returns all payloads received from the server on the given channel since the last call.
+  fn receive(&mut self, channel: ChannelType) -> Vec<Vec<u8>>;
+
+  /// Sends a payload to the server.
+  fn send(&mut self, channel: ChannelType, payload: &[u8]);
+
+  /// Disconnects from the server.
+  fn disconnect(&mut self);
+}
+
+/// A plugin that connects a [`ServerTransport`] backend to the transport-agnostic messages of this crate. Messages that
+/// can't be decoded are dropped and triggered as a [`NetworkErrorEvent::MalformedPacket`]; a client that sends too many
+/// of them is disconnected, see [`MalformedPackets`]. Sent and received packets are counted in [`PacketCounters`].
+pub struct ServerTransportPlugin<T: ServerTransport>(PhantomData<T>);
+
+impl<T: ServerTransport> Default for ServerTransportPlugin<T> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<T: ServerTransport> Plugin for ServerTransportPlugin<T> {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<MessageRegistry>()
+      .init_resource::<PacketCounters>()
+      .init_resource::<ForcedDisconnects>()
+      .add_systems(Startup, validate_server_transport_channels::<T>)
+      .add_systems(
+        Update,
+        (
+          receive_server_transport_messages::<T>,
+          send_server_transport_messages::<T>,
+        )
+          .chain()
+          .run_if(resource_exists::<T>)
+          .run_if(resource_exists::<ServerNetworkingActive>),
+      );
+  }
+}
+
+/// A plugin that connects a [`ClientTransport`] backend to the transport-agnostic messages of this crate. Messages that
+/// can't be decoded are dropped and triggered as a [`NetworkErrorEvent::MalformedPacket`]; if the server sends too many
+/// of them, the client disconnects, see [`MalformedPackets`]. Sent and received packets are counted in
+/// [`PacketCounters`].
+pub struct ClientTransportPlugin<T: ClientTransport>(PhantomData<T>);
+
+impl<T: ClientTransport> Default for ClientTransportPlugin<T> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<T: ClientTransport> Plugin for ClientTransportPlugin<T> {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<MessageRegistry>()
+      .init_resource::<PacketCounters>()
+      .add_systems(Startup, validate_client_transport_channels::<T>)
+      .add_systems(
+        Update,
+        (
+          receive_client_transport_messages::<T>,
+          send_client_transport_messages::<T>,
+        )
+          .chain()
+          .run_if(resource_exists::<T>)
+          .run_if(resource_exists::<ClientNetworkingActive>),
+      );
+  }
+}
+
+fn validate_server_transport_channels<T: ServerTransport>(registry: Res<MessageRegistry>) {
+  assert_transport_channels(&registry, type_name::<T>(), T::CHANNEL_SEMANTICS);
+}
+
+fn validate_client_transport_channels<T: ClientTransport>(registry: Res<MessageRegistry>) {
+  assert_transport_channels(&registry, type_name::<T>(), T::CHANNEL_SEMANTICS);
+}
+
+fn receive_server_transport_messages<T: ServerTransport>(
+  mut commands: Commands,
+  mut transport: ResMut<T>,
+  mut lobby: ResMut<Lobby>,
+  mut malformed_packets: ResMut<MalformedPackets>,
+  mut packet_counters: ResMut<PacketCounters>,
+  mut forced_disconnects: ResMut<ForcedDisconnects>,
+  mut inbound_client_message: MessageWriter<InboundClientMessage>,
+  mut inbound_server_message: MessageWriter<InboundServerMessage>,
+) {
+  match transport.poll_events() {
+    Ok(events) => {
+      for event in events {
+        let server_event = match event {
+          TransportEvent::ClientConnected(client_id) => {
+            trace!("Client with ID [{client_id}] connected");
+            lobby.connected.push(client_id);
+            InboundServerMessage::ClientConnected { client_id }
+          }
+          TransportEvent::ClientDisconnected(client_id) => {
+            trace!("Client with ID [{client_id}] disconnected");
+            lobby.connected.retain(|&id| id != client_id);
+            malformed_packets.forget(Some(client_id));
+            InboundServerMessage::ClientDisconnected {
+              client_id,
+              is_forced: forced_disconnects.0.remove(&client_id),
+            }
+          }
+        };
+        inbound_server_message.write(server_event);
+      }
+    }
+    Err(error) => commands.trigger(error),
+  }
+
+  for channel in CHANNELS {
+    for (client_id, packet) in transport.receive(channel) {
+      let client_messages = decode_packet::<ClientMessage>(&packet);
+      packet_counters.record_received(client_messages.len());
+      for client_message in client_messages {
+        // Ignore anything else a client sends once it is being disconnected for sending malformed packets
+        if malformed_packets.is_over_limit(Some(client_id)) {
+          break;
+        }
+        match client_message {
+          Ok(client_message) => {
+            inbound_client_message.write(client_message.to_inbound_message(client_id));
+          }
+          Err(error) => {
+            let error = malformed_packets.record(Some(client_id), channel, error.size, error);
+            warn!("{error}");
+            commands.trigger(error);
+            if malformed_packets.is_over_limit(Some(client_id)) {
+              warn!("Disconnecting client [{client_id}] because it has sent too many malformed packets");
+              forced_disconnects.0.insert(client_id);
+              transport.disconnect(client_id);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Sends all [`OutboundServerMessage`]s of this frame, packing them into one packet per client and channel. Pending
+/// packets are sent before any disconnect, so that e.g. a kicked client still receives the reason.
+fn send_server_transport_messages<T: ServerTransport>(
+  mut messages: ConditionedMessageReader<OutboundServerMessage>,
+  mut transport: ResMut<T>,
+  mut packet_counters: ResMut<PacketCounters>,
+  mut forced_disconnects: ResMut<ForcedDisconnects>,
+) {
+  let mut batcher = PacketBatcher::default();
+  for message in messages.read() {
+    match message {
+      OutboundServerMessage::Broadcast { channel, payload } => {
+        for client_id in transport.connected_clients() {
+          batcher.push(client_id, channel, &payload);
+        }
+      }
+      OutboundServerMessage::BroadcastExcept {
+        except_client_id,
+        channel,
+        payload,
+      } => {
+        for client_id in transport.connected_clients() {
+          if client_id != except_client_id {
+            batcher.push(client_id, channel, &payload);
+          }
+        }
+      }
+      OutboundServerMessage::Send {
+        client_id,
+        channel,
+        payload,
+      } => batcher.push(client_id, channel, &payload),
+      OutboundServerMessage::Disconnect { client_id } => {
+        send_server_packets(&mut batcher, &mut *transport, &mut packet_counters);
+        forced_disconnects.0.insert(client_id);
+        transport.disconnect(client_id);
+      }
+      OutboundServerMessage::DisconnectAll => {
+        send_server_packets(&mut batcher, &mut *transport, &mut packet_counters);
+        transport.disconnect_all();
+      }
+    }
+  }
+  send_server_packets(&mut batcher, &mut *transport, &mut packet_counters);
+}
+
+fn send_server_packets<T: ServerTransport>(
+  batcher: &mut PacketBatcher<ClientId>,
+  transport: &mut T,
+  packet_counters: &mut PacketCounters,
+) {
+  let messages = batcher.payloads();
+  let packets = batcher.drain();
+  packet_counters.record_sent(messages, packets.len());
+  for (client_id, channel, packet) in packets {
+    transport.send(client_id, channel, &packet);
+  }
+}
+
+fn receive_client_transport_messages<T: ClientTransport>(
+  mut commands: Commands,
+  mut transport: ResMut<T>,
+  mut malformed_packets: ResMut<MalformedPackets>,
+  mut packet_counters: ResMut<PacketCounters>,
+  mut inbound_server_message: MessageWriter<InboundServerMessage>,
+) {
+  if let Err(error) = transport.poll_connection() {
+    malformed_packets.forget(None);
+    commands.trigger(error);
+  }
+
+  for channel in CHANNELS {
+    for packet in transport.receive(channel) {
+      let server_messages = decode_packet::<InboundServerMessage>(&packet);
+      packet_counters.record_received(server_messages.len());
+      for server_message in server_messages {
+        match server_message {
+          Ok(server_message) => {
+            inbound_server_message.write(server_message);
+          }
+          Err(error) => {
+            let error = malformed_packets.record(None, channel, error.size, error);
+            warn!("{error}");
+            commands.trigger(error);
+            if malformed_packets.is_over_limit(None) {
+              warn!("Disconnecting because the server has sent too many malformed packets");
+              malformed_packets.forget(None);
+              transport.disconnect();
+              commands.trigger(NetworkErrorEvent::Disconnect(TOO_MANY_MALFORMED_PACKETS.to_string()));
+              return;
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Sends all [`OutboundClientMessage`]s of this frame, packing them into one packet per channel. Pending packets are
+/// sent before disconnecting.
+fn send_client_transport_messages<T: ClientTransport>(
+  mut messages: ConditionedMessageReader<OutboundClientMessage>,
+  mut transport: ResMut<T>,
+  mut packet_counters: ResMut<PacketCounters>,
+) {
+  let mut batcher = PacketBatcher::default();
+  for message in messages.read() {
+    match message {
+      OutboundClientMessage::Send { channel, payload } => batcher.push((), channel, &payload),
+      OutboundClientMessage::Disconnect => {
+        send_client_packets(&mut batcher, &mut *transport, &mut packet_counters);
+        transport.disconnect();
+      }
+    }
+  }
+  send_client_packets(&mut batcher, &mut *transport, &mut packet_counters);
+}
+
+fn send_client_packets<T: ClientTransport>(
+  batcher: &mut PacketBatcher<()>,
+  transport: &mut T,
+  packet_counters: &mut PacketCounters,
+) {
+  let messages = batcher.payloads();
+  let packets = batcher.drain();
+  packet_counters.record_sent(messages, packets.len());
+  for ((), channel, packet) in packets {
+    transport.send(channel, &packet);
+  }
+}
@@ -0,0 +1,133 @@
+use axum::{
+  Json,
+  http::header,
+  response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha1::Sha1;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_STUN_URL: &str = "stun:stun.l.google.com:19302";
+pub const DEFAULT_TURN_CREDENTIAL_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const TURN_USER_ID: &str = "mooplas";
+
+/// The ICE servers that the game should use to establish WebRTC connections, served from `GET /ice-servers`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IceConfig {
+  pub stun_urls: Vec<String>,
+  pub turn: Option<TurnConfig>,
+}
+
+impl Default for IceConfig {
+  fn default() -> Self {
+    Self {
+      stun_urls: vec![DEFAULT_STUN_URL.to_string()],
+      turn: None,
+    }
+  }
+}
+
+/// TURN servers that accept short-lived credentials derived from a secret they share with this server, following the
+/// TURN REST API convention (e.g. coturn with `use-auth-secret` and `static-auth-secret`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnConfig {
+  pub urls: Vec<String>,
+  pub secret: String,
+  pub credential_ttl: Duration,
+}
+
+/// The ICE servers as the game expects them. The username and credential only apply to TURN servers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct IceServers {
+  urls: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  username: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  credential: Option<String>,
+}
+
+/// Responds with the configured ICE servers, including freshly issued TURN credentials. Must not be cached, since the
+/// credentials expire.
+pub(crate) fn ice_servers_response(config: &IceConfig) -> Response {
+  (
+    [(header::CACHE_CONTROL, "no-store")],
+    Json(ice_servers(config, SystemTime::now())),
+  )
+    .into_response()
+}
+
+fn ice_servers(config: &IceConfig, now: SystemTime) -> IceServers {
+  let mut urls = config.stun_urls.clone();
+  let Some(turn) = &config.turn else {
+    return IceServers {
+      urls,
+      username: None,
+      credential: None,
+    };
+  };
+  urls.extend(turn.urls.iter().cloned());
+  let (username, credential) = turn_credentials(&turn.secret, now + turn.credential_ttl);
+  IceServers {
+    urls,
+    username: Some(username),
+    credential: Some(credential),
+  }
+}
+
+/// Issues TURN credentials that are valid until the given time: the username is the expiry as a Unix timestamp plus a
+/// user ID, and the credential is the Base64-encoded HMAC-SHA1 of the username, keyed with the shared secret.
+fn turn_credentials(secret: &str, expires_at: SystemTime) -> (String, String) {
+  let expiry = expires_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+  let username = format!("{expiry}:{TURN_USER_ID}");
+  let mut mac = Hmac::<Sha1>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+  mac.update(username.as_bytes());
+  let credential = STANDARD.encode(mac.finalize().into_bytes());
+  (username, credential)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ice_servers_only_include_stun_urls_without_turn_config() {
+    let ice_servers = ice_servers(&IceConfig::default(), SystemTime::now());
+
+    assert_eq!(
+      ice_servers,
+      IceServers {
+        urls: vec![DEFAULT_STUN_URL.to_string()],
+        username: None,
+        credential: None,
+      }
+    );
+  }
+
+  #[test]
+  fn ice_servers_include_turn_urls_with_credentials_that_expire_after_ttl() {
+    let config = IceConfig {
+      stun_urls: vec!["stun:turn.example.com:3478".to_string()],
+      turn: Some(TurnConfig {
+        urls: vec!["turn:turn.example.com:3478".to_string()],
+        secret: "north".to_string(),
+        credential_ttl: Duration::from_secs(86_400),
+      }),
+    };
+
+    let ice_servers = ice_servers(&config, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+    assert_eq!(
+      ice_servers,
+      IceServers {
+        urls: vec![
+          "stun:turn.example.com:3478".to_string(),
+          "turn:turn.example.com:3478".to_string()
+        ],
+        username: Some("1700086400:mooplas".to_string()),
+        credential: Some("B5E4KP5FVbFUpKQfezD/Uf3qKBo=".to_string()),
+      }
+    );
+  }
+}
@@ -2,6 +2,7 @@
 
 use axum::{Router, routing::get};
 use error::ServerError;
+use ice_servers::ice_servers_response;
 use room_aware_client_server::room_aware_client_server_builder;
 use server::StandaloneServer;
 use std::{
@@ -22,9 +23,12 @@ use tokio_rustls::{
 use tracing::info;
 
 pub mod error;
+mod ice_servers;
 mod room_aware_client_server;
 mod server;
 
+pub use ice_servers::{DEFAULT_STUN_URL, DEFAULT_TURN_CREDENTIAL_TTL, IceConfig, TurnConfig};
+
 pub const DEFAULT_PORT: u16 = 3536;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -37,6 +41,7 @@ pub struct TlsConfig {
 pub struct ServerConfig {
   pub port: u16,
   pub tls: Option<TlsConfig>,
+  pub ice: IceConfig,
 }
 
 impl Default for ServerConfig {
@@ -44,6 +49,7 @@ impl Default for ServerConfig {
     Self {
       port: DEFAULT_PORT,
       tls: None,
+      ice: IceConfig::default(),
     }
   }
 }
@@ -63,9 +69,9 @@ pub async fn run_server(config: ServerConfig) -> Result<(), ServerError> {
 ///
 /// Returns an error if the TLS certificate or key cannot be loaded when TLS is configured.
 pub fn build_server(config: ServerConfig) -> Result<StandaloneServer, ServerError> {
-  let ServerConfig { port, tls } = config;
+  let ServerConfig { port, tls, ice } = config;
   let requested_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port));
-  let service = build_router(requested_addr).into_make_service_with_connect_info::<SocketAddr>();
+  let service = build_router(requested_addr, ice).into_make_service_with_connect_info::<SocketAddr>();
   let tls_acceptor = tls.as_ref().map(load_tls_acceptor).transpose()?;
   info!("Server listening on port [{}]...", port);
   Ok(StandaloneServer {
@@ -76,10 +82,15 @@ pub fn build_server(config: ServerConfig) -> Result<StandaloneServer, ServerErro
   })
 }
 
-fn build_router(requested_addr: SocketAddr) -> Router {
+fn build_router(requested_addr: SocketAddr, ice: IceConfig) -> Router {
   let mut captured_router = None;
   let _ = room_aware_client_server_builder(requested_addr)
-    .mutate_router(|router| router.route("/health", get(health_check)))
+    .mutate_router(|router| {
+      router.route("/health", get(health_check)).route(
+        "/ice-servers",
+        get(move || std::future::ready(ice_servers_response(&ice))),
+      )
+    })
     .on_id_assignment(|(socket, id)| info!("Socket [{socket}] received ID [{id}]"))
     .cors()
     .trace()
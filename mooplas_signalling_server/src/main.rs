@@ -1,6 +1,10 @@
 use clap::Parser;
-use mooplas_signalling_server::{DEFAULT_PORT, ServerConfig, TlsConfig, run_server};
+use mooplas_signalling_server::{
+  DEFAULT_PORT, DEFAULT_STUN_URL, DEFAULT_TURN_CREDENTIAL_TTL, IceConfig, ServerConfig, TlsConfig, TurnConfig,
+  run_server,
+};
 use std::path::PathBuf;
+use std::time::Duration;
 use tracing_subscriber::prelude::*;
 
 #[derive(Debug, Parser)]
@@ -12,6 +16,14 @@ struct Cli {
   tls_cert: Option<PathBuf>,
   #[arg(long, requires = "tls_cert")]
   tls_key: Option<PathBuf>,
+  #[arg(long = "stun-url", default_value = DEFAULT_STUN_URL)]
+  stun_urls: Vec<String>,
+  #[arg(long = "turn-url", requires = "turn_secret")]
+  turn_urls: Vec<String>,
+  #[arg(long, env = "TURN_SECRET", hide_env_values = true)]
+  turn_secret: Option<String>,
+  #[arg(long, default_value_t = DEFAULT_TURN_CREDENTIAL_TTL.as_secs())]
+  turn_credential_ttl_seconds: u64,
 }
 
 impl Cli {
@@ -22,6 +34,17 @@ impl Cli {
         .tls_cert
         .zip(self.tls_key)
         .map(|(cert_path, key_path)| TlsConfig { cert_path, key_path }),
+      ice: IceConfig {
+        stun_urls: self.stun_urls,
+        turn: self
+          .turn_secret
+          .filter(|_| !self.turn_urls.is_empty())
+          .map(|secret| TurnConfig {
+            urls: self.turn_urls,
+            secret,
+            credential_ttl: Duration::from_secs(self.turn_credential_ttl_seconds),
+          }),
+      },
     }
   }
 }
@@ -43,6 +66,7 @@ fn setup_logging() {
 mod tests {
   use clap::Parser;
   use std::path::PathBuf;
+  use std::time::Duration;
 
   use super::Cli;
 
@@ -76,10 +100,49 @@ mod tests {
           cert_path: PathBuf::from("cert.pem"),
           key_path: PathBuf::from("key.pem"),
         }),
+        ice: mooplas_signalling_server::IceConfig::default(),
       }
     );
   }
 
+  #[test]
+  fn cli_builds_turn_config_from_turn_arguments() {
+    let cli = Cli::try_parse_from([
+      "mooplas_signalling_server",
+      "--stun-url",
+      "stun:turn.example.com:3478",
+      "--turn-url",
+      "turn:turn.example.com:3478",
+      "--turn-url",
+      "turns:turn.example.com:5349",
+      "--turn-secret",
+      "north",
+      "--turn-credential-ttl-seconds",
+      "600",
+    ])
+    .expect("parse cli arguments");
+    assert_eq!(
+      cli.server_config().ice,
+      mooplas_signalling_server::IceConfig {
+        stun_urls: vec!["stun:turn.example.com:3478".to_string()],
+        turn: Some(mooplas_signalling_server::TurnConfig {
+          urls: vec![
+            "turn:turn.example.com:3478".to_string(),
+            "turns:turn.example.com:5349".to_string()
+          ],
+          secret: "north".to_string(),
+          credential_ttl: Duration::from_secs(600),
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn cli_uses_public_stun_server_without_turn_by_default() {
+    let cli = Cli::try_parse_from(["mooplas_signalling_server"]).expect("parse cli arguments");
+    assert_eq!(cli.server_config().ice, mooplas_signalling_server::IceConfig::default());
+  }
+
   #[test]
   fn cli_rejects_tls_certificate_without_private_key() {
     let error = Cli::try_parse_from(["mooplas_signalling_server", "--tls-cert", "cert.pem"])
@@ -1,13 +1,16 @@
 use std::{
-  collections::{HashMap, VecDeque},
+  collections::{BTreeMap, HashMap, HashSet, VecDeque},
   net::SocketAddr,
+  time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
 use axum::{
-  extract::ws::Message,
+  Json,
+  extract::{Path, Query, ws::Message},
   http::StatusCode,
   response::{IntoResponse, Response},
+  routing::{delete, get, post},
 };
 use futures::StreamExt;
 use matchbox_protocol::{JsonPeerEvent, PeerId, PeerRequest};
@@ -16,7 +19,10 @@ use matchbox_signaling::{
   WsStateMeta,
   common_logic::{SignalingChannel, StateObj, parse_request, try_send},
 };
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
 /// Marker for the room-aware client-server topology.
 #[derive(Debug, Default)]
@@ -33,24 +39,309 @@ pub(crate) fn room_aware_client_server_builder(
 ) -> SignalingServerBuilder<RoomAwareClientServer, RoomAwareClientServerCallbacks, RoomAwareClientServerState> {
   let state = RoomAwareClientServerState::default();
   let request_state = state.clone();
-  SignalingServerBuilder::new(socket_addr, RoomAwareClientServer, state).on_connection_request(move |connection| {
-    info!("Connecting: {connection:?}...");
-    let room = connection.path.unwrap_or_else(|| "world".to_string());
-    let role = parse_role(&connection.query_params)?;
+  let ban_state = state.clone();
+  let removal_state = state.clone();
+  let successor_state = state.clone();
+  let capacity_state = state.clone();
+  let access_state = state.clone();
+  let listing_state = state.clone();
+  let unlisting_state = state.clone();
+  let public_rooms_state = state.clone();
+  SignalingServerBuilder::new(socket_addr, RoomAwareClientServer, state)
+    .mutate_router(move |router| {
+      router
+        .route(
+          "/rooms/{room}/bans/{peer_id}",
+          post(
+            move |Path((room, peer_id)): Path<(String, String)>,
+                  Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(ban_peer(&ban_state, &room, &peer_id, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms/{room}/peers/{peer_id}",
+          delete(
+            move |Path((room, peer_id)): Path<(String, String)>,
+                  Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(remove_peer(&removal_state, &room, &peer_id, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms/{room}/successor/{peer_id}",
+          post(
+            move |Path((room, peer_id)): Path<(String, String)>,
+                  Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(designate_successor(&successor_state, &room, &peer_id, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms/{room}/capacity/{capacity}",
+          post(
+            move |Path((room, capacity)): Path<(String, String)>,
+                  Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(change_capacity(&capacity_state, &room, &capacity, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms/{room}/access",
+          get(
+            move |Path(room): Path<String>, Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(check_room_access(&access_state, &room, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms/{room}/listing",
+          post(
+            move |Path(room): Path<String>,
+                  Query(query_params): Query<HashMap<String, String>>,
+                  Json(listing): Json<RoomListing>| {
+              std::future::ready(list_room(&listing_state, &room, &query_params, listing))
+            },
+          )
+          .delete(
+            move |Path(room): Path<String>, Query(query_params): Query<HashMap<String, String>>| {
+              std::future::ready(unlist_room(&unlisting_state, &room, &query_params))
+            },
+          ),
+        )
+        .route(
+          "/rooms",
+          get(move || std::future::ready(Json(public_rooms_state.public_rooms()))),
+        )
+    })
+    .on_connection_request(move |connection| {
+      let room = connection.path.unwrap_or_else(|| "world".to_string());
+      info!(
+        "Connecting to room [{room}] with {:?}...",
+        redacted_query_params(&connection.query_params)
+      );
+      let role = parse_role(&connection.query_params)?;
+      let successor = parse_successor(&connection.query_params)?;
+      let capacity = parse_capacity(&connection.query_params)?;
+      let identity = connection.query_params.get("identity").cloned();
+      let host_key = connection.query_params.get("key").cloned();
+      let password = connection
+        .query_params
+        .get("password")
+        .map(String::as_str)
+        .filter(|password| !password.is_empty());
 
-    match role {
-      PeerRole::Host if request_state.has_host_or_pending_host(&room) => {
-        Err((StatusCode::CONFLICT, "Room already has a host\n").into_response())
-      }
-      PeerRole::Client if !request_state.has_host_or_pending_host(&room) => {
-        Err((StatusCode::CONFLICT, "Room has no host\n").into_response())
-      }
-      role => {
-        request_state.approve_role(&room, role);
-        Ok(true)
+      match role {
+        PeerRole::Host if request_state.has_host_or_pending_host(&room) => {
+          Err((StatusCode::CONFLICT, "Room already has a host\n").into_response())
+        }
+        PeerRole::Host if !request_state.accepts_host(&room, successor) => {
+          Err((StatusCode::CONFLICT, "Room is not awaiting this successor\n").into_response())
+        }
+        PeerRole::Client if !request_state.accepts_client(&room) => {
+          Err((StatusCode::CONFLICT, "Room has no host\n").into_response())
+        }
+        PeerRole::Client if request_state.is_banned(&room, identity.as_deref()) => {
+          Err((StatusCode::FORBIDDEN, "Banned from this room\n").into_response())
+        }
+        PeerRole::Client if identity.is_none() && request_state.has_bans(&room) => {
+          Err((StatusCode::UNAUTHORIZED, "Identity required\n").into_response())
+        }
+        PeerRole::Client if request_state.is_full(&room) => {
+          Err((StatusCode::SERVICE_UNAVAILABLE, "Room is full\n").into_response())
+        }
+        PeerRole::Client if password.is_none() && request_state.requires_password(&room) => {
+          Err((StatusCode::UNAUTHORIZED, "Password required\n").into_response())
+        }
+        PeerRole::Client if !request_state.accepts_password(&room, password) => {
+          Err((StatusCode::FORBIDDEN, "Password is not valid\n").into_response())
+        }
+        role => {
+          let password_hash = match role {
+            PeerRole::Host => password.map(|password| hash_password(&room, password)),
+            PeerRole::Client => None,
+          };
+          request_state.approve_peer(
+            &room,
+            ApprovedPeer {
+              role,
+              identity,
+              host_key,
+              capacity,
+              password_hash,
+            },
+          );
+          Ok(true)
+        }
       }
+    })
+}
+
+/// Handles the host's request to ban a client from its room, authorised by the key the host connected with. The client
+/// is identified by the `identity` it connected with, so that it is refused even after reconnecting with a new peer ID.
+fn ban_peer(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  peer_id: &str,
+  query_params: &HashMap<String, String>,
+) -> Response {
+  let Ok(peer_id) = Uuid::parse_str(peer_id).map(PeerId) else {
+    return (StatusCode::BAD_REQUEST, "Peer ID is not valid\n").into_response();
+  };
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  match state.ban(room, host_key, peer_id) {
+    Ok(()) => {
+      info!("Banned [{peer_id}] from room [{room}]");
+      StatusCode::NO_CONTENT.into_response()
     }
-  })
+    Err(HostRequestError::UnknownRoom) => (StatusCode::NOT_FOUND, "Room not found\n").into_response(),
+    Err(HostRequestError::InvalidHostKey) => (StatusCode::FORBIDDEN, "Host key is not valid\n").into_response(),
+    Err(HostRequestError::UnknownIdentity) => (StatusCode::NOT_FOUND, "Peer has no known identity\n").into_response(),
+  }
+}
+
+/// Handles the host's request to remove a client from its room, authorised by the key the host connected with. The
+/// client's signalling connection is closed and the host is told that the client has left, which makes both sides close
+/// their WebRTC connection. Unlike a ban, the client may join the room again.
+fn remove_peer(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  peer_id: &str,
+  query_params: &HashMap<String, String>,
+) -> Response {
+  let Ok(peer_id) = Uuid::parse_str(peer_id).map(PeerId) else {
+    return (StatusCode::BAD_REQUEST, "Peer ID is not valid\n").into_response();
+  };
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  match state.remove_client(room, host_key, peer_id) {
+    Ok(()) => {
+      info!("Removed [{peer_id}] from room [{room}]");
+      StatusCode::NO_CONTENT.into_response()
+    }
+    Err(error) => host_request_error_response(error),
+  }
+}
+
+/// Handles the host's request to designate the client that takes over as host if the host leaves, authorised by the key
+/// the host connected with. Until [`DESIGNATED_SUCCESSOR_TIMEOUT`] has passed after the host has left, only this client
+/// may take over.
+fn designate_successor(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  peer_id: &str,
+  query_params: &HashMap<String, String>,
+) -> Response {
+  let Ok(peer_id) = Uuid::parse_str(peer_id).map(PeerId) else {
+    return (StatusCode::BAD_REQUEST, "Peer ID is not valid\n").into_response();
+  };
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  match state.set_successor(room, host_key, peer_id) {
+    Ok(()) => {
+      info!("Designated [{peer_id}] as successor of the host of room [{room}]");
+      StatusCode::NO_CONTENT.into_response()
+    }
+    Err(error) => host_request_error_response(error),
+  }
+}
+
+/// Handles the host's request to change how many clients its room can hold, authorised by the key the host connected
+/// with. Clients that have already joined stay, even if the room now holds more clients than its new capacity.
+fn change_capacity(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  capacity: &str,
+  query_params: &HashMap<String, String>,
+) -> Response {
+  let Some(capacity) = parse_capacity_value(capacity) else {
+    return (StatusCode::BAD_REQUEST, "Capacity must be a positive number\n").into_response();
+  };
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  match state.set_capacity(room, host_key, capacity) {
+    Ok(()) => {
+      info!("Changed capacity of room [{room}] to [{capacity}] clients");
+      StatusCode::NO_CONTENT.into_response()
+    }
+    Err(error) => host_request_error_response(error),
+  }
+}
+
+/// Handles the host's request to list its room publicly, or to update the listing, authorised by the key the host
+/// connected with.
+fn list_room(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  query_params: &HashMap<String, String>,
+  mut listing: RoomListing,
+) -> Response {
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  listing.name = listing.name.trim().to_string();
+  if listing.name.is_empty() || listing.name.chars().count() > MAX_ROOM_NAME_LENGTH {
+    return (StatusCode::BAD_REQUEST, "Room name is not valid\n").into_response();
+  }
+  if listing.max_players == 0 || listing.player_count > listing.max_players {
+    return (StatusCode::BAD_REQUEST, "Player count is not valid\n").into_response();
+  }
+  match state.set_listing(room, host_key, Some(listing)) {
+    Ok(()) => StatusCode::NO_CONTENT.into_response(),
+    Err(error) => host_request_error_response(error),
+  }
+}
+
+/// Handles the host's request to remove its room from the public room list, authorised by the key the host connected
+/// with.
+fn unlist_room(state: &RoomAwareClientServerState, room: &str, query_params: &HashMap<String, String>) -> Response {
+  let Some(host_key) = query_params.get("key") else {
+    return (StatusCode::UNAUTHORIZED, "Host key is missing\n").into_response();
+  };
+  match state.set_listing(room, host_key, None) {
+    Ok(()) => {
+      info!("Removed room [{room}] from the public room list");
+      StatusCode::NO_CONTENT.into_response()
+    }
+    Err(error) => host_request_error_response(error),
+  }
+}
+
+/// Returns the response for a host's request to the signalling server that failed.
+fn host_request_error_response(error: HostRequestError) -> Response {
+  match error {
+    HostRequestError::InvalidHostKey => (StatusCode::FORBIDDEN, "Host key is not valid\n").into_response(),
+    HostRequestError::UnknownRoom | HostRequestError::UnknownIdentity => {
+      (StatusCode::NOT_FOUND, "Room not found\n").into_response()
+    }
+    HostRequestError::UnknownPeer => (StatusCode::NOT_FOUND, "Peer not found\n").into_response(),
+  }
+}
+
+/// Tells a client whether it may join a room with the `password` it provides, if any. Uses the same status codes with
+/// which the WebSocket upgrade would be refused, so that the game can prompt for a password before connecting.
+fn check_room_access(
+  state: &RoomAwareClientServerState,
+  room: &str,
+  query_params: &HashMap<String, String>,
+) -> Response {
+  let password = query_params.get("password").map(String::as_str);
+  if !state.accepts_client(room) {
+    (StatusCode::NOT_FOUND, "Room not found\n").into_response()
+  } else if state.is_full(room) {
+    (StatusCode::SERVICE_UNAVAILABLE, "Room is full\n").into_response()
+  } else if password.is_none() && state.requires_password(room) {
+    (StatusCode::UNAUTHORIZED, "Password required\n").into_response()
+  } else if !state.accepts_password(room, password) {
+    (StatusCode::FORBIDDEN, "Password is not valid\n").into_response()
+  } else {
+    StatusCode::NO_CONTENT.into_response()
+  }
 }
 
 #[async_trait]
@@ -66,21 +357,28 @@ impl SignalingTopology<RoomAwareClientServerCallbacks, RoomAwareClientServerStat
       callbacks,
     } = metastate;
 
-    let Some(role) = state.take_pending_role(&room) else {
+    let Some(ApprovedPeer {
+      role,
+      identity,
+      host_key,
+      capacity,
+      password_hash,
+    }) = state.take_approved_peer(&room)
+    else {
       warn!("No approved role found for peer [{peer_id}] in room [{room}]");
       return;
     };
 
     match role {
       PeerRole::Host => {
-        if !state.add_host(&room, peer_id, sender.clone()) {
+        if !state.add_host(&room, peer_id, sender.clone(), host_key, capacity, password_hash) {
           warn!("Rejected duplicate host [{peer_id}] for room [{room}] after upgrade");
           return;
         }
         info!("Host joined and has ID [{peer_id}]");
         callbacks.host_connected.emit(peer_id);
       }
-      PeerRole::Client => match state.add_client(&room, peer_id, sender.clone()) {
+      PeerRole::Client => match state.add_client(&room, peer_id, sender.clone(), identity) {
         Ok(()) => {
           info!("Client with ID [{peer_id}] connected");
           callbacks.client_connected.emit(peer_id);
@@ -103,6 +401,9 @@ impl SignalingTopology<RoomAwareClientServerCallbacks, RoomAwareClientServerStat
       };
 
       match request {
+        PeerRequest::Signal { .. } if !state.is_member(&room, peer_id) => {
+          warn!("Ignoring signal from [{peer_id}] which is no longer a member of room [{room}]");
+        }
         PeerRequest::Signal { receiver, data } => {
           let event = Message::Text(JsonPeerEvent::Signal { sender: peer_id, data }.to_string().into());
           let result = match role {
@@ -147,7 +448,7 @@ impl SignalingState for RoomAwareClientServerState {}
 struct GlobalServerState {
   rooms: HashMap<String, RoomState>,
   peers: HashMap<PeerId, PeerRoomMembership>,
-  pending_roles: HashMap<String, VecDeque<PeerRole>>,
+  pending_peers: HashMap<String, VecDeque<ApprovedPeer>>,
 }
 
 /// Host and client channels for one room.
@@ -155,6 +456,66 @@ struct GlobalServerState {
 struct RoomState {
   host: Option<(PeerId, SignalingChannel)>,
   clients: HashMap<PeerId, SignalingChannel>,
+  /// Set while the room has lost its host and is waiting for one of its former clients to take over.
+  migration: Option<HostMigration>,
+  /// The secret with which the current host authorises bans. Bans aren't possible if the host didn't provide one.
+  host_key: Option<String>,
+  /// The identity of every client that has joined the room, including those that have left since.
+  client_identities: HashMap<PeerId, String>,
+  /// The identities of the clients that the host has banned. Kept for as long as the room exists, including across host
+  /// migrations.
+  banned_identities: HashSet<String>,
+  /// The hash of the password that clients must provide to join, if the host connected with one. Kept across host
+  /// migrations.
+  password_hash: Option<[u8; 32]>,
+  /// How the room appears in the public room list, if the host has listed it. Dropped when the host leaves, so that a
+  /// successor has to list the room again.
+  listing: Option<RoomListing>,
+  /// The maximum number of clients that the host has declared the room can hold, if any. Kept across host migrations
+  /// until the successor declares its own.
+  capacity: Option<usize>,
+  /// The client that the host has designated to take over as host if it leaves, if any.
+  successor: Option<PeerId>,
+}
+
+/// How a host describes its room in the public room list.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct RoomListing {
+  name: String,
+  player_count: u8,
+  max_players: u8,
+}
+
+/// A room in the public room list, as returned by `GET /rooms`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct PublicRoom {
+  room_id: String,
+  name: String,
+  player_count: u8,
+  max_players: u8,
+  password_required: bool,
+}
+
+/// A room whose host has left while clients were connected. The room is kept for [`HOST_MIGRATION_TIMEOUT`] so that
+/// the successor chosen by the game can connect as host and the remaining clients can reconnect to it.
+#[derive(Debug, Clone)]
+struct HostMigration {
+  /// The peer IDs of the clients that were connected when the host left. Only these can be promoted to host.
+  candidates: HashSet<PeerId>,
+  /// The candidate that the host has designated as its successor, if any. Only this candidate can be promoted to host
+  /// until [`DESIGNATED_SUCCESSOR_TIMEOUT`] has passed, so that no other former client can take over the room.
+  designated_successor: Option<PeerId>,
+  started_at: Instant,
+}
+
+impl HostMigration {
+  /// Returns whether the given former client may take over as host.
+  fn accepts(&self, successor: PeerId) -> bool {
+    self.candidates.contains(&successor)
+      && self.designated_successor.is_none_or(|designated_successor| {
+        designated_successor == successor || self.started_at.elapsed() >= DESIGNATED_SUCCESSOR_TIMEOUT
+      })
+  }
 }
 
 /// Tracks which room and role a peer owns.
@@ -164,6 +525,29 @@ struct PeerRoomMembership {
   role: PeerRole,
 }
 
+/// A connection request that has been approved, awaiting its WebSocket upgrade.
+#[derive(Debug, Clone)]
+struct ApprovedPeer {
+  role: PeerRole,
+  /// An optional, client-chosen identity that is kept across reconnects. Used to refuse banned clients.
+  identity: Option<String>,
+  /// An optional, host-chosen secret that authorises the host's ban requests.
+  host_key: Option<String>,
+  /// An optional, host-declared maximum number of clients in its room.
+  capacity: Option<usize>,
+  /// The hash of the password that a host has protected its room with, if any.
+  password_hash: Option<[u8; 32]>,
+}
+
+/// The reasons why a host's request to ban or remove a client or to list its room fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostRequestError {
+  UnknownRoom,
+  InvalidHostKey,
+  UnknownIdentity,
+  UnknownPeer,
+}
+
 /// Distinguishes host sockets from client sockets.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PeerRole {
@@ -172,6 +556,10 @@ enum PeerRole {
 }
 
 const LOCK_ERROR: &str = "Room state mutex is poisoned";
+const HOST_MIGRATION_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long only the designated successor may take over a room whose host has left, before any other former client may.
+const DESIGNATED_SUCCESSOR_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ROOM_NAME_LENGTH: usize = 32;
 
 impl RoomAwareClientServerState {
   /// Returns whether a room has, or is about to have, a host.
@@ -179,64 +567,295 @@ impl RoomAwareClientServerState {
     let state = self.state.lock().expect(LOCK_ERROR);
     state.rooms.get(room).and_then(|room| room.host.as_ref()).is_some()
       || state
-        .pending_roles
+        .pending_peers
         .get(room)
-        .is_some_and(|roles| roles.iter().any(|role| matches!(role, PeerRole::Host)))
+        .is_some_and(|peers| peers.iter().any(|peer| matches!(peer.role, PeerRole::Host)))
+  }
+
+  /// Returns whether a host may connect to a room. A successor (the former client peer ID it is taking over from) must
+  /// be provided if, and only if, the room is awaiting one, and must be the one designated by the previous host, if any,
+  /// until [`DESIGNATED_SUCCESSOR_TIMEOUT`] has passed.
+  fn accepts_host(&self, room: &str, successor: Option<PeerId>) -> bool {
+    let mut state = self.state.lock().expect(LOCK_ERROR);
+    state.expire_stale_migration(room);
+    match (
+      state.rooms.get(room).and_then(|room| room.migration.as_ref()),
+      successor,
+    ) {
+      (None, None) => true,
+      (Some(migration), Some(successor)) => migration.accepts(successor),
+      _ => false,
+    }
+  }
+
+  /// Returns whether a client may connect to a room i.e. whether the room has, or is about to have, a host.
+  fn accepts_client(&self, room: &str) -> bool {
+    self.has_host_or_pending_host(room) || self.is_awaiting_successor(room)
+  }
+
+  /// Returns whether a room has lost its host and is waiting for a successor to connect.
+  fn is_awaiting_successor(&self, room: &str) -> bool {
+    let mut state = self.state.lock().expect(LOCK_ERROR);
+    state.expire_stale_migration(room);
+    state.rooms.get(room).is_some_and(|room| room.migration.is_some())
+  }
+
+  /// Returns whether a peer is currently a member of a room. Peers stop being members when their host leaves.
+  fn is_member(&self, room: &str, peer_id: PeerId) -> bool {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    state
+      .peers
+      .get(&peer_id)
+      .is_some_and(|membership| membership.room == room)
+  }
+
+  /// Returns whether a client with the given identity has been banned from a room. Clients without an identity can't
+  /// be banned, which is why they are refused by rooms with bans, see [`Self::has_bans`].
+  fn is_banned(&self, room: &str, identity: Option<&str>) -> bool {
+    let Some(identity) = identity else {
+      return false;
+    };
+    let state = self.state.lock().expect(LOCK_ERROR);
+    state
+      .rooms
+      .get(room)
+      .is_some_and(|room_state| room_state.banned_identities.contains(identity))
+  }
+
+  /// Returns whether the host of a room has banned any client from it.
+  fn has_bans(&self, room: &str) -> bool {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    state
+      .rooms
+      .get(room)
+      .is_some_and(|room_state| !room_state.banned_identities.is_empty())
+  }
+
+  /// Bans the identity of a client that has joined a room, if the host key matches the one of the room's host.
+  fn ban(&self, room: &str, host_key: &str, peer_id: PeerId) -> Result<(), HostRequestError> {
+    let mut state = self.state.lock().expect(LOCK_ERROR);
+    let room_state = state.rooms.get_mut(room).ok_or(HostRequestError::UnknownRoom)?;
+    if room_state.host_key.as_deref() != Some(host_key) {
+      return Err(HostRequestError::InvalidHostKey);
+    }
+    let identity = room_state
+      .client_identities
+      .get(&peer_id)
+      .cloned()
+      .ok_or(HostRequestError::UnknownIdentity)?;
+    room_state.banned_identities.insert(identity);
+    Ok(())
+  }
+
+  /// Removes a client from a room and closes its signalling connection, if the host key matches the one of the room's
+  /// host.
+  fn remove_client(&self, room: &str, host_key: &str, peer_id: PeerId) -> Result<(), HostRequestError> {
+    let client_sender = {
+      let state = self.state.lock().expect(LOCK_ERROR);
+      let room_state = state.rooms.get(room).ok_or(HostRequestError::UnknownRoom)?;
+      if room_state.host_key.as_deref() != Some(host_key) {
+        return Err(HostRequestError::InvalidHostKey);
+      }
+      room_state
+        .clients
+        .get(&peer_id)
+        .cloned()
+        .ok_or(HostRequestError::UnknownPeer)?
+    };
+    self.disconnect_client(room, peer_id);
+    if let Err(error) = try_send(&client_sender, Message::Close(None)) {
+      warn!("Failure closing connection of removed client [{peer_id}]: {error:?}");
+    }
+    Ok(())
   }
 
-  /// Stores the approved role for the upcoming WebSocket upgrade.
-  fn approve_role(&self, room: &str, role: PeerRole) {
+  /// Designates the client that takes over as host of a room if its host leaves, if the host key matches the one of the
+  /// room's host.
+  fn set_successor(&self, room: &str, host_key: &str, peer_id: PeerId) -> Result<(), HostRequestError> {
     let mut state = self.state.lock().expect(LOCK_ERROR);
-    state.pending_roles.entry(room.to_string()).or_default().push_back(role);
+    let room_state = state.rooms.get_mut(room).ok_or(HostRequestError::UnknownRoom)?;
+    if room_state.host_key.as_deref() != Some(host_key) {
+      return Err(HostRequestError::InvalidHostKey);
+    }
+    if !room_state.clients.contains_key(&peer_id) {
+      return Err(HostRequestError::UnknownPeer);
+    }
+    room_state.successor = Some(peer_id);
+    Ok(())
   }
 
-  /// Consumes the next approved role for a room.
-  fn take_pending_role(&mut self, room: &str) -> Option<PeerRole> {
+  /// Changes the number of clients a room can hold, if the host key matches the one of the room's host.
+  fn set_capacity(&self, room: &str, host_key: &str, capacity: usize) -> Result<(), HostRequestError> {
     let mut state = self.state.lock().expect(LOCK_ERROR);
-    let roles = state.pending_roles.get_mut(room)?;
-    let role = roles.pop_front();
-    if roles.is_empty() {
-      state.pending_roles.remove(room);
+    let room_state = state.rooms.get_mut(room).ok_or(HostRequestError::UnknownRoom)?;
+    if room_state.host_key.as_deref() != Some(host_key) {
+      return Err(HostRequestError::InvalidHostKey);
     }
-    role
+    room_state.capacity = Some(capacity);
+    Ok(())
   }
 
-  /// Registers a peer as room host when no host exists.
-  fn add_host(&mut self, room: &str, peer_id: PeerId, sender: SignalingChannel) -> bool {
+  /// Lists a room publicly, updates its listing or removes it from the public room list, if the host key matches the
+  /// one of the room's host.
+  fn set_listing(&self, room: &str, host_key: &str, listing: Option<RoomListing>) -> Result<(), HostRequestError> {
     let mut state = self.state.lock().expect(LOCK_ERROR);
-    let room_state = state.rooms.entry(room.to_string()).or_default();
-    if room_state.host.is_some() {
+    let room_state = state.rooms.get_mut(room).ok_or(HostRequestError::UnknownRoom)?;
+    if room_state.host_key.as_deref() != Some(host_key) {
+      return Err(HostRequestError::InvalidHostKey);
+    }
+    room_state.listing = listing;
+    Ok(())
+  }
+
+  /// Returns every listed room that currently has a host, sorted by name.
+  fn public_rooms(&self) -> Vec<PublicRoom> {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    let mut public_rooms = state
+      .rooms
+      .iter()
+      .filter(|(_, room_state)| room_state.host.is_some())
+      .filter_map(|(room, room_state)| {
+        room_state.listing.as_ref().map(|listing| PublicRoom {
+          room_id: room.clone(),
+          name: listing.name.clone(),
+          player_count: listing.player_count,
+          max_players: listing.max_players,
+          password_required: room_state.password_hash.is_some(),
+        })
+      })
+      .collect::<Vec<_>>();
+    public_rooms.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.room_id.cmp(&b.room_id)));
+    public_rooms
+  }
+
+  /// Returns whether a room already holds as many clients as its host has declared it can, counting clients whose
+  /// connection has been approved but not yet upgraded. Rooms without a declared capacity are never full.
+  fn is_full(&self, room: &str) -> bool {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    let Some(room_state) = state.rooms.get(room) else {
+      return false;
+    };
+    let Some(capacity) = room_state.capacity else {
       return false;
+    };
+    let pending_client_count = state.pending_peers.get(room).map_or(0, |peers| {
+      peers
+        .iter()
+        .filter(|peer| matches!(peer.role, PeerRole::Client))
+        .count()
+    });
+    room_state.clients.len() + pending_client_count >= capacity
+  }
+
+  /// Returns whether clients must provide a password to join a room.
+  fn requires_password(&self, room: &str) -> bool {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    state
+      .rooms
+      .get(room)
+      .is_some_and(|room_state| room_state.password_hash.is_some())
+  }
+
+  /// Returns whether the given password, if any, lets a client join a room. Any password is accepted by rooms without
+  /// one.
+  fn accepts_password(&self, room: &str, password: Option<&str>) -> bool {
+    let state = self.state.lock().expect(LOCK_ERROR);
+    match state.rooms.get(room).and_then(|room_state| room_state.password_hash) {
+      None => true,
+      Some(password_hash) => password.is_some_and(|password| hash_password(room, password) == password_hash),
+    }
+  }
+
+  /// Stores the approved peer for the upcoming WebSocket upgrade.
+  fn approve_peer(&self, room: &str, peer: ApprovedPeer) {
+    let mut state = self.state.lock().expect(LOCK_ERROR);
+    state.pending_peers.entry(room.to_string()).or_default().push_back(peer);
+  }
+
+  /// Consumes the next approved peer for a room.
+  fn take_approved_peer(&mut self, room: &str) -> Option<ApprovedPeer> {
+    let mut state = self.state.lock().expect(LOCK_ERROR);
+    let peers = state.pending_peers.get_mut(room)?;
+    let peer = peers.pop_front();
+    if peers.is_empty() {
+      state.pending_peers.remove(room);
+    }
+    peer
+  }
+
+  /// Registers a peer as room host when no host exists. If the room was awaiting a successor, the peer is promoted to
+  /// host and notified of every client that has already reconnected.
+  fn add_host(
+    &mut self,
+    room: &str,
+    peer_id: PeerId,
+    sender: SignalingChannel,
+    host_key: Option<String>,
+    capacity: Option<usize>,
+    password_hash: Option<[u8; 32]>,
+  ) -> bool {
+    let waiting_client_ids = {
+      let mut state = self.state.lock().expect(LOCK_ERROR);
+      let room_state = state.rooms.entry(room.to_string()).or_default();
+      if room_state.host.is_some() {
+        return false;
+      }
+      room_state.host = Some((peer_id, sender.clone()));
+      room_state.host_key = host_key;
+      if capacity.is_some() {
+        room_state.capacity = capacity;
+      }
+      if password_hash.is_some() {
+        room_state.password_hash = password_hash;
+      }
+      if room_state.migration.take().is_some() {
+        info!("Promoted [{peer_id}] to host of room [{room}]");
+      }
+      let waiting_client_ids = room_state.clients.keys().copied().collect::<Vec<_>>();
+      state.peers.insert(
+        peer_id,
+        PeerRoomMembership {
+          room: room.to_string(),
+          role: PeerRole::Host,
+        },
+      );
+      waiting_client_ids
+    };
+
+    for client_id in waiting_client_ids {
+      let event = Message::Text(JsonPeerEvent::NewPeer(client_id).to_string().into());
+      if let Err(error) = try_send(&sender, event) {
+        error!("Failure sending waiting client to promoted host: {error:?}");
+      }
     }
-    room_state.host = Some((peer_id, sender));
-    state.peers.insert(
-      peer_id,
-      PeerRoomMembership {
-        room: room.to_string(),
-        role: PeerRole::Host,
-      },
-    );
     true
   }
 
-  /// Registers a client and notifies that room's host.
+  /// Registers a client and notifies that room's host. Clients that join while the room is awaiting a successor are
+  /// announced to the successor once it has connected.
   fn add_client(
     &mut self,
     room: &str,
     peer_id: PeerId,
     sender: SignalingChannel,
+    identity: Option<String>,
   ) -> Result<(), matchbox_signaling::SignalingError> {
-    let host_sender = self.get_signalling_channel_sender(room)?;
-    try_send(
-      &host_sender,
-      Message::Text(JsonPeerEvent::NewPeer(peer_id).to_string().into()),
-    )?;
+    if !self.is_awaiting_successor(room) {
+      let host_sender = self.get_signalling_channel_sender(room)?;
+      try_send(
+        &host_sender,
+        Message::Text(JsonPeerEvent::NewPeer(peer_id).to_string().into()),
+      )?;
+    }
     let mut state = self.state.lock().expect(LOCK_ERROR);
     let room_state = state
       .rooms
       .get_mut(room)
       .ok_or(matchbox_signaling::SignalingError::UnknownPeer)?;
     room_state.clients.insert(peer_id, sender);
+    if let Some(identity) = identity {
+      room_state.client_identities.insert(peer_id, identity);
+    }
     state.peers.insert(
       peer_id,
       PeerRoomMembership {
@@ -295,7 +914,7 @@ impl RoomAwareClientServerState {
   }
 
   /// Removes one client and notifies only its room host.
-  fn disconnect_client(&mut self, room: &str, peer_id: PeerId) {
+  fn disconnect_client(&self, room: &str, peer_id: PeerId) {
     let host_sender = {
       let mut state = self.state.lock().expect(LOCK_ERROR);
       let Some(membership) = state.peers.remove(&peer_id) else {
@@ -318,7 +937,8 @@ impl RoomAwareClientServerState {
     }
   }
 
-  /// Removes one host, clears its room, and notifies its clients.
+  /// Removes one host, clears its room, and notifies its clients. If any clients were connected, the room is kept so
+  /// that one of them can take over as host.
   fn disconnect_host(&mut self, room: &str, peer_id: PeerId) {
     let client_senders = {
       let mut state = self.state.lock().expect(LOCK_ERROR);
@@ -335,6 +955,25 @@ impl RoomAwareClientServerState {
       for client_id in room_state.clients.keys() {
         state.peers.remove(client_id);
       }
+      let candidates = room_state.clients.keys().copied().collect::<HashSet<_>>();
+      if !candidates.is_empty() {
+        info!("Room [{room}] is awaiting a successor for host [{peer_id}]");
+        state.rooms.insert(
+          room.to_string(),
+          RoomState {
+            migration: Some(HostMigration {
+              designated_successor: room_state.successor.filter(|successor| candidates.contains(successor)),
+              candidates,
+              started_at: Instant::now(),
+            }),
+            client_identities: std::mem::take(&mut room_state.client_identities),
+            banned_identities: std::mem::take(&mut room_state.banned_identities),
+            password_hash: room_state.password_hash,
+            capacity: room_state.capacity,
+            ..RoomState::default()
+          },
+        );
+      }
       room_state
         .clients
         .drain()
@@ -362,6 +1001,27 @@ impl RoomAwareClientServerState {
   }
 }
 
+impl GlobalServerState {
+  /// Removes a room whose successor has not connected within [`HOST_MIGRATION_TIMEOUT`], including the clients that
+  /// have reconnected to it in the meantime.
+  fn expire_stale_migration(&mut self, room: &str) {
+    let is_stale = self
+      .rooms
+      .get(room)
+      .and_then(|room_state| room_state.migration.as_ref())
+      .is_some_and(|migration| migration.started_at.elapsed() >= HOST_MIGRATION_TIMEOUT);
+    if !is_stale {
+      return;
+    }
+    if let Some(room_state) = self.rooms.remove(room) {
+      info!("No successor connected to room [{room}] in time, removing it");
+      for client_id in room_state.clients.keys() {
+        self.peers.remove(client_id);
+      }
+    }
+  }
+}
+
 #[expect(
   clippy::result_large_err,
   reason = "matchbox_signaling requires axum::response::Response for connection rejection"
@@ -375,6 +1035,65 @@ fn parse_role(query_params: &HashMap<String, String>) -> Result<PeerRole, Respon
   }
 }
 
+#[expect(
+  clippy::result_large_err,
+  reason = "matchbox_signaling requires axum::response::Response for connection rejection"
+)]
+/// Parses the optional `successor` query parameter, which a former client provides with its previous peer ID when it
+/// takes over as host of a room whose host has left.
+fn parse_successor(query_params: &HashMap<String, String>) -> Result<Option<PeerId>, Response> {
+  query_params
+    .get("successor")
+    .map(|successor| {
+      Uuid::parse_str(successor)
+        .map(PeerId)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Successor must be a peer ID\n").into_response())
+    })
+    .transpose()
+}
+
+#[expect(
+  clippy::result_large_err,
+  reason = "matchbox_signaling requires axum::response::Response for connection rejection"
+)]
+/// Parses the optional `capacity` query parameter, with which a host declares the maximum number of clients its room
+/// can hold.
+fn parse_capacity(query_params: &HashMap<String, String>) -> Result<Option<usize>, Response> {
+  query_params
+    .get("capacity")
+    .map(|capacity| {
+      parse_capacity_value(capacity)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Capacity must be a positive number\n").into_response())
+    })
+    .transpose()
+}
+
+/// Parses a capacity, which must be a positive number.
+fn parse_capacity_value(capacity: &str) -> Option<usize> {
+  capacity.parse::<usize>().ok().filter(|capacity| *capacity > 0)
+}
+
+/// Returns the query parameters of a connection request for logging, with the values of secrets such as passwords,
+/// host keys and identities redacted.
+fn redacted_query_params(query_params: &HashMap<String, String>) -> BTreeMap<&str, &str> {
+  query_params
+    .iter()
+    .map(|(name, value)| match name.as_str() {
+      "password" | "key" | "identity" => (name.as_str(), "[redacted]"),
+      _ => (name.as_str(), value.as_str()),
+    })
+    .collect()
+}
+
+/// Hashes a room password, salted with the room ID so that rooms with the same password don't share a hash.
+fn hash_password(room: &str, password: &str) -> [u8; 32] {
+  let mut hasher = Sha256::new();
+  hasher.update(room.as_bytes());
+  hasher.update([0]);
+  hasher.update(password.as_bytes());
+  hasher.finalize().into()
+}
+
 /// Logs a failed client WebSocket request.
 fn log_client_request_error(peer_id: PeerId, error: &ClientRequestError) {
   match error {
@@ -383,3 +1102,63 @@ fn log_client_request_error(peer_id: PeerId, error: &ClientRequestError) {
     ClientRequestError::Json(_) | ClientRequestError::UnsupportedType(_) => error!("Error with request: {error:?}"),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn peer_id(value: u128) -> PeerId {
+    PeerId(Uuid::from_u128(value))
+  }
+
+  fn host_migration(designated_successor: Option<PeerId>, elapsed: Duration) -> HostMigration {
+    HostMigration {
+      candidates: HashSet::from([peer_id(1), peer_id(2)]),
+      designated_successor,
+      started_at: Instant::now() - elapsed,
+    }
+  }
+
+  #[test]
+  fn host_migration_accepts_any_candidate_without_designated_successor() {
+    let migration = host_migration(None, Duration::ZERO);
+
+    assert!(migration.accepts(peer_id(1)));
+    assert!(migration.accepts(peer_id(2)));
+    assert!(!migration.accepts(peer_id(3)));
+  }
+
+  #[test]
+  fn host_migration_accepts_other_candidates_only_after_designated_successor_timeout() {
+    let migration = host_migration(Some(peer_id(1)), Duration::ZERO);
+    assert!(migration.accepts(peer_id(1)));
+    assert!(!migration.accepts(peer_id(2)));
+
+    let migration = host_migration(Some(peer_id(1)), DESIGNATED_SUCCESSOR_TIMEOUT);
+    assert!(migration.accepts(peer_id(1)));
+    assert!(migration.accepts(peer_id(2)));
+    assert!(!migration.accepts(peer_id(3)));
+  }
+
+  #[test]
+  fn redacted_query_params_hides_passwords_host_keys_and_identities() {
+    let query_params = HashMap::from([
+      ("role".to_string(), "host".to_string()),
+      ("key".to_string(), "secret".to_string()),
+      ("password".to_string(), "hunter2".to_string()),
+      ("identity".to_string(), "alice".to_string()),
+      ("capacity".to_string(), "8".to_string()),
+    ]);
+
+    assert_eq!(
+      redacted_query_params(&query_params),
+      BTreeMap::from([
+        ("capacity", "8"),
+        ("identity", "[redacted]"),
+        ("key", "[redacted]"),
+        ("password", "[redacted]"),
+        ("role", "host"),
+      ])
+    );
+  }
+}
@@ -19,7 +19,11 @@ async fn wait_for_health(client: &reqwest::Client, url: &str) -> reqwest::Respon
 
 #[tokio::test]
 async fn health_endpoint_responds_when_server_is_running() {
-  let mut server = build_server(ServerConfig { port: 0, tls: None }).expect("build signalling server");
+  let mut server = build_server(ServerConfig {
+    port: 0,
+    ..ServerConfig::default()
+  })
+  .expect("build signalling server");
   let socket_addr = server.bind().expect("bind signalling server");
   let server_handle = tokio::spawn(server.serve());
   let client = reqwest::Client::new();
@@ -41,6 +45,7 @@ async fn https_health_endpoint_responds_when_tls_is_enabled() {
       cert_path: PathBuf::from(TLS_CERT_PATH),
       key_path: PathBuf::from(TLS_KEY_PATH),
     }),
+    ..ServerConfig::default()
   })
   .expect("build signalling server");
   let socket_addr = server.bind().expect("bind signalling server");
@@ -0,0 +1,80 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use mooplas_signalling_server::{IceConfig, ServerConfig, TurnConfig, build_server};
+
+async fn get_ice_servers(config: IceConfig) -> (reqwest::StatusCode, Option<String>, serde_json::Value) {
+  let mut server = build_server(ServerConfig {
+    port: 0,
+    ice: config,
+    ..ServerConfig::default()
+  })
+  .expect("build signalling server");
+  let socket_addr = server.bind().expect("bind signalling server");
+  let server_handle = tokio::spawn(server.serve());
+
+  let response = reqwest::Client::new()
+    .get(format!("http://{socket_addr}/ice-servers"))
+    .send()
+    .await
+    .expect("request ICE servers");
+  let status = response.status();
+  let cache_control = response
+    .headers()
+    .get(reqwest::header::CACHE_CONTROL)
+    .and_then(|value| value.to_str().ok())
+    .map(str::to_string);
+  let body = response.text().await.expect("read ICE servers response");
+
+  server_handle.abort();
+  let _ = server_handle.await;
+  (
+    status,
+    cache_control,
+    serde_json::from_str(&body).expect("parse ICE servers response"),
+  )
+}
+
+#[tokio::test]
+async fn ice_servers_endpoint_returns_public_stun_server_by_default() {
+  let (status, _, body) = get_ice_servers(IceConfig::default()).await;
+
+  assert_eq!(status, reqwest::StatusCode::OK);
+  assert_eq!(body, serde_json::json!({ "urls": ["stun:stun.l.google.com:19302"] }));
+}
+
+#[tokio::test]
+async fn ice_servers_endpoint_issues_short_lived_turn_credentials() {
+  let (status, cache_control, body) = get_ice_servers(IceConfig {
+    stun_urls: vec!["stun:turn.example.com:3478".to_string()],
+    turn: Some(TurnConfig {
+      urls: vec!["turn:turn.example.com:3478".to_string()],
+      secret: "north".to_string(),
+      credential_ttl: Duration::from_secs(600),
+    }),
+  })
+  .await;
+
+  assert_eq!(status, reqwest::StatusCode::OK);
+  assert_eq!(cache_control.as_deref(), Some("no-store"));
+  assert_eq!(
+    body["urls"],
+    serde_json::json!(["stun:turn.example.com:3478", "turn:turn.example.com:3478"])
+  );
+  let username = body["username"].as_str().expect("username is a string");
+  let (expiry, user_id) = username.split_once(':').expect("username includes expiry");
+  let expiry: u64 = expiry.parse().expect("expiry is a Unix timestamp");
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .expect("valid system time")
+    .as_secs();
+  assert_eq!(user_id, "mooplas");
+  assert!(
+    (now + 590..=now + 600).contains(&expiry),
+    "Unexpected expiry [{expiry}]"
+  );
+  assert!(
+    body["credential"]
+      .as_str()
+      .is_some_and(|credential| !credential.is_empty())
+  );
+}
@@ -10,7 +10,11 @@ use tokio_tungstenite::{
 };
 
 async fn spawn_server() -> (std::net::SocketAddr, tokio::task::JoinHandle<Result<(), ServerError>>) {
-  let mut server = build_server(ServerConfig { port: 0, tls: None }).expect("Failed to build signalling server");
+  let mut server = build_server(ServerConfig {
+    port: 0,
+    ..ServerConfig::default()
+  })
+  .expect("Failed to build signalling server");
   let socket_addr = server.bind().expect("Failed to bind signalling server");
   let server_handle = tokio::spawn(server.serve());
   (socket_addr, server_handle)
@@ -175,3 +179,445 @@ async fn disconnects_only_affect_their_room() {
   server_handle.abort();
   let _ = server_handle.await;
 }
+
+#[tokio::test]
+async fn former_client_is_promoted_to_host_after_host_leaves() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host, host_id) = connect_peer(socket_addr, "/room-a?role=host").await;
+  let (mut client_a, client_a_id) = connect_peer(socket_addr, "/room-a").await;
+  let (mut client_b, client_b_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(client_a_id));
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(client_b_id));
+
+  host.close(None).await.expect("Failed to close host");
+  assert_eq!(read_event(&mut client_a).await, JsonPeerEvent::PeerLeft(host_id));
+  assert_eq!(read_event(&mut client_b).await, JsonPeerEvent::PeerLeft(host_id));
+
+  assert_connect_rejected(socket_addr, "/room-a?role=host", 409).await;
+  assert_connect_rejected(socket_addr, "/room-a?role=host&successor=not-a-peer-id", 400).await;
+  assert_connect_rejected(
+    socket_addr,
+    "/room-a?role=host&successor=00000000-0000-0000-0000-000000000000",
+    409,
+  )
+  .await;
+
+  let (_client_b_2, client_b_2_id) = connect_peer(socket_addr, "/room-a").await;
+  let (mut new_host, _) = connect_peer(socket_addr, &format!("/room-a?role=host&successor={client_a_id}")).await;
+  assert_eq!(read_event(&mut new_host).await, JsonPeerEvent::NewPeer(client_b_2_id));
+
+  let (_client_a_2, client_a_2_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut new_host).await, JsonPeerEvent::NewPeer(client_a_2_id));
+  assert_connect_rejected(socket_addr, &format!("/room-a?role=host&successor={client_b_id}"), 409).await;
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_successor(socket_addr: std::net::SocketAddr, path: &str) -> u16 {
+  reqwest::Client::new()
+    .post(format!("http://{socket_addr}{path}"))
+    .send()
+    .await
+    .expect("Failed to send successor request")
+    .status()
+    .as_u16()
+}
+
+#[tokio::test]
+async fn only_designated_successor_is_promoted_to_host() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host, host_id) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a").await;
+  let (mut client_a, client_a_id) = connect_peer(socket_addr, "/room-a").await;
+  let (mut client_b, client_b_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(client_a_id));
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(client_b_id));
+
+  assert_eq!(
+    request_successor(socket_addr, &format!("/rooms/room-a/successor/{client_b_id}")).await,
+    401
+  );
+  assert_eq!(
+    request_successor(socket_addr, &format!("/rooms/room-a/successor/{client_b_id}?key=wrong")).await,
+    403
+  );
+  assert_eq!(
+    request_successor(
+      socket_addr,
+      "/rooms/room-a/successor/00000000-0000-0000-0000-000000000000?key=secret-a"
+    )
+    .await,
+    404
+  );
+  assert_eq!(
+    request_successor(
+      socket_addr,
+      &format!("/rooms/room-a/successor/{client_b_id}?key=secret-a")
+    )
+    .await,
+    204
+  );
+
+  host.close(None).await.expect("Failed to close host");
+  assert_eq!(read_event(&mut client_a).await, JsonPeerEvent::PeerLeft(host_id));
+  assert_eq!(read_event(&mut client_b).await, JsonPeerEvent::PeerLeft(host_id));
+
+  assert_connect_rejected(socket_addr, &format!("/room-a?role=host&successor={client_a_id}"), 409).await;
+  let (_new_host, _) = connect_peer(socket_addr, &format!("/room-a?role=host&successor={client_b_id}")).await;
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+#[tokio::test]
+async fn room_without_clients_is_removed_when_host_leaves() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host, _) = connect_peer(socket_addr, "/room-a?role=host").await;
+  host.close(None).await.expect("Failed to close host");
+  tokio::time::sleep(Duration::from_millis(75)).await;
+
+  assert_connect_rejected(socket_addr, "/room-a", 409).await;
+  let (_new_host, _) = connect_peer(socket_addr, "/room-a?role=host").await;
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_ban(socket_addr: std::net::SocketAddr, path: &str) -> u16 {
+  reqwest::Client::new()
+    .post(format!("http://{socket_addr}{path}"))
+    .send()
+    .await
+    .expect("Failed to send ban request")
+    .status()
+    .as_u16()
+}
+
+#[tokio::test]
+async fn host_can_ban_client_identity_from_its_room() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host_a, _) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a").await;
+  let (_host_b, _) = connect_peer(socket_addr, "/room-b?role=host&key=secret-b").await;
+  let (mut client, client_id) = connect_peer(socket_addr, "/room-a?identity=alice").await;
+  let (_anonymous_client, anonymous_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::NewPeer(client_id));
+  assert_eq!(
+    read_event(&mut host_a).await,
+    JsonPeerEvent::NewPeer(anonymous_client_id)
+  );
+
+  assert_eq!(
+    request_ban(socket_addr, &format!("/rooms/room-a/bans/{client_id}")).await,
+    401
+  );
+  assert_eq!(
+    request_ban(socket_addr, &format!("/rooms/room-a/bans/{client_id}?key=secret-b")).await,
+    403
+  );
+  assert_eq!(
+    request_ban(socket_addr, &format!("/rooms/room-c/bans/{client_id}?key=secret-a")).await,
+    404
+  );
+  assert_eq!(
+    request_ban(socket_addr, "/rooms/room-a/bans/not-a-peer-id?key=secret-a").await,
+    400
+  );
+  assert_eq!(
+    request_ban(
+      socket_addr,
+      &format!("/rooms/room-a/bans/{anonymous_client_id}?key=secret-a")
+    )
+    .await,
+    404
+  );
+  assert_eq!(
+    request_ban(socket_addr, &format!("/rooms/room-a/bans/{client_id}?key=secret-a")).await,
+    204
+  );
+
+  client.close(None).await.expect("Failed to close client");
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::PeerLeft(client_id));
+  assert_connect_rejected(socket_addr, "/room-a?identity=alice", 403).await;
+  assert_connect_rejected(socket_addr, "/room-a", 401).await;
+  let (_other_client, other_client_id) = connect_peer(socket_addr, "/room-a?identity=bob").await;
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::NewPeer(other_client_id));
+  let (_client_in_other_room, _) = connect_peer(socket_addr, "/room-b?identity=alice").await;
+  let (_anonymous_client_in_other_room, _) = connect_peer(socket_addr, "/room-b").await;
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_peer_removal(socket_addr: std::net::SocketAddr, path: &str) -> u16 {
+  reqwest::Client::new()
+    .delete(format!("http://{socket_addr}{path}"))
+    .send()
+    .await
+    .expect("Failed to send peer removal request")
+    .status()
+    .as_u16()
+}
+
+async fn assert_closed(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) {
+  let message = tokio::time::timeout(Duration::from_secs(1), socket.next())
+    .await
+    .expect("Closing the websocket timed out");
+  assert!(
+    matches!(message, None | Some(Ok(Message::Close(_))) | Some(Err(_))),
+    "Expected websocket to be closed, got {message:?}"
+  );
+}
+
+#[tokio::test]
+async fn host_can_remove_client_from_its_room() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host_a, _) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a").await;
+  let (_host_b, _) = connect_peer(socket_addr, "/room-b?role=host&key=secret-b").await;
+  let (mut client, client_id) = connect_peer(socket_addr, "/room-a?identity=alice").await;
+  let (mut other_client, other_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::NewPeer(client_id));
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::NewPeer(other_client_id));
+
+  assert_eq!(
+    request_peer_removal(socket_addr, &format!("/rooms/room-a/peers/{client_id}")).await,
+    401
+  );
+  assert_eq!(
+    request_peer_removal(socket_addr, &format!("/rooms/room-a/peers/{client_id}?key=secret-b")).await,
+    403
+  );
+  assert_eq!(
+    request_peer_removal(socket_addr, "/rooms/room-a/peers/not-a-peer-id?key=secret-a").await,
+    400
+  );
+  assert_eq!(
+    request_peer_removal(
+      socket_addr,
+      "/rooms/room-a/peers/00000000-0000-0000-0000-000000000000?key=secret-a"
+    )
+    .await,
+    404
+  );
+  assert_eq!(
+    request_peer_removal(socket_addr, &format!("/rooms/room-a/peers/{client_id}?key=secret-a")).await,
+    204
+  );
+
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::PeerLeft(client_id));
+  assert_closed(&mut client).await;
+  assert_no_event(&mut host_a).await;
+  assert_no_event(&mut other_client).await;
+  let (_rejoined_client, rejoined_client_id) = connect_peer(socket_addr, "/room-a?identity=alice").await;
+  assert_eq!(
+    read_event(&mut host_a).await,
+    JsonPeerEvent::NewPeer(rejoined_client_id)
+  );
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_room_access(socket_addr: std::net::SocketAddr, path: &str) -> u16 {
+  reqwest::Client::new()
+    .get(format!("http://{socket_addr}{path}"))
+    .send()
+    .await
+    .expect("Failed to send access request")
+    .status()
+    .as_u16()
+}
+
+#[tokio::test]
+async fn host_can_protect_its_room_with_a_password() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host_a, _) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a&password=hunter2").await;
+  let (mut host_b, _) = connect_peer(socket_addr, "/room-b?role=host&key=secret-b&password=").await;
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-c/access").await, 404);
+
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 401);
+  assert_eq!(
+    request_room_access(socket_addr, "/rooms/room-a/access?password=hunter3").await,
+    403
+  );
+  assert_eq!(
+    request_room_access(socket_addr, "/rooms/room-a/access?password=hunter2").await,
+    204
+  );
+  assert_connect_rejected(socket_addr, "/room-a", 401).await;
+  assert_connect_rejected(socket_addr, "/room-a?password=hunter3", 403).await;
+  let (_client, client_id) = connect_peer(socket_addr, "/room-a?password=hunter2").await;
+  assert_eq!(read_event(&mut host_a).await, JsonPeerEvent::NewPeer(client_id));
+
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-b/access").await, 204);
+  let (_other_client, other_client_id) = connect_peer(socket_addr, "/room-b").await;
+  assert_eq!(read_event(&mut host_b).await, JsonPeerEvent::NewPeer(other_client_id));
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+#[tokio::test]
+async fn host_can_limit_the_capacity_of_its_room() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  assert_connect_rejected(socket_addr, "/room-a?role=host&capacity=0", 400).await;
+  assert_connect_rejected(socket_addr, "/room-a?role=host&capacity=many", 400).await;
+  let (mut host, _) = connect_peer(socket_addr, "/room-a?role=host&capacity=2").await;
+  let (mut first_client, first_client_id) = connect_peer(socket_addr, "/room-a").await;
+  let (_second_client, second_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(first_client_id));
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(second_client_id));
+
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 503);
+  assert_connect_rejected(socket_addr, "/room-a", 503).await;
+
+  first_client.close(None).await.expect("Failed to close first client");
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::PeerLeft(first_client_id));
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 204);
+  let (_third_client, third_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(third_client_id));
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_capacity(socket_addr: std::net::SocketAddr, path: &str) -> u16 {
+  reqwest::Client::new()
+    .post(format!("http://{socket_addr}{path}"))
+    .send()
+    .await
+    .expect("Failed to send capacity request")
+    .status()
+    .as_u16()
+}
+
+#[tokio::test]
+async fn host_can_change_the_capacity_of_its_room() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host, _) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a&capacity=1").await;
+  let (_first_client, first_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(first_client_id));
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 503);
+
+  assert_eq!(request_capacity(socket_addr, "/rooms/room-a/capacity/2").await, 401);
+  assert_eq!(
+    request_capacity(socket_addr, "/rooms/room-a/capacity/2?key=secret-b").await,
+    403
+  );
+  assert_eq!(
+    request_capacity(socket_addr, "/rooms/room-a/capacity/0?key=secret-a").await,
+    400
+  );
+  assert_eq!(
+    request_capacity(socket_addr, "/rooms/room-b/capacity/2?key=secret-a").await,
+    404
+  );
+  assert_eq!(
+    request_capacity(socket_addr, "/rooms/room-a/capacity/2?key=secret-a").await,
+    204
+  );
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 204);
+  let (_second_client, second_client_id) = connect_peer(socket_addr, "/room-a").await;
+  assert_eq!(read_event(&mut host).await, JsonPeerEvent::NewPeer(second_client_id));
+
+  assert_eq!(
+    request_capacity(socket_addr, "/rooms/room-a/capacity/1?key=secret-a").await,
+    204
+  );
+  assert_eq!(request_room_access(socket_addr, "/rooms/room-a/access").await, 503);
+  assert_no_event(&mut host).await;
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
+
+async fn request_room_listing(socket_addr: std::net::SocketAddr, path: &str, listing: &serde_json::Value) -> u16 {
+  reqwest::Client::new()
+    .post(format!("http://{socket_addr}{path}"))
+    .header("content-type", "application/json")
+    .body(listing.to_string())
+    .send()
+    .await
+    .expect("Failed to send listing request")
+    .status()
+    .as_u16()
+}
+
+async fn public_rooms(socket_addr: std::net::SocketAddr) -> serde_json::Value {
+  let body = reqwest::Client::new()
+    .get(format!("http://{socket_addr}/rooms"))
+    .send()
+    .await
+    .expect("Failed to request public rooms")
+    .text()
+    .await
+    .expect("Failed to read public rooms");
+  serde_json::from_str(&body).expect("Failed to parse public rooms")
+}
+
+#[tokio::test]
+async fn host_can_list_its_room_publicly() {
+  let (socket_addr, server_handle) = spawn_server().await;
+  let (mut host_a, _) = connect_peer(socket_addr, "/room-a?role=host&key=secret-a&password=hunter2").await;
+  let (_host_b, _) = connect_peer(socket_addr, "/room-b?role=host&key=secret-b").await;
+  let listing = serde_json::json!({ "name": "  Alice's game  ", "player_count": 1, "max_players": 5 });
+  assert_eq!(public_rooms(socket_addr).await, serde_json::json!([]));
+
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing", &listing).await,
+    401
+  );
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing?key=secret-b", &listing).await,
+    403
+  );
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-c/listing?key=secret-a", &listing).await,
+    404
+  );
+  let invalid_listing = serde_json::json!({ "name": " ", "player_count": 1, "max_players": 5 });
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing?key=secret-a", &invalid_listing).await,
+    400
+  );
+  let invalid_listing = serde_json::json!({ "name": "Alice's game", "player_count": 6, "max_players": 5 });
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing?key=secret-a", &invalid_listing).await,
+    400
+  );
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing?key=secret-a", &listing).await,
+    204
+  );
+
+  assert_eq!(
+    public_rooms(socket_addr).await,
+    serde_json::json!([{
+      "room_id": "room-a",
+      "name": "Alice's game",
+      "player_count": 1,
+      "max_players": 5,
+      "password_required": true
+    }])
+  );
+
+  let unlist_status = reqwest::Client::new()
+    .delete(format!("http://{socket_addr}/rooms/room-a/listing?key=secret-a"))
+    .send()
+    .await
+    .expect("Failed to send unlisting request")
+    .status()
+    .as_u16();
+  assert_eq!(unlist_status, 204);
+  assert_eq!(public_rooms(socket_addr).await, serde_json::json!([]));
+
+  assert_eq!(
+    request_room_listing(socket_addr, "/rooms/room-a/listing?key=secret-a", &listing).await,
+    204
+  );
+  host_a.close(None).await.expect("Failed to close host");
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert_eq!(public_rooms(socket_addr).await, serde_json::json!([]));
+
+  server_handle.abort();
+  let _ = server_handle.await;
+}
@@ -18,7 +18,11 @@ fn install_crypto_provider() {
 }
 
 async fn spawn_server() -> (SocketAddr, JoinHandle<Result<(), ServerError>>) {
-  let mut server = build_server(ServerConfig { port: 0, tls: None }).expect("Failed to build signalling server");
+  let mut server = build_server(ServerConfig {
+    port: 0,
+    ..ServerConfig::default()
+  })
+  .expect("Failed to build signalling server");
   let socket_addr = server.bind().expect("Failed to bind signalling server");
   let server_handle = tokio::spawn(server.serve());
   (socket_addr, server_handle)